@@ -0,0 +1,4 @@
+-- Let admins lock accounts and sign them out everywhere
+
+ALTER TABLE account ADD COLUMN locked BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE account ADD COLUMN sessions_revoked_at BIGINT;
@@ -0,0 +1,8 @@
+-- Full-text search over tasks, with titles weighted above descriptions
+
+ALTER TABLE task ADD COLUMN search tsvector GENERATED ALWAYS AS (
+    setweight(to_tsvector('english', title), 'A')
+    || setweight(to_tsvector('english', COALESCE(description, '')), 'B')
+) STORED;
+
+CREATE INDEX IF NOT EXISTS task_search ON task USING GIN (search);
@@ -0,0 +1,13 @@
+-- Let tasks have a checklist of items, and complete themselves once every item is done
+
+ALTER TABLE task ADD COLUMN auto_complete BOOLEAN NOT NULL DEFAULT FALSE;
+
+CREATE TABLE checklist_item(
+    item_id BIGSERIAL PRIMARY KEY,
+    task_id BIGINT NOT NULL REFERENCES task(task_id) ON DELETE CASCADE,
+    text TEXT NOT NULL,
+    done BOOLEAN NOT NULL DEFAULT FALSE,
+    position INTEGER NOT NULL DEFAULT 0
+);
+
+CREATE INDEX checklist_item_task_id ON checklist_item(task_id);
@@ -0,0 +1,32 @@
+-- Replace whether a task is complete with where it is in its workflow, and remember when it got there
+-- (0 to do, 1 in progress, 2 blocked, 3 deferred, 4 done, 5 cancelled)
+
+ALTER TABLE task ADD COLUMN status INTEGER NOT NULL DEFAULT 0
+    CHECK (status >= 0 AND status <= 5);
+ALTER TABLE task ADD COLUMN status_changed_at BIGINT;
+ALTER TABLE task ADD COLUMN completed_at BIGINT;
+
+UPDATE task SET status = 4 WHERE complete;
+-- Tasks don't remember when they were completed, so the best guess is the start of their day (in UTC)
+-- Days past the end of their month used to be accepted, so those count as its last day,
+-- and years that dates can't have are left unknown
+UPDATE task SET status_changed_at = EXTRACT(EPOCH FROM LEAST(
+        make_date(year, month, 1) + (day - 1),
+        (make_date(year, month, 1) + INTERVAL '1 month')::DATE - 1
+    ))::BIGINT
+    WHERE complete AND year BETWEEN 1 AND 9999;
+UPDATE task SET completed_at = status_changed_at WHERE complete;
+ALTER TABLE task DROP COLUMN complete;
+
+CREATE TABLE task_status_change(
+    change_id BIGSERIAL PRIMARY KEY,
+    task_id BIGINT NOT NULL REFERENCES task(task_id) ON DELETE CASCADE,
+    status INTEGER NOT NULL,
+    changed_at BIGINT NOT NULL
+);
+
+CREATE INDEX task_status_change_task_id ON task_status_change(task_id);
+
+-- So that the history of a converted task shows when it was done
+INSERT INTO task_status_change(task_id, status, changed_at)
+    SELECT task_id, status, completed_at FROM task WHERE completed_at IS NOT NULL;
@@ -0,0 +1,10 @@
+-- Let tasks depend on others, which have to be done before they can start
+
+CREATE TABLE task_dependency(
+    task_id BIGINT NOT NULL REFERENCES task(task_id) ON DELETE CASCADE,
+    depends_on BIGINT NOT NULL REFERENCES task(task_id) ON DELETE CASCADE,
+    PRIMARY KEY (task_id, depends_on),
+    CHECK (task_id <> depends_on)
+);
+
+CREATE INDEX task_dependency_depends_on ON task_dependency(depends_on);
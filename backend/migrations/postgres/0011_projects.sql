@@ -0,0 +1,16 @@
+-- Let accounts group tasks on any number of dates into projects
+
+CREATE TABLE project(
+    project_id BIGSERIAL PRIMARY KEY,
+    account_id BIGINT NOT NULL REFERENCES account(account_id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    description TEXT,
+    -- year * 10000 + month * 100 + day, like the dates that tasks are filtered by
+    due_date INTEGER
+);
+
+CREATE INDEX project_account_id ON project(account_id);
+
+ALTER TABLE task ADD COLUMN project_id BIGINT REFERENCES project(project_id) ON DELETE SET NULL;
+
+CREATE INDEX task_project_id ON task(project_id);
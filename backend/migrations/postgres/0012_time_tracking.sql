@@ -0,0 +1,19 @@
+-- Let tasks have an estimate, and track the time actually spent on them in timer sessions
+
+ALTER TABLE task ADD COLUMN estimate_mins INTEGER
+    CHECK (estimate_mins IS NULL OR estimate_mins >= 0);
+
+CREATE TABLE time_session(
+    session_id BIGSERIAL PRIMARY KEY,
+    task_id BIGINT NOT NULL REFERENCES task(task_id) ON DELETE CASCADE,
+    account_id BIGINT NOT NULL REFERENCES account(account_id) ON DELETE CASCADE,
+    -- In unix seconds; stopped_at is NULL while the timer is running
+    started_at BIGINT NOT NULL,
+    stopped_at BIGINT,
+    CHECK (stopped_at IS NULL OR stopped_at >= started_at)
+);
+
+CREATE INDEX time_session_task_id ON time_session(task_id);
+CREATE INDEX time_session_account_started_at ON time_session(account_id, started_at);
+-- An account's timer runs one session at a time
+CREATE UNIQUE INDEX time_session_running ON time_session(account_id) WHERE stopped_at IS NULL;
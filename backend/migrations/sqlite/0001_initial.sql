@@ -0,0 +1,37 @@
+-- The same schema as the Postgres migration of the same version, in SQLite's types
+
+CREATE TABLE IF NOT EXISTS account(
+    account_id INTEGER PRIMARY KEY AUTOINCREMENT,
+    username TEXT UNIQUE NOT NULL,
+    hashed_password TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS task(
+    account_id INTEGER references account(account_id),
+    task_id INTEGER PRIMARY KEY AUTOINCREMENT,
+    year INTEGER NOT NULL,
+    month INTEGER NOT NULL,
+    day INTEGER NOT NULL,
+    start_min INTEGER,
+    end_min INTEGER,
+    title TEXT NOT NULL,
+    description TEXT,
+    complete BOOLEAN NOT NULL,
+    CHECK (month >= 1 AND month <= 12),
+    CHECK (day >= 1 AND day <= 31),
+    CHECK (start_min IS NULL OR (start_min >= 0 AND start_min < 1440)),
+    CHECK (end_min IS NULL OR (end_min >= 0 AND end_min < 1440)),
+    CHECK (end_min IS NULL OR (end_min IS NOT NULL AND start_min IS NOT NULL))
+);
+
+CREATE TABLE IF NOT EXISTS api_token(
+    token_id INTEGER PRIMARY KEY AUTOINCREMENT,
+    account_id INTEGER NOT NULL references account(account_id),
+    name TEXT NOT NULL,
+    hashed_token TEXT UNIQUE NOT NULL,
+    scope TEXT NOT NULL,
+    created_at INTEGER NOT NULL,
+    expires_at INTEGER,
+    last_used_at INTEGER,
+    CHECK (scope IN ('read', 'read_write'))
+);
@@ -0,0 +1,3 @@
+-- Speed up reading an account's tasks by date (a month, or any range of dates)
+
+CREATE INDEX IF NOT EXISTS task_account_date ON task (account_id, year, month, day);
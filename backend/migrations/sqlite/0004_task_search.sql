@@ -0,0 +1,29 @@
+-- Full-text search over tasks, in an FTS5 index that triggers keep in step with the task table
+
+CREATE VIRTUAL TABLE IF NOT EXISTS task_search USING fts5(
+    title,
+    description,
+    content='task',
+    content_rowid='task_id',
+    tokenize='porter unicode61 remove_diacritics 2'
+);
+
+CREATE TRIGGER IF NOT EXISTS task_search_insert AFTER INSERT ON task BEGIN
+    INSERT INTO task_search(rowid, title, description)
+    VALUES (new.task_id, new.title, new.description);
+END;
+
+CREATE TRIGGER IF NOT EXISTS task_search_delete AFTER DELETE ON task BEGIN
+    INSERT INTO task_search(task_search, rowid, title, description)
+    VALUES ('delete', old.task_id, old.title, old.description);
+END;
+
+CREATE TRIGGER IF NOT EXISTS task_search_update AFTER UPDATE ON task BEGIN
+    INSERT INTO task_search(task_search, rowid, title, description)
+    VALUES ('delete', old.task_id, old.title, old.description);
+    INSERT INTO task_search(rowid, title, description)
+    VALUES (new.task_id, new.title, new.description);
+END;
+
+-- Index the tasks from before this migration
+INSERT INTO task_search(task_search) VALUES ('rebuild');
@@ -0,0 +1,6 @@
+-- Let accounts move their overdue tasks to today whenever the day changes (in their UTC offset)
+
+ALTER TABLE account ADD COLUMN auto_rollover BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE account ADD COLUMN utc_offset_min INTEGER NOT NULL DEFAULT 0
+    CHECK (utc_offset_min >= -840 AND utc_offset_min <= 840);
+ALTER TABLE account ADD COLUMN rolled_over_on INTEGER;
@@ -0,0 +1,5 @@
+-- Let tasks have a priority (0 for none up to 4 for urgent), and a position among their day's untimed tasks
+
+ALTER TABLE task ADD COLUMN priority INTEGER NOT NULL DEFAULT 0
+    CHECK (priority >= 0 AND priority <= 4);
+ALTER TABLE task ADD COLUMN position INTEGER NOT NULL DEFAULT 0;
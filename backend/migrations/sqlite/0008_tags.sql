@@ -0,0 +1,17 @@
+-- Let accounts put their tasks into colored categories
+
+CREATE TABLE tag(
+    tag_id INTEGER PRIMARY KEY AUTOINCREMENT,
+    account_id INTEGER NOT NULL REFERENCES account(account_id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    color INTEGER NOT NULL DEFAULT 4,
+    UNIQUE (account_id, name)
+);
+
+CREATE TABLE task_tag(
+    task_id INTEGER NOT NULL REFERENCES task(task_id) ON DELETE CASCADE,
+    tag_id INTEGER NOT NULL REFERENCES tag(tag_id) ON DELETE CASCADE,
+    PRIMARY KEY (task_id, tag_id)
+);
+
+CREATE INDEX task_tag_tag_id ON task_tag(tag_id);
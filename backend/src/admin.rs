@@ -0,0 +1,324 @@
+use clap::Subcommand;
+use std::io::BufRead;
+
+use crate::storage::{Account, Storage, StorageError};
+use crate::utils;
+
+// `rical-backend admin ...`, for operators to manage an instance without touching the database
+
+#[derive(Subcommand, Debug)]
+pub enum AdminCommand {
+    /// Create an account, reading its password from the first line of stdin
+    CreateUser { username: String },
+    /// Set a new password from the first line of stdin, and sign the account out everywhere
+    ResetPassword { username: String },
+    /// Stop an account from logging in or using any of its tokens
+    Lock { username: String },
+    /// Let a locked account back in
+    Unlock { username: String },
+    /// List all accounts with how many tasks each has
+    ListAccounts,
+    /// Delete an account along with all of its tasks and tokens
+    DeleteAccount {
+        username: String,
+        /// Confirm the deletion, which can't be undone
+        #[arg(long)]
+        yes: bool,
+    },
+    /// Revoke an account's API tokens and sign it out everywhere
+    RevokeTokens {
+        username: String,
+        /// Only revoke this API token, leaving the rest (and login sessions) alone
+        #[arg(long)]
+        token_id: Option<i64>,
+    },
+    /// Apply any database migrations that haven't been applied yet
+    Migrate,
+    /// Delete expired API tokens and give freed space back to the filesystem
+    Vacuum,
+}
+
+/// Run an admin command and return what to print
+pub async fn run(
+    storage: &dyn Storage,
+    command: AdminCommand,
+    input: &mut dyn BufRead,
+) -> Result<String, String> {
+    match command {
+        AdminCommand::CreateUser { username } => {
+            let password = read_password(input)?;
+            match storage
+                .create_account(&username, &utils::hash_password(&password))
+                .await
+            {
+                Ok(account_id) => Ok(format!(
+                    "Created account '{}' (ID {})",
+                    username, account_id
+                )),
+                Err(StorageError::Conflict) => {
+                    Err(format!("The username '{}' is already taken", username))
+                }
+                Err(err) => Err(err.to_string()),
+            }
+        }
+        AdminCommand::ResetPassword { username } => {
+            let account = find_account(storage, &username).await?;
+            let password = read_password(input)?;
+            storage
+                .set_password(account.account_id, &utils::hash_password(&password))
+                .await
+                .map_err(|err| err.to_string())?;
+            storage
+                .revoke_sessions(account.account_id, utils::now_unix())
+                .await
+                .map_err(|err| err.to_string())?;
+            Ok(format!(
+                "Reset the password of '{}' and signed it out everywhere",
+                username
+            ))
+        }
+        AdminCommand::Lock { username } => {
+            let account = find_account(storage, &username).await?;
+            storage
+                .set_locked(account.account_id, true)
+                .await
+                .map_err(|err| err.to_string())?;
+            Ok(format!("Locked '{}'", username))
+        }
+        AdminCommand::Unlock { username } => {
+            let account = find_account(storage, &username).await?;
+            storage
+                .set_locked(account.account_id, false)
+                .await
+                .map_err(|err| err.to_string())?;
+            Ok(format!("Unlocked '{}'", username))
+        }
+        AdminCommand::ListAccounts => {
+            let accounts = storage
+                .list_accounts()
+                .await
+                .map_err(|err| err.to_string())?;
+            let mut lines = vec![format!(
+                "{:>8}  {:>6}  {:<6}  USERNAME",
+                "ID", "TASKS", "LOCKED"
+            )];
+            for account in accounts {
+                lines.push(format!(
+                    "{:>8}  {:>6}  {:<6}  {}",
+                    account.account_id,
+                    account.task_count,
+                    if account.locked { "yes" } else { "no" },
+                    account.username
+                ));
+            }
+            Ok(lines.join("\n"))
+        }
+        AdminCommand::DeleteAccount { username, yes } => {
+            let account = find_account(storage, &username).await?;
+            if !yes {
+                return Err(format!(
+                    "This deletes '{}' and all of its tasks for good. Pass --yes to go ahead",
+                    username
+                ));
+            }
+            storage
+                .delete_account(account.account_id)
+                .await
+                .map_err(|err| err.to_string())?;
+            Ok(format!("Deleted '{}'", username))
+        }
+        AdminCommand::RevokeTokens { username, token_id } => {
+            let account = find_account(storage, &username).await?;
+            if let Some(token_id) = token_id {
+                let revoked = storage
+                    .revoke_api_token(account.account_id, token_id)
+                    .await
+                    .map_err(|err| err.to_string())?;
+                return if revoked {
+                    Ok(format!("Revoked token {} of '{}'", token_id, username))
+                } else {
+                    Err(format!("'{}' has no token with ID {}", username, token_id))
+                };
+            }
+            let count = storage
+                .revoke_all_api_tokens(account.account_id)
+                .await
+                .map_err(|err| err.to_string())?;
+            storage
+                .revoke_sessions(account.account_id, utils::now_unix())
+                .await
+                .map_err(|err| err.to_string())?;
+            Ok(format!(
+                "Revoked {} API token(s) of '{}' and signed it out everywhere",
+                count, username
+            ))
+        }
+        // Connecting to the storage already applies the migrations
+        AdminCommand::Migrate => Ok("The database schema is up to date".to_string()),
+        AdminCommand::Vacuum => {
+            let count = storage
+                .purge_expired_api_tokens(utils::now_unix())
+                .await
+                .map_err(|err| err.to_string())?;
+            storage.compact().await.map_err(|err| err.to_string())?;
+            Ok(format!(
+                "Deleted {} expired API token(s) and compacted the database",
+                count
+            ))
+        }
+    }
+}
+
+async fn find_account(storage: &dyn Storage, username: &str) -> Result<Account, String> {
+    storage
+        .find_account(username)
+        .await
+        .map_err(|err| err.to_string())?
+        .ok_or_else(|| format!("No account has the username '{}'", username))
+}
+
+/// Read a password from the first line of the input, so that it stays out of the shell history
+fn read_password(input: &mut dyn BufRead) -> Result<String, String> {
+    let mut line = String::new();
+    input
+        .read_line(&mut line)
+        .map_err(|err| format!("Couldn't read the password: {}", err))?;
+    let password = line.trim_end_matches(['\r', '\n']);
+    if password.is_empty() {
+        return Err("The password can't be empty".to_string());
+    }
+    Ok(password.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::routes::testing;
+    use crate::storage::testing::{test_storages, unique};
+
+    async fn run_with_input(
+        storage: &dyn Storage,
+        command: AdminCommand,
+        input: &str,
+    ) -> Result<String, String> {
+        run(storage, command, &mut input.as_bytes()).await
+    }
+
+    async fn run_ok(storage: &dyn Storage, command: AdminCommand) -> String {
+        run_with_input(storage, command, "").await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_create_user_and_reset_password() {
+        for (name, storage) in test_storages().await {
+            let storage = storage.as_ref();
+            let alice = unique("alice");
+            let create = || AdminCommand::CreateUser {
+                username: alice.clone(),
+            };
+            run_with_input(storage, create(), "first\n").await.unwrap();
+            assert!(
+                run_with_input(storage, create(), "again\n").await.is_err(),
+                "{}",
+                name
+            );
+            assert!(
+                run_with_input(
+                    storage,
+                    AdminCommand::CreateUser {
+                        username: unique("bob")
+                    },
+                    "\n"
+                )
+                .await
+                .is_err(),
+                "{}",
+                name
+            );
+
+            run_with_input(
+                storage,
+                AdminCommand::ResetPassword {
+                    username: alice.clone(),
+                },
+                "second\n",
+            )
+            .await
+            .unwrap();
+            let account = storage.find_account(&alice).await.unwrap().unwrap();
+            assert!(
+                utils::verify_password("second", &account.hashed_password),
+                "{}",
+                name
+            );
+            assert!(account.sessions_revoked_at.is_some(), "{}", name);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_lock_and_list_accounts() {
+        for (name, storage) in test_storages().await {
+            let storage = storage.as_ref();
+            let (alice, bob) = (unique("alice"), unique("bob"));
+            let alice_id = storage.create_account(&alice, "hash").await.unwrap();
+            storage.create_account(&bob, "hash").await.unwrap();
+            storage
+                .create_task(alice_id, &testing::task(1, "Task"))
+                .await
+                .unwrap();
+
+            run_ok(
+                storage,
+                AdminCommand::Lock {
+                    username: bob.clone(),
+                },
+            )
+            .await;
+            let listing = run_ok(storage, AdminCommand::ListAccounts).await;
+            // The database can have other tests' accounts too
+            let rows: Vec<Vec<&str>> = listing
+                .lines()
+                .skip(1)
+                .map(|line| line.split_whitespace().collect())
+                .filter(|row: &Vec<&str>| row[3] == alice || row[3] == bob)
+                .collect();
+            assert_eq!(rows[0][1..], ["1", "no", alice.as_str()], "{}", name);
+            assert_eq!(rows[1][1..], ["0", "yes", bob.as_str()], "{}", name);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_delete_account_needs_confirmation() {
+        for (name, storage) in test_storages().await {
+            let storage = storage.as_ref();
+            let alice = unique("alice");
+            storage.create_account(&alice, "hash").await.unwrap();
+            let delete = |yes| AdminCommand::DeleteAccount {
+                username: alice.clone(),
+                yes,
+            };
+
+            assert!(
+                run_with_input(storage, delete(false), "").await.is_err(),
+                "{}",
+                name
+            );
+            assert!(
+                storage.find_account(&alice).await.unwrap().is_some(),
+                "{}",
+                name
+            );
+            run_ok(storage, delete(true)).await;
+            assert!(
+                storage.find_account(&alice).await.unwrap().is_none(),
+                "{}",
+                name
+            );
+            assert!(
+                run_with_input(storage, delete(true), "").await.is_err(),
+                "{}",
+                name
+            );
+        }
+    }
+}
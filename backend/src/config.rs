@@ -1,12 +1,608 @@
-use std::collections::HashMap;
-use std::env;
+use axum::http::{HeaderValue, Method};
+use clap::{Args, ValueEnum};
+use serde::Deserialize;
+use std::fmt;
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use tracing_subscriber::EnvFilter;
 
-pub fn get_config() -> HashMap<String, String> {
-    let required: Vec<&str> = vec!["DATABASE_URL", "JWT_SECRET", "PORT"];
-    let mut res: HashMap<String, String> = HashMap::new();
-    for key in required {
-        let val = env::var(key).unwrap_or_else(|_| panic!("{} must be set", key));
-        res.insert(key.to_string(), val);
+use crate::storage::PoolConfig;
+
+// The backend's settings, loaded once at startup from (each overriding the last):
+// a TOML file, environment variables, and command-line flags
+
+/// Everything that can be configured
+/// Each section matches a table of the TOML file, e.g. `[database]`
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub server: ServerConfig,
+    pub database: DatabaseConfig,
+    pub auth: AuthConfig,
+    pub cors: CorsConfig,
+    pub web: WebConfig,
+    pub tls: TlsConfig,
+    pub rate_limit: RateLimitConfig,
+    pub logging: LogConfig,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    /// `BIND_ADDRESS`, the IP address to listen on
+    pub bind: String,
+    /// `PORT`
+    pub port: u16,
+    /// `SHUTDOWN_TIMEOUT_SECS`, how long running requests get to finish on shutdown
+    pub shutdown_timeout_secs: u64,
+}
+
+impl Default for ServerConfig {
+    fn default() -> ServerConfig {
+        ServerConfig {
+            bind: "0.0.0.0".to_string(),
+            port: 3001,
+            shutdown_timeout_secs: 30,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DatabaseConfig {
+    /// `DATABASE_URL`, a Postgres URL or `sqlite://` file (required)
+    pub url: String,
+    /// `DB_MAX_CONNECTIONS`
+    pub max_connections: u32,
+    /// `DB_ACQUIRE_TIMEOUT_SECS`, how long a request waits for a free connection
+    pub acquire_timeout_secs: u64,
+    /// `DB_CONNECT_RETRIES`, how many times to retry at startup if the database isn't up yet
+    pub connect_retries: u32,
+}
+
+impl Default for DatabaseConfig {
+    fn default() -> DatabaseConfig {
+        DatabaseConfig {
+            url: String::new(),
+            max_connections: 10,
+            acquire_timeout_secs: 30,
+            connect_retries: 10,
+        }
+    }
+}
+
+impl DatabaseConfig {
+    pub fn pool(&self) -> PoolConfig {
+        PoolConfig {
+            max_connections: self.max_connections,
+            acquire_timeout: std::time::Duration::from_secs(self.acquire_timeout_secs),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AuthConfig {
+    /// `JWT_SECRET`, what login tokens are signed with (required)
+    pub jwt_secret: String,
+    /// `LOGIN_TOKEN_LIFETIME_SECS`, or None for login tokens that never expire
+    pub login_token_lifetime_secs: Option<u64>,
+    /// `API_TOKEN_MAX_LIFETIME_DAYS`, or None to allow API tokens that never expire
+    pub api_token_max_lifetime_days: Option<i64>,
+}
+
+/// Which other origins (e.g. a separately hosted web client) browsers may call the API from
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CorsConfig {
+    /// `CORS_ALLOWED_ORIGINS`, full origins like `https://rical.example.com`, or just `*` for any
+    /// Empty allows no cross-origin calls at all
+    pub allowed_origins: Vec<String>,
+    /// `CORS_ALLOW_CREDENTIALS`, whether browsers may send cookies and other credentials
+    /// (can't be used with `*`)
+    pub allow_credentials: bool,
+    /// `CORS_ALLOWED_METHODS`
+    pub allowed_methods: Vec<String>,
+}
+
+impl Default for CorsConfig {
+    fn default() -> CorsConfig {
+        CorsConfig {
+            allowed_origins: Vec::new(),
+            allow_credentials: false,
+            allowed_methods: ["GET", "POST", "PUT", "DELETE"].map(String::from).to_vec(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WebConfig {
+    /// `STATIC_DIR`, a built web client to host at `/`
+    pub static_dir: Option<PathBuf>,
+}
+
+/// Serving HTTPS directly, which is off unless both paths are set
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TlsConfig {
+    /// `TLS_CERT_PATH`, the certificate chain as a PEM file (reloaded when it changes)
+    pub cert_path: Option<PathBuf>,
+    /// `TLS_KEY_PATH`, the private key as a PEM file
+    pub key_path: Option<PathBuf>,
+    /// `TLS_REDIRECT_HTTP_PORT`, a port to redirect plain HTTP from (e.g. 80)
+    pub redirect_http_port: Option<u16>,
+}
+
+impl TlsConfig {
+    /// The certificate and key paths, if HTTPS is on
+    pub fn paths(&self) -> Option<(&Path, &Path)> {
+        Some((self.cert_path.as_deref()?, self.key_path.as_deref()?))
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RateLimitConfig {
+    /// `LOGIN_ATTEMPTS_PER_MINUTE`, per username, or 0 for no limit
+    pub login_attempts_per_minute: u32,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> RateLimitConfig {
+        RateLimitConfig {
+            login_attempts_per_minute: 10,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LogConfig {
+    /// `RUST_LOG`, e.g. `debug` or `rical_backend=debug,tower_http=warn`
+    pub level: Option<String>,
+    /// `LOG_FORMAT`
+    pub format: LogFormat,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// Human-readable lines
+    #[default]
+    Text,
+    /// One JSON object per line, for log collectors
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<LogFormat, ()> {
+        match s.to_ascii_lowercase().as_str() {
+            "text" => Ok(LogFormat::Text),
+            "json" => Ok(LogFormat::Json),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Flags that override the file and environment, e.g. `rical-backend serve --port 8080`
+#[derive(Args, Debug, Default)]
+pub struct ServeArgs {
+    /// The IP address to listen on
+    #[arg(long)]
+    pub bind: Option<String>,
+    #[arg(long)]
+    pub port: Option<u16>,
+    #[arg(long)]
+    pub database_url: Option<String>,
+    #[arg(long, value_enum)]
+    pub log_format: Option<LogFormat>,
+}
+
+/// Everything that's wrong with a configuration, so that it can all be fixed in one go
+#[derive(Debug)]
+pub struct ConfigError(pub Vec<String>);
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid configuration:")?;
+        for problem in &self.0 {
+            write!(f, "\n  - {}", problem)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl Config {
+    /// Read the TOML file at `path` (if any), then apply the environment variables
+    pub fn load(path: Option<&Path>) -> Result<Config, ConfigError> {
+        let mut config = match path {
+            Some(path) => {
+                let text = std::fs::read_to_string(path).map_err(|err| {
+                    ConfigError(vec![format!("Couldn't read {}: {}", path.display(), err)])
+                })?;
+                toml::from_str(&text).map_err(|err| {
+                    ConfigError(vec![format!("Couldn't parse {}: {}", path.display(), err)])
+                })?
+            }
+            None => Config::default(),
+        };
+        config.apply_env(&|key| std::env::var(key).ok())?;
+        Ok(config)
+    }
+
+    /// Override settings with whichever variables `get_var` has set (and not empty)
+    fn apply_env(&mut self, get_var: &dyn Fn(&str) -> Option<String>) -> Result<(), ConfigError> {
+        let mut env = EnvLayer {
+            get_var,
+            problems: Vec::new(),
+        };
+        env.string("BIND_ADDRESS", &mut self.server.bind);
+        env.parse("PORT", &mut self.server.port);
+        env.parse(
+            "SHUTDOWN_TIMEOUT_SECS",
+            &mut self.server.shutdown_timeout_secs,
+        );
+        env.string("DATABASE_URL", &mut self.database.url);
+        env.parse("DB_MAX_CONNECTIONS", &mut self.database.max_connections);
+        env.parse(
+            "DB_ACQUIRE_TIMEOUT_SECS",
+            &mut self.database.acquire_timeout_secs,
+        );
+        env.parse("DB_CONNECT_RETRIES", &mut self.database.connect_retries);
+        env.string("JWT_SECRET", &mut self.auth.jwt_secret);
+        env.parse_optional(
+            "LOGIN_TOKEN_LIFETIME_SECS",
+            &mut self.auth.login_token_lifetime_secs,
+        );
+        env.parse_optional(
+            "API_TOKEN_MAX_LIFETIME_DAYS",
+            &mut self.auth.api_token_max_lifetime_days,
+        );
+        env.list("CORS_ALLOWED_ORIGINS", &mut self.cors.allowed_origins);
+        env.parse("CORS_ALLOW_CREDENTIALS", &mut self.cors.allow_credentials);
+        env.list("CORS_ALLOWED_METHODS", &mut self.cors.allowed_methods);
+        if let Some(dir) = env.get("STATIC_DIR") {
+            self.web.static_dir = Some(PathBuf::from(dir));
+        }
+        env.parse_optional("TLS_CERT_PATH", &mut self.tls.cert_path);
+        env.parse_optional("TLS_KEY_PATH", &mut self.tls.key_path);
+        env.parse_optional("TLS_REDIRECT_HTTP_PORT", &mut self.tls.redirect_http_port);
+        env.parse(
+            "LOGIN_ATTEMPTS_PER_MINUTE",
+            &mut self.rate_limit.login_attempts_per_minute,
+        );
+        env.parse_optional("RUST_LOG", &mut self.logging.level);
+        env.parse("LOG_FORMAT", &mut self.logging.format);
+        if env.problems.is_empty() {
+            Ok(())
+        } else {
+            Err(ConfigError(env.problems))
+        }
+    }
+
+    /// Override settings with the flags that were passed
+    pub fn apply_args(&mut self, args: &ServeArgs) {
+        if let Some(bind) = &args.bind {
+            self.server.bind = bind.clone();
+        }
+        if let Some(port) = args.port {
+            self.server.port = port;
+        }
+        if let Some(url) = &args.database_url {
+            self.database.url = url.clone();
+        }
+        if let Some(format) = args.log_format {
+            self.logging.format = format;
+        }
+    }
+
+    /// Check everything that can be checked before starting the server
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let mut problems = self.database_problems();
+        if self.server.bind.parse::<IpAddr>().is_err() {
+            problems.push(format!(
+                "server.bind (BIND_ADDRESS) '{}' isn't an IP address",
+                self.server.bind
+            ));
+        }
+        if self.auth.jwt_secret.is_empty() {
+            problems.push("auth.jwt_secret (JWT_SECRET) must be set".to_string());
+        }
+        if self.auth.login_token_lifetime_secs == Some(0) {
+            problems.push(
+                "auth.login_token_lifetime_secs (LOGIN_TOKEN_LIFETIME_SECS) must be positive"
+                    .to_string(),
+            );
+        }
+        if self
+            .auth
+            .api_token_max_lifetime_days
+            .is_some_and(|days| days <= 0)
+        {
+            problems.push(
+                "auth.api_token_max_lifetime_days (API_TOKEN_MAX_LIFETIME_DAYS) must be positive"
+                    .to_string(),
+            );
+        }
+        for origin in &self.cors.allowed_origins {
+            if origin != "*" && HeaderValue::from_str(origin).is_err() {
+                problems.push(format!(
+                    "cors.allowed_origins (CORS_ALLOWED_ORIGINS) '{}' isn't a valid origin",
+                    origin
+                ));
+            }
+        }
+        if self.cors.allow_credentials && self.cors.allowed_origins.iter().any(|o| o == "*") {
+            problems.push(
+                "cors.allow_credentials (CORS_ALLOW_CREDENTIALS) can't be used with any origin (*), list the origins instead"
+                    .to_string(),
+            );
+        }
+        for method in &self.cors.allowed_methods {
+            if Method::from_bytes(method.to_ascii_uppercase().as_bytes()).is_err() {
+                problems.push(format!(
+                    "cors.allowed_methods (CORS_ALLOWED_METHODS) '{}' isn't an HTTP method",
+                    method
+                ));
+            }
+        }
+        if let Some(dir) = &self.web.static_dir
+            && !dir.is_dir()
+        {
+            problems.push(format!(
+                "web.static_dir (STATIC_DIR) '{}' isn't a directory",
+                dir.display()
+            ));
+        }
+        if self.tls.cert_path.is_some() != self.tls.key_path.is_some() {
+            problems.push(
+                "tls.cert_path (TLS_CERT_PATH) and tls.key_path (TLS_KEY_PATH) must be set together"
+                    .to_string(),
+            );
+        }
+        for (name, path) in [
+            ("tls.cert_path (TLS_CERT_PATH)", &self.tls.cert_path),
+            ("tls.key_path (TLS_KEY_PATH)", &self.tls.key_path),
+        ] {
+            if let Some(path) = path
+                && !path.is_file()
+            {
+                problems.push(format!("{} '{}' isn't a file", name, path.display()));
+            }
+        }
+        if let Some(port) = self.tls.redirect_http_port {
+            if self.tls.paths().is_none() {
+                problems.push(
+                    "tls.redirect_http_port (TLS_REDIRECT_HTTP_PORT) needs HTTPS to redirect to"
+                        .to_string(),
+                );
+            } else if port == self.server.port {
+                problems.push(
+                    "tls.redirect_http_port (TLS_REDIRECT_HTTP_PORT) must differ from server.port"
+                        .to_string(),
+                );
+            }
+        }
+        if let Some(level) = &self.logging.level
+            && EnvFilter::try_new(level).is_err()
+        {
+            problems.push(format!(
+                "logging.level (RUST_LOG) '{}' isn't a valid log filter",
+                level
+            ));
+        }
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(ConfigError(problems))
+        }
+    }
+
+    /// Check only what's needed to reach the database, for commands that don't serve
+    pub fn validate_database(&self) -> Result<(), ConfigError> {
+        let problems = self.database_problems();
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(ConfigError(problems))
+        }
+    }
+
+    fn database_problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if self.database.url.is_empty() {
+            problems.push("database.url (DATABASE_URL) must be set".to_string());
+        }
+        if self.database.max_connections == 0 {
+            problems.push(
+                "database.max_connections (DB_MAX_CONNECTIONS) must be at least 1".to_string(),
+            );
+        }
+        problems
+    }
+}
+
+/// Reads environment variables into the config, collecting what can't be parsed
+struct EnvLayer<'a> {
+    get_var: &'a dyn Fn(&str) -> Option<String>,
+    problems: Vec<String>,
+}
+
+impl EnvLayer<'_> {
+    /// A variable that's set and not empty
+    fn get(&self, key: &str) -> Option<String> {
+        (self.get_var)(key).filter(|val| !val.trim().is_empty())
+    }
+
+    fn string(&self, key: &str, target: &mut String) {
+        if let Some(val) = self.get(key) {
+            *target = val;
+        }
+    }
+
+    fn parse<T: FromStr>(&mut self, key: &str, target: &mut T) {
+        let mut parsed = None;
+        self.parse_optional(key, &mut parsed);
+        if let Some(parsed) = parsed {
+            *target = parsed;
+        }
+    }
+
+    fn parse_optional<T: FromStr>(&mut self, key: &str, target: &mut Option<T>) {
+        if let Some(val) = self.get(key) {
+            match val.trim().parse() {
+                Ok(parsed) => *target = Some(parsed),
+                Err(_) => self
+                    .problems
+                    .push(format!("{} has an invalid value '{}'", key, val)),
+            }
+        }
+    }
+
+    /// A comma-separated list
+    fn list(&self, key: &str, target: &mut Vec<String>) {
+        if let Some(val) = self.get(key) {
+            *target = val
+                .split(',')
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .map(str::to_string)
+                .collect();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn with_env(config: &mut Config, vars: &[(&str, &str)]) -> Result<(), ConfigError> {
+        let vars: HashMap<String, String> = vars
+            .iter()
+            .map(|(key, val)| (key.to_string(), val.to_string()))
+            .collect();
+        config.apply_env(&|key| vars.get(key).cloned())
+    }
+
+    #[test]
+    fn test_layers_override_each_other() {
+        let mut config: Config = toml::from_str(
+            r#"
+            [server]
+            port = 4000
+            bind = "127.0.0.1"
+
+            [database]
+            url = "sqlite://file.db"
+            max_connections = 5
+
+            [auth]
+            jwt_secret = "from the file"
+
+            [cors]
+            allowed_origins = ["https://rical.example.com"]
+            "#,
+        )
+        .unwrap();
+        // Whatever the file leaves out keeps its default
+        assert_eq!(config.database.acquire_timeout_secs, 30);
+
+        with_env(
+            &mut config,
+            &[
+                ("PORT", "5000"),
+                ("JWT_SECRET", "from the env"),
+                (
+                    "CORS_ALLOWED_ORIGINS",
+                    "https://a.example.com, https://b.example.com",
+                ),
+                ("LOG_FORMAT", "JSON"),
+                ("DB_MAX_CONNECTIONS", ""),
+            ],
+        )
+        .unwrap();
+        config.apply_args(&ServeArgs {
+            port: Some(6000),
+            ..ServeArgs::default()
+        });
+
+        assert_eq!(config.server.port, 6000);
+        assert_eq!(config.server.bind, "127.0.0.1");
+        assert_eq!(config.database.url, "sqlite://file.db");
+        assert_eq!(config.database.max_connections, 5);
+        assert_eq!(config.auth.jwt_secret, "from the env");
+        assert_eq!(
+            config.cors.allowed_origins,
+            ["https://a.example.com", "https://b.example.com"]
+        );
+        assert_eq!(config.logging.format, LogFormat::Json);
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_tls_settings_go_together() {
+        let mut config = Config::default();
+        config.database.url = "sqlite::memory:".to_string();
+        config.auth.jwt_secret = "secret".to_string();
+        config.tls.cert_path = Some(PathBuf::from(file!()));
+        config.tls.redirect_http_port = Some(80);
+        let problems = config.validate().unwrap_err().0;
+        assert_eq!(problems.len(), 2, "{:?}", problems);
+        assert!(problems[0].starts_with("tls.cert_path"));
+        assert!(problems[1].starts_with("tls.redirect_http_port"));
+
+        config.tls.key_path = Some(PathBuf::from(file!()));
+        assert!(config.validate().is_ok());
+        config.tls.redirect_http_port = Some(config.server.port);
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_example_file_is_valid() {
+        let config: Config = toml::from_str(include_str!("../rical.example.toml")).unwrap();
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_reports_every_problem() {
+        assert!(toml::from_str::<Config>("[server]\nprot = 80").is_err());
+
+        let mut config = Config::default();
+        let err = with_env(
+            &mut config,
+            &[("PORT", "eighty"), ("DB_CONNECT_RETRIES", "-1")],
+        )
+        .unwrap_err();
+        assert_eq!(err.0.len(), 2);
+
+        config.server.bind = "localhost".to_string();
+        config.cors.allowed_origins = vec!["*".to_string()];
+        config.cors.allow_credentials = true;
+        config.cors.allowed_methods = vec!["GET".to_string(), "GE T".to_string()];
+        config.auth.login_token_lifetime_secs = Some(0);
+        let problems = config.validate().unwrap_err().0;
+        for expected in [
+            "server.bind",
+            "database.url",
+            "auth.jwt_secret",
+            "auth.login_token_lifetime_secs",
+            "cors.allow_credentials",
+            "cors.allowed_methods",
+        ] {
+            assert!(
+                problems.iter().any(|problem| problem.starts_with(expected)),
+                "no problem with {} in {:?}",
+                expected,
+                problems
+            );
+        }
+        assert_eq!(problems.len(), 6);
     }
-    res
 }
@@ -0,0 +1,110 @@
+use axum::{
+    Router,
+    body::Body,
+    extract::MatchedPath,
+    http::{HeaderName, Request, Response},
+};
+use std::time::Duration;
+use tower_http::request_id::{MakeRequestUuid, PropagateRequestIdLayer, SetRequestIdLayer};
+use tower_http::trace::TraceLayer;
+use tracing::{Span, field};
+use tracing_subscriber::EnvFilter;
+
+use crate::config::{LogConfig, LogFormat};
+
+// Structured logs, with a span for each request
+
+/// The header that carries each request's ID, both ways
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Start logging to stdout at the configured level, as text or JSON
+pub fn init(config: &LogConfig) {
+    let filter = EnvFilter::new(
+        config
+            .level
+            .as_deref()
+            .unwrap_or("rical_backend=info,tower_http=warn"),
+    );
+    let builder = tracing_subscriber::fmt().with_env_filter(filter);
+    if config.format == LogFormat::Json {
+        builder.json().init();
+    } else {
+        builder.init();
+    }
+}
+
+/// Give each request an ID (unless the client sent one) and a span that's logged when it finishes
+pub fn with_request_logs(router: Router) -> Router {
+    let request_id_header = HeaderName::from_static(REQUEST_ID_HEADER);
+    let trace = TraceLayer::new_for_http()
+        .make_span_with(|req: &Request<Body>| {
+            // Log the route's pattern rather than the actual path, so that it's easy to group by
+            let route = req
+                .extensions()
+                .get::<MatchedPath>()
+                .map_or(req.uri().path(), MatchedPath::as_str);
+            let request_id = req
+                .headers()
+                .get(REQUEST_ID_HEADER)
+                .and_then(|id| id.to_str().ok())
+                .unwrap_or_default();
+            tracing::info_span!(
+                "request",
+                method = %req.method(),
+                route,
+                request_id,
+                // Filled in once they're known
+                account_id = field::Empty,
+                status = field::Empty,
+                latency_ms = field::Empty,
+            )
+        })
+        .on_request(())
+        .on_response(|res: &Response<Body>, latency: Duration, span: &Span| {
+            span.record("status", res.status().as_u16());
+            span.record("latency_ms", latency.as_millis() as u64);
+            tracing::info!("finished request");
+        })
+        .on_failure(());
+    router
+        .layer(PropagateRequestIdLayer::new(request_id_header.clone()))
+        .layer(trace)
+        .layer(SetRequestIdLayer::new(request_id_header, MakeRequestUuid))
+}
+
+/// Add the authenticated account to the request's span
+pub fn record_account_id(account_id: i64) {
+    Span::current().record("account_id", account_id);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::routing::get;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_request_ids() {
+        let router = with_request_logs(Router::new().route("/", get(|| async { "ok" })));
+
+        let res = router
+            .clone()
+            .oneshot(Request::get("/").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let generated = res.headers()[REQUEST_ID_HEADER].to_str().unwrap();
+        assert_eq!(generated.len(), 36);
+
+        // A client's own ID is kept, so that logs can be matched up across services
+        let res = router
+            .oneshot(
+                Request::get("/")
+                    .header(REQUEST_ID_HEADER, "client-id")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(res.headers()[REQUEST_ID_HEADER], "client-id");
+    }
+}
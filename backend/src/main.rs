@@ -1,16 +1,100 @@
-use axum::Router;
+use axum::{Router, middleware};
+use clap::{Parser, Subcommand};
+use std::net::{IpAddr, SocketAddr};
+use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Duration;
 
-use sqlx::postgres::PgPoolOptions;
+use crate::config::{Config, ServeArgs};
+use crate::rate_limit::RateLimiter;
+use crate::storage::Storage;
 
+mod admin;
 mod config;
+mod logging;
+mod metrics;
+mod openapi;
+mod rate_limit;
+mod rollover;
 mod routes;
-mod types;
+mod shutdown;
+mod storage;
+mod tls;
 mod utils;
+mod web;
+
+/// The version of this build, as reported at startup and by `/version`
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
 
 #[derive(Clone)]
 pub struct AppState {
-    pub db_pool: sqlx::PgPool,
+    pub storage: Arc<dyn Storage>,
+    pub config: Arc<Config>,
+    pub metrics: metrics::Metrics,
+    /// Login attempts per username
+    pub login_limiter: Arc<RateLimiter>,
+}
+
+impl AppState {
+    pub fn new(storage: Arc<dyn Storage>, config: Config) -> AppState {
+        AppState {
+            storage,
+            login_limiter: Arc::new(RateLimiter::per_minute(
+                config.rate_limit.login_attempts_per_minute,
+            )),
+            config: Arc::new(config),
+            metrics: metrics::Metrics::new(),
+        }
+    }
+}
+
+/// Set up the Axum app with all of the routes
+pub fn app(state: AppState) -> Router {
+    let state = Arc::new(state);
+    Router::new()
+        .nest("/account", routes::account::get_routes(&state))
+        .nest("/task", routes::task::get_routes(&state))
+        .nest(
+            "/task/{id}/checklist",
+            routes::checklist::get_routes(&state),
+        )
+        .nest(
+            "/task/{id}/dependencies",
+            routes::dependency::get_routes(&state),
+        )
+        .nest("/calendar", routes::calendar::get_routes(&state))
+        .nest("/tasks", routes::agenda::get_routes(&state))
+        .nest("/tasks/batch", routes::batch::get_routes(&state))
+        .nest("/tasks/reschedule", routes::reschedule::get_routes(&state))
+        .nest("/search", routes::search::get_routes(&state))
+        .nest("/tags", routes::tag::get_routes(&state))
+        .nest("/projects", routes::project::get_routes(&state))
+        .nest("/tracking", routes::tracking::get_routes(&state))
+        .merge(routes::health::get_routes(&state))
+        .merge(openapi::get_routes())
+        .layer(middleware::from_fn_with_state(
+            state.metrics.clone(),
+            metrics::track_requests,
+        ))
+}
+
+#[derive(Parser, Debug)]
+#[command(version, about = "The Rical backend")]
+struct Cli {
+    /// A TOML config file, whose settings environment variables and flags override
+    #[arg(long, global = true, env = "RICAL_CONFIG")]
+    config: Option<PathBuf>,
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Run the HTTP server (the default)
+    Serve(ServeArgs),
+    /// Manage the instance's accounts and database
+    #[command(subcommand)]
+    Admin(admin::AdminCommand),
 }
 
 #[tokio::main]
@@ -18,33 +102,106 @@ async fn main() {
     // Initialize env variables
     dotenvy::dotenv().ok();
 
-    let db_url = &config::get_config()["DATABASE_URL"];
-    let port = &config::get_config()["PORT"];
+    let cli = Cli::parse();
+    let mut config = Config::load(cli.config.as_deref()).unwrap_or_else(|err| exit_with(err));
 
-    println!("Connecting to db...");
-    let pool = PgPoolOptions::new()
-        .max_connections(10)
-        .connect(db_url)
-        .await
-        .expect("Couldn't connect to the database");
-    println!("Connected to db");
+    match cli.command {
+        None => serve(config).await,
+        Some(Command::Serve(args)) => {
+            config.apply_args(&args);
+            serve(config).await
+        }
+        Some(Command::Admin(command)) => {
+            config
+                .validate_database()
+                .unwrap_or_else(|err| exit_with(err));
+            let storage = storage::connect(&config.database.url, &config.database.pool())
+                .await
+                .unwrap_or_else(|err| exit_with(format!("Couldn't set up the database: {}", err)));
+            match admin::run(storage.as_ref(), command, &mut std::io::stdin().lock()).await {
+                Ok(output) => println!("{}", output),
+                Err(err) => exit_with(err),
+            }
+        }
+    }
+}
 
-    let state = Arc::new(AppState { db_pool: pool });
+fn exit_with(err: impl std::fmt::Display) -> ! {
+    eprintln!("Error: {}", err);
+    std::process::exit(1);
+}
 
-    // Set up the Axum app
-    let app = Router::new()
-        .nest("/account", routes::account::get_routes(&state))
-        .nest("/task", routes::task::get_routes(&state))
-        .nest("/calendar", routes::calendar::get_routes(&state));
+async fn serve(config: Config) {
+    config.validate().unwrap_or_else(|err| exit_with(err));
+    logging::init(&config.logging);
 
-    let addr = format!("0.0.0.0:{}", port);
-    println!(
-        "Rical backend v{} is listening on {}",
-        option_env!("CARGO_PKG_VERSION").unwrap_or("?"),
-        addr
+    tracing::info!("Connecting to db...");
+    let storage = storage::connect_with_retries(
+        &config.database.url,
+        &config.database.pool(),
+        config.database.connect_retries,
+    )
+    .await
+    .expect("Couldn't set up the database");
+    tracing::info!("Connected to db");
+
+    rollover::spawn(storage.clone());
+
+    let cors = config.cors.clone();
+    let static_files = web::StaticFiles::from_config(&config.web);
+    let tls_config = config.tls.clone();
+    let ip: IpAddr = config
+        .server
+        .bind
+        .parse()
+        .expect("Validated with the config");
+    let addr = SocketAddr::new(ip, config.server.port);
+    let handle =
+        shutdown::handle_on_signal(Duration::from_secs(config.server.shutdown_timeout_secs));
+
+    let app = web::with_web(
+        app(AppState::new(storage.clone(), config)),
+        &cors,
+        static_files.as_ref(),
     );
+    let app = logging::with_request_logs(app).into_make_service();
 
     // Run with hyper
-    let listener = tokio::net::TcpListener::bind(&addr).await.unwrap();
-    axum::serve(listener, app).await.unwrap();
+    let res = match tls_config.paths() {
+        Some((cert_path, key_path)) => {
+            let rustls = tls::load(cert_path, key_path).await.unwrap_or_else(|err| {
+                exit_with(format!("Couldn't load the TLS certificate: {}", err))
+            });
+            tls::reload_on_change(rustls.clone(), &tls_config);
+            if let Some(http_port) = tls_config.redirect_http_port {
+                let http_addr = SocketAddr::new(ip, http_port);
+                tracing::info!("Redirecting plain HTTP from {} to HTTPS", http_addr);
+                let redirect = axum_server::bind(http_addr)
+                    .handle(handle.clone())
+                    .serve(tls::redirect_app(addr.port()).into_make_service());
+                tokio::spawn(async move {
+                    redirect.await.unwrap_or_else(|err| {
+                        exit_with(format!("Couldn't serve on {}: {}", http_addr, err))
+                    })
+                });
+            }
+            tracing::info!(
+                "Rical backend v{} is listening on https://{}",
+                VERSION,
+                addr
+            );
+            axum_server::bind_rustls(addr, rustls)
+                .handle(handle)
+                .serve(app)
+                .await
+        }
+        None => {
+            tracing::info!("Rical backend v{} is listening on http://{}", VERSION, addr);
+            axum_server::bind(addr).handle(handle).serve(app).await
+        }
+    };
+    res.unwrap_or_else(|err| exit_with(format!("Couldn't serve on {}: {}", addr, err)));
+
+    storage.close().await;
+    tracing::info!("Shut down");
 }
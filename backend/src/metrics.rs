@@ -0,0 +1,129 @@
+use axum::{
+    extract::{MatchedPath, Request, State},
+    middleware::Next,
+    response::Response,
+};
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, IntGaugeVec, Opts, Registry,
+    TextEncoder,
+};
+use std::time::Instant;
+
+use crate::storage::PoolStats;
+
+// Counters and histograms for Prometheus, served at `/metrics`
+
+/// Every metric the backend reports
+/// They live in a registry of their own rather than the global one, so that each app
+/// (e.g. one per test) counts separately
+#[derive(Clone)]
+pub struct Metrics {
+    registry: Registry,
+    http_requests: IntCounterVec,
+    http_request_duration: HistogramVec,
+    db_pool_connections: IntGaugeVec,
+    pub signups: IntCounter,
+    /// By reason: `unknown_user`, `wrong_password`, `locked` or `rate_limited`
+    pub logins_failed: IntCounterVec,
+    pub tasks_created: IntCounter,
+}
+
+impl Metrics {
+    pub fn new() -> Metrics {
+        let registry = Registry::new_custom(Some("rical".to_string()), None)
+            .expect("The metrics prefix is valid");
+        let http_requests = IntCounterVec::new(
+            Opts::new("http_requests_total", "HTTP requests handled"),
+            &["method", "route", "status"],
+        )
+        .unwrap();
+        let http_request_duration = HistogramVec::new(
+            HistogramOpts::new(
+                "http_request_duration_seconds",
+                "How long HTTP requests took to handle",
+            ),
+            &["method", "route"],
+        )
+        .unwrap();
+        let db_pool_connections = IntGaugeVec::new(
+            Opts::new("db_pool_connections", "Open database connections"),
+            &["state"],
+        )
+        .unwrap();
+        let signups = IntCounter::new("signups_total", "Accounts signed up").unwrap();
+        let logins_failed = IntCounterVec::new(
+            Opts::new("logins_failed_total", "Rejected login attempts"),
+            &["reason"],
+        )
+        .unwrap();
+        let tasks_created = IntCounter::new("tasks_created_total", "Tasks created").unwrap();
+
+        registry.register(Box::new(http_requests.clone())).unwrap();
+        registry
+            .register(Box::new(http_request_duration.clone()))
+            .unwrap();
+        registry
+            .register(Box::new(db_pool_connections.clone()))
+            .unwrap();
+        registry.register(Box::new(signups.clone())).unwrap();
+        registry.register(Box::new(logins_failed.clone())).unwrap();
+        registry.register(Box::new(tasks_created.clone())).unwrap();
+        Metrics {
+            registry,
+            http_requests,
+            http_request_duration,
+            db_pool_connections,
+            signups,
+            logins_failed,
+            tasks_created,
+        }
+    }
+
+    /// Everything in Prometheus' text format, with the pool's usage as of now
+    pub fn render(&self, pool: PoolStats) -> String {
+        let in_use = pool.open.saturating_sub(pool.idle);
+        self.db_pool_connections
+            .with_label_values(&["idle"])
+            .set(pool.idle.into());
+        self.db_pool_connections
+            .with_label_values(&["in_use"])
+            .set(in_use.into());
+
+        let mut buffer = Vec::new();
+        TextEncoder::new()
+            .encode(&self.registry.gather(), &mut buffer)
+            .expect("Metrics can always be encoded");
+        String::from_utf8(buffer).expect("Metrics are UTF-8")
+    }
+}
+
+impl Default for Metrics {
+    fn default() -> Metrics {
+        Metrics::new()
+    }
+}
+
+/// Middleware that counts and times every request by its route
+pub async fn track_requests(State(metrics): State<Metrics>, req: Request, next: Next) -> Response {
+    // Group by the route's pattern, and lump everything else together so that
+    // random paths can't create endless series
+    let route = req
+        .extensions()
+        .get::<MatchedPath>()
+        .map_or("unmatched", MatchedPath::as_str)
+        .to_string();
+    let method = req.method().to_string();
+    let start = Instant::now();
+
+    let res = next.run(req).await;
+
+    metrics
+        .http_request_duration
+        .with_label_values(&[&method, &route])
+        .observe(start.elapsed().as_secs_f64());
+    metrics
+        .http_requests
+        .with_label_values(&[&method, &route, res.status().as_str()])
+        .inc();
+    res
+}
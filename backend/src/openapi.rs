@@ -0,0 +1,140 @@
+use axum::{Json, Router, routing::get};
+use utoipa::{
+    Modify, OpenApi,
+    openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme},
+};
+use utoipa_scalar::{Scalar, Servable};
+
+use crate::routes;
+
+// The OpenAPI document describing the whole HTTP API
+// Routes and types are registered here so that the document stays generated from the code itself
+
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "Rical API",
+        description = "A friendly and simple API to access and write calendars",
+        license(name = "MIT"),
+        version = rical_types::API_VERSION
+    ),
+    paths(
+        routes::account::signup,
+        routes::account::login,
+        routes::account::create_token,
+        routes::account::list_tokens,
+        routes::account::revoke_token,
+        routes::account::get_settings,
+        routes::account::put_settings,
+        routes::task::get_task,
+        routes::task::post_task,
+        routes::task::put_task,
+        routes::task::delete_task,
+        routes::task::get_history,
+        routes::checklist::get_checklist,
+        routes::checklist::post_item,
+        routes::checklist::put_item,
+        routes::checklist::delete_item,
+        routes::dependency::get_dependencies,
+        routes::dependency::post_dependency,
+        routes::dependency::delete_dependency,
+        routes::batch::run_batch,
+        routes::reschedule::reschedule,
+        routes::calendar::get_calendar,
+        routes::agenda::query_tasks,
+        routes::search::search,
+        routes::tag::list_tags,
+        routes::tag::post_tag,
+        routes::tag::put_tag,
+        routes::tag::delete_tag,
+        routes::project::list_projects,
+        routes::project::post_project,
+        routes::project::get_project,
+        routes::project::put_project,
+        routes::project::delete_project,
+        routes::project::get_project_tasks,
+        routes::tracking::get_timer,
+        routes::tracking::start_timer,
+        routes::tracking::stop_timer,
+        routes::tracking::get_sessions,
+        routes::tracking::get_report,
+        routes::health::healthz,
+        routes::health::readyz,
+        routes::health::version,
+        routes::health::metrics,
+    ),
+    modifiers(&SecuritySchemes),
+    tags(
+        (name = "account", description = "Signing up, logging in, managing API tokens, and settings"),
+        (name = "task", description = "Creating, reading, updating, and deleting tasks, one at a time or in batches, their checklists and the dependencies between them"),
+        (name = "calendar", description = "Reading tasks a month at a time, or across any range of dates"),
+        (name = "search", description = "Finding tasks by their title or description"),
+        (name = "tag", description = "Managing the colored tags that put tasks into categories"),
+        (name = "project", description = "Grouping tasks into projects and tracking their progress"),
+        (name = "tracking", description = "Timing the work on tasks and reporting the tracked time"),
+        (name = "health", description = "Probes and metrics for load balancers and monitoring"),
+    )
+)]
+pub struct ApiDoc;
+
+struct SecuritySchemes;
+
+impl Modify for SecuritySchemes {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "login_token",
+            SecurityScheme::Http(
+                HttpBuilder::new()
+                    .scheme(HttpAuthScheme::Bearer)
+                    .bearer_format("JWT")
+                    .description(Some("The token returned by `/account/login`"))
+                    .build(),
+            ),
+        );
+        components.add_security_scheme(
+            "api_token",
+            SecurityScheme::Http(
+                HttpBuilder::new()
+                    .scheme(HttpAuthScheme::Bearer)
+                    .description(Some(
+                        "A personal API token (`rical_pat_...`) created with `/account/tokens`",
+                    ))
+                    .build(),
+            ),
+        );
+    }
+}
+
+/// Serve the document as JSON along with a browsable docs page
+pub fn get_routes() -> Router {
+    Router::new()
+        .route("/openapi.json", get(|| async { Json(ApiDoc::openapi()) }))
+        .merge(Scalar::with_url("/docs", ApiDoc::openapi()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SNAPSHOT_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/openapi.json");
+
+    /// The committed `openapi.json` must match the routes; when the API changes on purpose,
+    /// regenerate it with `UPDATE_OPENAPI_SNAPSHOT=1 cargo test`
+    #[test]
+    fn test_openapi_snapshot() {
+        let generated = ApiDoc::openapi()
+            .to_pretty_json()
+            .expect("Could not serialize the OpenAPI document")
+            + "\n";
+        if std::env::var("UPDATE_OPENAPI_SNAPSHOT").is_ok() {
+            std::fs::write(SNAPSHOT_PATH, &generated).expect("Could not write the snapshot");
+            return;
+        }
+        let committed = std::fs::read_to_string(SNAPSHOT_PATH).unwrap_or_default();
+        assert!(
+            committed == generated,
+            "openapi.json is out of date; run `UPDATE_OPENAPI_SNAPSHOT=1 cargo test` to update it"
+        );
+    }
+}
@@ -0,0 +1,62 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+// Limits on how often something can be attempted, e.g. logging in to one account
+
+/// Counts attempts per key (e.g. a username) in fixed one-minute windows
+pub struct RateLimiter {
+    per_minute: u32,
+    /// Each key's current window (its start in unix seconds) and the attempts in it
+    windows: Mutex<HashMap<String, (i64, u32)>>,
+}
+
+const WINDOW_SECS: i64 = 60;
+/// Past this many keys, windows that are over get cleared out
+const MAX_KEYS: usize = 10_000;
+
+impl RateLimiter {
+    /// A limit of 0 allows everything
+    pub fn per_minute(per_minute: u32) -> RateLimiter {
+        RateLimiter {
+            per_minute,
+            windows: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Record an attempt at `now` (unix seconds) and return whether it's allowed
+    pub fn check(&self, key: &str, now: i64) -> bool {
+        if self.per_minute == 0 {
+            return true;
+        }
+        let mut windows = self.windows.lock().expect("Rate limiter lock poisoned");
+        if windows.len() >= MAX_KEYS {
+            windows.retain(|_, (start, _)| now - *start < WINDOW_SECS);
+        }
+        let (start, count) = windows.entry(key.to_string()).or_insert((now, 0));
+        if now - *start >= WINDOW_SECS {
+            *start = now;
+            *count = 0;
+        }
+        *count += 1;
+        *count <= self.per_minute
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_limits_each_key_per_window() {
+        let limiter = RateLimiter::per_minute(2);
+        assert!(limiter.check("alice", 1000));
+        assert!(limiter.check("alice", 1010));
+        assert!(!limiter.check("alice", 1020));
+        assert!(limiter.check("bob", 1020));
+        // A new window
+        assert!(limiter.check("alice", 1060));
+
+        let unlimited = RateLimiter::per_minute(0);
+        assert!((0..100).all(|_| unlimited.check("alice", 1000)));
+    }
+}
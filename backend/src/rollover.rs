@@ -0,0 +1,207 @@
+use rical_types::RicalDate;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::storage::{Reschedule, Storage, StorageResult, date_number};
+use crate::utils;
+
+// Auto-rollover: moving the overdue tasks of the accounts that turned it on to today,
+// once each time their day changes
+
+/// How often to check whether an account's day has changed
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Move every incomplete task dated before `today` to `today`, keeping their times,
+/// and return how many there were
+pub async fn reschedule_overdue(
+    storage: &dyn Storage,
+    account_id: i64,
+    today: &RicalDate,
+) -> StorageResult<u64> {
+    let (year, month, day) = (today.year, today.month as i32, today.day as i32);
+    storage
+        .reschedule_tasks(
+            account_id,
+            &Reschedule {
+                from: i32::MIN,
+                // Date numbers are plain integers, so this is any day before today
+                to: date_number(year, month, day) - 1,
+                year,
+                month,
+                day,
+                keep_times: true,
+            },
+        )
+        .await
+}
+
+/// Roll over every account whose day has changed since it was last rolled over,
+/// and return how many tasks were moved
+/// An account that fails is logged and tried again next time, without holding up the others
+pub async fn roll_over(storage: &dyn Storage, now: i64) -> StorageResult<u64> {
+    let mut moved = 0;
+    for account in storage.auto_rollover_accounts().await? {
+        let today = RicalDate::at_unix(now, account.utc_offset_min);
+        let today_number = date_number(today.year, today.month as i32, today.day as i32);
+        if account.rolled_over_on == Some(today_number) {
+            continue;
+        }
+        match roll_over_account(storage, account.account_id, &today, today_number).await {
+            Ok(count) => moved += count,
+            Err(err) => err.log(&format!(
+                "roll over the overdue tasks of account {}",
+                account.account_id
+            )),
+        }
+    }
+    Ok(moved)
+}
+
+async fn roll_over_account(
+    storage: &dyn Storage,
+    account_id: i64,
+    today: &RicalDate,
+    today_number: i32,
+) -> StorageResult<u64> {
+    let moved = reschedule_overdue(storage, account_id, today).await?;
+    storage.set_rolled_over(account_id, today_number).await?;
+    Ok(moved)
+}
+
+/// Keep rolling over accounts in the background for as long as the server runs
+pub fn spawn(storage: Arc<dyn Storage>) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(CHECK_INTERVAL);
+        loop {
+            interval.tick().await;
+            match roll_over(storage.as_ref(), utils::now_unix()).await {
+                Ok(0) => (),
+                Ok(moved) => tracing::info!(moved, "Rolled overdue tasks over to today"),
+                Err(err) => err.log("roll over the overdue tasks"),
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::routes::testing::{saved, task};
+    use crate::storage::memory::MemoryStorage;
+    use crate::storage::testing::{test_storages, unique};
+    use rical_types::{AccountSettings, TaskData, TaskStatus};
+
+    #[tokio::test]
+    async fn test_roll_over_once_a_day() {
+        // 2025-08-04 23:30 UTC, already the 5th an hour ahead of UTC
+        let now = 1754350200;
+        for (name, storage) in test_storages().await {
+            let account_id = storage
+                .create_account(&unique("user"), "hash")
+                .await
+                .unwrap();
+            let other_id = storage
+                .create_account(&unique("user"), "hash")
+                .await
+                .unwrap();
+            let timed = TaskData {
+                start_min: Some(9 * 60),
+                ..task(1, "Task")
+            };
+            let finished = TaskData {
+                status: Some(TaskStatus::Done),
+                ..task(2, "Task")
+            };
+            let overdue = storage.create_task(account_id, &timed).await.unwrap();
+            let done = storage.create_task(account_id, &finished).await.unwrap();
+            let today = storage
+                .create_task(account_id, &task(5, "Task"))
+                .await
+                .unwrap();
+            let others = storage
+                .create_task(other_id, &task(1, "Task"))
+                .await
+                .unwrap();
+            let settings = AccountSettings {
+                auto_rollover: true,
+                utc_offset_min: 60,
+            };
+            storage.set_settings(account_id, &settings).await.unwrap();
+
+            // Other tests' accounts may be rolled over too, so only the tasks themselves are checked
+            roll_over(storage.as_ref(), now).await.unwrap();
+            let get = |task_id| storage.get_task(account_id, task_id);
+            assert_eq!(
+                get(overdue).await.unwrap(),
+                Some(saved(TaskData { day: 5, ..timed })),
+                "{}",
+                name
+            );
+            assert_eq!(get(done).await.unwrap(), Some(saved(finished)), "{}", name);
+            assert_eq!(
+                get(today).await.unwrap(),
+                Some(saved(task(5, "Task"))),
+                "{}",
+                name
+            );
+            assert_eq!(
+                storage.get_task(other_id, others).await.unwrap(),
+                Some(saved(task(1, "Task"))),
+                "{}",
+                name
+            );
+
+            // Until the day changes again, new overdue tasks stay where they are
+            let late = storage
+                .create_task(account_id, &task(3, "Task"))
+                .await
+                .unwrap();
+            roll_over(storage.as_ref(), now + 60).await.unwrap();
+            assert_eq!(
+                get(late).await.unwrap(),
+                Some(saved(task(3, "Task"))),
+                "{}",
+                name
+            );
+            roll_over(storage.as_ref(), now + 24 * 60 * 60)
+                .await
+                .unwrap();
+            for task_id in [overdue, today, late] {
+                assert_eq!(get(task_id).await.unwrap().unwrap().day, 6, "{}", name);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_failing_account_doesnt_stop_the_others() {
+        let now = 1754350200;
+        let storage = MemoryStorage::new();
+        let settings = AccountSettings {
+            auto_rollover: true,
+            utc_offset_min: 0,
+        };
+        let failing_id = storage.create_account("failing", "hash").await.unwrap();
+        let account_id = storage.create_account("user", "hash").await.unwrap();
+        for id in [failing_id, account_id] {
+            storage.set_settings(id, &settings).await.unwrap();
+        }
+        let stuck = storage
+            .create_task(failing_id, &task(1, "Task"))
+            .await
+            .unwrap();
+        let overdue = storage
+            .create_task(account_id, &task(1, "Task"))
+            .await
+            .unwrap();
+        storage.fail_reschedules(failing_id);
+
+        assert_eq!(roll_over(&storage, now).await.unwrap(), 1);
+        let get = |account_id, task_id| storage.get_task(account_id, task_id);
+        assert_eq!(get(account_id, overdue).await.unwrap().unwrap().day, 4);
+        assert_eq!(get(failing_id, stuck).await.unwrap().unwrap().day, 1);
+        // The failed account is tried again on the next check
+        let rolled_over = storage.auto_rollover_accounts().await.unwrap();
+        assert_eq!(rolled_over[0].account_id, failing_id);
+        assert_eq!(rolled_over[0].rolled_over_on, None);
+    }
+}
@@ -1,3 +1,186 @@
 pub mod account;
+pub mod agenda;
+pub mod batch;
 pub mod calendar;
+pub mod checklist;
+pub mod dependency;
+pub mod health;
+pub mod project;
+pub mod reschedule;
+pub mod search;
+pub mod tag;
 pub mod task;
+pub mod tracking;
+
+/// A harness that runs the real router in-process, against every test storage
+#[cfg(test)]
+pub mod testing {
+    use axum::{
+        Router,
+        body::Body,
+        http::{Request, StatusCode},
+    };
+    use http_body_util::BodyExt;
+    use rical_types::{AuthToken, TaskData, TaskId, TaskStatus};
+    use serde_json::{Value, json};
+    use std::sync::Arc;
+    use tower::ServiceExt;
+
+    use crate::config::Config;
+    use crate::storage::Storage;
+    use crate::storage::testing::{test_storages, unique};
+    use crate::{AppState, app};
+
+    pub const JWT_SECRET: &str = "test secret";
+
+    pub struct TestApp {
+        /// The storage's name, for assertion messages
+        pub name: &'static str,
+        /// The storage behind the router, to set up what the API can't
+        pub storage: Arc<dyn Storage>,
+        router: Router,
+    }
+
+    /// The defaults, with what's required filled in
+    pub fn test_config() -> Config {
+        let mut config = Config::default();
+        config.auth.jwt_secret = JWT_SECRET.to_string();
+        config
+    }
+
+    /// One app for each storage in `storage::testing::test_storages`
+    pub async fn test_apps() -> Vec<TestApp> {
+        test_apps_with_config(test_config()).await
+    }
+
+    pub async fn test_apps_with_config(config: Config) -> Vec<TestApp> {
+        test_storages()
+            .await
+            .into_iter()
+            .map(|(name, storage)| TestApp {
+                name,
+                storage: storage.clone(),
+                router: app(AppState::new(storage, config.clone())),
+            })
+            .collect()
+    }
+
+    impl TestApp {
+        /// Send a request and return the status with the JSON body (null if there isn't one)
+        pub async fn request(
+            &self,
+            method: &str,
+            uri: &str,
+            token: Option<&str>,
+            body: Option<Value>,
+        ) -> (StatusCode, Value) {
+            let mut req = Request::builder()
+                .method(method)
+                .uri(uri)
+                .header("content-type", "application/json");
+            if let Some(token) = token {
+                req = req.header("authorization", format!("Bearer {}", token));
+            }
+            let body = body.map_or_else(Body::empty, |body| Body::from(body.to_string()));
+            let res = self
+                .router
+                .clone()
+                .oneshot(req.body(body).unwrap())
+                .await
+                .unwrap();
+            let status = res.status();
+            let bytes = res.into_body().collect().await.unwrap().to_bytes();
+            (
+                status,
+                serde_json::from_slice(&bytes).unwrap_or(Value::Null),
+            )
+        }
+
+        /// Send a body-less request and return the status with the body as text
+        pub async fn request_text(&self, method: &str, uri: &str) -> (StatusCode, String) {
+            let req = Request::builder().method(method).uri(uri);
+            let res = self
+                .router
+                .clone()
+                .oneshot(req.body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+            let status = res.status();
+            let bytes = res.into_body().collect().await.unwrap().to_bytes();
+            (status, String::from_utf8(bytes.to_vec()).unwrap())
+        }
+
+        /// Sign up a new account and return its username
+        pub async fn signup(&self, password: &str) -> String {
+            let username = unique("user");
+            let (status, _) = self
+                .request(
+                    "POST",
+                    "/account/signup",
+                    None,
+                    Some(json!({ "username": username, "password": password })),
+                )
+                .await;
+            assert_eq!(status, StatusCode::CREATED, "{}", self.name);
+            username
+        }
+
+        /// Sign up a new account and return a login token for it
+        pub async fn new_user(&self) -> String {
+            let username = self.signup("password").await;
+            let (status, body) = self
+                .request(
+                    "POST",
+                    "/account/login",
+                    None,
+                    Some(json!({ "username": username, "password": "password" })),
+                )
+                .await;
+            assert_eq!(status, StatusCode::OK, "{}", self.name);
+            serde_json::from_value::<AuthToken>(body).unwrap().token
+        }
+
+        /// Create a task and return its ID
+        pub async fn create_task(&self, token: &str, task: &TaskData) -> i64 {
+            let (status, body) = self
+                .request("POST", "/task", Some(token), Some(json!(task)))
+                .await;
+            assert_eq!(status, StatusCode::CREATED, "{}", self.name);
+            serde_json::from_value::<TaskId>(body).unwrap().task_id
+        }
+    }
+
+    /// A simple untimed task on a day of August 2025
+    pub fn task(day: i32, title: &str) -> TaskData {
+        TaskData {
+            year: 2025,
+            month: 8,
+            day,
+            start_min: None,
+            end_min: None,
+            title: title.to_string(),
+            description: None,
+            complete: None,
+            status: None,
+            priority: None,
+            position: 0,
+            auto_complete: false,
+            project_id: None,
+            estimate_mins: None,
+            tags: None,
+        }
+    }
+
+    /// A task as reading it back returns it, with the status, priority and tags it was saved with
+    pub fn saved(task: TaskData) -> TaskData {
+        let status = task.resolved_status(None);
+        TaskData {
+            complete: Some(status == TaskStatus::Done),
+            status: Some(status),
+            priority: Some(task.priority.unwrap_or_default()),
+            project_id: Some(task.project_id.flatten()),
+            tags: Some(task.tags.clone().unwrap_or_default()),
+            ..task
+        }
+    }
+}
@@ -1,88 +1,728 @@
-use axum::{Json, Router, extract::State, http::StatusCode, routing::post};
-use serde::{Deserialize, Serialize};
-use sqlx;
+use axum::{
+    Json, Router,
+    extract::{Path, State},
+    http::StatusCode,
+    routing::{delete, get, post, put},
+};
+use axum_extra::{
+    TypedHeader,
+    headers::{Authorization, authorization::Bearer},
+};
+use rical_types::{
+    AccountSettings, ApiTokenInfo, AuthToken, CreatedApiToken, NewApiToken, TokenScope,
+    UserCredentials,
+};
 use std::sync::Arc;
 
 use crate::AppState;
+use crate::logging;
+use crate::storage::{NewApiTokenRecord, StorageError};
 use crate::utils;
 
 pub fn get_routes(state: &Arc<AppState>) -> Router {
     Router::new()
         .route("/signup", post(signup))
         .route("/login", post(login))
+        .route("/tokens", get(list_tokens))
+        .route("/tokens", post(create_token))
+        .route("/tokens/{id}", delete(revoke_token))
+        .route("/settings", get(get_settings))
+        .route("/settings", put(put_settings))
         .with_state(state.clone())
 }
 
-#[derive(Deserialize)]
-struct UserCredentials {
-    username: String,
-    password: String,
-}
-
+#[utoipa::path(
+    post,
+    path = "/account/signup",
+    tag = "account",
+    request_body = UserCredentials,
+    responses(
+        (status = CREATED, description = "The account was created"),
+        (status = CONFLICT, description = "The username is already taken"),
+    )
+)]
 async fn signup(
     State(state): State<Arc<AppState>>,
     Json(payload): Json<UserCredentials>,
 ) -> StatusCode {
-    let matching_username: Option<String> =
-        sqlx::query_scalar("SELECT username FROM account WHERE username=$1;")
-            .bind(&payload.username)
-            .fetch_optional(&state.db_pool)
-            .await
-            .expect("Could not select username");
-    if matching_username.is_some() {
-        // The username is already taken
-        return StatusCode::CONFLICT;
-    }
     let hashed_password = utils::hash_password(&payload.password);
-    let res = sqlx::query("INSERT INTO account (username, hashed_password) VALUES ($1, $2)")
-        .bind(&payload.username)
-        .bind(&hashed_password)
-        .execute(&state.db_pool)
-        .await;
-    if res.is_err() {
+    match state
+        .storage
+        .create_account(&payload.username, &hashed_password)
+        .await
+    {
+        Ok(account_id) => logging::record_account_id(account_id),
+        // The username is already taken
+        Err(StorageError::Conflict) => return StatusCode::CONFLICT,
         // TODO: better error message
-        return StatusCode::BAD_REQUEST;
+        Err(err) => {
+            err.log("create the account");
+            return err.status_code();
+        }
     }
-    println!("- User signed up with username: '{}'", payload.username);
+    state.metrics.signups.inc();
+    tracing::info!(username = payload.username, "User signed up");
     StatusCode::CREATED
 }
 
-#[derive(Serialize)]
-struct AuthToken {
-    token: String,
-}
-
-struct Account {
-    hashed_password: String,
-    account_id: i64,
-}
-
+#[utoipa::path(
+    post,
+    path = "/account/login",
+    tag = "account",
+    request_body = UserCredentials,
+    responses(
+        (status = OK, description = "A login token for the account", body = AuthToken),
+        (status = NOT_FOUND, description = "No account has this username"),
+        (status = UNAUTHORIZED, description = "The password is incorrect"),
+        (status = FORBIDDEN, description = "The account has been locked by an admin"),
+        (status = TOO_MANY_REQUESTS, description = "Too many attempts for this username, try again in a minute"),
+    )
+)]
 async fn login(
     State(state): State<Arc<AppState>>,
     Json(payload): Json<UserCredentials>,
 ) -> (StatusCode, Json<Option<AuthToken>>) {
-    let account = match sqlx::query_as!(
-        Account,
-        "SELECT hashed_password, account_id FROM account WHERE username=$1;",
-        &payload.username
-    )
-    .fetch_one(&state.db_pool)
-    .await
+    if !state
+        .login_limiter
+        .check(&payload.username, utils::now_unix())
     {
-        Ok(row) => row,
-        Err(_) => {
+        state
+            .metrics
+            .logins_failed
+            .with_label_values(&["rate_limited"])
+            .inc();
+        return (StatusCode::TOO_MANY_REQUESTS, Json(None));
+    }
+    let account = match state.storage.find_account(&payload.username).await {
+        Ok(Some(account)) => account,
+        Ok(None) => {
+            state
+                .metrics
+                .logins_failed
+                .with_label_values(&["unknown_user"])
+                .inc();
             return (StatusCode::NOT_FOUND, Json(None));
         }
+        Err(err) => {
+            err.log("look up the account");
+            return (err.status_code(), Json(None));
+        }
     };
     if !utils::verify_password(&payload.password, &account.hashed_password) {
+        state
+            .metrics
+            .logins_failed
+            .with_label_values(&["wrong_password"])
+            .inc();
         // TODO: better error message
         return (StatusCode::UNAUTHORIZED, Json(None));
     }
+    if account.locked {
+        state
+            .metrics
+            .logins_failed
+            .with_label_values(&["locked"])
+            .inc();
+        return (StatusCode::FORBIDDEN, Json(None));
+    }
+    logging::record_account_id(account.account_id);
 
     (
         StatusCode::OK,
         Json(Some(AuthToken {
-            token: utils::create_jwt(account.account_id),
+            token: utils::create_jwt(
+                &state.config.auth.jwt_secret,
+                account.account_id,
+                state.config.auth.login_token_lifetime_secs,
+            ),
+        })),
+    )
+}
+
+/// Tokens can only be managed with a login JWT, so that a leaked API token can't mint more of itself
+async fn verify_login_token(state: &AppState, bearer: &Bearer) -> Option<i64> {
+    if bearer.token().starts_with(utils::API_TOKEN_PREFIX) {
+        return None;
+    }
+    utils::authenticate_login(state, bearer.token()).await
+}
+
+#[utoipa::path(
+    post,
+    path = "/account/tokens",
+    tag = "account",
+    request_body = NewApiToken,
+    security(("login_token" = [])),
+    responses(
+        (status = CREATED, description = "The new token, shown only this once", body = CreatedApiToken),
+        (status = BAD_REQUEST, description = "The name is empty, or the expiry is not positive or longer than the server allows"),
+        (status = UNAUTHORIZED, description = "Missing or invalid login token"),
+    )
+)]
+async fn create_token(
+    TypedHeader(Authorization(bearer)): TypedHeader<Authorization<Bearer>>,
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<NewApiToken>,
+) -> (StatusCode, Json<Option<CreatedApiToken>>) {
+    let account_id = match verify_login_token(&state, &bearer).await {
+        Some(id) => id,
+        None => {
+            return (StatusCode::UNAUTHORIZED, Json(None));
+        }
+    };
+    if payload.name.trim().is_empty() || payload.expires_in_days.is_some_and(|days| days <= 0) {
+        return (StatusCode::BAD_REQUEST, Json(None));
+    }
+    if let Some(max_days) = state.config.auth.api_token_max_lifetime_days
+        && payload.expires_in_days.is_none_or(|days| days > max_days)
+    {
+        return (StatusCode::BAD_REQUEST, Json(None));
+    }
+
+    const SECS_PER_DAY: i64 = 24 * 60 * 60;
+    let token = utils::generate_api_token();
+    let created_at = utils::now_unix();
+    let expires_at = payload.expires_in_days.map(|days| {
+        days.checked_mul(SECS_PER_DAY)
+            .and_then(|secs| created_at.checked_add(secs))
+    });
+    // Too far out to be a time at all
+    let expires_at = match expires_at {
+        Some(None) => return (StatusCode::BAD_REQUEST, Json(None)),
+        expires_at => expires_at.flatten(),
+    };
+    let token_id = match state
+        .storage
+        .create_api_token(NewApiTokenRecord {
+            account_id,
+            name: &payload.name,
+            hashed_token: &utils::hash_api_token(&token),
+            scope: payload.scope,
+            created_at,
+            expires_at,
+        })
+        .await
+    {
+        Ok(id) => id,
+        Err(err) => {
+            err.log("create the API token");
+            return (err.status_code(), Json(None));
+        }
+    };
+
+    (
+        StatusCode::CREATED,
+        Json(Some(CreatedApiToken {
+            token_id,
+            token,
+            name: payload.name,
+            scope: payload.scope,
+            created_at,
+            expires_at,
         })),
     )
 }
+
+#[utoipa::path(
+    get,
+    path = "/account/tokens",
+    tag = "account",
+    security(("login_token" = [])),
+    responses(
+        (status = OK, description = "All of the account's API tokens", body = Vec<ApiTokenInfo>),
+        (status = UNAUTHORIZED, description = "Missing or invalid login token"),
+    )
+)]
+async fn list_tokens(
+    TypedHeader(Authorization(bearer)): TypedHeader<Authorization<Bearer>>,
+    State(state): State<Arc<AppState>>,
+) -> (StatusCode, Json<Option<Vec<ApiTokenInfo>>>) {
+    let account_id = match verify_login_token(&state, &bearer).await {
+        Some(id) => id,
+        None => {
+            return (StatusCode::UNAUTHORIZED, Json(None));
+        }
+    };
+    let res = match state.storage.list_api_tokens(account_id).await {
+        Ok(tokens) => tokens,
+        Err(err) => {
+            err.log("list the API tokens");
+            return (err.status_code(), Json(None));
+        }
+    };
+    (StatusCode::OK, Json(Some(res)))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/account/tokens/{id}",
+    tag = "account",
+    params(("id" = i64, Path, description = "The ID of the token to revoke")),
+    security(("login_token" = [])),
+    responses(
+        (status = OK, description = "The token was revoked"),
+        (status = NOT_FOUND, description = "The account has no token with this ID"),
+        (status = UNAUTHORIZED, description = "Missing or invalid login token"),
+    )
+)]
+async fn revoke_token(
+    TypedHeader(Authorization(bearer)): TypedHeader<Authorization<Bearer>>,
+    State(state): State<Arc<AppState>>,
+    Path(token_id): Path<i64>,
+) -> StatusCode {
+    let account_id = match verify_login_token(&state, &bearer).await {
+        Some(id) => id,
+        None => {
+            return StatusCode::UNAUTHORIZED;
+        }
+    };
+    match state.storage.revoke_api_token(account_id, token_id).await {
+        Ok(false) => StatusCode::NOT_FOUND,
+        Ok(true) => StatusCode::OK,
+        Err(err) => {
+            err.log("revoke the API token");
+            err.status_code()
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/account/settings",
+    tag = "account",
+    security(("login_token" = []), ("api_token" = ["read"])),
+    responses(
+        (status = OK, description = "The account's settings", body = AccountSettings),
+        (status = UNAUTHORIZED, description = "Missing or invalid token"),
+    )
+)]
+async fn get_settings(
+    TypedHeader(Authorization(bearer)): TypedHeader<Authorization<Bearer>>,
+    State(state): State<Arc<AppState>>,
+) -> (StatusCode, Json<Option<AccountSettings>>) {
+    let account_id = match utils::authenticate(&state, bearer.token(), TokenScope::Read).await {
+        Ok(id) => id,
+        Err(status) => {
+            return (status, Json(None));
+        }
+    };
+    match state.storage.get_settings(account_id).await {
+        Ok(Some(settings)) => (StatusCode::OK, Json(Some(settings))),
+        Ok(None) => (StatusCode::UNAUTHORIZED, Json(None)),
+        Err(err) => {
+            err.log("get the settings");
+            (err.status_code(), Json(None))
+        }
+    }
+}
+
+#[utoipa::path(
+    put,
+    path = "/account/settings",
+    tag = "account",
+    request_body = AccountSettings,
+    security(("login_token" = []), ("api_token" = ["read_write"])),
+    responses(
+        (status = OK, description = "The settings were replaced"),
+        (status = BAD_REQUEST, description = "The UTC offset is out of range"),
+        (status = UNAUTHORIZED, description = "Missing or invalid token"),
+        (status = FORBIDDEN, description = "The API token's scope doesn't allow this"),
+    )
+)]
+async fn put_settings(
+    TypedHeader(Authorization(bearer)): TypedHeader<Authorization<Bearer>>,
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<AccountSettings>,
+) -> StatusCode {
+    let account_id = match utils::authenticate(&state, bearer.token(), TokenScope::ReadWrite).await
+    {
+        Ok(id) => id,
+        Err(status) => {
+            return status;
+        }
+    };
+    if !payload.is_valid() {
+        return StatusCode::BAD_REQUEST;
+    }
+    match state.storage.set_settings(account_id, &payload).await {
+        Ok(true) => StatusCode::OK,
+        Ok(false) => StatusCode::UNAUTHORIZED,
+        Err(err) => {
+            err.log("set the settings");
+            err.status_code()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::routes::testing::{self, test_apps, test_apps_with_config};
+    use crate::storage::testing::unique;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_signup_conflict() {
+        for app in test_apps().await {
+            let username = app.signup("password").await;
+            let (status, _) = app
+                .request(
+                    "POST",
+                    "/account/signup",
+                    None,
+                    Some(json!({ "username": username, "password": "other" })),
+                )
+                .await;
+            assert_eq!(status, StatusCode::CONFLICT, "{}", app.name);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_login() {
+        for app in test_apps().await {
+            let username = app.signup("password").await;
+            let login =
+                |password: &str| Some(json!({ "username": username, "password": password }));
+
+            let (status, body) = app
+                .request("POST", "/account/login", None, login("password"))
+                .await;
+            assert_eq!(status, StatusCode::OK, "{}", app.name);
+            let token = serde_json::from_value::<AuthToken>(body).unwrap().token;
+            assert!(utils::verify_jwt(testing::JWT_SECRET, &token).is_some());
+
+            let (status, _) = app
+                .request("POST", "/account/login", None, login("wrong"))
+                .await;
+            assert_eq!(status, StatusCode::UNAUTHORIZED, "{}", app.name);
+
+            let (status, _) = app
+                .request(
+                    "POST",
+                    "/account/login",
+                    None,
+                    Some(json!({ "username": unique("nobody"), "password": "password" })),
+                )
+                .await;
+            assert_eq!(status, StatusCode::NOT_FOUND, "{}", app.name);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_jwt_checks() {
+        for app in test_apps().await {
+            let token = app.new_user().await;
+            let (status, _) = app
+                .request("GET", "/calendar/2025/8", Some(&token), None)
+                .await;
+            assert_eq!(status, StatusCode::OK, "{}", app.name);
+
+            let forged = utils::create_jwt("some other secret", 1, None);
+            let account_id = utils::verify_jwt(testing::JWT_SECRET, &token).unwrap().sub;
+            let expired = utils::create_jwt(testing::JWT_SECRET, account_id, Some(0));
+            for bad_token in [
+                "garbage",
+                forged.as_str(),
+                expired.as_str(),
+                &token[..token.len() - 1],
+            ] {
+                let (status, _) = app
+                    .request("GET", "/calendar/2025/8", Some(bad_token), None)
+                    .await;
+                assert_eq!(status, StatusCode::UNAUTHORIZED, "{}", app.name);
+            }
+
+            // Axum rejects a missing header before the handler runs
+            let (status, _) = app.request("GET", "/calendar/2025/8", None, None).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST, "{}", app.name);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_login_rate_limit() {
+        let mut config = testing::test_config();
+        config.rate_limit.login_attempts_per_minute = 2;
+        for app in test_apps_with_config(config).await {
+            let username = app.signup("password").await;
+            let login =
+                |password: &str| Some(json!({ "username": username, "password": password }));
+            for _ in 0..2 {
+                let (status, _) = app
+                    .request("POST", "/account/login", None, login("wrong"))
+                    .await;
+                assert_eq!(status, StatusCode::UNAUTHORIZED, "{}", app.name);
+            }
+            // Even the right password is turned away until the minute is up
+            let (status, _) = app
+                .request("POST", "/account/login", None, login("password"))
+                .await;
+            assert_eq!(status, StatusCode::TOO_MANY_REQUESTS, "{}", app.name);
+
+            // Other accounts aren't affected
+            app.new_user().await;
+        }
+    }
+
+    async fn create_api_token(
+        app: &testing::TestApp,
+        login_token: &str,
+        scope: &str,
+    ) -> (StatusCode, Option<CreatedApiToken>) {
+        let (status, body) = app
+            .request(
+                "POST",
+                "/account/tokens",
+                Some(login_token),
+                Some(json!({ "name": "script", "scope": scope, "expires_in_days": 30 })),
+            )
+            .await;
+        (status, serde_json::from_value(body).ok())
+    }
+
+    #[tokio::test]
+    async fn test_api_token_scopes() {
+        for app in test_apps().await {
+            let login_token = app.new_user().await;
+            let (status, created) = create_api_token(&app, &login_token, "read").await;
+            assert_eq!(status, StatusCode::CREATED, "{}", app.name);
+            let read_token = created.unwrap().token;
+            let (_, created) = create_api_token(&app, &login_token, "read_write").await;
+            let write_token = created.unwrap().token;
+
+            let (status, _) = app
+                .request("GET", "/calendar/2025/8", Some(&read_token), None)
+                .await;
+            assert_eq!(status, StatusCode::OK, "{}", app.name);
+
+            let task = Some(json!(testing::task(1, "Task")));
+            let (status, _) = app
+                .request("POST", "/task", Some(&read_token), task.clone())
+                .await;
+            assert_eq!(status, StatusCode::FORBIDDEN, "{}", app.name);
+            let (status, _) = app.request("POST", "/task", Some(&write_token), task).await;
+            assert_eq!(status, StatusCode::CREATED, "{}", app.name);
+
+            // API tokens can't manage tokens, even with write access
+            let (status, _) = create_api_token(&app, &write_token, "read").await;
+            assert_eq!(status, StatusCode::UNAUTHORIZED, "{}", app.name);
+            let (status, _) = app
+                .request("GET", "/account/tokens", Some(&write_token), None)
+                .await;
+            assert_eq!(status, StatusCode::UNAUTHORIZED, "{}", app.name);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_create_api_token_validation() {
+        for app in test_apps().await {
+            let login_token = app.new_user().await;
+            for invalid in [
+                json!({ "name": " ", "scope": "read", "expires_in_days": null }),
+                json!({ "name": "script", "scope": "read", "expires_in_days": 0 }),
+                json!({ "name": "script", "scope": "read", "expires_in_days": -30 }),
+                json!({ "name": "script", "scope": "read", "expires_in_days": i64::MAX }),
+                json!({ "name": "script", "scope": "read", "expires_in_days": i64::MAX / 86400 }),
+            ] {
+                let (status, _) = app
+                    .request("POST", "/account/tokens", Some(&login_token), Some(invalid))
+                    .await;
+                assert_eq!(status, StatusCode::BAD_REQUEST, "{}", app.name);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_api_token_max_lifetime() {
+        let mut config = testing::test_config();
+        config.auth.api_token_max_lifetime_days = Some(7);
+        for app in test_apps_with_config(config).await {
+            let login_token = app.new_user().await;
+            for (expires_in_days, expected) in [
+                (json!(null), StatusCode::BAD_REQUEST),
+                (json!(30), StatusCode::BAD_REQUEST),
+                (json!(7), StatusCode::CREATED),
+            ] {
+                let (status, _) = app
+                    .request(
+                        "POST",
+                        "/account/tokens",
+                        Some(&login_token),
+                        Some(json!({
+                            "name": "script",
+                            "scope": "read",
+                            "expires_in_days": expires_in_days
+                        })),
+                    )
+                    .await;
+                assert_eq!(status, expected, "{}", app.name);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_list_and_revoke_api_tokens() {
+        for app in test_apps().await {
+            let login_token = app.new_user().await;
+            let other_login_token = app.new_user().await;
+            let (_, created) = create_api_token(&app, &login_token, "read").await;
+            let created = created.unwrap();
+
+            let (status, body) = app
+                .request("GET", "/account/tokens", Some(&login_token), None)
+                .await;
+            assert_eq!(status, StatusCode::OK, "{}", app.name);
+            let tokens = serde_json::from_value::<Vec<ApiTokenInfo>>(body).unwrap();
+            assert_eq!(tokens.len(), 1, "{}", app.name);
+            assert_eq!(tokens[0].token_id, created.token_id, "{}", app.name);
+            assert!(tokens[0].last_used_at.is_none(), "{}", app.name);
+
+            // Using a token records when it was last used
+            app.request("GET", "/calendar/2025/8", Some(&created.token), None)
+                .await;
+            let (_, body) = app
+                .request("GET", "/account/tokens", Some(&login_token), None)
+                .await;
+            let tokens = serde_json::from_value::<Vec<ApiTokenInfo>>(body).unwrap();
+            assert!(tokens[0].last_used_at.is_some(), "{}", app.name);
+
+            // Other accounts can't see or revoke the token
+            let (_, body) = app
+                .request("GET", "/account/tokens", Some(&other_login_token), None)
+                .await;
+            assert_eq!(body, json!([]), "{}", app.name);
+            let uri = format!("/account/tokens/{}", created.token_id);
+            let (status, _) = app
+                .request("DELETE", &uri, Some(&other_login_token), None)
+                .await;
+            assert_eq!(status, StatusCode::NOT_FOUND, "{}", app.name);
+
+            let (status, _) = app.request("DELETE", &uri, Some(&login_token), None).await;
+            assert_eq!(status, StatusCode::OK, "{}", app.name);
+            let (status, _) = app
+                .request("GET", "/calendar/2025/8", Some(&created.token), None)
+                .await;
+            assert_eq!(status, StatusCode::UNAUTHORIZED, "{}", app.name);
+            let (status, _) = app.request("DELETE", &uri, Some(&login_token), None).await;
+            assert_eq!(status, StatusCode::NOT_FOUND, "{}", app.name);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_locked_accounts() {
+        for app in test_apps().await {
+            let username = app.signup("password").await;
+            let credentials = Some(json!({ "username": username, "password": "password" }));
+            let (_, body) = app
+                .request("POST", "/account/login", None, credentials.clone())
+                .await;
+            let token = serde_json::from_value::<AuthToken>(body).unwrap().token;
+            let (_, created) = create_api_token(&app, &token, "read").await;
+            let api_token = created.unwrap().token;
+            let account_id = utils::verify_jwt(testing::JWT_SECRET, &token).unwrap().sub;
+
+            app.storage.set_locked(account_id, true).await.unwrap();
+            for token in [&token, &api_token] {
+                let (status, _) = app
+                    .request("GET", "/calendar/2025/8", Some(token), None)
+                    .await;
+                assert_eq!(status, StatusCode::UNAUTHORIZED, "{}", app.name);
+            }
+            let (status, _) = app
+                .request("POST", "/account/login", None, credentials.clone())
+                .await;
+            assert_eq!(status, StatusCode::FORBIDDEN, "{}", app.name);
+
+            app.storage.set_locked(account_id, false).await.unwrap();
+            for token in [&token, &api_token] {
+                let (status, _) = app
+                    .request("GET", "/calendar/2025/8", Some(token), None)
+                    .await;
+                assert_eq!(status, StatusCode::OK, "{}", app.name);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_revoked_sessions() {
+        for app in test_apps().await {
+            let token = app.new_user().await;
+            let account_id = utils::verify_jwt(testing::JWT_SECRET, &token).unwrap().sub;
+            // As if the sessions were revoked a while after logging in
+            app.storage
+                .revoke_sessions(account_id, utils::now_unix() + 10)
+                .await
+                .unwrap();
+
+            let (status, _) = app
+                .request("GET", "/calendar/2025/8", Some(&token), None)
+                .await;
+            assert_eq!(status, StatusCode::UNAUTHORIZED, "{}", app.name);
+            let (status, _) = app
+                .request("GET", "/account/tokens", Some(&token), None)
+                .await;
+            assert_eq!(status, StatusCode::UNAUTHORIZED, "{}", app.name);
+
+            // Revoking in the same second as the login still applies to it
+            let token = app.new_user().await;
+            let claims = utils::verify_jwt(testing::JWT_SECRET, &token).unwrap();
+            app.storage
+                .revoke_sessions(claims.sub, claims.iat.unwrap())
+                .await
+                .unwrap();
+            let (status, _) = app
+                .request("GET", "/calendar/2025/8", Some(&token), None)
+                .await;
+            assert_eq!(status, StatusCode::UNAUTHORIZED, "{}", app.name);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_settings() {
+        for app in test_apps().await {
+            let token = app.new_user().await;
+            let (status, body) = app
+                .request("GET", "/account/settings", Some(&token), None)
+                .await;
+            assert_eq!(status, StatusCode::OK, "{}", app.name);
+            assert_eq!(
+                serde_json::from_value::<AccountSettings>(body).unwrap(),
+                AccountSettings::default(),
+                "{}",
+                app.name
+            );
+
+            let settings = AccountSettings {
+                auto_rollover: true,
+                utc_offset_min: -5 * 60,
+            };
+            let (status, _) = app
+                .request(
+                    "PUT",
+                    "/account/settings",
+                    Some(&token),
+                    Some(json!(settings)),
+                )
+                .await;
+            assert_eq!(status, StatusCode::OK, "{}", app.name);
+            let (_, body) = app
+                .request("GET", "/account/settings", Some(&token), None)
+                .await;
+            assert_eq!(
+                serde_json::from_value::<AccountSettings>(body).unwrap(),
+                settings,
+                "{}",
+                app.name
+            );
+
+            let (status, _) = app
+                .request(
+                    "PUT",
+                    "/account/settings",
+                    Some(&token),
+                    Some(json!({ "auto_rollover": false, "utc_offset_min": 15 * 60 })),
+                )
+                .await;
+            assert_eq!(status, StatusCode::BAD_REQUEST, "{}", app.name);
+        }
+    }
+}
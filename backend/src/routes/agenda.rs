@@ -0,0 +1,403 @@
+use axum::{
+    Json, Router,
+    extract::{Query, State},
+    http::StatusCode,
+    routing::get,
+};
+use axum_extra::{
+    TypedHeader,
+    headers::{Authorization, authorization::Bearer},
+};
+use rical_types::{
+    DateTasks, RicalDate, TaskDataWithId, TaskLayout, TaskList, TaskPage, TaskQuery, TaskSort,
+    TasksByDate, TokenScope, parse_status_filter, parse_tag_filter,
+};
+use std::sync::Arc;
+
+use crate::AppState;
+use crate::storage::{TaskFilter, TaskKey, date_number};
+use crate::utils;
+
+// Reading the tasks of any range of dates, e.g. for a week view or the next two weeks' agenda
+
+/// How many tasks a page has unless the query asks for fewer or more
+const DEFAULT_LIMIT: u32 = 100;
+const MAX_LIMIT: u32 = 500;
+
+pub fn get_routes(state: &Arc<AppState>) -> Router {
+    Router::new()
+        .route("/", get(query_tasks))
+        .with_state(state.clone())
+}
+
+#[utoipa::path(
+    get,
+    path = "/tasks",
+    tag = "calendar",
+    params(TaskQuery),
+    security(("login_token" = []), ("api_token" = ["read"])),
+    responses(
+        (status = OK, description = "A page of the matching tasks, as a `TaskList` or `TasksByDate` depending on `layout`", body = TaskPage),
+        (status = BAD_REQUEST, description = "Invalid dates, limit, cursor or status, or `layout=by_date` without a date sort"),
+        (status = UNAUTHORIZED, description = "Missing or invalid token"),
+    )
+)]
+async fn query_tasks(
+    TypedHeader(Authorization(bearer)): TypedHeader<Authorization<Bearer>>,
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<TaskQuery>,
+) -> (StatusCode, Json<Option<TaskPage>>) {
+    let account_id = match utils::authenticate(&state, bearer.token(), TokenScope::Read).await {
+        Ok(id) => id,
+        Err(status) => {
+            return (status, Json(None));
+        }
+    };
+    let (Some(from), Some(to)) = (
+        RicalDate::parse_iso(&query.from),
+        RicalDate::parse_iso(&query.to),
+    ) else {
+        return (StatusCode::BAD_REQUEST, Json(None));
+    };
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT);
+    let after = match query.cursor.as_deref().map(decode_cursor) {
+        Some(Some(key)) => Some(key),
+        Some(None) => {
+            return (StatusCode::BAD_REQUEST, Json(None));
+        }
+        None => None,
+    };
+    if from > to
+        || !(1..=MAX_LIMIT).contains(&limit)
+        || (query.layout == TaskLayout::ByDate && query.sort == TaskSort::Title)
+    {
+        return (StatusCode::BAD_REQUEST, Json(None));
+    }
+
+    let tags = parse_tag_filter(query.tags.as_deref().unwrap_or_default());
+    let Some(statuses) = parse_status_filter(query.status.as_deref().unwrap_or_default()) else {
+        return (StatusCode::BAD_REQUEST, Json(None));
+    };
+    let filter = TaskFilter {
+        from: date_number(from.year, from.month as i32, from.day as i32),
+        to: date_number(to.year, to.month as i32, to.day as i32),
+        completed: query.completed,
+        timed: query.timed,
+        text: query.q.as_deref().filter(|q| !q.is_empty()),
+        tags: &tags,
+        statuses: &statuses,
+        sort: query.sort,
+        after: after.as_ref(),
+        // One more than asked for, to tell whether there's another page
+        limit: limit + 1,
+    };
+    let mut tasks = match state.storage.query_tasks(account_id, &filter).await {
+        Ok(tasks) => tasks,
+        Err(err) => {
+            err.log("query the tasks");
+            return (err.status_code(), Json(None));
+        }
+    };
+    let next_cursor = if tasks.len() > limit as usize {
+        tasks.truncate(limit as usize);
+        tasks.last().map(|last| encode_cursor(&TaskKey::of(last)))
+    } else {
+        None
+    };
+
+    let page = match query.layout {
+        TaskLayout::List => TaskPage::List(TaskList { tasks, next_cursor }),
+        TaskLayout::ByDate => TaskPage::ByDate(TasksByDate {
+            days: group_by_date(tasks),
+            next_cursor,
+        }),
+    };
+    (StatusCode::OK, Json(Some(page)))
+}
+
+/// Group tasks that are already sorted by date
+fn group_by_date(tasks: Vec<TaskDataWithId>) -> Vec<DateTasks> {
+    let mut days: Vec<DateTasks> = Vec::new();
+    for task in tasks {
+        // Not `RicalDate::new`, which panics on dates like the 31st of June that tasks can have
+        let date = RicalDate {
+            year: task.year,
+            month: task.month as u32,
+            day: task.day as u32,
+        }
+        .format_iso();
+        match days.last_mut() {
+            Some(day) if day.date == date => day.tasks.push(task),
+            _ => days.push(DateTasks {
+                date,
+                tasks: vec![task],
+            }),
+        }
+    }
+    days
+}
+
+/// The cursor is the key of the page's last task, which is all that's needed to continue
+/// after it in any sort, even if that task has since been changed or deleted
+/// Its title is hex so that the cursor needs no escaping in a URL
+fn encode_cursor(key: &TaskKey) -> String {
+    let title: String = key.title.bytes().map(|b| format!("{:02x}", b)).collect();
+    format!(
+        "{}.{}.{}.{}.{}.{}.{}",
+        key.date, key.start, key.neg_end, key.position, key.neg_priority, key.task_id, title
+    )
+}
+
+fn decode_cursor(cursor: &str) -> Option<TaskKey> {
+    let mut parts = cursor.split('.');
+    let mut next = || parts.next();
+    let date = next()?.parse().ok()?;
+    let start = next()?.parse().ok()?;
+    let neg_end = next()?.parse().ok()?;
+    let position = next()?.parse().ok()?;
+    let neg_priority = next()?.parse().ok()?;
+    let task_id = next()?.parse().ok()?;
+    let hex = next()?;
+    if next().is_some() || hex.len() % 2 != 0 {
+        return None;
+    }
+    let title = (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect::<Option<Vec<u8>>>()?;
+    Some(TaskKey {
+        date,
+        start,
+        neg_end,
+        position,
+        title: String::from_utf8(title).ok()?,
+        task_id,
+        neg_priority,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::routes::testing::{TestApp, task, test_apps};
+    use rical_types::{Priority, TaskData, TaskStatus};
+
+    /// GET `/tasks` with a query string, expecting success
+    async fn get_page(app: &TestApp, token: &str, query: &str) -> TaskPage {
+        let (status, body) = app
+            .request("GET", &format!("/tasks?{}", query), Some(token), None)
+            .await;
+        assert_eq!(status, StatusCode::OK, "{}: {}", app.name, query);
+        serde_json::from_value(body).unwrap()
+    }
+
+    fn titles(page: &TaskPage) -> Vec<String> {
+        match page {
+            TaskPage::List(list) => list.tasks.iter().map(|t| t.title.clone()).collect(),
+            TaskPage::ByDate(_) => panic!("Expected a list"),
+        }
+    }
+
+    #[test]
+    fn test_cursor_round_trip() {
+        let key = TaskKey {
+            date: 20250804,
+            start: 1440,
+            neg_end: -600,
+            position: -2,
+            title: "Lunch. With Ämma & co".to_string(),
+            task_id: 42,
+            neg_priority: -3,
+        };
+        assert_eq!(decode_cursor(&encode_cursor(&key)), Some(key));
+        for invalid in [
+            "",
+            "1.2.3.4.5.6",
+            "1.2.3.4.5.6.zz",
+            "1.2.3.4.5.6.6",
+            "1.2.3.4.5.6.61.5",
+        ] {
+            assert_eq!(decode_cursor(invalid), None, "{}", invalid);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_range_and_filters() {
+        for app in test_apps().await {
+            let token = app.new_user().await;
+            let other_token = app.new_user().await;
+            app.create_task(
+                &token,
+                &TaskData {
+                    month: 7,
+                    ..task(31, "July")
+                },
+            )
+            .await;
+            app.create_task(&token, &task(2, "Gym")).await;
+            app.create_task(
+                &token,
+                &TaskData {
+                    start_min: Some(9 * 60),
+                    description: Some("Bring the 50% OFF coupon".to_string()),
+                    ..task(1, "Shopping")
+                },
+            )
+            .await;
+            app.create_task(
+                &token,
+                &TaskData {
+                    complete: Some(true),
+                    status: Some(TaskStatus::Done),
+                    ..task(1, "Done")
+                },
+            )
+            .await;
+            app.create_task(
+                &token,
+                &TaskData {
+                    month: 9,
+                    ..task(1, "September")
+                },
+            )
+            .await;
+            app.create_task(&other_token, &task(1, "Someone else's"))
+                .await;
+
+            let range = "from=2025-08-01&to=2025-09-01";
+            let page = get_page(&app, &token, range).await;
+            assert_eq!(
+                titles(&page),
+                ["Shopping", "Done", "Gym", "September"],
+                "{}",
+                app.name
+            );
+            assert_eq!(page.next_cursor(), None, "{}", app.name);
+
+            for (filters, expected) in [
+                ("completed=true", vec!["Done"]),
+                ("completed=false&timed=false", vec!["Gym", "September"]),
+                ("timed=true", vec!["Shopping"]),
+                ("q=gy", vec!["Gym"]),
+                // Descriptions count, case doesn't, and LIKE's wildcards are just text
+                ("q=50%25%20off", vec!["Shopping"]),
+                ("q=5_", vec![]),
+            ] {
+                let page = get_page(&app, &token, &format!("{}&{}", range, filters)).await;
+                assert_eq!(titles(&page), expected, "{}: {}", app.name, filters);
+            }
+
+            let page = get_page(&app, &token, "from=2025-07-31&to=2025-07-31").await;
+            assert_eq!(titles(&page), ["July"], "{}", app.name);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_pages_in_each_sort() {
+        for app in test_apps().await {
+            let token = app.new_user().await;
+            for t in [
+                task(3, "b"),
+                task(1, "c"),
+                TaskData {
+                    start_min: Some(60),
+                    ..task(2, "a")
+                },
+                task(2, "a"),
+                TaskData {
+                    priority: Some(Priority::Urgent),
+                    ..task(2, "d")
+                },
+            ] {
+                app.create_task(&token, &t).await;
+            }
+
+            for (sort, expected) in [
+                ("date", ["c", "a", "a", "d", "b"]),
+                ("date_desc", ["b", "d", "a", "a", "c"]),
+                ("title", ["a", "a", "b", "c", "d"]),
+                ("priority", ["c", "d", "a", "a", "b"]),
+            ] {
+                // Two at a time, following the cursors
+                let mut seen = Vec::new();
+                let mut cursor = None;
+                for _ in 0..3 {
+                    let mut query = format!("from=2025-08-01&to=2025-08-31&sort={}&limit=2", sort);
+                    if let Some(cursor) = cursor {
+                        query += &format!("&cursor={}", cursor);
+                    }
+                    let page = get_page(&app, &token, &query).await;
+                    seen.extend(titles(&page));
+                    cursor = page.next_cursor().map(String::from);
+                }
+                assert_eq!(seen, expected, "{}: {}", app.name, sort);
+                assert_eq!(cursor, None, "{}: {}", app.name, sort);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_grouped_by_date() {
+        for app in test_apps().await {
+            let token = app.new_user().await;
+            for t in [task(4, "First"), task(4, "Second"), task(6, "Third")] {
+                app.create_task(&token, &t).await;
+            }
+
+            let page = get_page(
+                &app,
+                &token,
+                "from=2025-08-01&to=2025-08-31&layout=by_date&limit=2",
+            )
+            .await;
+            let TaskPage::ByDate(by_date) = page else {
+                panic!("Expected tasks by date");
+            };
+            assert_eq!(by_date.days.len(), 1, "{}", app.name);
+            assert_eq!(by_date.days[0].date, "2025-08-04", "{}", app.name);
+            assert_eq!(by_date.days[0].tasks.len(), 2, "{}", app.name);
+
+            let page = get_page(
+                &app,
+                &token,
+                &format!(
+                    "from=2025-08-01&to=2025-08-31&layout=by_date&cursor={}",
+                    by_date.next_cursor.unwrap()
+                ),
+            )
+            .await;
+            let TaskPage::ByDate(by_date) = page else {
+                panic!("Expected tasks by date");
+            };
+            assert_eq!(by_date.days.len(), 1, "{}", app.name);
+            assert_eq!(by_date.days[0].date, "2025-08-06", "{}", app.name);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_invalid_queries() {
+        for app in test_apps().await {
+            let token = app.new_user().await;
+            for query in [
+                "to=2025-08-31",
+                "from=2025-08-31&to=2025-08-01",
+                "from=2025-02-30&to=2025-03-01",
+                "from=2025-08-01&to=2025-08-31&limit=0",
+                "from=2025-08-01&to=2025-08-31&limit=501",
+                "from=2025-08-01&to=2025-08-31&cursor=nonsense",
+                "from=2025-08-01&to=2025-08-31&sort=title&layout=by_date",
+                "from=2025-08-01&to=2025-08-31&sort=random",
+            ] {
+                let (status, _) = app
+                    .request("GET", &format!("/tasks?{}", query), Some(&token), None)
+                    .await;
+                assert_eq!(status, StatusCode::BAD_REQUEST, "{}: {}", app.name, query);
+            }
+
+            let (status, _) = app
+                .request("GET", "/tasks?from=2025-08-01&to=2025-08-31", None, None)
+                .await;
+            assert_eq!(status, StatusCode::BAD_REQUEST, "{}", app.name);
+        }
+    }
+}
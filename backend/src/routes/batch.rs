@@ -0,0 +1,276 @@
+use axum::{Json, Router, extract::State, http::StatusCode, routing::post};
+use axum_extra::{
+    TypedHeader,
+    headers::{Authorization, authorization::Bearer},
+};
+use rical_types::{BatchResults, TaskBatch, TaskOperation, TokenScope};
+use std::sync::Arc;
+
+use crate::AppState;
+use crate::utils;
+
+// Many task changes in one request and one transaction, for bulk actions like moving a week
+
+/// The most operations one batch can hold
+const MAX_OPERATIONS: usize = 500;
+
+pub fn get_routes(state: &Arc<AppState>) -> Router {
+    Router::new()
+        .route("/", post(run_batch))
+        .with_state(state.clone())
+}
+
+#[utoipa::path(
+    post,
+    path = "/tasks/batch",
+    tag = "task",
+    request_body = TaskBatch,
+    security(("login_token" = []), ("api_token" = ["read_write"])),
+    responses(
+        (status = OK, description = "The result of each operation, and whether they were kept", body = BatchResults),
+        (status = BAD_REQUEST, description = "Too many operations"),
+        (status = UNAUTHORIZED, description = "Missing or invalid token"),
+        (status = FORBIDDEN, description = "The API token's scope doesn't allow this"),
+    )
+)]
+async fn run_batch(
+    TypedHeader(Authorization(bearer)): TypedHeader<Authorization<Bearer>>,
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<TaskBatch>,
+) -> (StatusCode, Json<Option<BatchResults>>) {
+    let account_id = match utils::authenticate(&state, bearer.token(), TokenScope::ReadWrite).await
+    {
+        Ok(id) => id,
+        Err(status) => {
+            return (status, Json(None));
+        }
+    };
+    if payload.operations.len() > MAX_OPERATIONS {
+        return (StatusCode::BAD_REQUEST, Json(None));
+    }
+    let results = match state
+        .storage
+        .run_batch(account_id, &payload.operations, payload.atomic)
+        .await
+    {
+        Ok(results) => results,
+        Err(err) => {
+            err.log("run the batch");
+            return (err.status_code(), Json(None));
+        }
+    };
+
+    let committed = !payload.atomic || results.iter().all(|result| result.is_ok());
+    if committed {
+        let created = payload
+            .operations
+            .iter()
+            .zip(&results)
+            .filter(|(operation, result)| {
+                matches!(operation, TaskOperation::Create { .. }) && result.is_ok()
+            })
+            .count();
+        state.metrics.tasks_created.inc_by(created as u64);
+    }
+    (
+        StatusCode::OK,
+        Json(Some(BatchResults { committed, results })),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::routes::testing::{TestApp, saved, task, test_apps};
+    use rical_types::{OperationResult, TaskData, TaskStatus};
+    use serde_json::{Value, json};
+
+    async fn get_task(app: &TestApp, token: &str, task_id: i64) -> Option<TaskData> {
+        let (status, body) = app
+            .request("GET", &format!("/task/{}", task_id), Some(token), None)
+            .await;
+        (status == StatusCode::OK).then(|| serde_json::from_value(body).unwrap())
+    }
+
+    fn parse_results(body: Value) -> BatchResults {
+        serde_json::from_value(body).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_batch_runs_every_operation() {
+        for app in test_apps().await {
+            let token = app.new_user().await;
+            let to_move = app.create_task(&token, &task(4, "Move me")).await;
+            let to_finish = app.create_task(&token, &task(4, "Finish me")).await;
+            let to_delete = app.create_task(&token, &task(4, "Delete me")).await;
+
+            let (status, body) = app
+                .request(
+                    "POST",
+                    "/tasks/batch",
+                    Some(&token),
+                    Some(json!({
+                        "operations": [
+                            { "op": "create", "task": task(6, "New") },
+                            { "op": "update", "task_id": to_move, "task": task(5, "Moved") },
+                            { "op": "complete", "task_id": to_finish, "complete": true },
+                            { "op": "delete", "task_id": to_delete },
+                            { "op": "update", "task_id": to_move + 1000, "task": task(5, "Missing") },
+                            { "op": "create", "task": task(32, "Invalid") },
+                        ]
+                    })),
+                )
+                .await;
+            assert_eq!(status, StatusCode::OK, "{}", app.name);
+            let BatchResults { committed, results } = parse_results(body);
+            assert!(committed, "{}", app.name);
+            let OperationResult::Ok { task_id: created } = results[0] else {
+                panic!("{}: {:?}", app.name, results[0]);
+            };
+            assert_eq!(
+                results[1..],
+                [
+                    OperationResult::Ok { task_id: to_move },
+                    OperationResult::Ok { task_id: to_finish },
+                    OperationResult::Ok { task_id: to_delete },
+                    OperationResult::NotFound,
+                    OperationResult::Invalid,
+                ],
+                "{}",
+                app.name
+            );
+
+            // Failed operations don't undo the others
+            assert_eq!(
+                get_task(&app, &token, created).await,
+                Some(saved(task(6, "New"))),
+                "{}",
+                app.name
+            );
+            assert_eq!(
+                get_task(&app, &token, to_move).await,
+                Some(saved(task(5, "Moved"))),
+                "{}",
+                app.name
+            );
+            assert_eq!(
+                get_task(&app, &token, to_finish).await.unwrap().status,
+                Some(TaskStatus::Done),
+                "{}",
+                app.name
+            );
+            assert_eq!(
+                get_task(&app, &token, to_delete).await,
+                None,
+                "{}",
+                app.name
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_atomic_batch_is_all_or_nothing() {
+        for app in test_apps().await {
+            let token = app.new_user().await;
+            let other_token = app.new_user().await;
+            let task_id = app.create_task(&token, &task(4, "Task")).await;
+            let others_task = app.create_task(&other_token, &task(4, "Not yours")).await;
+
+            let (status, body) = app
+                .request(
+                    "POST",
+                    "/tasks/batch",
+                    Some(&token),
+                    Some(json!({
+                        "atomic": true,
+                        "operations": [
+                            { "op": "complete", "task_id": task_id, "complete": true },
+                            { "op": "create", "task": task(5, "Created") },
+                            { "op": "delete", "task_id": task_id },
+                            { "op": "complete", "task_id": others_task, "complete": true },
+                            { "op": "create", "task": task(6, "Never created") },
+                        ]
+                    })),
+                )
+                .await;
+            assert_eq!(status, StatusCode::OK, "{}", app.name);
+            let BatchResults { committed, results } = parse_results(body);
+            assert!(!committed, "{}", app.name);
+            assert_eq!(
+                results[3..],
+                [OperationResult::NotFound, OperationResult::Skipped],
+                "{}",
+                app.name
+            );
+            // Neither the task nor the other account's task changed, and nothing was created
+            assert_eq!(
+                get_task(&app, &token, task_id).await,
+                Some(saved(task(4, "Task"))),
+                "{}",
+                app.name
+            );
+            assert_eq!(
+                get_task(&app, &other_token, others_task)
+                    .await
+                    .unwrap()
+                    .status,
+                Some(TaskStatus::Todo),
+                "{}",
+                app.name
+            );
+            let (_, body) = app
+                .request(
+                    "GET",
+                    "/tasks?from=2025-08-01&to=2025-08-31",
+                    Some(&token),
+                    None,
+                )
+                .await;
+            assert_eq!(body["tasks"].as_array().unwrap().len(), 1, "{}", app.name);
+
+            // Without failures, an atomic batch is kept
+            let (status, body) = app
+                .request(
+                    "POST",
+                    "/tasks/batch",
+                    Some(&token),
+                    Some(json!({
+                        "atomic": true,
+                        "operations": [{ "op": "delete", "task_id": task_id }]
+                    })),
+                )
+                .await;
+            assert_eq!(status, StatusCode::OK, "{}", app.name);
+            assert!(parse_results(body).committed, "{}", app.name);
+            assert_eq!(get_task(&app, &token, task_id).await, None, "{}", app.name);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_batch_limits() {
+        for app in test_apps().await {
+            let token = app.new_user().await;
+            let (status, body) = app
+                .request(
+                    "POST",
+                    "/tasks/batch",
+                    Some(&token),
+                    Some(json!({ "operations": [] })),
+                )
+                .await;
+            assert_eq!(status, StatusCode::OK, "{}", app.name);
+            assert!(parse_results(body).results.is_empty(), "{}", app.name);
+
+            let operations = vec![json!({ "op": "delete", "task_id": 1 }); MAX_OPERATIONS + 1];
+            let (status, _) = app
+                .request(
+                    "POST",
+                    "/tasks/batch",
+                    Some(&token),
+                    Some(json!({ "operations": operations })),
+                )
+                .await;
+            assert_eq!(status, StatusCode::BAD_REQUEST, "{}", app.name);
+        }
+    }
+}
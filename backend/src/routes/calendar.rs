@@ -1,6 +1,6 @@
 use axum::{
     Json, Router,
-    extract::{Path, State},
+    extract::{Path, Query, State},
     http::StatusCode,
     routing::get,
 };
@@ -8,12 +8,13 @@ use axum_extra::{
     TypedHeader,
     headers::{Authorization, authorization::Bearer},
 };
-use serde::Serialize;
-use sqlx;
+use rical_types::{
+    CalendarQuery, CalendarTasks, MAX_DAYS_PER_MONTH, TokenScope, parse_status_filter,
+    parse_tag_filter,
+};
 use std::sync::Arc;
 
 use crate::AppState;
-use crate::types::TaskDataWithId;
 use crate::utils;
 
 pub fn get_routes(state: &Arc<AppState>) -> Router {
@@ -22,55 +23,247 @@ pub fn get_routes(state: &Arc<AppState>) -> Router {
         .with_state(state.clone())
 }
 
-#[derive(Serialize)]
-struct Calendar {
-    days: Vec<Vec<TaskDataWithId>>,
-}
-
+#[utoipa::path(
+    get,
+    path = "/calendar/{year}/{month}",
+    tag = "calendar",
+    params(
+        ("year" = i32, Path),
+        ("month" = i32, Path, description = "1-indexed month"),
+        CalendarQuery,
+    ),
+    security(("login_token" = []), ("api_token" = ["read"])),
+    responses(
+        (status = OK, description = "The month's tasks, grouped by day", body = CalendarTasks),
+        (status = BAD_REQUEST, description = "An unknown status in `status`"),
+        (status = UNAUTHORIZED, description = "Missing or invalid token"),
+    )
+)]
 async fn get_calendar(
     TypedHeader(Authorization(bearer)): TypedHeader<Authorization<Bearer>>,
     State(state): State<Arc<AppState>>,
     Path((year, month)): Path<(i32, i32)>,
-) -> (StatusCode, Json<Option<Calendar>>) {
-    let account_id = match utils::verify_jwt(bearer.token()) {
-        Some(id) => id,
-        None => {
-            return (StatusCode::UNAUTHORIZED, Json(None));
+    Query(query): Query<CalendarQuery>,
+) -> (StatusCode, Json<Option<CalendarTasks>>) {
+    let account_id = match utils::authenticate(&state, bearer.token(), TokenScope::Read).await {
+        Ok(id) => id,
+        Err(status) => {
+            return (status, Json(None));
         }
     };
-    let all_tasks = match sqlx::query_as!(
-        TaskDataWithId,
-        r#"
-        SELECT year, month, day,
-        start_min, end_min, title, description, complete, task_id
-        FROM task WHERE year=$1 AND month=$2 AND account_id=$3
-        ORDER BY day, start_min, end_min DESC, title;
-    "#,
-        year,
-        month,
-        &account_id
-    )
-    .fetch_all(&state.db_pool)
-    .await
+    let tags = parse_tag_filter(query.tags.as_deref().unwrap_or_default());
+    let Some(statuses) = parse_status_filter(query.status.as_deref().unwrap_or_default()) else {
+        return (StatusCode::BAD_REQUEST, Json(None));
+    };
+    let all_tasks = match state
+        .storage
+        .get_month_tasks(account_id, year, month, query.order, &tags, &statuses)
+        .await
     {
         Ok(rows) => rows,
-        Err(_) => {
-            return (StatusCode::NOT_FOUND, Json(None));
+        Err(err) => {
+            err.log("get the month's tasks");
+            return (err.status_code(), Json(None));
         }
     };
 
-    const MAX_DAYS_PER_MONTH: usize = 31;
-
     // Split up the results by day so that the frontend can easily render them
-    let mut res: Calendar = Calendar { days: Vec::new() };
+    let mut res = CalendarTasks { days: Vec::new() };
     for _ in 0..MAX_DAYS_PER_MONTH {
         res.days.push(Vec::new());
     }
     for task in all_tasks {
-        // The DB should ensure task days would fit properly as indices here
+        // The storage should ensure task days would fit properly as indices here
         // Days are stored 1-indexed, so day 1 should be index 0
         res.days[(task.day - 1) as usize].push(task);
     }
 
     (StatusCode::OK, Json(Some(res)))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::routes::testing::{task, test_apps};
+    use rical_types::{Priority, TaskData, TaskStatus};
+
+    #[tokio::test]
+    async fn test_calendar_splits_tasks_by_day() {
+        for app in test_apps().await {
+            let token = app.new_user().await;
+            let other_token = app.new_user().await;
+            let first = app.create_task(&token, &task(1, "First")).await;
+            let last = app.create_task(&token, &task(31, "Last")).await;
+            let timed = app
+                .create_task(
+                    &token,
+                    &TaskData {
+                        start_min: Some(8 * 60),
+                        ..task(31, "Timed")
+                    },
+                )
+                .await;
+            // None of these belong in the account's August 2025
+            app.create_task(
+                &token,
+                &TaskData {
+                    month: 9,
+                    ..task(1, "September")
+                },
+            )
+            .await;
+            app.create_task(
+                &token,
+                &TaskData {
+                    year: 2024,
+                    ..task(1, "Last year")
+                },
+            )
+            .await;
+            app.create_task(&other_token, &task(1, "Someone else's"))
+                .await;
+
+            let (status, body) = app
+                .request("GET", "/calendar/2025/8", Some(&token), None)
+                .await;
+            assert_eq!(status, StatusCode::OK, "{}", app.name);
+            let calendar = serde_json::from_value::<CalendarTasks>(body).unwrap();
+            assert_eq!(calendar.days.len(), MAX_DAYS_PER_MONTH, "{}", app.name);
+            let ids =
+                |day: usize| -> Vec<i64> { calendar.days[day].iter().map(|t| t.task_id).collect() };
+            assert_eq!(ids(0), [first], "{}", app.name);
+            assert_eq!(ids(30), [timed, last], "{}", app.name);
+            assert!(
+                (1..30).all(|day| calendar.days[day].is_empty()),
+                "{}",
+                app.name
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_day_orders() {
+        for app in test_apps().await {
+            let token = app.new_user().await;
+            let timed = app
+                .create_task(
+                    &token,
+                    &TaskData {
+                        start_min: Some(9 * 60),
+                        ..task(4, "Timed")
+                    },
+                )
+                .await;
+            let second = app
+                .create_task(
+                    &token,
+                    &TaskData {
+                        position: 1,
+                        priority: Some(Priority::High),
+                        ..task(4, "A second")
+                    },
+                )
+                .await;
+            let first = app
+                .create_task(
+                    &token,
+                    &TaskData {
+                        priority: Some(Priority::Low),
+                        ..task(4, "B first")
+                    },
+                )
+                .await;
+
+            for (query, expected) in [
+                ("", [timed, first, second]),
+                ("?order=time", [timed, first, second]),
+                ("?order=priority", [second, first, timed]),
+            ] {
+                let (status, body) = app
+                    .request(
+                        "GET",
+                        &format!("/calendar/2025/8{}", query),
+                        Some(&token),
+                        None,
+                    )
+                    .await;
+                assert_eq!(status, StatusCode::OK, "{}: {}", app.name, query);
+                let calendar = serde_json::from_value::<CalendarTasks>(body).unwrap();
+                let ids: Vec<i64> = calendar.days[3].iter().map(|t| t.task_id).collect();
+                assert_eq!(ids, expected, "{}: {}", app.name, query);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_status_filter() {
+        for app in test_apps().await {
+            let token = app.new_user().await;
+            let todo = app.create_task(&token, &task(4, "To do")).await;
+            let started = app
+                .create_task(
+                    &token,
+                    &TaskData {
+                        status: Some(TaskStatus::InProgress),
+                        ..task(4, "Started")
+                    },
+                )
+                .await;
+            // Only `complete`, as clients from before statuses send it
+            let done = app
+                .create_task(
+                    &token,
+                    &TaskData {
+                        complete: Some(true),
+                        ..task(4, "Done")
+                    },
+                )
+                .await;
+
+            for (query, expected) in [
+                ("", vec![done, started, todo]),
+                ("?status=todo,in_progress", vec![started, todo]),
+                ("?status=done", vec![done]),
+                ("?status=In%20progress", vec![started]),
+                ("?status=cancelled", vec![]),
+            ] {
+                let (status, body) = app
+                    .request(
+                        "GET",
+                        &format!("/calendar/2025/8{}", query),
+                        Some(&token),
+                        None,
+                    )
+                    .await;
+                assert_eq!(status, StatusCode::OK, "{}: {}", app.name, query);
+                let calendar = serde_json::from_value::<CalendarTasks>(body).unwrap();
+                let ids: Vec<i64> = calendar.days[3].iter().map(|t| t.task_id).collect();
+                assert_eq!(ids, expected, "{}: {}", app.name, query);
+            }
+
+            let (status, _) = app
+                .request(
+                    "GET",
+                    "/calendar/2025/8?status=todo,nope",
+                    Some(&token),
+                    None,
+                )
+                .await;
+            assert_eq!(status, StatusCode::BAD_REQUEST, "{}", app.name);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_empty_month() {
+        for app in test_apps().await {
+            let token = app.new_user().await;
+            let (status, body) = app
+                .request("GET", "/calendar/2025/2", Some(&token), None)
+                .await;
+            assert_eq!(status, StatusCode::OK, "{}", app.name);
+            let calendar = serde_json::from_value::<CalendarTasks>(body).unwrap();
+            assert_eq!(calendar.days.len(), MAX_DAYS_PER_MONTH, "{}", app.name);
+            assert!(calendar.days.iter().all(Vec::is_empty), "{}", app.name);
+        }
+    }
+}
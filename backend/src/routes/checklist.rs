@@ -0,0 +1,386 @@
+use axum::{
+    Json, Router,
+    extract::{Path, State},
+    http::StatusCode,
+    routing::{delete, get, post, put},
+};
+use axum_extra::{
+    TypedHeader,
+    headers::{Authorization, authorization::Bearer},
+};
+use rical_types::{Checklist, ChecklistItemData, ChecklistItemId, TokenScope};
+use std::sync::Arc;
+
+use crate::AppState;
+use crate::utils;
+
+// The checklist items inside a task, for tasks that are small procedures of their own
+
+pub fn get_routes(state: &Arc<AppState>) -> Router {
+    Router::new()
+        .route("/", get(get_checklist))
+        .route("/", post(post_item))
+        .route("/{item_id}", put(put_item))
+        .route("/{item_id}", delete(delete_item))
+        .with_state(state.clone())
+}
+
+#[utoipa::path(
+    get,
+    path = "/task/{id}/checklist",
+    tag = "task",
+    params(("id" = i64, Path, description = "The ID of the task")),
+    security(("login_token" = []), ("api_token" = ["read"])),
+    responses(
+        (status = OK, description = "The task's checklist", body = Checklist),
+        (status = NOT_FOUND, description = "The account has no task with this ID"),
+        (status = UNAUTHORIZED, description = "Missing or invalid token"),
+    )
+)]
+async fn get_checklist(
+    TypedHeader(Authorization(bearer)): TypedHeader<Authorization<Bearer>>,
+    State(state): State<Arc<AppState>>,
+    Path(task_id): Path<i64>,
+) -> (StatusCode, Json<Option<Checklist>>) {
+    let account_id = match utils::authenticate(&state, bearer.token(), TokenScope::Read).await {
+        Ok(id) => id,
+        Err(status) => {
+            return (status, Json(None));
+        }
+    };
+    match state.storage.get_checklist(account_id, task_id).await {
+        Ok(Some(items)) => (StatusCode::OK, Json(Some(Checklist { items }))),
+        Ok(None) => (StatusCode::NOT_FOUND, Json(None)),
+        Err(err) => {
+            err.log("get the checklist");
+            (err.status_code(), Json(None))
+        }
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/task/{id}/checklist",
+    tag = "task",
+    params(("id" = i64, Path, description = "The ID of the task")),
+    request_body = ChecklistItemData,
+    security(("login_token" = []), ("api_token" = ["read_write"])),
+    responses(
+        (status = CREATED, description = "The ID of the new item", body = ChecklistItemId),
+        (status = NOT_FOUND, description = "The account has no task with this ID"),
+        (status = BAD_REQUEST, description = "The item is invalid"),
+        (status = UNAUTHORIZED, description = "Missing or invalid token"),
+        (status = FORBIDDEN, description = "The API token's scope doesn't allow this"),
+    )
+)]
+async fn post_item(
+    TypedHeader(Authorization(bearer)): TypedHeader<Authorization<Bearer>>,
+    State(state): State<Arc<AppState>>,
+    Path(task_id): Path<i64>,
+    Json(payload): Json<ChecklistItemData>,
+) -> (StatusCode, Json<Option<ChecklistItemId>>) {
+    let account_id = match utils::authenticate(&state, bearer.token(), TokenScope::ReadWrite).await
+    {
+        Ok(id) => id,
+        Err(status) => {
+            return (status, Json(None));
+        }
+    };
+    match state
+        .storage
+        .create_checklist_item(account_id, task_id, &payload)
+        .await
+    {
+        Ok(Some(item_id)) => (StatusCode::CREATED, Json(Some(ChecklistItemId { item_id }))),
+        Ok(None) => (StatusCode::NOT_FOUND, Json(None)),
+        Err(err) => {
+            err.log("create the checklist item");
+            (err.status_code(), Json(None))
+        }
+    }
+}
+
+#[utoipa::path(
+    put,
+    path = "/task/{id}/checklist/{item_id}",
+    tag = "task",
+    params(
+        ("id" = i64, Path, description = "The ID of the task"),
+        ("item_id" = i64, Path, description = "The ID of the item"),
+    ),
+    request_body = ChecklistItemData,
+    security(("login_token" = []), ("api_token" = ["read_write"])),
+    responses(
+        (status = OK, description = "The item was replaced"),
+        (status = NOT_FOUND, description = "The task has no item with this ID"),
+        (status = BAD_REQUEST, description = "The item is invalid"),
+        (status = UNAUTHORIZED, description = "Missing or invalid token"),
+        (status = FORBIDDEN, description = "The API token's scope doesn't allow this"),
+    )
+)]
+async fn put_item(
+    TypedHeader(Authorization(bearer)): TypedHeader<Authorization<Bearer>>,
+    State(state): State<Arc<AppState>>,
+    Path((task_id, item_id)): Path<(i64, i64)>,
+    Json(payload): Json<ChecklistItemData>,
+) -> StatusCode {
+    let account_id = match utils::authenticate(&state, bearer.token(), TokenScope::ReadWrite).await
+    {
+        Ok(id) => id,
+        Err(status) => {
+            return status;
+        }
+    };
+    match state
+        .storage
+        .update_checklist_item(account_id, task_id, item_id, &payload)
+        .await
+    {
+        Ok(true) => StatusCode::OK,
+        Ok(false) => StatusCode::NOT_FOUND,
+        Err(err) => {
+            err.log("update the checklist item");
+            err.status_code()
+        }
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/task/{id}/checklist/{item_id}",
+    tag = "task",
+    params(
+        ("id" = i64, Path, description = "The ID of the task"),
+        ("item_id" = i64, Path, description = "The ID of the item"),
+    ),
+    security(("login_token" = []), ("api_token" = ["read_write"])),
+    responses(
+        (status = OK, description = "The item was deleted (or never existed)"),
+        (status = UNAUTHORIZED, description = "Missing or invalid token"),
+        (status = FORBIDDEN, description = "The API token's scope doesn't allow this"),
+    )
+)]
+async fn delete_item(
+    TypedHeader(Authorization(bearer)): TypedHeader<Authorization<Bearer>>,
+    State(state): State<Arc<AppState>>,
+    Path((task_id, item_id)): Path<(i64, i64)>,
+) -> StatusCode {
+    let account_id = match utils::authenticate(&state, bearer.token(), TokenScope::ReadWrite).await
+    {
+        Ok(id) => id,
+        Err(status) => {
+            return status;
+        }
+    };
+    match state
+        .storage
+        .delete_checklist_item(account_id, task_id, item_id)
+        .await
+    {
+        Ok(_) => StatusCode::OK,
+        Err(err) => {
+            err.log("delete the checklist item");
+            err.status_code()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::routes::testing::{TestApp, task, test_apps};
+    use rical_types::{CalendarTasks, ChecklistProgress, TaskData, TaskStatus};
+    use serde_json::json;
+
+    async fn get_checklist(app: &TestApp, token: &str, task_id: i64) -> (StatusCode, Checklist) {
+        let (status, body) = app
+            .request(
+                "GET",
+                &format!("/task/{}/checklist", task_id),
+                Some(token),
+                None,
+            )
+            .await;
+        let checklist = serde_json::from_value(body).unwrap_or(Checklist { items: vec![] });
+        (status, checklist)
+    }
+
+    async fn add_item(app: &TestApp, token: &str, task_id: i64, text: &str) -> i64 {
+        let (status, body) = app
+            .request(
+                "POST",
+                &format!("/task/{}/checklist", task_id),
+                Some(token),
+                Some(json!({ "text": text })),
+            )
+            .await;
+        assert_eq!(status, StatusCode::CREATED, "{}", app.name);
+        serde_json::from_value::<ChecklistItemId>(body)
+            .unwrap()
+            .item_id
+    }
+
+    async fn tick(
+        app: &TestApp,
+        token: &str,
+        task_id: i64,
+        item_id: i64,
+        done: bool,
+    ) -> StatusCode {
+        app.request(
+            "PUT",
+            &format!("/task/{}/checklist/{}", task_id, item_id),
+            Some(token),
+            Some(json!({ "text": "Ticked", "done": done })),
+        )
+        .await
+        .0
+    }
+
+    async fn is_complete(app: &TestApp, token: &str, task_id: i64) -> bool {
+        let (_, body) = app
+            .request("GET", &format!("/task/{}", task_id), Some(token), None)
+            .await;
+        serde_json::from_value::<TaskData>(body).unwrap().complete == Some(true)
+    }
+
+    #[tokio::test]
+    async fn test_checklist_items() {
+        for app in test_apps().await {
+            let token = app.new_user().await;
+            let other_token = app.new_user().await;
+            let task_id = app.create_task(&token, &task(4, "Laundry")).await;
+            let wash = add_item(&app, &token, task_id, "Wash").await;
+            let dry = add_item(&app, &token, task_id, "Dry").await;
+            // Before the others
+            let (status, _) = app
+                .request(
+                    "POST",
+                    &format!("/task/{}/checklist", task_id),
+                    Some(&token),
+                    Some(json!({ "text": "Sort", "position": -1 })),
+                )
+                .await;
+            assert_eq!(status, StatusCode::CREATED, "{}", app.name);
+
+            assert_eq!(
+                tick(&app, &token, task_id, wash, true).await,
+                StatusCode::OK
+            );
+            let (status, checklist) = get_checklist(&app, &token, task_id).await;
+            assert_eq!(status, StatusCode::OK, "{}", app.name);
+            let texts: Vec<&str> = checklist.items.iter().map(|i| i.text.as_str()).collect();
+            assert_eq!(texts, ["Sort", "Ticked", "Dry"], "{}", app.name);
+            assert_eq!(
+                checklist.progress(),
+                ChecklistProgress { done: 1, total: 3 },
+                "{}",
+                app.name
+            );
+
+            // The calendar has the progress, and only for tasks with a checklist
+            app.create_task(&token, &task(4, "No checklist")).await;
+            let (_, body) = app
+                .request("GET", "/calendar/2025/8", Some(&token), None)
+                .await;
+            let calendar = serde_json::from_value::<CalendarTasks>(body).unwrap();
+            let progress: Vec<Option<ChecklistProgress>> =
+                calendar.days[3].iter().map(|t| t.checklist).collect();
+            assert_eq!(
+                progress,
+                [Some(ChecklistProgress { done: 1, total: 3 }), None],
+                "{}",
+                app.name
+            );
+
+            app.request(
+                "DELETE",
+                &format!("/task/{}/checklist/{}", task_id, dry),
+                Some(&token),
+                None,
+            )
+            .await;
+            assert_eq!(get_checklist(&app, &token, task_id).await.1.items.len(), 2);
+
+            // Other accounts can't see or change the checklist
+            let (status, _) = get_checklist(&app, &other_token, task_id).await;
+            assert_eq!(status, StatusCode::NOT_FOUND, "{}", app.name);
+            assert_eq!(
+                tick(&app, &other_token, task_id, wash, false).await,
+                StatusCode::NOT_FOUND,
+                "{}",
+                app.name
+            );
+            assert_eq!(
+                tick(&app, &token, task_id + 1000, wash, false).await,
+                StatusCode::NOT_FOUND,
+                "{}",
+                app.name
+            );
+
+            // Deleting the task deletes its checklist
+            app.request("DELETE", &format!("/task/{}", task_id), Some(&token), None)
+                .await;
+            assert_eq!(
+                tick(&app, &token, task_id, wash, false).await,
+                StatusCode::NOT_FOUND,
+                "{}",
+                app.name
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_auto_complete() {
+        for app in test_apps().await {
+            let token = app.new_user().await;
+            let auto = TaskData {
+                auto_complete: true,
+                ..task(4, "Auto")
+            };
+            let auto_id = app.create_task(&token, &auto).await;
+            let manual_id = app.create_task(&token, &task(4, "Manual")).await;
+            let first = add_item(&app, &token, auto_id, "First").await;
+            let second = add_item(&app, &token, auto_id, "Second").await;
+            let manual_item = add_item(&app, &token, manual_id, "Only").await;
+
+            tick(&app, &token, auto_id, first, true).await;
+            assert!(!is_complete(&app, &token, auto_id).await, "{}", app.name);
+            tick(&app, &token, auto_id, second, true).await;
+            assert!(is_complete(&app, &token, auto_id).await, "{}", app.name);
+            // An item that isn't done makes it not done again
+            add_item(&app, &token, auto_id, "Third").await;
+            assert!(!is_complete(&app, &token, auto_id).await, "{}", app.name);
+
+            tick(&app, &token, manual_id, manual_item, true).await;
+            assert!(!is_complete(&app, &token, manual_id).await, "{}", app.name);
+
+            // A cancelled task stays cancelled
+            let cancelled_id = app
+                .create_task(
+                    &token,
+                    &TaskData {
+                        status: Some(TaskStatus::Cancelled),
+                        ..auto
+                    },
+                )
+                .await;
+            let item = add_item(&app, &token, cancelled_id, "Only").await;
+            tick(&app, &token, cancelled_id, item, true).await;
+            let (_, body) = app
+                .request(
+                    "GET",
+                    &format!("/task/{}", cancelled_id),
+                    Some(&token),
+                    None,
+                )
+                .await;
+            assert_eq!(
+                serde_json::from_value::<TaskData>(body).unwrap().status,
+                Some(TaskStatus::Cancelled),
+                "{}",
+                app.name
+            );
+        }
+    }
+}
@@ -0,0 +1,311 @@
+use axum::{
+    Json, Router,
+    extract::{Path, State},
+    http::StatusCode,
+    routing::{delete, get, post},
+};
+use axum_extra::{
+    TypedHeader,
+    headers::{Authorization, authorization::Bearer},
+};
+use rical_types::{Dependencies, DependencyData, TokenScope};
+use std::sync::Arc;
+
+use crate::AppState;
+use crate::storage::StorageError;
+use crate::utils;
+
+// Links between tasks that can't start until others are done, for planning projects
+
+pub fn get_routes(state: &Arc<AppState>) -> Router {
+    Router::new()
+        .route("/", get(get_dependencies))
+        .route("/", post(post_dependency))
+        .route("/{depends_on}", delete(delete_dependency))
+        .with_state(state.clone())
+}
+
+#[utoipa::path(
+    get,
+    path = "/task/{id}/dependencies",
+    tag = "task",
+    params(("id" = i64, Path, description = "The ID of the task")),
+    security(("login_token" = []), ("api_token" = ["read"])),
+    responses(
+        (status = OK, description = "The tasks the task depends on, and the tasks that depend on it", body = Dependencies),
+        (status = NOT_FOUND, description = "The account has no task with this ID"),
+        (status = UNAUTHORIZED, description = "Missing or invalid token"),
+    )
+)]
+async fn get_dependencies(
+    TypedHeader(Authorization(bearer)): TypedHeader<Authorization<Bearer>>,
+    State(state): State<Arc<AppState>>,
+    Path(task_id): Path<i64>,
+) -> (StatusCode, Json<Option<Dependencies>>) {
+    let account_id = match utils::authenticate(&state, bearer.token(), TokenScope::Read).await {
+        Ok(id) => id,
+        Err(status) => {
+            return (status, Json(None));
+        }
+    };
+    match state.storage.get_dependencies(account_id, task_id).await {
+        Ok(Some(dependencies)) => (StatusCode::OK, Json(Some(dependencies))),
+        Ok(None) => (StatusCode::NOT_FOUND, Json(None)),
+        Err(err) => {
+            err.log("get the dependencies");
+            (err.status_code(), Json(None))
+        }
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/task/{id}/dependencies",
+    tag = "task",
+    params(("id" = i64, Path, description = "The ID of the task")),
+    request_body = DependencyData,
+    security(("login_token" = []), ("api_token" = ["read_write"])),
+    responses(
+        (status = OK, description = "The task depends on the other task (or already did)"),
+        (status = NOT_FOUND, description = "The account has no task with one of the IDs"),
+        (status = CONFLICT, description = "The other task already depends on this one, directly or not, or is the same task"),
+        (status = UNAUTHORIZED, description = "Missing or invalid token"),
+        (status = FORBIDDEN, description = "The API token's scope doesn't allow this"),
+    )
+)]
+async fn post_dependency(
+    TypedHeader(Authorization(bearer)): TypedHeader<Authorization<Bearer>>,
+    State(state): State<Arc<AppState>>,
+    Path(task_id): Path<i64>,
+    Json(payload): Json<DependencyData>,
+) -> StatusCode {
+    let account_id = match utils::authenticate(&state, bearer.token(), TokenScope::ReadWrite).await
+    {
+        Ok(id) => id,
+        Err(status) => {
+            return status;
+        }
+    };
+    match state
+        .storage
+        .add_dependency(account_id, task_id, payload.depends_on)
+        .await
+    {
+        Ok(true) => StatusCode::OK,
+        Ok(false) => StatusCode::NOT_FOUND,
+        Err(StorageError::Conflict) => StatusCode::CONFLICT,
+        Err(err) => {
+            err.log("add the dependency");
+            err.status_code()
+        }
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/task/{id}/dependencies/{depends_on}",
+    tag = "task",
+    params(
+        ("id" = i64, Path, description = "The ID of the task"),
+        ("depends_on" = i64, Path, description = "The ID of the task it depends on"),
+    ),
+    security(("login_token" = []), ("api_token" = ["read_write"])),
+    responses(
+        (status = OK, description = "The task doesn't depend on the other task anymore (or never did)"),
+        (status = UNAUTHORIZED, description = "Missing or invalid token"),
+        (status = FORBIDDEN, description = "The API token's scope doesn't allow this"),
+    )
+)]
+async fn delete_dependency(
+    TypedHeader(Authorization(bearer)): TypedHeader<Authorization<Bearer>>,
+    State(state): State<Arc<AppState>>,
+    Path((task_id, depends_on)): Path<(i64, i64)>,
+) -> StatusCode {
+    let account_id = match utils::authenticate(&state, bearer.token(), TokenScope::ReadWrite).await
+    {
+        Ok(id) => id,
+        Err(status) => {
+            return status;
+        }
+    };
+    match state
+        .storage
+        .remove_dependency(account_id, task_id, depends_on)
+        .await
+    {
+        Ok(_) => StatusCode::OK,
+        Err(err) => {
+            err.log("remove the dependency");
+            err.status_code()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::routes::testing::{TestApp, task, test_apps};
+    use rical_types::{CalendarTasks, TaskData, TaskStatus};
+    use serde_json::json;
+
+    async fn depend(app: &TestApp, token: &str, task_id: i64, depends_on: i64) -> StatusCode {
+        app.request(
+            "POST",
+            &format!("/task/{}/dependencies", task_id),
+            Some(token),
+            Some(json!({ "depends_on": depends_on })),
+        )
+        .await
+        .0
+    }
+
+    async fn get_dependencies(app: &TestApp, token: &str, task_id: i64) -> Dependencies {
+        let (status, body) = app
+            .request(
+                "GET",
+                &format!("/task/{}/dependencies", task_id),
+                Some(token),
+                None,
+            )
+            .await;
+        assert_eq!(status, StatusCode::OK, "{}", app.name);
+        serde_json::from_value(body).unwrap()
+    }
+
+    async fn get_calendar(app: &TestApp, token: &str) -> CalendarTasks {
+        let (_, body) = app
+            .request("GET", "/calendar/2025/8", Some(token), None)
+            .await;
+        serde_json::from_value(body).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_dependencies() {
+        for app in test_apps().await {
+            let token = app.new_user().await;
+            let a = app.create_task(&token, &task(4, "A")).await;
+            let b = app.create_task(&token, &task(5, "B")).await;
+            let c = app.create_task(&token, &task(6, "C")).await;
+
+            assert_eq!(
+                depend(&app, &token, b, a).await,
+                StatusCode::OK,
+                "{}",
+                app.name
+            );
+            assert_eq!(
+                depend(&app, &token, c, b).await,
+                StatusCode::OK,
+                "{}",
+                app.name
+            );
+            // Adding it again changes nothing
+            assert_eq!(
+                depend(&app, &token, c, b).await,
+                StatusCode::OK,
+                "{}",
+                app.name
+            );
+            assert_eq!(
+                get_dependencies(&app, &token, b).await,
+                Dependencies {
+                    depends_on: vec![a],
+                    dependents: vec![c],
+                },
+                "{}",
+                app.name
+            );
+
+            // Cycles, however long, are rejected
+            for (task_id, depends_on) in [(a, a), (a, b), (a, c)] {
+                assert_eq!(
+                    depend(&app, &token, task_id, depends_on).await,
+                    StatusCode::CONFLICT,
+                    "{}",
+                    app.name
+                );
+            }
+            assert_eq!(
+                depend(&app, &token, a, c + 1000).await,
+                StatusCode::NOT_FOUND,
+                "{}",
+                app.name
+            );
+            let other_token = app.new_user().await;
+            let others = app.create_task(&other_token, &task(4, "Not yours")).await;
+            assert_eq!(
+                depend(&app, &token, a, others).await,
+                StatusCode::NOT_FOUND,
+                "{}",
+                app.name
+            );
+
+            let (status, _) = app
+                .request(
+                    "DELETE",
+                    &format!("/task/{}/dependencies/{}", c, b),
+                    Some(&token),
+                    None,
+                )
+                .await;
+            assert_eq!(status, StatusCode::OK, "{}", app.name);
+            assert_eq!(
+                get_dependencies(&app, &token, c).await,
+                Dependencies::default(),
+                "{}",
+                app.name
+            );
+
+            // Deleting a task takes its links with it
+            app.request("DELETE", &format!("/task/{}", a), Some(&token), None)
+                .await;
+            assert_eq!(
+                get_dependencies(&app, &token, b).await,
+                Dependencies::default(),
+                "{}",
+                app.name
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_blocked_tasks() {
+        for app in test_apps().await {
+            let token = app.new_user().await;
+            let first_task = task(10, "First");
+            let first = app.create_task(&token, &first_task).await;
+            let later_task = task(20, "Later");
+            let later = app.create_task(&token, &later_task).await;
+            let blocked = app.create_task(&token, &task(15, "Blocked")).await;
+            depend(&app, &token, blocked, first).await;
+            depend(&app, &token, blocked, later).await;
+
+            let month = get_calendar(&app, &token).await;
+            let blocked_task = &month.days[14][0];
+            assert_eq!(blocked_task.blocked_by, [first, later], "{}", app.name);
+            assert_eq!(blocked_task.later_dependencies, [later], "{}", app.name);
+            assert!(!month.days[9][0].is_blocked(), "{}", app.name);
+
+            // Done and cancelled tasks don't block anything
+            for (task_id, status, data) in [
+                (first, TaskStatus::Done, first_task),
+                (later, TaskStatus::Cancelled, later_task),
+            ] {
+                let (status, _) = app
+                    .request(
+                        "PUT",
+                        &format!("/task/{}", task_id),
+                        Some(&token),
+                        Some(json!(TaskData {
+                            status: Some(status),
+                            ..data
+                        })),
+                    )
+                    .await;
+                assert_eq!(status, StatusCode::OK, "{}", app.name);
+            }
+            let month = get_calendar(&app, &token).await;
+            assert!(!month.days[14][0].is_blocked(), "{}", app.name);
+        }
+    }
+}
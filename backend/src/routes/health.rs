@@ -0,0 +1,143 @@
+use axum::{
+    Json, Router,
+    extract::State,
+    http::{StatusCode, header},
+    routing::get,
+};
+use prometheus::TEXT_FORMAT;
+use rical_types::VersionInfo;
+use std::sync::Arc;
+
+use crate::AppState;
+
+// Probes for load balancers and monitoring, which need no authentication
+
+pub fn get_routes(state: &Arc<AppState>) -> Router {
+    Router::new()
+        .route("/healthz", get(healthz))
+        .route("/readyz", get(readyz))
+        .route("/version", get(version))
+        .route("/metrics", get(metrics))
+        .with_state(state.clone())
+}
+
+/// Whether the process is up, without touching the database
+#[utoipa::path(
+    get,
+    path = "/healthz",
+    tag = "health",
+    responses((status = OK, description = "The server is running"))
+)]
+async fn healthz() -> StatusCode {
+    StatusCode::OK
+}
+
+/// Whether the server can handle requests: the database answers and its schema is up to date
+#[utoipa::path(
+    get,
+    path = "/readyz",
+    tag = "health",
+    responses(
+        (status = OK, description = "The server is ready"),
+        (status = SERVICE_UNAVAILABLE, description = "The database is unreachable or not migrated"),
+    )
+)]
+async fn readyz(State(state): State<Arc<AppState>>) -> StatusCode {
+    match state.storage.check_ready().await {
+        Ok(()) => StatusCode::OK,
+        Err(err) => {
+            err.log("check that the database is ready");
+            StatusCode::SERVICE_UNAVAILABLE
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/version",
+    tag = "health",
+    responses((status = OK, description = "The versions of the backend and its API", body = VersionInfo))
+)]
+async fn version() -> Json<VersionInfo> {
+    Json(VersionInfo {
+        version: crate::VERSION.to_string(),
+        api_version: rical_types::API_VERSION.to_string(),
+    })
+}
+
+#[utoipa::path(
+    get,
+    path = "/metrics",
+    tag = "health",
+    responses(
+        (status = OK, description = "Request, database and usage metrics in Prometheus' text format", content_type = "text/plain")
+    )
+)]
+async fn metrics(
+    State(state): State<Arc<AppState>>,
+) -> ([(header::HeaderName, &'static str); 1], String) {
+    (
+        [(header::CONTENT_TYPE, TEXT_FORMAT)],
+        state.metrics.render(state.storage.pool_stats()),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::StatusCode;
+    use rical_types::VersionInfo;
+    use serde_json::json;
+
+    use crate::routes::testing::{task, test_apps};
+
+    #[tokio::test]
+    async fn test_probes() {
+        for app in test_apps().await {
+            let (status, _) = app.request("GET", "/healthz", None, None).await;
+            assert_eq!(status, StatusCode::OK, "{}", app.name);
+            let (status, _) = app.request("GET", "/readyz", None, None).await;
+            assert_eq!(status, StatusCode::OK, "{}", app.name);
+
+            let (status, body) = app.request("GET", "/version", None, None).await;
+            assert_eq!(status, StatusCode::OK, "{}", app.name);
+            let version: VersionInfo = serde_json::from_value(body).unwrap();
+            assert_eq!(version.version, env!("CARGO_PKG_VERSION"));
+            assert_eq!(version.api_version, rical_types::API_VERSION);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_metrics() {
+        for app in test_apps().await {
+            let token = app.new_user().await;
+            app.create_task(&token, &task(1, "Task")).await;
+            let (status, _) = app
+                .request(
+                    "POST",
+                    "/account/login",
+                    None,
+                    Some(json!({ "username": "nobody", "password": "password" })),
+                )
+                .await;
+            assert_eq!(status, StatusCode::NOT_FOUND, "{}", app.name);
+
+            let (status, metrics) = app.request_text("GET", "/metrics").await;
+            assert_eq!(status, StatusCode::OK, "{}", app.name);
+            for line in [
+                "rical_signups_total 1",
+                "rical_tasks_created_total 1",
+                r#"rical_logins_failed_total{reason="unknown_user"} 1"#,
+                r#"rical_http_requests_total{method="POST",route="/task",status="201"} 1"#,
+                r#"rical_http_request_duration_seconds_count{method="POST",route="/account/login"} 2"#,
+            ] {
+                assert!(
+                    metrics.lines().any(|l| l == line),
+                    "{}: no '{}' in\n{}",
+                    app.name,
+                    line,
+                    metrics
+                );
+            }
+        }
+    }
+}
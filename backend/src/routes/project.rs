@@ -0,0 +1,522 @@
+use axum::{
+    Json, Router,
+    extract::{Path, State},
+    http::StatusCode,
+    routing::{delete, get, post, put},
+};
+use axum_extra::{
+    TypedHeader,
+    headers::{Authorization, authorization::Bearer},
+};
+use rical_types::{
+    Project, ProjectData, ProjectId, ProjectList, ProjectTasks, RicalDate, TokenScope,
+};
+use std::sync::Arc;
+
+use crate::AppState;
+use crate::storage::{StorageResult, date_number};
+use crate::utils;
+
+// An account's projects, which group tasks on any number of dates and track how far along they are
+
+pub fn get_routes(state: &Arc<AppState>) -> Router {
+    Router::new()
+        .route("/", get(list_projects))
+        .route("/", post(post_project))
+        .route("/{id}", get(get_project))
+        .route("/{id}", put(put_project))
+        .route("/{id}", delete(delete_project))
+        .route("/{id}/tasks", get(get_project_tasks))
+        .with_state(state.clone())
+}
+
+/// Today in the account's time zone, as a `date_number`, which tasks are overdue before
+async fn today(state: &AppState, account_id: i64) -> StorageResult<i32> {
+    let utc_offset_min = utils::utc_offset_min(state, account_id).await?;
+    let today = RicalDate::at_unix(utils::now_unix(), utc_offset_min);
+    Ok(date_number(
+        today.year,
+        today.month as i32,
+        today.day as i32,
+    ))
+}
+
+#[utoipa::path(
+    get,
+    path = "/projects",
+    tag = "project",
+    security(("login_token" = []), ("api_token" = ["read"])),
+    responses(
+        (status = OK, description = "The account's projects with their progress", body = ProjectList),
+        (status = UNAUTHORIZED, description = "Missing or invalid token"),
+    )
+)]
+async fn list_projects(
+    TypedHeader(Authorization(bearer)): TypedHeader<Authorization<Bearer>>,
+    State(state): State<Arc<AppState>>,
+) -> (StatusCode, Json<Option<ProjectList>>) {
+    let account_id = match utils::authenticate(&state, bearer.token(), TokenScope::Read).await {
+        Ok(id) => id,
+        Err(status) => {
+            return (status, Json(None));
+        }
+    };
+    let projects = match today(&state, account_id).await {
+        Ok(today) => state.storage.list_projects(account_id, today).await,
+        Err(err) => Err(err),
+    };
+    match projects {
+        Ok(projects) => (StatusCode::OK, Json(Some(ProjectList { projects }))),
+        Err(err) => {
+            err.log("list the projects");
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(None))
+        }
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/projects",
+    tag = "project",
+    request_body = ProjectData,
+    security(("login_token" = []), ("api_token" = ["read_write"])),
+    responses(
+        (status = CREATED, description = "The ID of the new project", body = ProjectId),
+        (status = BAD_REQUEST, description = "The name is empty or the due date is invalid"),
+        (status = UNAUTHORIZED, description = "Missing or invalid token"),
+        (status = FORBIDDEN, description = "The API token's scope doesn't allow this"),
+    )
+)]
+async fn post_project(
+    TypedHeader(Authorization(bearer)): TypedHeader<Authorization<Bearer>>,
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<ProjectData>,
+) -> (StatusCode, Json<Option<ProjectId>>) {
+    let account_id = match utils::authenticate(&state, bearer.token(), TokenScope::ReadWrite).await
+    {
+        Ok(id) => id,
+        Err(status) => {
+            return (status, Json(None));
+        }
+    };
+    match state.storage.create_project(account_id, &payload).await {
+        Ok(project_id) => (StatusCode::CREATED, Json(Some(ProjectId { project_id }))),
+        Err(err) => {
+            err.log("create the project");
+            (err.status_code(), Json(None))
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/projects/{id}",
+    tag = "project",
+    params(("id" = i64, Path, description = "The ID of the project")),
+    security(("login_token" = []), ("api_token" = ["read"])),
+    responses(
+        (status = OK, description = "The project with its progress", body = Project),
+        (status = NOT_FOUND, description = "The account has no project with this ID"),
+        (status = UNAUTHORIZED, description = "Missing or invalid token"),
+    )
+)]
+async fn get_project(
+    TypedHeader(Authorization(bearer)): TypedHeader<Authorization<Bearer>>,
+    State(state): State<Arc<AppState>>,
+    Path(project_id): Path<i64>,
+) -> (StatusCode, Json<Option<Project>>) {
+    let account_id = match utils::authenticate(&state, bearer.token(), TokenScope::Read).await {
+        Ok(id) => id,
+        Err(status) => {
+            return (status, Json(None));
+        }
+    };
+    let project = match today(&state, account_id).await {
+        Ok(today) => {
+            state
+                .storage
+                .get_project(account_id, project_id, today)
+                .await
+        }
+        Err(err) => Err(err),
+    };
+    match project {
+        Ok(Some(project)) => (StatusCode::OK, Json(Some(project))),
+        Ok(None) => (StatusCode::NOT_FOUND, Json(None)),
+        Err(err) => {
+            err.log("get the project");
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(None))
+        }
+    }
+}
+
+#[utoipa::path(
+    put,
+    path = "/projects/{id}",
+    tag = "project",
+    params(("id" = i64, Path, description = "The ID of the project")),
+    request_body = ProjectData,
+    security(("login_token" = []), ("api_token" = ["read_write"])),
+    responses(
+        (status = OK, description = "The project was replaced"),
+        (status = NOT_FOUND, description = "The account has no project with this ID"),
+        (status = BAD_REQUEST, description = "The name is empty or the due date is invalid"),
+        (status = UNAUTHORIZED, description = "Missing or invalid token"),
+        (status = FORBIDDEN, description = "The API token's scope doesn't allow this"),
+    )
+)]
+async fn put_project(
+    TypedHeader(Authorization(bearer)): TypedHeader<Authorization<Bearer>>,
+    State(state): State<Arc<AppState>>,
+    Path(project_id): Path<i64>,
+    Json(payload): Json<ProjectData>,
+) -> StatusCode {
+    let account_id = match utils::authenticate(&state, bearer.token(), TokenScope::ReadWrite).await
+    {
+        Ok(id) => id,
+        Err(status) => {
+            return status;
+        }
+    };
+    match state
+        .storage
+        .update_project(account_id, project_id, &payload)
+        .await
+    {
+        Ok(true) => StatusCode::OK,
+        Ok(false) => StatusCode::NOT_FOUND,
+        Err(err) => {
+            err.log("update the project");
+            err.status_code()
+        }
+    }
+}
+
+/// Delete a project, keeping its tasks (which aren't in a project anymore)
+#[utoipa::path(
+    delete,
+    path = "/projects/{id}",
+    tag = "project",
+    params(("id" = i64, Path, description = "The ID of the project")),
+    security(("login_token" = []), ("api_token" = ["read_write"])),
+    responses(
+        (status = OK, description = "The project was deleted"),
+        (status = NOT_FOUND, description = "The account has no project with this ID"),
+        (status = UNAUTHORIZED, description = "Missing or invalid token"),
+        (status = FORBIDDEN, description = "The API token's scope doesn't allow this"),
+    )
+)]
+async fn delete_project(
+    TypedHeader(Authorization(bearer)): TypedHeader<Authorization<Bearer>>,
+    State(state): State<Arc<AppState>>,
+    Path(project_id): Path<i64>,
+) -> StatusCode {
+    let account_id = match utils::authenticate(&state, bearer.token(), TokenScope::ReadWrite).await
+    {
+        Ok(id) => id,
+        Err(status) => {
+            return status;
+        }
+    };
+    match state.storage.delete_project(account_id, project_id).await {
+        Ok(true) => StatusCode::OK,
+        Ok(false) => StatusCode::NOT_FOUND,
+        Err(err) => {
+            err.log("delete the project");
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    }
+}
+
+/// A project's tasks as a timeline, by date and then in calendar order
+#[utoipa::path(
+    get,
+    path = "/projects/{id}/tasks",
+    tag = "project",
+    params(("id" = i64, Path, description = "The ID of the project")),
+    security(("login_token" = []), ("api_token" = ["read"])),
+    responses(
+        (status = OK, description = "The project's tasks", body = ProjectTasks),
+        (status = NOT_FOUND, description = "The account has no project with this ID"),
+        (status = UNAUTHORIZED, description = "Missing or invalid token"),
+    )
+)]
+async fn get_project_tasks(
+    TypedHeader(Authorization(bearer)): TypedHeader<Authorization<Bearer>>,
+    State(state): State<Arc<AppState>>,
+    Path(project_id): Path<i64>,
+) -> (StatusCode, Json<Option<ProjectTasks>>) {
+    let account_id = match utils::authenticate(&state, bearer.token(), TokenScope::Read).await {
+        Ok(id) => id,
+        Err(status) => {
+            return (status, Json(None));
+        }
+    };
+    match state.storage.project_tasks(account_id, project_id).await {
+        Ok(Some(tasks)) => (StatusCode::OK, Json(Some(ProjectTasks { tasks }))),
+        Ok(None) => (StatusCode::NOT_FOUND, Json(None)),
+        Err(err) => {
+            err.log("get the project's tasks");
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(None))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::routes::testing::{TestApp, task, test_apps};
+    use rical_types::{TaskData, TaskStatus};
+    use serde_json::json;
+
+    async fn create(app: &TestApp, token: &str, body: serde_json::Value) -> i64 {
+        let (status, body) = app
+            .request("POST", "/projects", Some(token), Some(body))
+            .await;
+        assert_eq!(status, StatusCode::CREATED, "{}", app.name);
+        serde_json::from_value::<ProjectId>(body)
+            .unwrap()
+            .project_id
+    }
+
+    async fn list(app: &TestApp, token: &str) -> Vec<Project> {
+        let (status, body) = app.request("GET", "/projects", Some(token), None).await;
+        assert_eq!(status, StatusCode::OK, "{}", app.name);
+        serde_json::from_value::<ProjectList>(body)
+            .unwrap()
+            .projects
+    }
+
+    async fn project_tasks(app: &TestApp, token: &str, project_id: i64) -> Vec<String> {
+        let (status, body) = app
+            .request(
+                "GET",
+                &format!("/projects/{}/tasks", project_id),
+                Some(token),
+                None,
+            )
+            .await;
+        assert_eq!(status, StatusCode::OK, "{}", app.name);
+        serde_json::from_value::<ProjectTasks>(body)
+            .unwrap()
+            .tasks
+            .into_iter()
+            .map(|task| task.title)
+            .collect()
+    }
+
+    fn in_project(project_id: i64, task: TaskData) -> TaskData {
+        TaskData {
+            project_id: Some(Some(project_id)),
+            ..task
+        }
+    }
+
+    #[tokio::test]
+    async fn test_projects() {
+        for app in test_apps().await {
+            let token = app.new_user().await;
+            let other_token = app.new_user().await;
+            let later = create(
+                &app,
+                &token,
+                json!({ "name": "Later", "due": "2025-12-01" }),
+            )
+            .await;
+            let undated = create(&app, &token, json!({ "name": "Someday" })).await;
+            let sooner = create(
+                &app,
+                &token,
+                json!({ "name": "Move house", "description": "Boxes", "due": "2025-09-01" }),
+            )
+            .await;
+
+            let projects = list(&app, &token).await;
+            let ids: Vec<i64> = projects.iter().map(|p| p.project_id).collect();
+            assert_eq!(ids, vec![sooner, later, undated], "{}", app.name);
+            assert_eq!(
+                projects[0],
+                Project {
+                    project_id: sooner,
+                    name: "Move house".to_string(),
+                    description: Some("Boxes".to_string()),
+                    due: Some("2025-09-01".to_string()),
+                    progress: Default::default(),
+                },
+                "{}",
+                app.name
+            );
+            assert!(list(&app, &other_token).await.is_empty(), "{}", app.name);
+
+            let uri = format!("/projects/{}", undated);
+            let (status, _) = app
+                .request(
+                    "PUT",
+                    &uri,
+                    Some(&token),
+                    Some(json!({ "name": "Renamed", "due": "2026-01-01" })),
+                )
+                .await;
+            assert_eq!(status, StatusCode::OK, "{}", app.name);
+            let (_, body) = app.request("GET", &uri, Some(&token), None).await;
+            let project: Project = serde_json::from_value(body).unwrap();
+            assert_eq!(project.name, "Renamed", "{}", app.name);
+            assert_eq!(project.due.as_deref(), Some("2026-01-01"), "{}", app.name);
+
+            for body in [
+                json!({ "name": " " }),
+                json!({ "name": "Bad date", "due": "2025-02-30" }),
+                json!({ "name": "Bad format", "due": "2025/09/01" }),
+            ] {
+                let (status, _) = app
+                    .request("POST", "/projects", Some(&token), Some(body.clone()))
+                    .await;
+                assert_eq!(status, StatusCode::BAD_REQUEST, "{}: {}", app.name, body);
+            }
+            let (status, _) = app.request("GET", &uri, Some(&other_token), None).await;
+            assert_eq!(status, StatusCode::NOT_FOUND, "{}", app.name);
+            let (status, _) = app.request("DELETE", &uri, Some(&other_token), None).await;
+            assert_eq!(status, StatusCode::NOT_FOUND, "{}", app.name);
+            let (status, _) = app.request("DELETE", &uri, Some(&token), None).await;
+            assert_eq!(status, StatusCode::OK, "{}", app.name);
+            let (status, _) = app.request("GET", &uri, Some(&token), None).await;
+            assert_eq!(status, StatusCode::NOT_FOUND, "{}", app.name);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_project_progress_and_tasks() {
+        for app in test_apps().await {
+            let token = app.new_user().await;
+            let project_id = create(&app, &token, json!({ "name": "Launch" })).await;
+            let done = TaskData {
+                status: Some(TaskStatus::Done),
+                ..task(3, "Done")
+            };
+            let cancelled = TaskData {
+                status: Some(TaskStatus::Cancelled),
+                ..task(2, "Cancelled")
+            };
+            let timed = TaskData {
+                start_min: Some(9 * 60),
+                ..task(5, "Timed")
+            };
+            let future = TaskData {
+                year: 2100,
+                ..task(1, "Future")
+            };
+            for task in [
+                task(5, "Untimed"),
+                future,
+                done,
+                cancelled,
+                timed,
+                TaskData {
+                    month: 7,
+                    ..task(20, "July")
+                },
+            ] {
+                app.create_task(&token, &in_project(project_id, task)).await;
+            }
+            app.create_task(&token, &task(1, "Not in it")).await;
+
+            // August 2025 is in the past, so only the year 2100 task isn't overdue
+            let (_, body) = app
+                .request(
+                    "GET",
+                    &format!("/projects/{}", project_id),
+                    Some(&token),
+                    None,
+                )
+                .await;
+            let project: Project = serde_json::from_value(body).unwrap();
+            assert_eq!(
+                project.progress,
+                rical_types::ProjectProgress {
+                    done: 1,
+                    total: 5,
+                    overdue: 3,
+                },
+                "{}",
+                app.name
+            );
+            assert_eq!(
+                project_tasks(&app, &token, project_id).await,
+                vec!["July", "Cancelled", "Done", "Timed", "Untimed", "Future"],
+                "{}",
+                app.name
+            );
+            let other_token = app.new_user().await;
+            let (status, _) = app
+                .request(
+                    "GET",
+                    &format!("/projects/{}/tasks", project_id),
+                    Some(&other_token),
+                    None,
+                )
+                .await;
+            assert_eq!(status, StatusCode::NOT_FOUND, "{}", app.name);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_task_projects() {
+        for app in test_apps().await {
+            let token = app.new_user().await;
+            let other_token = app.new_user().await;
+            let project_id = create(&app, &token, json!({ "name": "Mine" })).await;
+            let others_project = create(&app, &other_token, json!({ "name": "Theirs" })).await;
+
+            // A task can only be in one of the account's own projects
+            for project in [others_project, project_id + others_project + 1000] {
+                let (status, _) = app
+                    .request(
+                        "POST",
+                        "/task",
+                        Some(&token),
+                        Some(json!(in_project(project, task(1, "Task")))),
+                    )
+                    .await;
+                assert_eq!(status, StatusCode::BAD_REQUEST, "{}", app.name);
+            }
+            let task_id = app.create_task(&token, &task(1, "Task")).await;
+            let uri = format!("/task/{}", task_id);
+            let (status, _) = app
+                .request(
+                    "PUT",
+                    &uri,
+                    Some(&token),
+                    Some(json!(in_project(others_project, task(1, "Task")))),
+                )
+                .await;
+            assert_eq!(status, StatusCode::BAD_REQUEST, "{}", app.name);
+            let (status, _) = app
+                .request(
+                    "PUT",
+                    &uri,
+                    Some(&token),
+                    Some(json!(in_project(project_id, task(1, "Task")))),
+                )
+                .await;
+            assert_eq!(status, StatusCode::OK, "{}", app.name);
+            let (_, body) = app.request("GET", &uri, Some(&token), None).await;
+            let saved: TaskData = serde_json::from_value(body).unwrap();
+            assert_eq!(saved.project_id, Some(Some(project_id)), "{}", app.name);
+
+            // Deleting the project keeps its tasks
+            let (status, _) = app
+                .request(
+                    "DELETE",
+                    &format!("/projects/{}", project_id),
+                    Some(&token),
+                    None,
+                )
+                .await;
+            assert_eq!(status, StatusCode::OK, "{}", app.name);
+            let (status, body) = app.request("GET", &uri, Some(&token), None).await;
+            assert_eq!(status, StatusCode::OK, "{}", app.name);
+            let saved: TaskData = serde_json::from_value(body).unwrap();
+            assert_eq!(saved.project_id, Some(None), "{}", app.name);
+        }
+    }
+}
@@ -0,0 +1,219 @@
+use axum::{Json, Router, extract::State, http::StatusCode, routing::post};
+use axum_extra::{
+    TypedHeader,
+    headers::{Authorization, authorization::Bearer},
+};
+use rical_types::{RescheduleTasks, RescheduledTasks, RicalDate, TokenScope};
+use std::sync::Arc;
+
+use crate::AppState;
+use crate::storage::{Reschedule, date_number};
+use crate::utils;
+
+// Moving every incomplete task in a range of dates to one date, e.g. rolling overdue tasks over to today
+
+pub fn get_routes(state: &Arc<AppState>) -> Router {
+    Router::new()
+        .route("/", post(reschedule))
+        .with_state(state.clone())
+}
+
+#[utoipa::path(
+    post,
+    path = "/tasks/reschedule",
+    tag = "task",
+    request_body = RescheduleTasks,
+    security(("login_token" = []), ("api_token" = ["read_write"])),
+    responses(
+        (status = OK, description = "How many tasks were moved", body = RescheduledTasks),
+        (status = BAD_REQUEST, description = "Invalid dates, or `from` is after `to`"),
+        (status = UNAUTHORIZED, description = "Missing or invalid token"),
+        (status = FORBIDDEN, description = "The API token's scope doesn't allow this"),
+    )
+)]
+async fn reschedule(
+    TypedHeader(Authorization(bearer)): TypedHeader<Authorization<Bearer>>,
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<RescheduleTasks>,
+) -> (StatusCode, Json<Option<RescheduledTasks>>) {
+    let account_id = match utils::authenticate(&state, bearer.token(), TokenScope::ReadWrite).await
+    {
+        Ok(id) => id,
+        Err(status) => {
+            return (status, Json(None));
+        }
+    };
+    let number = |date: &RicalDate| date_number(date.year, date.month as i32, date.day as i32);
+    let from = match &payload.from {
+        Some(from) => RicalDate::parse_iso(from).map(|date| number(&date)),
+        None => Some(i32::MIN),
+    };
+    let (Some(from), Some(to), Some(target)) = (
+        from,
+        RicalDate::parse_iso(&payload.to),
+        RicalDate::parse_iso(&payload.target),
+    ) else {
+        return (StatusCode::BAD_REQUEST, Json(None));
+    };
+    if from > number(&to) {
+        return (StatusCode::BAD_REQUEST, Json(None));
+    }
+
+    let reschedule = Reschedule {
+        from,
+        to: number(&to),
+        year: target.year,
+        month: target.month as i32,
+        day: target.day as i32,
+        keep_times: payload.keep_times,
+    };
+    match state
+        .storage
+        .reschedule_tasks(account_id, &reschedule)
+        .await
+    {
+        Ok(moved) => (StatusCode::OK, Json(Some(RescheduledTasks { moved }))),
+        Err(err) => {
+            err.log("reschedule the tasks");
+            (err.status_code(), Json(None))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::routes::testing::{TestApp, saved, task, test_apps};
+    use rical_types::{TaskData, TaskStatus};
+    use serde_json::{Value, json};
+
+    async fn reschedule(app: &TestApp, token: &str, body: Value) -> (StatusCode, Option<u64>) {
+        let (status, body) = app
+            .request("POST", "/tasks/reschedule", Some(token), Some(body))
+            .await;
+        let moved = serde_json::from_value::<RescheduledTasks>(body)
+            .ok()
+            .map(|res| res.moved);
+        (status, moved)
+    }
+
+    async fn get_task(app: &TestApp, token: &str, task_id: i64) -> TaskData {
+        let (_, body) = app
+            .request("GET", &format!("/task/{}", task_id), Some(token), None)
+            .await;
+        serde_json::from_value(body).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_reschedule_moves_incomplete_tasks() {
+        for app in test_apps().await {
+            let token = app.new_user().await;
+            let other_token = app.new_user().await;
+            let timed = TaskData {
+                start_min: Some(9 * 60),
+                end_min: Some(10 * 60),
+                ..task(2, "Timed")
+            };
+            let timed_id = app.create_task(&token, &timed).await;
+            let untimed_id = app.create_task(&token, &task(3, "Untimed")).await;
+            let done = TaskData {
+                complete: Some(true),
+                status: Some(TaskStatus::Done),
+                ..task(3, "Done")
+            };
+            let done_id = app.create_task(&token, &done).await;
+            let cancelled = TaskData {
+                status: Some(TaskStatus::Cancelled),
+                ..task(4, "Cancelled")
+            };
+            let cancelled_id = app.create_task(&token, &cancelled).await;
+            let outside_id = app.create_task(&token, &task(10, "Outside")).await;
+            let others_id = app.create_task(&other_token, &task(3, "Not yours")).await;
+
+            let (status, moved) = reschedule(
+                &app,
+                &token,
+                json!({ "from": "2025-08-01", "to": "2025-08-05", "target": "2025-08-20", "keep_times": true }),
+            )
+            .await;
+            assert_eq!(status, StatusCode::OK, "{}", app.name);
+            assert_eq!(moved, Some(2), "{}", app.name);
+            assert_eq!(
+                get_task(&app, &token, timed_id).await,
+                saved(TaskData {
+                    day: 20,
+                    ..timed.clone()
+                }),
+                "{}",
+                app.name
+            );
+            assert_eq!(
+                get_task(&app, &token, untimed_id).await.day,
+                20,
+                "{}",
+                app.name
+            );
+            assert_eq!(
+                get_task(&app, &token, done_id).await,
+                saved(done),
+                "{}",
+                app.name
+            );
+            assert_eq!(
+                get_task(&app, &token, cancelled_id).await,
+                saved(cancelled),
+                "{}",
+                app.name
+            );
+            assert_eq!(
+                get_task(&app, &token, outside_id).await.day,
+                10,
+                "{}",
+                app.name
+            );
+            assert_eq!(
+                get_task(&app, &other_token, others_id).await.day,
+                3,
+                "{}",
+                app.name
+            );
+
+            // Without `from`, every earlier date; without `keep_times`, the times are dropped
+            let (_, moved) = reschedule(
+                &app,
+                &token,
+                json!({ "to": "2025-08-31", "target": "2025-09-01" }),
+            )
+            .await;
+            assert_eq!(moved, Some(3), "{}", app.name);
+            assert_eq!(
+                get_task(&app, &token, timed_id).await,
+                saved(TaskData {
+                    year: 2025,
+                    month: 9,
+                    day: 1,
+                    start_min: None,
+                    end_min: None,
+                    ..timed
+                }),
+                "{}",
+                app.name
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_invalid_reschedules() {
+        for app in test_apps().await {
+            let token = app.new_user().await;
+            for body in [
+                json!({ "to": "2025-08-31", "target": "2025-02-30" }),
+                json!({ "to": "2025/08/31", "target": "2025-09-01" }),
+                json!({ "from": "2025-09-01", "to": "2025-08-31", "target": "2025-09-01" }),
+            ] {
+                let (status, _) = reschedule(&app, &token, body.clone()).await;
+                assert_eq!(status, StatusCode::BAD_REQUEST, "{}: {}", app.name, body);
+            }
+        }
+    }
+}
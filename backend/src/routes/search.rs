@@ -0,0 +1,273 @@
+use axum::{
+    Json, Router,
+    extract::{Query, State},
+    http::StatusCode,
+    routing::get,
+};
+use axum_extra::{
+    TypedHeader,
+    headers::{Authorization, authorization::Bearer},
+};
+use rical_types::{RicalDate, SearchQuery, SearchResults, TokenScope};
+use std::sync::Arc;
+
+use crate::AppState;
+use crate::storage::{SearchFilter, date_number};
+use crate::utils;
+
+// Full-text search over all of an account's tasks
+
+/// How many hits a search returns unless it asks for fewer or more
+const DEFAULT_LIMIT: u32 = 20;
+const MAX_LIMIT: u32 = 100;
+
+pub fn get_routes(state: &Arc<AppState>) -> Router {
+    Router::new()
+        .route("/", get(search))
+        .with_state(state.clone())
+}
+
+#[utoipa::path(
+    get,
+    path = "/search",
+    tag = "search",
+    params(SearchQuery),
+    security(("login_token" = []), ("api_token" = ["read"])),
+    responses(
+        (status = OK, description = "The matching tasks, best match first, with the matches highlighted", body = SearchResults),
+        (status = BAD_REQUEST, description = "An empty search, or invalid dates or limit"),
+        (status = UNAUTHORIZED, description = "Missing or invalid token"),
+    )
+)]
+async fn search(
+    TypedHeader(Authorization(bearer)): TypedHeader<Authorization<Bearer>>,
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<SearchQuery>,
+) -> (StatusCode, Json<Option<SearchResults>>) {
+    let account_id = match utils::authenticate(&state, bearer.token(), TokenScope::Read).await {
+        Ok(id) => id,
+        Err(status) => {
+            return (status, Json(None));
+        }
+    };
+    let parse_date = |date: &Option<String>| match date {
+        Some(date) => RicalDate::parse_iso(date)
+            .map(|date| Some(date_number(date.year, date.month as i32, date.day as i32))),
+        None => Some(None),
+    };
+    let (Some(from), Some(to)) = (parse_date(&query.from), parse_date(&query.to)) else {
+        return (StatusCode::BAD_REQUEST, Json(None));
+    };
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT);
+    if query.q.trim().is_empty()
+        || from.zip(to).is_some_and(|(from, to)| from > to)
+        || !(1..=MAX_LIMIT).contains(&limit)
+    {
+        return (StatusCode::BAD_REQUEST, Json(None));
+    }
+
+    let filter = SearchFilter {
+        text: &query.q,
+        from,
+        to,
+        completed: query.completed,
+        limit,
+    };
+    match state.storage.search_tasks(account_id, &filter).await {
+        Ok(hits) => (StatusCode::OK, Json(Some(SearchResults { hits }))),
+        Err(err) => {
+            err.log("search the tasks");
+            (err.status_code(), Json(None))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::routes::testing::{TestApp, task, test_apps};
+    use rical_types::{SearchHit, SnippetPart, TaskData, TaskStatus};
+
+    async fn search(app: &TestApp, token: &str, query: &str) -> Vec<SearchHit> {
+        let (status, body) = app
+            .request("GET", &format!("/search?{}", query), Some(token), None)
+            .await;
+        assert_eq!(status, StatusCode::OK, "{}: {}", app.name, query);
+        serde_json::from_value::<SearchResults>(body).unwrap().hits
+    }
+
+    fn titles(hits: &[SearchHit]) -> Vec<&str> {
+        hits.iter().map(|hit| hit.task.title.as_str()).collect()
+    }
+
+    fn matched(parts: &[SnippetPart]) -> Vec<&str> {
+        parts
+            .iter()
+            .filter(|part| part.matched)
+            .map(|part| part.text.as_str())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_search_ranks_and_highlights() {
+        for app in test_apps().await {
+            let token = app.new_user().await;
+            let other_token = app.new_user().await;
+            app.create_task(
+                &token,
+                &TaskData {
+                    description: Some("Call the dentist first".to_string()),
+                    ..task(3, "Pharmacy")
+                },
+            )
+            .await;
+            app.create_task(
+                &token,
+                &TaskData {
+                    description: Some("Ask about the filling".to_string()),
+                    ..task(4, "Dentist appointment")
+                },
+            )
+            .await;
+            app.create_task(&token, &task(5, "Gym")).await;
+            app.create_task(&other_token, &task(4, "Dentist")).await;
+
+            let hits = search(&app, &token, "q=dentist").await;
+            // A match in the title beats one in the description
+            assert_eq!(
+                titles(&hits),
+                ["Dentist appointment", "Pharmacy"],
+                "{}",
+                app.name
+            );
+            assert!(hits[0].rank > hits[1].rank, "{}", app.name);
+            assert_eq!(
+                hits[0].title,
+                [
+                    SnippetPart {
+                        text: "Dentist".to_string(),
+                        matched: true
+                    },
+                    SnippetPart {
+                        text: " appointment".to_string(),
+                        matched: false
+                    },
+                ],
+                "{}",
+                app.name
+            );
+            assert_eq!(
+                matched(hits[1].description.as_ref().unwrap()),
+                ["dentist"],
+                "{}",
+                app.name
+            );
+            assert!(matched(&hits[1].title).is_empty(), "{}", app.name);
+
+            // Every word must match, in the title or the description
+            let hits = search(&app, &token, "q=dentist%20filling").await;
+            assert_eq!(titles(&hits), ["Dentist appointment"], "{}", app.name);
+            let hits = search(&app, &token, "q=dentist%20gym").await;
+            assert!(hits.is_empty(), "{}", app.name);
+            let hits = search(&app, &token, "q=%22call%20the%20dentist%22").await;
+            assert_eq!(titles(&hits), ["Pharmacy"], "{}", app.name);
+            // Nothing that's typed is taken as syntax that could fail the search
+            search(&app, &token, "q=%22dentist%20OR%20(*%20-").await;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_search_filters() {
+        for app in test_apps().await {
+            let token = app.new_user().await;
+            app.create_task(&token, &task(1, "Haircut")).await;
+            app.create_task(
+                &token,
+                &TaskData {
+                    complete: Some(true),
+                    status: Some(TaskStatus::Done),
+                    ..task(20, "Haircut")
+                },
+            )
+            .await;
+            app.create_task(
+                &token,
+                &TaskData {
+                    year: 2024,
+                    ..task(20, "Haircut")
+                },
+            )
+            .await;
+
+            let days = |hits: &[SearchHit]| -> Vec<(i32, i32)> {
+                hits.iter()
+                    .map(|hit| (hit.task.year, hit.task.day))
+                    .collect()
+            };
+            // Equally good matches, latest first
+            let hits = search(&app, &token, "q=haircut").await;
+            assert_eq!(
+                days(&hits),
+                [(2025, 20), (2025, 1), (2024, 20)],
+                "{}",
+                app.name
+            );
+            let hits = search(&app, &token, "q=haircut&completed=false").await;
+            assert_eq!(days(&hits), [(2025, 1), (2024, 20)], "{}", app.name);
+            let hits = search(&app, &token, "q=haircut&from=2025-01-01&to=2025-08-10").await;
+            assert_eq!(days(&hits), [(2025, 1)], "{}", app.name);
+            let hits = search(&app, &token, "q=haircut&limit=1").await;
+            assert_eq!(days(&hits), [(2025, 20)], "{}", app.name);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_search_sees_changes() {
+        for app in test_apps().await {
+            let token = app.new_user().await;
+            let task_id = app.create_task(&token, &task(1, "Old name")).await;
+            let uri = format!("/task/{}", task_id);
+            app.request(
+                "PUT",
+                &uri,
+                Some(&token),
+                Some(serde_json::json!(task(1, "New name"))),
+            )
+            .await;
+            assert!(
+                search(&app, &token, "q=old").await.is_empty(),
+                "{}",
+                app.name
+            );
+            assert_eq!(search(&app, &token, "q=new").await.len(), 1, "{}", app.name);
+
+            app.request("DELETE", &uri, Some(&token), None).await;
+            assert!(
+                search(&app, &token, "q=new").await.is_empty(),
+                "{}",
+                app.name
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_invalid_searches() {
+        for app in test_apps().await {
+            let token = app.new_user().await;
+            for query in [
+                "q=",
+                "q=%20%20",
+                "from=2025-01-01",
+                "q=x&from=2025-13-01",
+                "q=x&from=2025-08-02&to=2025-08-01",
+                "q=x&limit=0",
+                "q=x&limit=101",
+            ] {
+                let (status, _) = app
+                    .request("GET", &format!("/search?{}", query), Some(&token), None)
+                    .await;
+                assert_eq!(status, StatusCode::BAD_REQUEST, "{}: {}", app.name, query);
+            }
+        }
+    }
+}
@@ -0,0 +1,398 @@
+use axum::{
+    Json, Router,
+    extract::{Path, State},
+    http::StatusCode,
+    routing::{delete, get, post, put},
+};
+use axum_extra::{
+    TypedHeader,
+    headers::{Authorization, authorization::Bearer},
+};
+use rical_types::{TagData, TagId, TagList, TokenScope};
+use std::sync::Arc;
+
+use crate::AppState;
+use crate::storage::StorageError;
+use crate::utils;
+
+// An account's tags, which tasks name to put themselves into categories across days
+
+pub fn get_routes(state: &Arc<AppState>) -> Router {
+    Router::new()
+        .route("/", get(list_tags))
+        .route("/", post(post_tag))
+        .route("/{id}", put(put_tag))
+        .route("/{id}", delete(delete_tag))
+        .with_state(state.clone())
+}
+
+#[utoipa::path(
+    get,
+    path = "/tags",
+    tag = "tag",
+    security(("login_token" = []), ("api_token" = ["read"])),
+    responses(
+        (status = OK, description = "The account's tags", body = TagList),
+        (status = UNAUTHORIZED, description = "Missing or invalid token"),
+    )
+)]
+async fn list_tags(
+    TypedHeader(Authorization(bearer)): TypedHeader<Authorization<Bearer>>,
+    State(state): State<Arc<AppState>>,
+) -> (StatusCode, Json<Option<TagList>>) {
+    let account_id = match utils::authenticate(&state, bearer.token(), TokenScope::Read).await {
+        Ok(id) => id,
+        Err(status) => {
+            return (status, Json(None));
+        }
+    };
+    match state.storage.list_tags(account_id).await {
+        Ok(tags) => (StatusCode::OK, Json(Some(TagList { tags }))),
+        Err(err) => {
+            err.log("list the tags");
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(None))
+        }
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/tags",
+    tag = "tag",
+    request_body = TagData,
+    security(("login_token" = []), ("api_token" = ["read_write"])),
+    responses(
+        (status = CREATED, description = "The ID of the new tag", body = TagId),
+        (status = CONFLICT, description = "The account already has a tag with this name"),
+        (status = BAD_REQUEST, description = "The name is invalid"),
+        (status = UNAUTHORIZED, description = "Missing or invalid token"),
+        (status = FORBIDDEN, description = "The API token's scope doesn't allow this"),
+    )
+)]
+async fn post_tag(
+    TypedHeader(Authorization(bearer)): TypedHeader<Authorization<Bearer>>,
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<TagData>,
+) -> (StatusCode, Json<Option<TagId>>) {
+    let account_id = match utils::authenticate(&state, bearer.token(), TokenScope::ReadWrite).await
+    {
+        Ok(id) => id,
+        Err(status) => {
+            return (status, Json(None));
+        }
+    };
+    match state.storage.create_tag(account_id, &payload).await {
+        Ok(tag_id) => (StatusCode::CREATED, Json(Some(TagId { tag_id }))),
+        Err(StorageError::Conflict) => (StatusCode::CONFLICT, Json(None)),
+        Err(err) => {
+            err.log("create the tag");
+            (err.status_code(), Json(None))
+        }
+    }
+}
+
+/// Rename or recolor a tag, which changes it on every task that has it
+#[utoipa::path(
+    put,
+    path = "/tags/{id}",
+    tag = "tag",
+    params(("id" = i64, Path, description = "The ID of the tag")),
+    request_body = TagData,
+    security(("login_token" = []), ("api_token" = ["read_write"])),
+    responses(
+        (status = OK, description = "The tag was replaced"),
+        (status = NOT_FOUND, description = "The account has no tag with this ID"),
+        (status = CONFLICT, description = "The account already has another tag with this name"),
+        (status = BAD_REQUEST, description = "The name is invalid"),
+        (status = UNAUTHORIZED, description = "Missing or invalid token"),
+        (status = FORBIDDEN, description = "The API token's scope doesn't allow this"),
+    )
+)]
+async fn put_tag(
+    TypedHeader(Authorization(bearer)): TypedHeader<Authorization<Bearer>>,
+    State(state): State<Arc<AppState>>,
+    Path(tag_id): Path<i64>,
+    Json(payload): Json<TagData>,
+) -> StatusCode {
+    let account_id = match utils::authenticate(&state, bearer.token(), TokenScope::ReadWrite).await
+    {
+        Ok(id) => id,
+        Err(status) => {
+            return status;
+        }
+    };
+    match state.storage.update_tag(account_id, tag_id, &payload).await {
+        Ok(true) => StatusCode::OK,
+        Ok(false) => StatusCode::NOT_FOUND,
+        Err(StorageError::Conflict) => StatusCode::CONFLICT,
+        Err(err) => {
+            err.log("update the tag");
+            err.status_code()
+        }
+    }
+}
+
+/// Delete a tag, taking it off every task that has it
+#[utoipa::path(
+    delete,
+    path = "/tags/{id}",
+    tag = "tag",
+    params(("id" = i64, Path, description = "The ID of the tag")),
+    security(("login_token" = []), ("api_token" = ["read_write"])),
+    responses(
+        (status = OK, description = "The tag was deleted"),
+        (status = NOT_FOUND, description = "The account has no tag with this ID"),
+        (status = UNAUTHORIZED, description = "Missing or invalid token"),
+        (status = FORBIDDEN, description = "The API token's scope doesn't allow this"),
+    )
+)]
+async fn delete_tag(
+    TypedHeader(Authorization(bearer)): TypedHeader<Authorization<Bearer>>,
+    State(state): State<Arc<AppState>>,
+    Path(tag_id): Path<i64>,
+) -> StatusCode {
+    let account_id = match utils::authenticate(&state, bearer.token(), TokenScope::ReadWrite).await
+    {
+        Ok(id) => id,
+        Err(status) => {
+            return status;
+        }
+    };
+    match state.storage.delete_tag(account_id, tag_id).await {
+        Ok(true) => StatusCode::OK,
+        Ok(false) => StatusCode::NOT_FOUND,
+        Err(err) => {
+            err.log("delete the tag");
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::routes::testing::{TestApp, task, test_apps};
+    use rical_types::{CalendarTasks, TagColor, TaskData, TaskList};
+    use serde_json::json;
+
+    async fn list(app: &TestApp, token: &str) -> TagList {
+        let (status, body) = app.request("GET", "/tags", Some(token), None).await;
+        assert_eq!(status, StatusCode::OK, "{}", app.name);
+        serde_json::from_value(body).unwrap()
+    }
+
+    fn tagged(day: i32, title: &str, tags: &[&str]) -> TaskData {
+        TaskData {
+            tags: Some(tags.iter().map(|tag| tag.to_string()).collect()),
+            ..task(day, title)
+        }
+    }
+
+    async fn task_tags(app: &TestApp, token: &str, task_id: i64) -> Vec<String> {
+        let (_, body) = app
+            .request("GET", &format!("/task/{}", task_id), Some(token), None)
+            .await;
+        serde_json::from_value::<TaskData>(body)
+            .unwrap()
+            .tags
+            .unwrap_or_default()
+    }
+
+    #[tokio::test]
+    async fn test_tags() {
+        for app in test_apps().await {
+            let token = app.new_user().await;
+            let other_token = app.new_user().await;
+            let (status, body) = app
+                .request(
+                    "POST",
+                    "/tags",
+                    Some(&token),
+                    Some(json!({ "name": "work", "color": "red" })),
+                )
+                .await;
+            assert_eq!(status, StatusCode::CREATED, "{}", app.name);
+            let work = serde_json::from_value::<TagId>(body).unwrap().tag_id;
+            for (name, expected) in [
+                ("work", StatusCode::CONFLICT),
+                ("two words", StatusCode::BAD_REQUEST),
+                ("", StatusCode::BAD_REQUEST),
+            ] {
+                let (status, _) = app
+                    .request("POST", "/tags", Some(&token), Some(json!({ "name": name })))
+                    .await;
+                assert_eq!(status, expected, "{}: {:?}", app.name, name);
+            }
+            // Another account can have its own tag of the same name
+            let (status, _) = app
+                .request(
+                    "POST",
+                    "/tags",
+                    Some(&other_token),
+                    Some(json!({ "name": "work" })),
+                )
+                .await;
+            assert_eq!(status, StatusCode::CREATED, "{}", app.name);
+
+            // Tasks create the tags they name that don't exist yet
+            let task_id = app
+                .create_task(&token, &tagged(4, "Report", &["work", "errand"]))
+                .await;
+            assert_eq!(
+                task_tags(&app, &token, task_id).await,
+                ["errand", "work"],
+                "{}",
+                app.name
+            );
+            let tags = list(&app, &token).await.tags;
+            let summary: Vec<(&str, TagColor)> =
+                tags.iter().map(|t| (t.name.as_str(), t.color)).collect();
+            assert_eq!(
+                summary,
+                [("errand", TagColor::Blue), ("work", TagColor::Red)],
+                "{}",
+                app.name
+            );
+            let (status, _) = app
+                .request(
+                    "POST",
+                    "/task",
+                    Some(&token),
+                    Some(json!(tagged(4, "Bad", &["not,valid"]))),
+                )
+                .await;
+            assert_eq!(status, StatusCode::BAD_REQUEST, "{}", app.name);
+
+            // Renaming a tag renames it on its tasks, and deleting it takes it off them
+            let (status, _) = app
+                .request(
+                    "PUT",
+                    &format!("/tags/{}", work),
+                    Some(&token),
+                    Some(json!({ "name": "job", "color": "green" })),
+                )
+                .await;
+            assert_eq!(status, StatusCode::OK, "{}", app.name);
+            assert_eq!(
+                task_tags(&app, &token, task_id).await,
+                ["errand", "job"],
+                "{}",
+                app.name
+            );
+            let (status, _) = app
+                .request(
+                    "DELETE",
+                    &format!("/tags/{}", work),
+                    Some(&other_token),
+                    None,
+                )
+                .await;
+            assert_eq!(status, StatusCode::NOT_FOUND, "{}", app.name);
+            let (status, _) = app
+                .request("DELETE", &format!("/tags/{}", work), Some(&token), None)
+                .await;
+            assert_eq!(status, StatusCode::OK, "{}", app.name);
+            assert_eq!(
+                task_tags(&app, &token, task_id).await,
+                ["errand"],
+                "{}",
+                app.name
+            );
+
+            // Updating a task replaces its tags
+            app.request(
+                "PUT",
+                &format!("/task/{}", task_id),
+                Some(&token),
+                Some(json!(tagged(4, "Report", &["health"]))),
+            )
+            .await;
+            assert_eq!(
+                task_tags(&app, &token, task_id).await,
+                ["health"],
+                "{}",
+                app.name
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_tag_filters() {
+        for app in test_apps().await {
+            let token = app.new_user().await;
+            let work = app.create_task(&token, &tagged(4, "Work", &["work"])).await;
+            let errand = app
+                .create_task(&token, &tagged(5, "Errand", &["errand", "home"]))
+                .await;
+            let untagged = app.create_task(&token, &task(5, "Untagged")).await;
+
+            for (tags, expected) in [
+                ("", vec![work, errand, untagged]),
+                ("work", vec![work]),
+                ("work,home", vec![work, errand]),
+                ("unknown", vec![]),
+            ] {
+                let (status, body) = app
+                    .request(
+                        "GET",
+                        &format!("/calendar/2025/8?tags={}", tags),
+                        Some(&token),
+                        None,
+                    )
+                    .await;
+                assert_eq!(status, StatusCode::OK, "{}: {}", app.name, tags);
+                let calendar = serde_json::from_value::<CalendarTasks>(body).unwrap();
+                let ids: Vec<i64> = calendar.days.iter().flatten().map(|t| t.task_id).collect();
+                assert_eq!(ids, expected, "{}: {}", app.name, tags);
+
+                let (status, body) = app
+                    .request(
+                        "GET",
+                        &format!("/tasks?from=2025-08-01&to=2025-08-31&tags={}", tags),
+                        Some(&token),
+                        None,
+                    )
+                    .await;
+                assert_eq!(status, StatusCode::OK, "{}: {}", app.name, tags);
+                let list = serde_json::from_value::<TaskList>(body).unwrap();
+                let ids: Vec<i64> = list.tasks.iter().map(|t| t.task_id).collect();
+                assert_eq!(ids, expected, "{}: {}", app.name, tags);
+            }
+
+            // The tasks come with their tags, even when paging by a cursor
+            let (_, body) = app
+                .request(
+                    "GET",
+                    "/tasks?from=2025-08-01&to=2025-08-31&tags=home,work&limit=1",
+                    Some(&token),
+                    None,
+                )
+                .await;
+            let first_page = serde_json::from_value::<TaskList>(body).unwrap();
+            assert_eq!(first_page.tasks[0].tags, ["work"], "{}", app.name);
+            let (_, body) = app
+                .request(
+                    "GET",
+                    &format!(
+                        "/tasks?from=2025-08-01&to=2025-08-31&tags=home,work&cursor={}",
+                        first_page.next_cursor.unwrap()
+                    ),
+                    Some(&token),
+                    None,
+                )
+                .await;
+            let second_page = serde_json::from_value::<TaskList>(body).unwrap();
+            let tasks: Vec<(i64, Vec<String>)> = second_page
+                .tasks
+                .into_iter()
+                .map(|t| (t.task_id, t.tags))
+                .collect();
+            assert_eq!(
+                tasks,
+                [(errand, vec!["errand".to_string(), "home".to_string()])],
+                "{}",
+                app.name
+            );
+        }
+    }
+}
@@ -8,11 +8,10 @@ use axum_extra::{
     TypedHeader,
     headers::{Authorization, authorization::Bearer},
 };
-use sqlx;
+use rical_types::{StatusHistory, TaskData, TaskId, TokenScope};
 use std::sync::Arc;
 
 use crate::AppState;
-use crate::types::{TaskData, TaskId};
 use crate::utils;
 
 pub fn get_routes(state: &Arc<AppState>) -> Router {
@@ -21,139 +20,585 @@ pub fn get_routes(state: &Arc<AppState>) -> Router {
         .route("/", post(post_task))
         .route("/{id}", put(put_task))
         .route("/{id}", delete(delete_task))
+        .route("/{id}/history", get(get_history))
         .with_state(state.clone())
 }
 
+#[utoipa::path(
+    get,
+    path = "/task/{id}",
+    tag = "task",
+    params(("id" = i64, Path, description = "The ID of the task")),
+    security(("login_token" = []), ("api_token" = ["read"])),
+    responses(
+        (status = OK, description = "The task", body = TaskData),
+        (status = NOT_FOUND, description = "The account has no task with this ID"),
+        (status = UNAUTHORIZED, description = "Missing or invalid token"),
+    )
+)]
 async fn get_task(
     TypedHeader(Authorization(bearer)): TypedHeader<Authorization<Bearer>>,
     State(state): State<Arc<AppState>>,
     Path(task_id): Path<i64>,
 ) -> (StatusCode, Json<Option<TaskData>>) {
     // TODO: refactor into middleware?
-    let account_id = match utils::verify_jwt(bearer.token()) {
-        Some(id) => id,
-        None => {
-            return (StatusCode::UNAUTHORIZED, Json(None));
+    let account_id = match utils::authenticate(&state, bearer.token(), TokenScope::Read).await {
+        Ok(id) => id,
+        Err(status) => {
+            return (status, Json(None));
         }
     };
-    let res = match sqlx::query_as!(
-        TaskData,
-        r#"
-        SELECT year, month, day,
-        start_min, end_min, title, description, complete
-        FROM task WHERE task_id=$1 AND account_id=$2;
-    "#,
-        &task_id,
-        &account_id
-    )
-    .fetch_one(&state.db_pool)
-    .await
-    {
-        Ok(row) => row,
-        Err(_) => {
+    let res = match state.storage.get_task(account_id, task_id).await {
+        Ok(Some(task)) => task,
+        Ok(None) => {
             return (StatusCode::NOT_FOUND, Json(None));
         }
+        Err(err) => {
+            err.log("get the task");
+            return (err.status_code(), Json(None));
+        }
     };
 
     (StatusCode::OK, Json(Some(res)))
 }
 
+#[utoipa::path(
+    post,
+    path = "/task",
+    tag = "task",
+    request_body = TaskData,
+    security(("login_token" = []), ("api_token" = ["read_write"])),
+    responses(
+        (status = CREATED, description = "The ID of the new task", body = TaskId),
+        (status = BAD_REQUEST, description = "The task is invalid"),
+        (status = UNAUTHORIZED, description = "Missing or invalid token"),
+        (status = FORBIDDEN, description = "The API token's scope doesn't allow this"),
+    )
+)]
 async fn post_task(
     TypedHeader(Authorization(bearer)): TypedHeader<Authorization<Bearer>>,
     State(state): State<Arc<AppState>>,
     Json(payload): Json<TaskData>,
 ) -> (StatusCode, Json<Option<TaskId>>) {
     // TODO: refactor into middleware?
-    let account_id = match utils::verify_jwt(bearer.token()) {
-        Some(id) => id,
-        None => {
-            return (StatusCode::UNAUTHORIZED, Json(None));
+    let account_id = match utils::authenticate(&state, bearer.token(), TokenScope::ReadWrite).await
+    {
+        Ok(id) => id,
+        Err(status) => {
+            return (status, Json(None));
         }
     };
-    let task_id = match sqlx::query_as!(
-        TaskId,
-        r#"
-        INSERT INTO task
-        (account_id, year, month, day, start_min, end_min, title, description, complete)
-        VALUES
-        ($1, $2, $3, $4, $5, $6, $7, $8, $9)
-        RETURNING task_id
-    "#,
-        account_id,
-        payload.year,
-        payload.month,
-        payload.day,
-        payload.start_min,
-        payload.end_min,
-        payload.title,
-        payload.description,
-        payload.complete
-    )
-    .fetch_one(&state.db_pool)
-    .await
-    {
-        Ok(result) => result,
-        Err(_) => {
-            return (StatusCode::BAD_REQUEST, Json(None));
+    let task_id = match state.storage.create_task(account_id, &payload).await {
+        Ok(task_id) => {
+            state.metrics.tasks_created.inc();
+            TaskId { task_id }
+        }
+        Err(err) => {
+            err.log("create the task");
+            return (err.status_code(), Json(None));
         }
     };
     (StatusCode::CREATED, Json(Some(task_id)))
 }
 
 /// Update a task and return the original
+#[utoipa::path(
+    put,
+    path = "/task/{id}",
+    tag = "task",
+    params(("id" = i64, Path, description = "The ID of the task")),
+    request_body = TaskData,
+    security(("login_token" = []), ("api_token" = ["read_write"])),
+    responses(
+        (status = OK, description = "The task as it was before the update", body = TaskData),
+        (status = BAD_REQUEST, description = "The task is invalid or doesn't exist"),
+        (status = UNAUTHORIZED, description = "Missing or invalid token"),
+        (status = FORBIDDEN, description = "The API token's scope doesn't allow this"),
+    )
+)]
 async fn put_task(
     TypedHeader(Authorization(bearer)): TypedHeader<Authorization<Bearer>>,
     State(state): State<Arc<AppState>>,
     Path(task_id): Path<i64>,
     Json(payload): Json<TaskData>,
 ) -> (StatusCode, Json<Option<TaskData>>) {
-    let account_id = match utils::verify_jwt(bearer.token()) {
-        Some(id) => id,
-        None => {
-            return (StatusCode::UNAUTHORIZED, Json(None));
+    let account_id = match utils::authenticate(&state, bearer.token(), TokenScope::ReadWrite).await
+    {
+        Ok(id) => id,
+        Err(status) => {
+            return (status, Json(None));
         }
     };
-    let res = match sqlx::query_as!(TaskData, r#"
-        UPDATE task x
-        SET year = $1, month = $2, day = $3, start_min = $4, end_min = $5, title = $6,
-            description = $7, complete = $8
-        FROM task y
-        WHERE x.task_id = y.task_id AND x.account_id = y.account_id
-        AND x.task_id = $9 AND x.account_id = $10
-        RETURNING y.year, y.month, y.day, y.start_min, y.end_min, y.title, y.description, y.complete;
-    "#, payload.year, payload.month, payload.day, payload.start_min, payload.end_min, payload.title, payload.description, payload.complete, task_id, account_id).fetch_one(&state.db_pool).await {
-        Ok(result) => result,
-        Err(_) => {
+    let res = match state
+        .storage
+        .update_task(account_id, task_id, &payload)
+        .await
+    {
+        Ok(Some(original)) => original,
+        Ok(None) => {
             return (StatusCode::BAD_REQUEST, Json(None));
         }
+        Err(err) => {
+            err.log("update the task");
+            return (err.status_code(), Json(None));
+        }
     };
     (StatusCode::OK, Json(Some(res)))
 }
 
+#[utoipa::path(
+    delete,
+    path = "/task/{id}",
+    tag = "task",
+    params(("id" = i64, Path, description = "The ID of the task")),
+    security(("login_token" = []), ("api_token" = ["read_write"])),
+    responses(
+        (status = OK, description = "The task was deleted (or never existed)"),
+        (status = UNAUTHORIZED, description = "Missing or invalid token"),
+        (status = FORBIDDEN, description = "The API token's scope doesn't allow this"),
+    )
+)]
 async fn delete_task(
     TypedHeader(Authorization(bearer)): TypedHeader<Authorization<Bearer>>,
     State(state): State<Arc<AppState>>,
     Path(task_id): Path<i64>,
 ) -> StatusCode {
-    let account_id = match utils::verify_jwt(bearer.token()) {
-        Some(id) => id,
-        None => {
-            return StatusCode::UNAUTHORIZED;
+    let account_id = match utils::authenticate(&state, bearer.token(), TokenScope::ReadWrite).await
+    {
+        Ok(id) => id,
+        Err(status) => {
+            return status;
         }
     };
-    match sqlx::query_as!(
-        TaskData,
-        r#"
-        DELETE FROM task
-        WHERE task_id = $1 AND account_id = $2;
-    "#,
-        task_id,
-        account_id
-    )
-    .execute(&state.db_pool)
-    .await
-    {
+    match state.storage.delete_task(account_id, task_id).await {
         Ok(_) => StatusCode::OK,
-        Err(_) => StatusCode::BAD_REQUEST,
+        Err(err) => {
+            err.log("delete the task");
+            err.status_code()
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/task/{id}/history",
+    tag = "task",
+    params(("id" = i64, Path, description = "The ID of the task")),
+    security(("login_token" = []), ("api_token" = ["read"])),
+    responses(
+        (status = OK, description = "Every change of the task's status, oldest first", body = StatusHistory),
+        (status = NOT_FOUND, description = "The account has no task with this ID"),
+        (status = UNAUTHORIZED, description = "Missing or invalid token"),
+    )
+)]
+async fn get_history(
+    TypedHeader(Authorization(bearer)): TypedHeader<Authorization<Bearer>>,
+    State(state): State<Arc<AppState>>,
+    Path(task_id): Path<i64>,
+) -> (StatusCode, Json<Option<StatusHistory>>) {
+    let account_id = match utils::authenticate(&state, bearer.token(), TokenScope::Read).await {
+        Ok(id) => id,
+        Err(status) => {
+            return (status, Json(None));
+        }
+    };
+    match state.storage.status_history(account_id, task_id).await {
+        Ok(Some(changes)) => (StatusCode::OK, Json(Some(StatusHistory { changes }))),
+        Ok(None) => (StatusCode::NOT_FOUND, Json(None)),
+        Err(err) => {
+            err.log("get the task's status history");
+            (err.status_code(), Json(None))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::routes::testing::{saved, task, test_apps};
+    use rical_types::{Priority, TaskStatus};
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_get_task() {
+        for app in test_apps().await {
+            let token = app.new_user().await;
+            let new_task = TaskData {
+                start_min: Some(9 * 60),
+                end_min: Some(10 * 60),
+                description: Some("Details".to_string()),
+                ..task(4, "Task")
+            };
+            let task_id = app.create_task(&token, &new_task).await;
+
+            let (status, body) = app
+                .request("GET", &format!("/task/{}", task_id), Some(&token), None)
+                .await;
+            assert_eq!(status, StatusCode::OK, "{}", app.name);
+            assert_eq!(
+                serde_json::from_value::<TaskData>(body).unwrap(),
+                saved(new_task),
+                "{}",
+                app.name
+            );
+
+            let (status, _) = app
+                .request(
+                    "GET",
+                    &format!("/task/{}", task_id + 1000),
+                    Some(&token),
+                    None,
+                )
+                .await;
+            assert_eq!(status, StatusCode::NOT_FOUND, "{}", app.name);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_post_invalid_task() {
+        for app in test_apps().await {
+            let token = app.new_user().await;
+            let task_id = app.create_task(&token, &task(1, "Task")).await;
+            for invalid in [
+                TaskData {
+                    month: 13,
+                    ..task(1, "Task")
+                },
+                task(32, "Task"),
+                TaskData {
+                    month: 2,
+                    day: 30,
+                    ..task(1, "Task")
+                },
+                TaskData {
+                    year: 300000,
+                    ..task(1, "Task")
+                },
+                TaskData {
+                    year: 0,
+                    ..task(1, "Task")
+                },
+                TaskData {
+                    start_min: Some(-1),
+                    ..task(1, "Task")
+                },
+                TaskData {
+                    end_min: Some(60),
+                    ..task(1, "Task")
+                },
+                TaskData {
+                    estimate_mins: Some(-5),
+                    ..task(1, "Task")
+                },
+            ] {
+                let (status, _) = app
+                    .request("POST", "/task", Some(&token), Some(json!(invalid)))
+                    .await;
+                assert_eq!(status, StatusCode::BAD_REQUEST, "{}", app.name);
+                let (status, _) = app
+                    .request(
+                        "PUT",
+                        &format!("/task/{}", task_id),
+                        Some(&token),
+                        Some(json!(invalid)),
+                    )
+                    .await;
+                assert_eq!(status, StatusCode::BAD_REQUEST, "{}", app.name);
+            }
+
+            // Not even JSON for a task
+            let (status, _) = app
+                .request("POST", "/task", Some(&token), Some(json!({ "title": 1 })))
+                .await;
+            assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY, "{}", app.name);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_put_task_returns_original() {
+        for app in test_apps().await {
+            let token = app.new_user().await;
+            let original = task(4, "Original");
+            let task_id = app.create_task(&token, &original).await;
+            let uri = format!("/task/{}", task_id);
+
+            let updated = TaskData {
+                complete: Some(true),
+                status: Some(TaskStatus::Done),
+                ..task(5, "Updated")
+            };
+            let (status, body) = app
+                .request("PUT", &uri, Some(&token), Some(json!(updated)))
+                .await;
+            assert_eq!(status, StatusCode::OK, "{}", app.name);
+            assert_eq!(
+                serde_json::from_value::<TaskData>(body).unwrap(),
+                saved(original.clone()),
+                "{}",
+                app.name
+            );
+
+            let (_, body) = app.request("GET", &uri, Some(&token), None).await;
+            assert_eq!(
+                serde_json::from_value::<TaskData>(body).unwrap(),
+                saved(updated.clone()),
+                "{}",
+                app.name
+            );
+
+            // An invalid update leaves the task alone
+            let invalid = task(0, "Invalid");
+            let (status, _) = app
+                .request("PUT", &uri, Some(&token), Some(json!(invalid)))
+                .await;
+            assert_eq!(status, StatusCode::BAD_REQUEST, "{}", app.name);
+            let (_, body) = app.request("GET", &uri, Some(&token), None).await;
+            assert_eq!(
+                serde_json::from_value::<TaskData>(body).unwrap(),
+                saved(updated.clone()),
+                "{}",
+                app.name
+            );
+
+            let (status, _) = app
+                .request(
+                    "PUT",
+                    &format!("/task/{}", task_id + 1000),
+                    Some(&token),
+                    Some(json!(updated)),
+                )
+                .await;
+            assert_eq!(status, StatusCode::BAD_REQUEST, "{}", app.name);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_status_history() {
+        for app in test_apps().await {
+            let token = app.new_user().await;
+            let task_id = app.create_task(&token, &task(4, "Task")).await;
+            let uri = format!("/task/{}", task_id);
+            for status in [
+                TaskStatus::InProgress,
+                // Saving the task without changing its status records nothing
+                TaskStatus::InProgress,
+                TaskStatus::Done,
+            ] {
+                let (res, _) = app
+                    .request(
+                        "PUT",
+                        &uri,
+                        Some(&token),
+                        Some(json!(TaskData {
+                            status: Some(status),
+                            ..task(4, "Task")
+                        })),
+                    )
+                    .await;
+                assert_eq!(res, StatusCode::OK, "{}", app.name);
+            }
+
+            let (status, body) = app
+                .request("GET", &format!("{}/history", uri), Some(&token), None)
+                .await;
+            assert_eq!(status, StatusCode::OK, "{}", app.name);
+            let history = serde_json::from_value::<StatusHistory>(body).unwrap();
+            let statuses: Vec<TaskStatus> = history.changes.iter().map(|c| c.status).collect();
+            assert_eq!(
+                statuses,
+                [TaskStatus::Todo, TaskStatus::InProgress, TaskStatus::Done],
+                "{}",
+                app.name
+            );
+
+            // Done is reported through `complete` too, for older clients
+            let (_, body) = app.request("GET", &uri, Some(&token), None).await;
+            let reread = serde_json::from_value::<TaskData>(body).unwrap();
+            assert_eq!(reread.complete, Some(true), "{}", app.name);
+
+            // An older client that only sends `complete: false` reopens the task
+            let (_, _) = app
+                .request(
+                    "PUT",
+                    &uri,
+                    Some(&token),
+                    Some(json!({ "year": 2025, "month": 8, "day": 4, "title": "Task", "complete": false })),
+                )
+                .await;
+            let (_, body) = app.request("GET", &uri, Some(&token), None).await;
+            let reread = serde_json::from_value::<TaskData>(body).unwrap();
+            assert_eq!(reread.status, Some(TaskStatus::Todo), "{}", app.name);
+            assert_eq!(reread.complete, Some(false), "{}", app.name);
+
+            let other_token = app.new_user().await;
+            let (status, _) = app
+                .request("GET", &format!("{}/history", uri), Some(&other_token), None)
+                .await;
+            assert_eq!(status, StatusCode::NOT_FOUND, "{}", app.name);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_put_with_only_complete() {
+        for app in test_apps().await {
+            let token = app.new_user().await;
+            // Clients from before statuses send the whole task, with only `complete`
+            let legacy = |title: &str, complete: bool| json!({ "year": 2025, "month": 8, "day": 4, "title": title, "complete": complete });
+            for status in [
+                TaskStatus::InProgress,
+                TaskStatus::Blocked,
+                TaskStatus::Deferred,
+                TaskStatus::Cancelled,
+            ] {
+                let task_id = app
+                    .create_task(
+                        &token,
+                        &TaskData {
+                            status: Some(status),
+                            ..task(4, "Task")
+                        },
+                    )
+                    .await;
+                let uri = format!("/task/{}", task_id);
+                let (res, _) = app
+                    .request("PUT", &uri, Some(&token), Some(legacy("Renamed", false)))
+                    .await;
+                assert_eq!(res, StatusCode::OK, "{}", app.name);
+                let (_, body) = app.request("GET", &uri, Some(&token), None).await;
+                let reread = serde_json::from_value::<TaskData>(body).unwrap();
+                assert_eq!(reread.title, "Renamed", "{}", app.name);
+                assert_eq!(reread.status, Some(status), "{}", app.name);
+
+                app.request("PUT", &uri, Some(&token), Some(legacy("Renamed", true)))
+                    .await;
+                let (_, body) = app.request("GET", &uri, Some(&token), None).await;
+                let reread = serde_json::from_value::<TaskData>(body).unwrap();
+                assert_eq!(reread.status, Some(TaskStatus::Done), "{}", app.name);
+            }
+
+            // `complete` counts even next to a status that says otherwise
+            let task_id = app.create_task(&token, &task(4, "Task")).await;
+            let uri = format!("/task/{}", task_id);
+            let contradicting = TaskData {
+                complete: Some(false),
+                status: Some(TaskStatus::Done),
+                ..task(4, "Task")
+            };
+            app.request("PUT", &uri, Some(&token), Some(json!(contradicting)))
+                .await;
+            let (_, body) = app.request("GET", &uri, Some(&token), None).await;
+            let reread = serde_json::from_value::<TaskData>(body).unwrap();
+            assert_eq!(reread.status, Some(TaskStatus::Todo), "{}", app.name);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_put_keeps_left_out_fields() {
+        for app in test_apps().await {
+            let token = app.new_user().await;
+            let (_, body) = app
+                .request(
+                    "POST",
+                    "/projects",
+                    Some(&token),
+                    Some(json!({ "name": "Launch" })),
+                )
+                .await;
+            let project_id = body["project_id"].as_i64().unwrap();
+            let original = TaskData {
+                priority: Some(Priority::High),
+                project_id: Some(Some(project_id)),
+                tags: Some(vec!["work".to_string()]),
+                ..task(4, "Task")
+            };
+            let task_id = app.create_task(&token, &original).await;
+            let uri = format!("/task/{}", task_id);
+
+            // Clients from before these fields existed leave them out
+            let older = json!({ "year": 2025, "month": 8, "day": 5, "title": "Renamed" });
+            let (status, _) = app.request("PUT", &uri, Some(&token), Some(older)).await;
+            assert_eq!(status, StatusCode::OK, "{}", app.name);
+            let (_, body) = app.request("GET", &uri, Some(&token), None).await;
+            assert_eq!(
+                serde_json::from_value::<TaskData>(body).unwrap(),
+                saved(TaskData {
+                    day: 5,
+                    title: "Renamed".to_string(),
+                    ..original.clone()
+                }),
+                "{}",
+                app.name
+            );
+
+            // While null takes the task out of its project
+            let mut cleared = json!(original);
+            cleared["project_id"] = serde_json::Value::Null;
+            let (status, _) = app.request("PUT", &uri, Some(&token), Some(cleared)).await;
+            assert_eq!(status, StatusCode::OK, "{}", app.name);
+            let (_, body) = app.request("GET", &uri, Some(&token), None).await;
+            assert_eq!(
+                serde_json::from_value::<TaskData>(body).unwrap(),
+                saved(TaskData {
+                    project_id: Some(None),
+                    ..original
+                }),
+                "{}",
+                app.name
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_delete_task() {
+        for app in test_apps().await {
+            let token = app.new_user().await;
+            let task_id = app.create_task(&token, &task(4, "Task")).await;
+            let uri = format!("/task/{}", task_id);
+
+            let (status, _) = app.request("DELETE", &uri, Some(&token), None).await;
+            assert_eq!(status, StatusCode::OK, "{}", app.name);
+            let (status, _) = app.request("GET", &uri, Some(&token), None).await;
+            assert_eq!(status, StatusCode::NOT_FOUND, "{}", app.name);
+
+            // Deleting is idempotent
+            let (status, _) = app.request("DELETE", &uri, Some(&token), None).await;
+            assert_eq!(status, StatusCode::OK, "{}", app.name);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_tasks_are_isolated_between_accounts() {
+        for app in test_apps().await {
+            let token_a = app.new_user().await;
+            let token_b = app.new_user().await;
+            let original = task(4, "A's task");
+            let task_id = app.create_task(&token_a, &original).await;
+            let uri = format!("/task/{}", task_id);
+
+            let (status, _) = app.request("GET", &uri, Some(&token_b), None).await;
+            assert_eq!(status, StatusCode::NOT_FOUND, "{}", app.name);
+
+            let (status, _) = app
+                .request(
+                    "PUT",
+                    &uri,
+                    Some(&token_b),
+                    Some(json!(task(5, "B's edit"))),
+                )
+                .await;
+            assert_eq!(status, StatusCode::BAD_REQUEST, "{}", app.name);
+
+            // Succeeds like deleting a missing task, without touching A's task
+            app.request("DELETE", &uri, Some(&token_b), None).await;
+
+            let (status, body) = app.request("GET", &uri, Some(&token_a), None).await;
+            assert_eq!(status, StatusCode::OK, "{}", app.name);
+            assert_eq!(
+                serde_json::from_value::<TaskData>(body).unwrap(),
+                saved(original.clone()),
+                "{}",
+                app.name
+            );
+        }
     }
 }
@@ -0,0 +1,529 @@
+use axum::{
+    Json, Router,
+    extract::{Query, State},
+    http::StatusCode,
+    routing::{get, post},
+};
+use axum_extra::{
+    TypedHeader,
+    headers::{Authorization, authorization::Bearer},
+};
+use rical_types::{
+    ReportGrouping, ReportQuery, RicalDate, SessionList, SessionQuery, StartTimer, TimeReport,
+    TimeReportEntry, TimeSession, Timer, TokenScope,
+};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use crate::AppState;
+use crate::storage::TrackedSession;
+use crate::utils;
+
+// Tracking the time spent on tasks with a timer, and reporting it by day, week, tag or project
+
+pub fn get_routes(state: &Arc<AppState>) -> Router {
+    Router::new()
+        .route("/timer", get(get_timer))
+        .route("/timer/start", post(start_timer))
+        .route("/timer/stop", post(stop_timer))
+        .route("/sessions", get(get_sessions))
+        .route("/report", get(get_report))
+        .with_state(state.clone())
+}
+
+/// Add up the sessions' time by `group_by`; running sessions count up to `now`
+fn time_report(
+    sessions: &[TrackedSession],
+    group_by: ReportGrouping,
+    utc_offset_min: i32,
+    now: i64,
+) -> TimeReport {
+    // Dates sort as their ISO strings, and None (no tag or project) sorts after every name
+    let mut totals: BTreeMap<(bool, String), i64> = BTreeMap::new();
+    let mut add = |key: Option<String>, secs: i64| {
+        *totals
+            .entry((key.is_none(), key.unwrap_or_default()))
+            .or_default() += secs;
+    };
+    let mut total_secs = 0;
+    for tracked in sessions {
+        let secs = tracked.session.tracked_secs(now);
+        total_secs += secs;
+        let date = RicalDate::at_unix(tracked.session.started_at, utc_offset_min);
+        match group_by {
+            ReportGrouping::Day => add(Some(date.format_iso()), secs),
+            ReportGrouping::Week => add(Some(date.week_start().format_iso()), secs),
+            ReportGrouping::Tag if tracked.tags.is_empty() => add(None, secs),
+            ReportGrouping::Tag => {
+                for tag in &tracked.tags {
+                    add(Some(tag.clone()), secs);
+                }
+            }
+            ReportGrouping::Project => {
+                add(tracked.project.as_ref().map(|(_, name)| name.clone()), secs)
+            }
+        }
+    }
+    let entries = totals
+        .into_iter()
+        .map(|((none, key), tracked_secs)| TimeReportEntry {
+            key: (!none).then_some(key),
+            tracked_secs,
+        })
+        .collect();
+    TimeReport {
+        entries,
+        total_secs,
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/tracking/timer",
+    tag = "tracking",
+    security(("login_token" = []), ("api_token" = ["read"])),
+    responses(
+        (status = OK, description = "The session the account's timer is running, if any", body = Timer),
+        (status = UNAUTHORIZED, description = "Missing or invalid token"),
+    )
+)]
+async fn get_timer(
+    TypedHeader(Authorization(bearer)): TypedHeader<Authorization<Bearer>>,
+    State(state): State<Arc<AppState>>,
+) -> (StatusCode, Json<Option<Timer>>) {
+    let account_id = match utils::authenticate(&state, bearer.token(), TokenScope::Read).await {
+        Ok(id) => id,
+        Err(status) => {
+            return (status, Json(None));
+        }
+    };
+    match state.storage.running_timer(account_id).await {
+        Ok(running) => (StatusCode::OK, Json(Some(Timer { running }))),
+        Err(err) => {
+            err.log("get the timer");
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(None))
+        }
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/tracking/timer/start",
+    tag = "tracking",
+    request_body = StartTimer,
+    security(("login_token" = []), ("api_token" = ["read_write"])),
+    responses(
+        (status = CREATED, description = "The new session; the one that was running is stopped", body = TimeSession),
+        (status = NOT_FOUND, description = "The account has no task with this ID"),
+        (status = UNAUTHORIZED, description = "Missing or invalid token"),
+        (status = FORBIDDEN, description = "The API token's scope doesn't allow this"),
+    )
+)]
+async fn start_timer(
+    TypedHeader(Authorization(bearer)): TypedHeader<Authorization<Bearer>>,
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<StartTimer>,
+) -> (StatusCode, Json<Option<TimeSession>>) {
+    let account_id = match utils::authenticate(&state, bearer.token(), TokenScope::ReadWrite).await
+    {
+        Ok(id) => id,
+        Err(status) => {
+            return (status, Json(None));
+        }
+    };
+    match state
+        .storage
+        .start_timer(account_id, payload.task_id, utils::now_unix())
+        .await
+    {
+        Ok(Some(session)) => (StatusCode::CREATED, Json(Some(session))),
+        Ok(None) => (StatusCode::NOT_FOUND, Json(None)),
+        Err(err) => {
+            err.log("start the timer");
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(None))
+        }
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/tracking/timer/stop",
+    tag = "tracking",
+    security(("login_token" = []), ("api_token" = ["read_write"])),
+    responses(
+        (status = OK, description = "The session that was running", body = TimeSession),
+        (status = NOT_FOUND, description = "The timer isn't running"),
+        (status = UNAUTHORIZED, description = "Missing or invalid token"),
+        (status = FORBIDDEN, description = "The API token's scope doesn't allow this"),
+    )
+)]
+async fn stop_timer(
+    TypedHeader(Authorization(bearer)): TypedHeader<Authorization<Bearer>>,
+    State(state): State<Arc<AppState>>,
+) -> (StatusCode, Json<Option<TimeSession>>) {
+    let account_id = match utils::authenticate(&state, bearer.token(), TokenScope::ReadWrite).await
+    {
+        Ok(id) => id,
+        Err(status) => {
+            return (status, Json(None));
+        }
+    };
+    match state
+        .storage
+        .stop_timer(account_id, utils::now_unix())
+        .await
+    {
+        Ok(Some(session)) => (StatusCode::OK, Json(Some(session))),
+        Ok(None) => (StatusCode::NOT_FOUND, Json(None)),
+        Err(err) => {
+            err.log("stop the timer");
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(None))
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/tracking/sessions",
+    tag = "tracking",
+    params(SessionQuery),
+    security(("login_token" = []), ("api_token" = ["read"])),
+    responses(
+        (status = OK, description = "The task's sessions, oldest first", body = SessionList),
+        (status = NOT_FOUND, description = "The account has no task with this ID"),
+        (status = UNAUTHORIZED, description = "Missing or invalid token"),
+    )
+)]
+async fn get_sessions(
+    TypedHeader(Authorization(bearer)): TypedHeader<Authorization<Bearer>>,
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<SessionQuery>,
+) -> (StatusCode, Json<Option<SessionList>>) {
+    let account_id = match utils::authenticate(&state, bearer.token(), TokenScope::Read).await {
+        Ok(id) => id,
+        Err(status) => {
+            return (status, Json(None));
+        }
+    };
+    match state.storage.task_sessions(account_id, query.task_id).await {
+        Ok(Some(sessions)) => (StatusCode::OK, Json(Some(SessionList { sessions }))),
+        Ok(None) => (StatusCode::NOT_FOUND, Json(None)),
+        Err(err) => {
+            err.log("get the sessions");
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(None))
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/tracking/report",
+    tag = "tracking",
+    params(ReportQuery),
+    security(("login_token" = []), ("api_token" = ["read"])),
+    responses(
+        (status = OK, description = "The time tracked in sessions started between the dates (in the account's time zone)", body = TimeReport),
+        (status = BAD_REQUEST, description = "Invalid dates, or `from` is after `to`"),
+        (status = UNAUTHORIZED, description = "Missing or invalid token"),
+    )
+)]
+async fn get_report(
+    TypedHeader(Authorization(bearer)): TypedHeader<Authorization<Bearer>>,
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<ReportQuery>,
+) -> (StatusCode, Json<Option<TimeReport>>) {
+    let account_id = match utils::authenticate(&state, bearer.token(), TokenScope::Read).await {
+        Ok(id) => id,
+        Err(status) => {
+            return (status, Json(None));
+        }
+    };
+    let (Some(from), Some(to)) = (
+        RicalDate::parse_iso(&query.from),
+        RicalDate::parse_iso(&query.to),
+    ) else {
+        return (StatusCode::BAD_REQUEST, Json(None));
+    };
+    if from.to_naive_date() > to.to_naive_date() {
+        return (StatusCode::BAD_REQUEST, Json(None));
+    }
+
+    let utc_offset_min = match utils::utc_offset_min(&state, account_id).await {
+        Ok(utc_offset_min) => utc_offset_min,
+        Err(err) => {
+            err.log("get the time zone");
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(None));
+        }
+    };
+    let sessions = state
+        .storage
+        .tracked_sessions(
+            account_id,
+            from.start_unix(utc_offset_min),
+            to.add_days(1).start_unix(utc_offset_min),
+        )
+        .await;
+    match sessions {
+        Ok(sessions) => {
+            let report = time_report(&sessions, query.group_by, utc_offset_min, utils::now_unix());
+            (StatusCode::OK, Json(Some(report)))
+        }
+        Err(err) => {
+            err.log("report the tracked time");
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(None))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::routes::testing::{self, task, test_apps};
+    use rical_types::{ProjectData, TaskData};
+    use serde_json::json;
+
+    fn tracked(started_at: i64, stopped_at: Option<i64>, tags: &[&str]) -> TrackedSession {
+        TrackedSession {
+            session: TimeSession {
+                session_id: started_at,
+                task_id: 1,
+                started_at,
+                stopped_at,
+            },
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+            project: None,
+        }
+    }
+
+    fn entries(report: &TimeReport) -> Vec<(Option<&str>, i64)> {
+        report
+            .entries
+            .iter()
+            .map(|entry| (entry.key.as_deref(), entry.tracked_secs))
+            .collect()
+    }
+
+    #[test]
+    fn test_time_report() {
+        // Wednesday 2025-01-01 00:00 UTC
+        let day = 1_735_689_600;
+        let sessions = [
+            tracked(day + 3600, Some(day + 5400), &["work", "deep"]),
+            tracked(day + 86400, Some(day + 86400 + 600), &[]),
+            // Monday 2025-01-06, still running
+            tracked(day + 5 * 86400, None, &["work"]),
+        ];
+        let now = day + 5 * 86400 + 60;
+
+        let report = time_report(&sessions, ReportGrouping::Day, 0, now);
+        assert_eq!(
+            entries(&report),
+            [
+                (Some("2025-01-01"), 1800),
+                (Some("2025-01-02"), 600),
+                (Some("2025-01-06"), 60)
+            ]
+        );
+        assert_eq!(report.total_secs, 2460);
+
+        // Two hours behind UTC, the first session started the day before
+        let report = time_report(&sessions, ReportGrouping::Day, -120, now);
+        assert_eq!(report.entries[0].key.as_deref(), Some("2024-12-31"));
+
+        let report = time_report(&sessions, ReportGrouping::Week, 0, now);
+        assert_eq!(
+            entries(&report),
+            [(Some("2024-12-30"), 2400), (Some("2025-01-06"), 60)]
+        );
+
+        // Tagged twice counts twice in the entries, but once in the total
+        let report = time_report(&sessions, ReportGrouping::Tag, 0, now);
+        assert_eq!(
+            entries(&report),
+            [(Some("deep"), 1800), (Some("work"), 1860), (None, 600)]
+        );
+        assert_eq!(report.total_secs, 2460);
+    }
+
+    #[tokio::test]
+    async fn test_timer() {
+        for app in test_apps().await {
+            let token = app.new_user().await;
+            let other_token = app.new_user().await;
+            let first = app.create_task(&token, &task(1, "First")).await;
+            let second = app.create_task(&token, &task(2, "Second")).await;
+
+            let (status, body) = app
+                .request("GET", "/tracking/timer", Some(&token), None)
+                .await;
+            assert_eq!(status, StatusCode::OK, "{}", app.name);
+            assert_eq!(body, json!({ "running": null }), "{}", app.name);
+            let (status, _) = app
+                .request("POST", "/tracking/timer/stop", Some(&token), None)
+                .await;
+            assert_eq!(status, StatusCode::NOT_FOUND, "{}", app.name);
+
+            let start = |task_id: i64| Some(json!({ "task_id": task_id }));
+            let (status, body) = app
+                .request("POST", "/tracking/timer/start", Some(&token), start(first))
+                .await;
+            assert_eq!(status, StatusCode::CREATED, "{}", app.name);
+            let session = serde_json::from_value::<TimeSession>(body).unwrap();
+            assert_eq!(session.task_id, first, "{}", app.name);
+            assert_eq!(session.stopped_at, None, "{}", app.name);
+
+            let (_, body) = app
+                .request("GET", "/tracking/timer", Some(&token), None)
+                .await;
+            let running = serde_json::from_value::<Timer>(body).unwrap().running;
+            let running = running.unwrap();
+            assert_eq!(running.session_id, session.session_id, "{}", app.name);
+            assert_eq!(running.title, "First", "{}", app.name);
+
+            // Other accounts can't track the task, and have their own timer
+            let (status, _) = app
+                .request(
+                    "POST",
+                    "/tracking/timer/start",
+                    Some(&other_token),
+                    start(first),
+                )
+                .await;
+            assert_eq!(status, StatusCode::NOT_FOUND, "{}", app.name);
+            let (_, body) = app
+                .request("GET", "/tracking/timer", Some(&other_token), None)
+                .await;
+            assert_eq!(body, json!({ "running": null }), "{}", app.name);
+
+            // Starting another task stops the first one's session
+            let (status, _) = app
+                .request("POST", "/tracking/timer/start", Some(&token), start(second))
+                .await;
+            assert_eq!(status, StatusCode::CREATED, "{}", app.name);
+            let (status, body) = app
+                .request(
+                    "GET",
+                    &format!("/tracking/sessions?task_id={}", first),
+                    Some(&token),
+                    None,
+                )
+                .await;
+            assert_eq!(status, StatusCode::OK, "{}", app.name);
+            let sessions = serde_json::from_value::<SessionList>(body)
+                .unwrap()
+                .sessions;
+            assert_eq!(sessions.len(), 1, "{}", app.name);
+            assert!(sessions[0].stopped_at.is_some(), "{}", app.name);
+
+            let (status, body) = app
+                .request("POST", "/tracking/timer/stop", Some(&token), None)
+                .await;
+            assert_eq!(status, StatusCode::OK, "{}", app.name);
+            let session = serde_json::from_value::<TimeSession>(body).unwrap();
+            assert_eq!(session.task_id, second, "{}", app.name);
+            assert!(session.stopped_at.is_some(), "{}", app.name);
+            let (_, body) = app
+                .request("GET", "/tracking/timer", Some(&token), None)
+                .await;
+            assert_eq!(body, json!({ "running": null }), "{}", app.name);
+
+            let uri = format!("/tracking/sessions?task_id={}", second);
+            let (status, _) = app.request("GET", &uri, Some(&other_token), None).await;
+            assert_eq!(status, StatusCode::NOT_FOUND, "{}", app.name);
+
+            // Deleting a task deletes its sessions
+            app.request("DELETE", &format!("/task/{}", second), Some(&token), None)
+                .await;
+            let (status, _) = app.request("GET", &uri, Some(&token), None).await;
+            assert_eq!(status, StatusCode::NOT_FOUND, "{}", app.name);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_report() {
+        for app in test_apps().await {
+            let token = app.new_user().await;
+            let account_id = utils::verify_jwt(testing::JWT_SECRET, &token).unwrap().sub;
+            let project_id = app
+                .storage
+                .create_project(
+                    account_id,
+                    &ProjectData {
+                        name: "Launch".to_string(),
+                        description: None,
+                        due: None,
+                    },
+                )
+                .await
+                .unwrap();
+            let launch = app
+                .create_task(
+                    &token,
+                    &TaskData {
+                        project_id: Some(Some(project_id)),
+                        tags: Some(vec!["work".to_string()]),
+                        ..task(1, "Launch")
+                    },
+                )
+                .await;
+            let chores = app.create_task(&token, &task(1, "Chores")).await;
+
+            // 2025-01-01 00:00 UTC
+            let day = 1_735_689_600;
+            for (task_id, start, stop) in [
+                (launch, day + 3600, day + 7200),
+                (chores, day + 7200, day + 7500),
+                (launch, day + 86400, day + 86400 + 900),
+                // Outside of the range
+                (launch, day + 3 * 86400, day + 3 * 86400 + 60),
+            ] {
+                let started = app.storage.start_timer(account_id, task_id, start).await;
+                assert!(started.unwrap().is_some(), "{}", app.name);
+                app.storage.stop_timer(account_id, stop).await.unwrap();
+            }
+
+            let report = |query: &str| {
+                let uri = format!("/tracking/report?from=2025-01-01&to=2025-01-02{}", query);
+                let token = token.clone();
+                let app = &app;
+                async move {
+                    let (status, body) = app.request("GET", &uri, Some(&token), None).await;
+                    assert_eq!(status, StatusCode::OK, "{}: {}", app.name, uri);
+                    serde_json::from_value::<TimeReport>(body).unwrap()
+                }
+            };
+            let by_day = report("").await;
+            assert_eq!(
+                entries(&by_day),
+                [(Some("2025-01-01"), 3900), (Some("2025-01-02"), 900)],
+                "{}",
+                app.name
+            );
+            assert_eq!(by_day.total_secs, 4800, "{}", app.name);
+            let by_project = report("&group_by=project").await;
+            assert_eq!(
+                entries(&by_project),
+                [(Some("Launch"), 4500), (None, 300)],
+                "{}",
+                app.name
+            );
+            let by_tag = report("&group_by=tag").await;
+            assert_eq!(
+                entries(&by_tag),
+                [(Some("work"), 4500), (None, 300)],
+                "{}",
+                app.name
+            );
+
+            for query in [
+                "from=2025-01-02&to=2025-01-01",
+                "from=2025-13-01&to=2025-01-01",
+                "from=2025-01-01",
+            ] {
+                let uri = format!("/tracking/report?{}", query);
+                let (status, _) = app.request("GET", &uri, Some(&token), None).await;
+                assert_eq!(status, StatusCode::BAD_REQUEST, "{}: {}", app.name, query);
+            }
+        }
+    }
+}
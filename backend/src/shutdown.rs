@@ -0,0 +1,46 @@
+use axum_server::Handle;
+use std::net::SocketAddr;
+use std::time::Duration;
+
+// Stopping the server without dropping requests, e.g. when a deploy replaces it
+
+/// A handle for servers to share, which shuts them down on SIGTERM or Ctrl+C:
+/// they stop accepting connections and let the requests that are already running
+/// finish, for at most `timeout`
+pub fn handle_on_signal(timeout: Duration) -> Handle<SocketAddr> {
+    let handle = Handle::new();
+    tokio::spawn({
+        let handle = handle.clone();
+        async move {
+            signal().await;
+            tracing::info!(
+                "Shutting down, waiting up to {}s for requests to finish",
+                timeout.as_secs()
+            );
+            handle.graceful_shutdown(Some(timeout));
+        }
+    });
+    handle
+}
+
+/// Wait for SIGTERM (what orchestrators send) or SIGINT (Ctrl+C)
+async fn signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("Couldn't listen for Ctrl+C");
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("Couldn't listen for SIGTERM")
+            .recv()
+            .await;
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+    tokio::select! {
+        () = ctrl_c => {}
+        () = terminate => {}
+    }
+}
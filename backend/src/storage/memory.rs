@@ -0,0 +1,1464 @@
+use async_trait::async_trait;
+use rical_types::{
+    AccountSettings, ApiTokenInfo, ChecklistItem, ChecklistItemData, ChecklistProgress, DayOrder,
+    Dependencies, OperationResult, Project, ProjectData, ProjectProgress, RunningTimer, SearchHit,
+    SnippetPart, StatusChange, Tag, TagData, TaskData, TaskDataWithId, TaskOperation, TaskSort,
+    TaskStatus, TimeSession, is_valid_tag_name,
+};
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::sync::{Mutex, MutexGuard};
+
+use crate::utils;
+
+use crate::storage::{
+    Account, AccountSummary, ApiTokenAuth, AutoRollover, NewApiTokenRecord, PoolStats, Reschedule,
+    SearchFilter, Storage, StorageError, StorageResult, TaskFilter, TaskKey, TrackedSession,
+    date_number, format_date_number, operation_result, project_due_date, search_terms,
+};
+
+// Storage that only lives as long as the process, for tests
+// It checks the same rules as the SQL schemas, so that tests behave like the real thing
+
+#[derive(Clone)]
+struct TaskRecord {
+    account_id: i64,
+    /// Its `tags` are always None, since the tags are linked by ID so that renaming one renames it
+    /// on every task, and its `status`, `complete`, `priority` and `project_id` are always there
+    task: TaskData,
+    /// In no particular order
+    checklist: Vec<ChecklistItem>,
+    tag_ids: Vec<i64>,
+    /// The IDs of the tasks it depends on, in the order they were added
+    depends_on: Vec<i64>,
+    status_changed_at: Option<i64>,
+    completed_at: Option<i64>,
+    /// Oldest first
+    history: Vec<StatusChange>,
+}
+
+impl TaskRecord {
+    /// The history starts with the status the task is created with
+    fn new(account_id: i64, task: &TaskData, tag_ids: Vec<i64>) -> TaskRecord {
+        let status = task.resolved_status(None);
+        let now = utils::now_unix();
+        TaskRecord {
+            account_id,
+            task: TaskData {
+                status: Some(status),
+                complete: Some(status == TaskStatus::Done),
+                priority: Some(task.priority.unwrap_or_default()),
+                project_id: Some(task.project_id.flatten()),
+                ..without_tags(task)
+            },
+            checklist: Vec::new(),
+            tag_ids,
+            depends_on: Vec::new(),
+            status_changed_at: Some(now),
+            completed_at: (status == TaskStatus::Done).then_some(now),
+            history: vec![StatusChange {
+                status,
+                changed_at: now,
+            }],
+        }
+    }
+
+    fn status(&self) -> TaskStatus {
+        self.task.status.unwrap_or_default()
+    }
+
+    /// Like the SQL storages' `set_status`
+    fn set_status(&mut self, status: TaskStatus) {
+        if self.status() == status {
+            return;
+        }
+        let now = utils::now_unix();
+        self.task.status = Some(status);
+        self.task.complete = Some(status == TaskStatus::Done);
+        self.status_changed_at = Some(now);
+        self.completed_at = (status == TaskStatus::Done).then_some(now);
+        self.history.push(StatusChange {
+            status,
+            changed_at: now,
+        });
+    }
+
+    /// Like the SQL storages' `apply_auto_complete`
+    fn apply_auto_complete(&mut self) {
+        if !self.task.auto_complete
+            || self.checklist.is_empty()
+            || self.status() == TaskStatus::Cancelled
+        {
+            return;
+        }
+        if self.checklist.iter().all(|item| item.done) {
+            self.set_status(TaskStatus::Done);
+        } else if self.status() == TaskStatus::Done {
+            self.set_status(TaskStatus::Todo);
+        }
+    }
+
+    fn progress(&self) -> Option<ChecklistProgress> {
+        (!self.checklist.is_empty()).then(|| ChecklistProgress {
+            done: self.checklist.iter().filter(|item| item.done).count() as u32,
+            total: self.checklist.len() as u32,
+        })
+    }
+}
+
+#[derive(Clone)]
+struct TagRecord {
+    account_id: i64,
+    tag: Tag,
+}
+
+struct ProjectRecord {
+    account_id: i64,
+    project: ProjectData,
+    /// The `date_number` of `project.due`
+    due_date: Option<i32>,
+}
+
+struct SessionRecord {
+    account_id: i64,
+    session: TimeSession,
+}
+
+struct ApiTokenRecord {
+    account_id: i64,
+    hashed_token: String,
+    info: ApiTokenInfo,
+}
+
+#[derive(Default)]
+struct MemoryData {
+    // IDs are handed out in increasing order, like Postgres' BIGSERIAL
+    accounts: BTreeMap<i64, Account>,
+    // Accounts without an entry have the default settings and were never rolled over
+    settings: BTreeMap<i64, AccountSettings>,
+    rolled_over_on: BTreeMap<i64, i32>,
+    tasks: BTreeMap<i64, TaskRecord>,
+    tags: BTreeMap<i64, TagRecord>,
+    projects: BTreeMap<i64, ProjectRecord>,
+    sessions: BTreeMap<i64, SessionRecord>,
+    api_tokens: BTreeMap<i64, ApiTokenRecord>,
+    last_id: i64,
+    /// Accounts whose tasks can't be rescheduled, as if the database failed
+    #[cfg(test)]
+    failing_reschedules: std::collections::BTreeSet<i64>,
+}
+
+impl MemoryData {
+    fn next_id(&mut self) -> i64 {
+        self.last_id += 1;
+        self.last_id
+    }
+
+    fn create_task(&mut self, account_id: i64, task: &TaskData) -> StorageResult<i64> {
+        validate_task(task)?;
+        if !self.accounts.contains_key(&account_id) {
+            return Err(StorageError::Invalid);
+        }
+        self.check_project(account_id, task)?;
+        let tag_ids = self.tag_ids(account_id, task.tags.as_deref().unwrap_or_default())?;
+        let task_id = self.next_id();
+        self.tasks
+            .insert(task_id, TaskRecord::new(account_id, task, tag_ids));
+        Ok(task_id)
+    }
+
+    /// Replace the account's task and return it as it was before, if it exists
+    fn replace_task(
+        &mut self,
+        account_id: i64,
+        task_id: i64,
+        task: &TaskData,
+    ) -> StorageResult<Option<TaskData>> {
+        validate_task(task)?;
+        if self.record_mut(account_id, task_id).is_none() {
+            return Ok(None);
+        }
+        self.check_project(account_id, task)?;
+        let tag_ids = match &task.tags {
+            Some(names) => Some(self.tag_ids(account_id, names)?),
+            None => None,
+        };
+        let record = self
+            .tasks
+            .get_mut(&task_id)
+            .expect("the task was just found");
+        // What was left out stays as it was
+        let replacement = TaskData {
+            status: record.task.status,
+            complete: record.task.complete,
+            priority: task.priority.or(record.task.priority),
+            project_id: task.project_id.or(record.task.project_id),
+            ..without_tags(task)
+        };
+        let status = task.resolved_status(Some(record.status()));
+        let original = std::mem::replace(&mut record.task, replacement);
+        record.set_status(status);
+        let original_tag_ids = record.tag_ids.clone();
+        if let Some(tag_ids) = tag_ids {
+            record.tag_ids = tag_ids;
+        }
+        Ok(Some(TaskData {
+            tags: Some(self.tag_names(&original_tag_ids)),
+            ..original
+        }))
+    }
+
+    /// Like the SQL storages' `owns_project` check, since the schema can't tell whose project it is
+    fn check_project(&self, account_id: i64, task: &TaskData) -> StorageResult<()> {
+        match task.project_id.flatten() {
+            Some(project_id) if self.project_record(account_id, project_id).is_none() => {
+                Err(StorageError::Invalid)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn project_record(&self, account_id: i64, project_id: i64) -> Option<&ProjectRecord> {
+        self.projects
+            .get(&project_id)
+            .filter(|record| record.account_id == account_id)
+    }
+
+    /// A project as the routes see it, with its progress as of `today`
+    fn project(&self, record: &ProjectRecord, project_id: i64, today: i32) -> Project {
+        let mut progress = ProjectProgress::default();
+        for record in self.tasks.values() {
+            let task = &record.task;
+            if task.project_id != Some(Some(project_id)) || record.status() == TaskStatus::Cancelled
+            {
+                continue;
+            }
+            progress.total += 1;
+            if record.status() == TaskStatus::Done {
+                progress.done += 1;
+            } else if date_number(task.year, task.month, task.day) < today {
+                progress.overdue += 1;
+            }
+        }
+        Project {
+            project_id,
+            name: record.project.name.clone(),
+            description: record.project.description.clone(),
+            due: record.due_date.map(format_date_number),
+            progress,
+        }
+    }
+
+    /// The IDs of the account's tags with these names, creating the ones that don't exist yet
+    /// like the SQL storages' `set_task_tags`
+    fn tag_ids(&mut self, account_id: i64, names: &[String]) -> StorageResult<Vec<i64>> {
+        if !names.iter().all(|name| is_valid_tag_name(name)) {
+            return Err(StorageError::Invalid);
+        }
+        let mut tag_ids = Vec::new();
+        for name in names {
+            let existing = self
+                .tags
+                .values()
+                .find(|record| record.account_id == account_id && &record.tag.name == name)
+                .map(|record| record.tag.tag_id);
+            let tag_id = match existing {
+                Some(tag_id) => tag_id,
+                None => self.insert_tag(
+                    account_id,
+                    &TagData {
+                        name: name.clone(),
+                        color: Default::default(),
+                    },
+                ),
+            };
+            if !tag_ids.contains(&tag_id) {
+                tag_ids.push(tag_id);
+            }
+        }
+        Ok(tag_ids)
+    }
+
+    fn insert_tag(&mut self, account_id: i64, tag: &TagData) -> i64 {
+        let tag_id = self.next_id();
+        self.tags.insert(
+            tag_id,
+            TagRecord {
+                account_id,
+                tag: Tag {
+                    tag_id,
+                    name: tag.name.clone(),
+                    color: tag.color,
+                },
+            },
+        );
+        tag_id
+    }
+
+    /// The names of the tags, alphabetically
+    fn tag_names(&self, tag_ids: &[i64]) -> Vec<String> {
+        let mut names: Vec<String> = tag_ids
+            .iter()
+            .filter_map(|tag_id| self.tags.get(tag_id))
+            .map(|record| record.tag.name.clone())
+            .collect();
+        names.sort();
+        names
+    }
+
+    /// A task as the routes see it, with its tags and what it's blocked by
+    fn task_with_id(&self, record: &TaskRecord, task_id: i64) -> TaskDataWithId {
+        let date = |task: &TaskData| date_number(task.year, task.month, task.day);
+        let mut blocked_by: Vec<i64> = record
+            .depends_on
+            .iter()
+            .copied()
+            .filter(|id| {
+                self.tasks
+                    .get(id)
+                    .is_some_and(|dep| !dep.status().is_closed())
+            })
+            .collect();
+        blocked_by.sort();
+        let later_dependencies = blocked_by
+            .iter()
+            .copied()
+            .filter(|id| date(&self.tasks[id].task) > date(&record.task))
+            .collect();
+        TaskDataWithId {
+            tags: self.tag_names(&record.tag_ids),
+            blocked_by,
+            later_dependencies,
+            status_changed_at: record.status_changed_at,
+            completed_at: record.completed_at,
+            ..with_id(&record.task, task_id)
+        }
+    }
+
+    /// Whether a task has any of the tags, or there are none to have
+    fn has_any_tag(&self, record: &TaskRecord, names: &[String]) -> bool {
+        names.is_empty()
+            || self
+                .tag_names(&record.tag_ids)
+                .iter()
+                .any(|name| names.contains(name))
+    }
+
+    fn record_mut(&mut self, account_id: i64, task_id: i64) -> Option<&mut TaskRecord> {
+        self.tasks
+            .get_mut(&task_id)
+            .filter(|record| record.account_id == account_id)
+    }
+
+    fn delete_task(&mut self, account_id: i64, task_id: i64) -> bool {
+        match self.tasks.get(&task_id) {
+            Some(record) if record.account_id == account_id => {
+                self.tasks.remove(&task_id);
+                for other in self.tasks.values_mut() {
+                    other.depends_on.retain(|id| *id != task_id);
+                }
+                self.sessions
+                    .retain(|_, record| record.session.task_id != task_id);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Run one of a batch's operations and return the ID of its task, or None if there is no such task
+    fn run_operation(
+        &mut self,
+        account_id: i64,
+        operation: &TaskOperation,
+    ) -> StorageResult<Option<i64>> {
+        match operation {
+            TaskOperation::Create { task } => self.create_task(account_id, task).map(Some),
+            TaskOperation::Update { task_id, task } => Ok(self
+                .replace_task(account_id, *task_id, task)?
+                .map(|_| *task_id)),
+            TaskOperation::Delete { task_id } => {
+                self.delete_task(account_id, *task_id);
+                Ok(Some(*task_id))
+            }
+            TaskOperation::Complete { task_id, complete } => Ok(self
+                .record_mut(account_id, *task_id)
+                .map(|record| record.set_status(record.status().marked(*complete)))
+                .map(|_| *task_id)),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct MemoryStorage {
+    data: Mutex<MemoryData>,
+}
+
+impl MemoryStorage {
+    pub fn new() -> MemoryStorage {
+        MemoryStorage::default()
+    }
+
+    fn data(&self) -> MutexGuard<'_, MemoryData> {
+        self.data.lock().expect("Memory storage lock poisoned")
+    }
+
+    /// Make `reschedule_tasks` fail for the account, to test how failures are handled
+    #[cfg(test)]
+    pub fn fail_reschedules(&self, account_id: i64) {
+        self.data().failing_reschedules.insert(account_id);
+    }
+
+    /// Change an account and return whether it exists
+    fn update_account(&self, account_id: i64, update: impl FnOnce(&mut Account)) -> bool {
+        self.data()
+            .accounts
+            .get_mut(&account_id)
+            .map(update)
+            .is_some()
+    }
+}
+
+#[async_trait]
+impl Storage for MemoryStorage {
+    async fn create_account(&self, username: &str, hashed_password: &str) -> StorageResult<i64> {
+        let mut data = self.data();
+        if data.accounts.values().any(|a| a.username == username) {
+            return Err(StorageError::Conflict);
+        }
+        let account_id = data.next_id();
+        data.accounts.insert(
+            account_id,
+            Account {
+                account_id,
+                username: username.to_string(),
+                hashed_password: hashed_password.to_string(),
+                locked: false,
+                sessions_revoked_at: None,
+            },
+        );
+        Ok(account_id)
+    }
+
+    async fn find_account(&self, username: &str) -> StorageResult<Option<Account>> {
+        Ok(self
+            .data()
+            .accounts
+            .values()
+            .find(|a| a.username == username)
+            .cloned())
+    }
+
+    async fn get_account(&self, account_id: i64) -> StorageResult<Option<Account>> {
+        Ok(self.data().accounts.get(&account_id).cloned())
+    }
+
+    async fn list_accounts(&self) -> StorageResult<Vec<AccountSummary>> {
+        let data = self.data();
+        Ok(data
+            .accounts
+            .values()
+            .map(|a| AccountSummary {
+                account_id: a.account_id,
+                username: a.username.clone(),
+                locked: a.locked,
+                task_count: data
+                    .tasks
+                    .values()
+                    .filter(|t| t.account_id == a.account_id)
+                    .count() as i64,
+            })
+            .collect())
+    }
+
+    async fn set_password(&self, account_id: i64, hashed_password: &str) -> StorageResult<bool> {
+        Ok(self.update_account(account_id, |a| {
+            a.hashed_password = hashed_password.to_string()
+        }))
+    }
+
+    async fn set_locked(&self, account_id: i64, locked: bool) -> StorageResult<bool> {
+        Ok(self.update_account(account_id, |a| a.locked = locked))
+    }
+
+    async fn revoke_sessions(&self, account_id: i64, revoked_at: i64) -> StorageResult<bool> {
+        Ok(self.update_account(account_id, |a| a.sessions_revoked_at = Some(revoked_at)))
+    }
+
+    async fn delete_account(&self, account_id: i64) -> StorageResult<bool> {
+        let mut data = self.data();
+        data.tasks.retain(|_, t| t.account_id != account_id);
+        data.tags.retain(|_, t| t.account_id != account_id);
+        data.projects.retain(|_, p| p.account_id != account_id);
+        data.sessions.retain(|_, s| s.account_id != account_id);
+        data.api_tokens.retain(|_, t| t.account_id != account_id);
+        data.settings.remove(&account_id);
+        data.rolled_over_on.remove(&account_id);
+        Ok(data.accounts.remove(&account_id).is_some())
+    }
+
+    async fn get_settings(&self, account_id: i64) -> StorageResult<Option<AccountSettings>> {
+        let data = self.data();
+        if !data.accounts.contains_key(&account_id) {
+            return Ok(None);
+        }
+        Ok(Some(
+            data.settings.get(&account_id).cloned().unwrap_or_default(),
+        ))
+    }
+
+    async fn set_settings(
+        &self,
+        account_id: i64,
+        settings: &AccountSettings,
+    ) -> StorageResult<bool> {
+        if !settings.is_valid() {
+            return Err(StorageError::Invalid);
+        }
+        let mut data = self.data();
+        if !data.accounts.contains_key(&account_id) {
+            return Ok(false);
+        }
+        data.settings.insert(account_id, settings.clone());
+        Ok(true)
+    }
+
+    async fn auto_rollover_accounts(&self) -> StorageResult<Vec<AutoRollover>> {
+        let data = self.data();
+        Ok(data
+            .settings
+            .iter()
+            .filter(|(_, settings)| settings.auto_rollover)
+            .map(|(account_id, settings)| AutoRollover {
+                account_id: *account_id,
+                utc_offset_min: settings.utc_offset_min,
+                rolled_over_on: data.rolled_over_on.get(account_id).copied(),
+            })
+            .collect())
+    }
+
+    async fn set_rolled_over(&self, account_id: i64, date: i32) -> StorageResult<bool> {
+        let mut data = self.data();
+        if !data.accounts.contains_key(&account_id) {
+            return Ok(false);
+        }
+        data.rolled_over_on.insert(account_id, date);
+        Ok(true)
+    }
+
+    async fn get_task(&self, account_id: i64, task_id: i64) -> StorageResult<Option<TaskData>> {
+        let data = self.data();
+        Ok(data
+            .tasks
+            .get(&task_id)
+            .filter(|record| record.account_id == account_id)
+            .map(|record| TaskData {
+                tags: Some(data.tag_names(&record.tag_ids)),
+                ..record.task.clone()
+            }))
+    }
+
+    async fn create_task(&self, account_id: i64, task: &TaskData) -> StorageResult<i64> {
+        self.data().create_task(account_id, task)
+    }
+
+    async fn update_task(
+        &self,
+        account_id: i64,
+        task_id: i64,
+        task: &TaskData,
+    ) -> StorageResult<Option<TaskData>> {
+        self.data().replace_task(account_id, task_id, task)
+    }
+
+    async fn delete_task(&self, account_id: i64, task_id: i64) -> StorageResult<bool> {
+        Ok(self.data().delete_task(account_id, task_id))
+    }
+
+    async fn status_history(
+        &self,
+        account_id: i64,
+        task_id: i64,
+    ) -> StorageResult<Option<Vec<StatusChange>>> {
+        Ok(self
+            .data()
+            .record_mut(account_id, task_id)
+            .map(|record| record.history.clone()))
+    }
+
+    async fn get_month_tasks(
+        &self,
+        account_id: i64,
+        year: i32,
+        month: i32,
+        order: DayOrder,
+        tags: &[String],
+        statuses: &[TaskStatus],
+    ) -> StorageResult<Vec<TaskDataWithId>> {
+        let data = self.data();
+        let mut tasks: Vec<TaskDataWithId> = data
+            .tasks
+            .iter()
+            .filter(|(_, record)| {
+                record.account_id == account_id
+                    && record.task.year == year
+                    && record.task.month == month
+                    && data.has_any_tag(record, tags)
+                    && (statuses.is_empty() || statuses.contains(&record.status()))
+            })
+            .map(|(task_id, record)| TaskDataWithId {
+                checklist: record.progress(),
+                ..data.task_with_id(record, *task_id)
+            })
+            .collect();
+        tasks.sort_by(|a, b| cmp_calendar_order(order, a, b));
+        Ok(tasks)
+    }
+
+    async fn query_tasks(
+        &self,
+        account_id: i64,
+        filter: &TaskFilter<'_>,
+    ) -> StorageResult<Vec<TaskDataWithId>> {
+        let text = filter.text.map(str::to_lowercase);
+        let contains_text = |task: &TaskData| {
+            text.as_ref().is_none_or(|text| {
+                task.title.to_lowercase().contains(text)
+                    || task
+                        .description
+                        .as_ref()
+                        .is_some_and(|description| description.to_lowercase().contains(text))
+            })
+        };
+        let data = self.data();
+        let mut tasks: Vec<(TaskKey, TaskDataWithId)> = data
+            .tasks
+            .iter()
+            .filter(|(_, record)| {
+                let task = &record.task;
+                record.account_id == account_id
+                    && (filter.from..=filter.to)
+                        .contains(&date_number(task.year, task.month, task.day))
+                    && filter
+                        .completed
+                        .is_none_or(|complete| task.complete == Some(complete))
+                    && filter
+                        .timed
+                        .is_none_or(|timed| task.start_min.is_some() == timed)
+                    && contains_text(task)
+                    && data.has_any_tag(record, filter.tags)
+                    && (filter.statuses.is_empty() || filter.statuses.contains(&record.status()))
+            })
+            .map(|(task_id, record)| {
+                let task = data.task_with_id(record, *task_id);
+                (TaskKey::of(&task), task)
+            })
+            .collect();
+        tasks.sort_by(|(a, _), (b, _)| cmp_sort_order(filter.sort, a, b));
+        Ok(tasks
+            .into_iter()
+            .filter(|(key, _)| {
+                filter.after.is_none_or(|after| {
+                    cmp_sort_order(filter.sort, key, after) == Ordering::Greater
+                })
+            })
+            .take(filter.limit as usize)
+            .map(|(_, task)| task)
+            .collect())
+    }
+
+    async fn search_tasks(
+        &self,
+        account_id: i64,
+        filter: &SearchFilter<'_>,
+    ) -> StorageResult<Vec<SearchHit>> {
+        let terms: Vec<String> = search_terms(filter.text)
+            .iter()
+            .map(|term| term.to_ascii_lowercase())
+            .collect();
+        if terms.is_empty() {
+            return Ok(Vec::new());
+        }
+        let data = self.data();
+        let mut hits: Vec<SearchHit> = data
+            .tasks
+            .iter()
+            .filter(|(_, record)| {
+                let task = &record.task;
+                let date = date_number(task.year, task.month, task.day);
+                record.account_id == account_id
+                    && filter.from.is_none_or(|from| date >= from)
+                    && filter.to.is_none_or(|to| date <= to)
+                    && filter
+                        .completed
+                        .is_none_or(|complete| task.complete == Some(complete))
+            })
+            .filter_map(|(task_id, record)| {
+                let title = record.task.title.to_ascii_lowercase();
+                let description = record
+                    .task
+                    .description
+                    .as_deref()
+                    .unwrap_or_default()
+                    .to_ascii_lowercase();
+                let all_found = terms
+                    .iter()
+                    .all(|term| title.contains(term) || description.contains(term));
+                if !all_found {
+                    return None;
+                }
+                // Like the SQL storages, matches in the title count for more
+                let count = |text: &str| {
+                    terms
+                        .iter()
+                        .map(|term| text.matches(term.as_str()).count())
+                        .sum::<usize>()
+                };
+                let rank = 10 * count(&title) + count(&description);
+                Some(SearchHit {
+                    task: data.task_with_id(record, *task_id),
+                    rank: rank as f64,
+                    title: highlight(&record.task.title, &terms),
+                    description: record
+                        .task
+                        .description
+                        .as_deref()
+                        .filter(|description| !description.is_empty())
+                        .map(|description| highlight(description, &terms)),
+                })
+            })
+            .collect();
+        hits.sort_by(|a, b| {
+            b.rank.total_cmp(&a.rank).then_with(|| {
+                let key = |hit: &SearchHit| (hit.task.year, hit.task.month, hit.task.day);
+                key(b)
+                    .cmp(&key(a))
+                    .then(b.task.task_id.cmp(&a.task.task_id))
+            })
+        });
+        hits.truncate(filter.limit as usize);
+        Ok(hits)
+    }
+
+    async fn reschedule_tasks(
+        &self,
+        account_id: i64,
+        reschedule: &Reschedule,
+    ) -> StorageResult<u64> {
+        #[cfg(test)]
+        if self.data().failing_reschedules.contains(&account_id) {
+            return Err(StorageError::Backend("The reschedule failed".to_string()));
+        }
+        let mut moved = 0;
+        for record in self.data().tasks.values_mut() {
+            let closed = record.status().is_closed();
+            let task = &mut record.task;
+            if record.account_id != account_id
+                || closed
+                || !(reschedule.from..=reschedule.to)
+                    .contains(&date_number(task.year, task.month, task.day))
+            {
+                continue;
+            }
+            task.year = reschedule.year;
+            task.month = reschedule.month;
+            task.day = reschedule.day;
+            if !reschedule.keep_times {
+                task.start_min = None;
+                task.end_min = None;
+            }
+            moved += 1;
+        }
+        Ok(moved)
+    }
+
+    async fn run_batch(
+        &self,
+        account_id: i64,
+        operations: &[TaskOperation],
+        atomic: bool,
+    ) -> StorageResult<Vec<OperationResult>> {
+        let mut data = self.data();
+        // Each operation either fails or succeeds whole, so only an atomic batch needs undoing
+        // (along with the tags its operations created)
+        let original = atomic.then(|| (data.tasks.clone(), data.tags.clone()));
+        let mut results = Vec::with_capacity(operations.len());
+        for operation in operations {
+            let result = operation_result(data.run_operation(account_id, operation))?;
+            if !result.is_ok()
+                && let Some((original_tasks, original_tags)) = &original
+            {
+                data.tasks = original_tasks.clone();
+                data.tags = original_tags.clone();
+                results.push(result);
+                results.resize(operations.len(), OperationResult::Skipped);
+                return Ok(results);
+            }
+            results.push(result);
+        }
+        Ok(results)
+    }
+
+    async fn get_checklist(
+        &self,
+        account_id: i64,
+        task_id: i64,
+    ) -> StorageResult<Option<Vec<ChecklistItem>>> {
+        Ok(self.data().record_mut(account_id, task_id).map(|record| {
+            let mut items = record.checklist.clone();
+            items.sort_by_key(|item| (item.position, item.item_id));
+            items
+        }))
+    }
+
+    async fn create_checklist_item(
+        &self,
+        account_id: i64,
+        task_id: i64,
+        item: &ChecklistItemData,
+    ) -> StorageResult<Option<i64>> {
+        let mut data = self.data();
+        let item_id = data.next_id();
+        let Some(record) = data.record_mut(account_id, task_id) else {
+            return Ok(None);
+        };
+        let position = item.position.unwrap_or_else(|| {
+            record
+                .checklist
+                .iter()
+                .map(|item| item.position + 1)
+                .max()
+                .unwrap_or(0)
+        });
+        record.checklist.push(ChecklistItem {
+            item_id,
+            text: item.text.clone(),
+            done: item.done,
+            position,
+        });
+        record.apply_auto_complete();
+        Ok(Some(item_id))
+    }
+
+    async fn update_checklist_item(
+        &self,
+        account_id: i64,
+        task_id: i64,
+        item_id: i64,
+        item: &ChecklistItemData,
+    ) -> StorageResult<bool> {
+        let mut data = self.data();
+        let Some(record) = data.record_mut(account_id, task_id) else {
+            return Ok(false);
+        };
+        let Some(existing) = record
+            .checklist
+            .iter_mut()
+            .find(|existing| existing.item_id == item_id)
+        else {
+            return Ok(false);
+        };
+        existing.text = item.text.clone();
+        existing.done = item.done;
+        existing.position = item.position.unwrap_or(existing.position);
+        record.apply_auto_complete();
+        Ok(true)
+    }
+
+    async fn delete_checklist_item(
+        &self,
+        account_id: i64,
+        task_id: i64,
+        item_id: i64,
+    ) -> StorageResult<bool> {
+        let mut data = self.data();
+        let Some(record) = data.record_mut(account_id, task_id) else {
+            return Ok(false);
+        };
+        let count = record.checklist.len();
+        record.checklist.retain(|item| item.item_id != item_id);
+        if record.checklist.len() == count {
+            return Ok(false);
+        }
+        record.apply_auto_complete();
+        Ok(true)
+    }
+
+    async fn get_dependencies(
+        &self,
+        account_id: i64,
+        task_id: i64,
+    ) -> StorageResult<Option<Dependencies>> {
+        let data = self.data();
+        let Some(record) = data
+            .tasks
+            .get(&task_id)
+            .filter(|record| record.account_id == account_id)
+        else {
+            return Ok(None);
+        };
+        let mut depends_on = record.depends_on.clone();
+        depends_on.sort();
+        let dependents = data
+            .tasks
+            .iter()
+            .filter(|(_, other)| other.depends_on.contains(&task_id))
+            .map(|(id, _)| *id)
+            .collect();
+        Ok(Some(Dependencies {
+            depends_on,
+            dependents,
+        }))
+    }
+
+    async fn add_dependency(
+        &self,
+        account_id: i64,
+        task_id: i64,
+        depends_on: i64,
+    ) -> StorageResult<bool> {
+        let mut data = self.data();
+        let owned = |id: i64| {
+            data.tasks
+                .get(&id)
+                .is_some_and(|record| record.account_id == account_id)
+        };
+        if !owned(task_id) || !owned(depends_on) {
+            return Ok(false);
+        }
+        // Like the SQL storages' recursive query: the tasks `depends_on` depends on, directly or not
+        let mut reachable = vec![depends_on];
+        let mut next = 0;
+        while let Some(id) = reachable.get(next).copied() {
+            if id == task_id {
+                return Err(StorageError::Conflict);
+            }
+            for dep in &data.tasks[&id].depends_on {
+                if !reachable.contains(dep) {
+                    reachable.push(*dep);
+                }
+            }
+            next += 1;
+        }
+        let record = data
+            .tasks
+            .get_mut(&task_id)
+            .expect("the task was just found");
+        if !record.depends_on.contains(&depends_on) {
+            record.depends_on.push(depends_on);
+        }
+        Ok(true)
+    }
+
+    async fn remove_dependency(
+        &self,
+        account_id: i64,
+        task_id: i64,
+        depends_on: i64,
+    ) -> StorageResult<bool> {
+        let mut data = self.data();
+        let Some(record) = data.record_mut(account_id, task_id) else {
+            return Ok(false);
+        };
+        let count = record.depends_on.len();
+        record.depends_on.retain(|id| *id != depends_on);
+        Ok(record.depends_on.len() < count)
+    }
+
+    async fn start_timer(
+        &self,
+        account_id: i64,
+        task_id: i64,
+        now: i64,
+    ) -> StorageResult<Option<TimeSession>> {
+        let mut data = self.data();
+        if data.record_mut(account_id, task_id).is_none() {
+            return Ok(None);
+        }
+        stop_running(&mut data, account_id, now);
+        let session = TimeSession {
+            session_id: data.next_id(),
+            task_id,
+            started_at: now,
+            stopped_at: None,
+        };
+        data.sessions.insert(
+            session.session_id,
+            SessionRecord {
+                account_id,
+                session: session.clone(),
+            },
+        );
+        Ok(Some(session))
+    }
+
+    async fn stop_timer(&self, account_id: i64, now: i64) -> StorageResult<Option<TimeSession>> {
+        Ok(stop_running(&mut self.data(), account_id, now))
+    }
+
+    async fn running_timer(&self, account_id: i64) -> StorageResult<Option<RunningTimer>> {
+        let data = self.data();
+        Ok(data
+            .sessions
+            .values()
+            .find(|record| record.account_id == account_id && record.session.stopped_at.is_none())
+            .map(|record| RunningTimer {
+                session_id: record.session.session_id,
+                task_id: record.session.task_id,
+                title: data.tasks[&record.session.task_id].task.title.clone(),
+                started_at: record.session.started_at,
+            }))
+    }
+
+    async fn task_sessions(
+        &self,
+        account_id: i64,
+        task_id: i64,
+    ) -> StorageResult<Option<Vec<TimeSession>>> {
+        let mut data = self.data();
+        if data.record_mut(account_id, task_id).is_none() {
+            return Ok(None);
+        }
+        let mut sessions: Vec<TimeSession> = data
+            .sessions
+            .values()
+            .filter(|record| record.session.task_id == task_id)
+            .map(|record| record.session.clone())
+            .collect();
+        sessions.sort_by_key(|session| (session.started_at, session.session_id));
+        Ok(Some(sessions))
+    }
+
+    async fn tracked_sessions(
+        &self,
+        account_id: i64,
+        from: i64,
+        to: i64,
+    ) -> StorageResult<Vec<TrackedSession>> {
+        let data = self.data();
+        let mut sessions: Vec<TrackedSession> = data
+            .sessions
+            .values()
+            .filter(|record| {
+                record.account_id == account_id && (from..to).contains(&record.session.started_at)
+            })
+            .map(|record| {
+                let task = &data.tasks[&record.session.task_id];
+                TrackedSession {
+                    session: record.session.clone(),
+                    tags: data.tag_names(&task.tag_ids),
+                    project: task.task.project_id.flatten().map(|project_id| {
+                        (project_id, data.projects[&project_id].project.name.clone())
+                    }),
+                }
+            })
+            .collect();
+        sessions.sort_by_key(|tracked| (tracked.session.started_at, tracked.session.session_id));
+        Ok(sessions)
+    }
+
+    async fn list_tags(&self, account_id: i64) -> StorageResult<Vec<Tag>> {
+        let mut tags: Vec<Tag> = self
+            .data()
+            .tags
+            .values()
+            .filter(|record| record.account_id == account_id)
+            .map(|record| record.tag.clone())
+            .collect();
+        tags.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(tags)
+    }
+
+    async fn create_tag(&self, account_id: i64, tag: &TagData) -> StorageResult<i64> {
+        if !is_valid_tag_name(&tag.name) {
+            return Err(StorageError::Invalid);
+        }
+        let mut data = self.data();
+        if data
+            .tags
+            .values()
+            .any(|record| record.account_id == account_id && record.tag.name == tag.name)
+        {
+            return Err(StorageError::Conflict);
+        }
+        Ok(data.insert_tag(account_id, tag))
+    }
+
+    async fn update_tag(&self, account_id: i64, tag_id: i64, tag: &TagData) -> StorageResult<bool> {
+        if !is_valid_tag_name(&tag.name) {
+            return Err(StorageError::Invalid);
+        }
+        let mut data = self.data();
+        if data.tags.values().any(|record| {
+            record.account_id == account_id
+                && record.tag.name == tag.name
+                && record.tag.tag_id != tag_id
+        }) {
+            return Err(StorageError::Conflict);
+        }
+        match data.tags.get_mut(&tag_id) {
+            Some(record) if record.account_id == account_id => {
+                record.tag.name = tag.name.clone();
+                record.tag.color = tag.color;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    async fn delete_tag(&self, account_id: i64, tag_id: i64) -> StorageResult<bool> {
+        let mut data = self.data();
+        match data.tags.get(&tag_id) {
+            Some(record) if record.account_id == account_id => {
+                data.tags.remove(&tag_id);
+                for record in data.tasks.values_mut() {
+                    record.tag_ids.retain(|id| *id != tag_id);
+                }
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    async fn list_projects(&self, account_id: i64, today: i32) -> StorageResult<Vec<Project>> {
+        let data = self.data();
+        let mut records: Vec<(&i64, &ProjectRecord)> = data
+            .projects
+            .iter()
+            .filter(|(_, record)| record.account_id == account_id)
+            .collect();
+        // Like `ORDER BY due_date NULLS LAST, name, project_id`
+        records.sort_by(|(a_id, a), (b_id, b)| {
+            (a.due_date.is_none(), a.due_date, &a.project.name, a_id).cmp(&(
+                b.due_date.is_none(),
+                b.due_date,
+                &b.project.name,
+                b_id,
+            ))
+        });
+        Ok(records
+            .into_iter()
+            .map(|(project_id, record)| data.project(record, *project_id, today))
+            .collect())
+    }
+
+    async fn get_project(
+        &self,
+        account_id: i64,
+        project_id: i64,
+        today: i32,
+    ) -> StorageResult<Option<Project>> {
+        let data = self.data();
+        Ok(data
+            .project_record(account_id, project_id)
+            .map(|record| data.project(record, project_id, today)))
+    }
+
+    async fn create_project(&self, account_id: i64, project: &ProjectData) -> StorageResult<i64> {
+        let due_date = project_due_date(project)?;
+        let mut data = self.data();
+        if !data.accounts.contains_key(&account_id) {
+            return Err(StorageError::Invalid);
+        }
+        let project_id = data.next_id();
+        data.projects.insert(
+            project_id,
+            ProjectRecord {
+                account_id,
+                project: project.clone(),
+                due_date,
+            },
+        );
+        Ok(project_id)
+    }
+
+    async fn update_project(
+        &self,
+        account_id: i64,
+        project_id: i64,
+        project: &ProjectData,
+    ) -> StorageResult<bool> {
+        let due_date = project_due_date(project)?;
+        match self.data().projects.get_mut(&project_id) {
+            Some(record) if record.account_id == account_id => {
+                record.project = project.clone();
+                record.due_date = due_date;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    async fn delete_project(&self, account_id: i64, project_id: i64) -> StorageResult<bool> {
+        let mut data = self.data();
+        if data.project_record(account_id, project_id).is_none() {
+            return Ok(false);
+        }
+        data.projects.remove(&project_id);
+        for record in data.tasks.values_mut() {
+            if record.task.project_id == Some(Some(project_id)) {
+                record.task.project_id = Some(None);
+            }
+        }
+        Ok(true)
+    }
+
+    async fn project_tasks(
+        &self,
+        account_id: i64,
+        project_id: i64,
+    ) -> StorageResult<Option<Vec<TaskDataWithId>>> {
+        let data = self.data();
+        if data.project_record(account_id, project_id).is_none() {
+            return Ok(None);
+        }
+        let mut tasks: Vec<TaskDataWithId> = data
+            .tasks
+            .iter()
+            .filter(|(_, record)| record.task.project_id == Some(Some(project_id)))
+            .map(|(task_id, record)| data.task_with_id(record, *task_id))
+            .collect();
+        tasks.sort_by(|a, b| {
+            (a.year, a.month)
+                .cmp(&(b.year, b.month))
+                .then_with(|| cmp_calendar_order(DayOrder::Time, a, b))
+                .then(a.task_id.cmp(&b.task_id))
+        });
+        Ok(Some(tasks))
+    }
+
+    async fn create_api_token(&self, token: NewApiTokenRecord<'_>) -> StorageResult<i64> {
+        let mut data = self.data();
+        if data
+            .api_tokens
+            .values()
+            .any(|t| t.hashed_token == token.hashed_token)
+        {
+            return Err(StorageError::Conflict);
+        }
+        let token_id = data.next_id();
+        data.api_tokens.insert(
+            token_id,
+            ApiTokenRecord {
+                account_id: token.account_id,
+                hashed_token: token.hashed_token.to_string(),
+                info: ApiTokenInfo {
+                    token_id,
+                    name: token.name.to_string(),
+                    scope: token.scope,
+                    created_at: token.created_at,
+                    expires_at: token.expires_at,
+                    last_used_at: None,
+                },
+            },
+        );
+        Ok(token_id)
+    }
+
+    async fn list_api_tokens(&self, account_id: i64) -> StorageResult<Vec<ApiTokenInfo>> {
+        let mut tokens: Vec<ApiTokenInfo> = self
+            .data()
+            .api_tokens
+            .values()
+            .filter(|t| t.account_id == account_id)
+            .map(|t| t.info.clone())
+            .collect();
+        tokens.sort_by_key(|t| (t.created_at, t.token_id));
+        Ok(tokens)
+    }
+
+    async fn find_api_token(&self, hashed_token: &str) -> StorageResult<Option<ApiTokenAuth>> {
+        Ok(self
+            .data()
+            .api_tokens
+            .values()
+            .find(|t| t.hashed_token == hashed_token)
+            .map(|t| ApiTokenAuth {
+                token_id: t.info.token_id,
+                account_id: t.account_id,
+                scope: t.info.scope,
+                expires_at: t.info.expires_at,
+            }))
+    }
+
+    async fn touch_api_token(&self, token_id: i64, last_used_at: i64) -> StorageResult<()> {
+        if let Some(t) = self.data().api_tokens.get_mut(&token_id) {
+            t.info.last_used_at = Some(last_used_at);
+        }
+        Ok(())
+    }
+
+    async fn revoke_api_token(&self, account_id: i64, token_id: i64) -> StorageResult<bool> {
+        let mut data = self.data();
+        match data.api_tokens.get(&token_id) {
+            Some(t) if t.account_id == account_id => {
+                data.api_tokens.remove(&token_id);
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    async fn revoke_all_api_tokens(&self, account_id: i64) -> StorageResult<u64> {
+        let mut data = self.data();
+        let before = data.api_tokens.len();
+        data.api_tokens.retain(|_, t| t.account_id != account_id);
+        Ok((before - data.api_tokens.len()) as u64)
+    }
+
+    async fn purge_expired_api_tokens(&self, now: i64) -> StorageResult<u64> {
+        let mut data = self.data();
+        let before = data.api_tokens.len();
+        data.api_tokens
+            .retain(|_, t| t.info.expires_at.is_none_or(|expires_at| expires_at > now));
+        Ok((before - data.api_tokens.len()) as u64)
+    }
+
+    async fn compact(&self) -> StorageResult<()> {
+        Ok(())
+    }
+
+    async fn check_ready(&self) -> StorageResult<()> {
+        Ok(())
+    }
+
+    fn pool_stats(&self) -> PoolStats {
+        PoolStats::default()
+    }
+
+    async fn close(&self) {}
+}
+
+/// Stop the account's running session at `now` and return it, if there is one
+fn stop_running(data: &mut MemoryData, account_id: i64, now: i64) -> Option<TimeSession> {
+    let record = data
+        .sessions
+        .values_mut()
+        .find(|record| record.account_id == account_id && record.session.stopped_at.is_none())?;
+    record.session.stopped_at = Some(now);
+    Some(record.session.clone())
+}
+
+/// Check the same rules that the database schema enforces on tasks
+fn validate_task(task: &TaskData) -> StorageResult<()> {
+    let valid_min = |min: Option<i32>| min.is_none_or(|min| (0..24 * 60).contains(&min));
+    let valid = task.has_valid_date()
+        && valid_min(task.start_min)
+        && valid_min(task.end_min)
+        && (task.end_min.is_none() || task.start_min.is_some())
+        && task.estimate_mins.is_none_or(|mins| mins >= 0);
+    if valid {
+        Ok(())
+    } else {
+        Err(StorageError::Invalid)
+    }
+}
+
+/// The order of `get_month_tasks`, like the SQL storages'
+/// `ORDER BY day, [priority DESC,] start_min, end_min DESC, position, title`
+fn cmp_calendar_order(order: DayOrder, a: &TaskDataWithId, b: &TaskDataWithId) -> Ordering {
+    // Postgres sorts NULL as larger than any value
+    let nulls_last = |a: Option<i32>, b: Option<i32>| match (a, b) {
+        (Some(a), Some(b)) => a.cmp(&b),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    };
+    let priority = match order {
+        DayOrder::Time => Ordering::Equal,
+        DayOrder::Priority => b.priority.cmp(&a.priority),
+    };
+    a.day
+        .cmp(&b.day)
+        .then(priority)
+        .then_with(|| nulls_last(a.start_min, b.start_min))
+        .then_with(|| nulls_last(b.end_min, a.end_min))
+        .then_with(|| a.position.cmp(&b.position))
+        .then_with(|| a.title.cmp(&b.title))
+}
+
+/// The order of `query_tasks`, like the SQL storages' `ORDER BY` for each sort
+fn cmp_sort_order(sort: TaskSort, a: &TaskKey, b: &TaskKey) -> Ordering {
+    match sort {
+        TaskSort::Date => a.cmp(b),
+        TaskSort::DateDesc => b.cmp(a),
+        TaskSort::Title => a.title.cmp(&b.title).then_with(|| a.cmp(b)),
+        TaskSort::Priority => a
+            .date
+            .cmp(&b.date)
+            .then(a.neg_priority.cmp(&b.neg_priority))
+            .then_with(|| a.cmp(b)),
+    }
+}
+
+/// Split text up around the (ASCII case-insensitive) occurrences of any of the lowercase terms
+fn highlight(text: &str, terms: &[String]) -> Vec<SnippetPart> {
+    let lower = text.to_ascii_lowercase();
+    let mut parts = Vec::new();
+    let mut unmatched_start = 0;
+    let mut i = 0;
+    while i < text.len() {
+        let found = terms
+            .iter()
+            .filter(|term| lower[i..].starts_with(term.as_str()))
+            .map(String::len)
+            .max();
+        match found {
+            Some(len) => {
+                if unmatched_start < i {
+                    parts.push(SnippetPart {
+                        text: text[unmatched_start..i].to_string(),
+                        matched: false,
+                    });
+                }
+                parts.push(SnippetPart {
+                    text: text[i..i + len].to_string(),
+                    matched: true,
+                });
+                i += len;
+                unmatched_start = i;
+            }
+            None => i += text[i..].chars().next().map_or(1, char::len_utf8),
+        }
+    }
+    if unmatched_start < text.len() {
+        parts.push(SnippetPart {
+            text: text[unmatched_start..].to_string(),
+            matched: false,
+        });
+    }
+    parts
+}
+
+fn with_id(task: &TaskData, task_id: i64) -> TaskDataWithId {
+    TaskDataWithId {
+        year: task.year,
+        month: task.month,
+        day: task.day,
+        start_min: task.start_min,
+        end_min: task.end_min,
+        title: task.title.clone(),
+        description: task.description.clone(),
+        complete: task.complete.unwrap_or_default(),
+        status: task.status.unwrap_or_default(),
+        priority: task.priority.unwrap_or_default(),
+        position: task.position,
+        auto_complete: task.auto_complete,
+        project_id: task.project_id.flatten(),
+        estimate_mins: task.estimate_mins,
+        tags: task.tags.clone().unwrap_or_default(),
+        task_id,
+        status_changed_at: None,
+        completed_at: None,
+        checklist: None,
+        blocked_by: Vec::new(),
+        later_dependencies: Vec::new(),
+    }
+}
+
+/// The task to keep in a `TaskRecord`
+fn without_tags(task: &TaskData) -> TaskData {
+    TaskData {
+        tags: None,
+        ..task.clone()
+    }
+}
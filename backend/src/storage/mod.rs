@@ -0,0 +1,787 @@
+use async_trait::async_trait;
+use axum::http::StatusCode;
+use rical_types::{
+    AccountSettings, ApiTokenInfo, ChecklistItem, ChecklistItemData, DayOrder, Dependencies,
+    OperationResult, Project, ProjectData, RicalDate, RunningTimer, SearchHit, StatusChange, Tag,
+    TagData, TaskData, TaskDataWithId, TaskOperation, TaskSort, TaskStatus, TimeSession,
+    TokenScope,
+};
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+#[cfg(test)]
+pub mod memory;
+pub mod postgres;
+mod sql;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+
+// Everything the routes need from the database, behind one trait
+// so that the same routes can run against Postgres or (in tests) plain memory
+
+#[derive(Debug)]
+pub enum StorageError {
+    /// Something unique already exists (e.g. a taken username)
+    Conflict,
+    /// The data breaks one of the schema's rules (e.g. a month of 13)
+    Invalid,
+    /// The storage itself failed (e.g. the database is unreachable)
+    Backend(String),
+}
+
+impl fmt::Display for StorageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StorageError::Conflict => write!(f, "conflicts with existing data"),
+            StorageError::Invalid => write!(f, "invalid data"),
+            StorageError::Backend(msg) => write!(f, "storage failure: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for StorageError {}
+
+impl StorageError {
+    /// Log a failed storage call, e.g. `err.log("create the task")`
+    /// Only failures of the storage itself are errors; invalid data is the client's mistake
+    pub fn log(&self, action: &str) {
+        match self {
+            StorageError::Backend(_) => tracing::error!(error = %self, "Couldn't {}", action),
+            _ => tracing::debug!(error = %self, "Couldn't {}", action),
+        }
+    }
+
+    /// The response to a failed storage call: the client's mistake, or the server's
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            StorageError::Conflict => StatusCode::CONFLICT,
+            StorageError::Invalid => StatusCode::BAD_REQUEST,
+            StorageError::Backend(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+pub type StorageResult<T> = Result<T, StorageError>;
+
+#[derive(Clone, sqlx::FromRow)]
+pub struct Account {
+    pub account_id: i64,
+    pub username: String,
+    pub hashed_password: String,
+    /// Locked accounts can't log in or use any token
+    pub locked: bool,
+    /// Login tokens issued before this time (unix seconds) are no longer accepted
+    pub sessions_revoked_at: Option<i64>,
+}
+
+/// An account as shown to admins
+#[derive(sqlx::FromRow)]
+pub struct AccountSummary {
+    pub account_id: i64,
+    pub username: String,
+    pub locked: bool,
+    pub task_count: i64,
+}
+
+/// An account with auto-rollover on, and when it was last rolled over
+#[derive(sqlx::FromRow)]
+pub struct AutoRollover {
+    pub account_id: i64,
+    pub utc_offset_min: i32,
+    /// The `date_number` of the day that overdue tasks were last moved to
+    pub rolled_over_on: Option<i32>,
+}
+
+/// What's needed to check a personal API token on each request
+pub struct ApiTokenAuth {
+    pub token_id: i64,
+    pub account_id: i64,
+    pub scope: TokenScope,
+    pub expires_at: Option<i64>,
+}
+
+/// How many database connections are open, for metrics
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PoolStats {
+    pub open: u32,
+    pub idle: u32,
+}
+
+/// Which of an account's tasks `query_tasks` returns, and in what order
+pub struct TaskFilter<'a> {
+    /// The first and last dates (both included), as `date_number`s
+    pub from: i32,
+    pub to: i32,
+    pub completed: Option<bool>,
+    /// Whether the tasks have a start time
+    pub timed: Option<bool>,
+    /// Text that the title or description contains, ignoring case
+    pub text: Option<&'a str>,
+    /// Names of tags, any of which the tasks have (or every task, if empty)
+    pub tags: &'a [String],
+    /// Statuses, any of which the tasks have (or every task, if empty)
+    pub statuses: &'a [TaskStatus],
+    pub sort: TaskSort,
+    /// Only the tasks that come after this one in the sort order
+    pub after: Option<&'a TaskKey>,
+    pub limit: u32,
+}
+
+/// Which of an account's tasks `reschedule_tasks` moves (only ever the open ones, which aren't done
+/// or cancelled), and where to
+pub struct Reschedule {
+    /// The first and last dates (both included), as `date_number`s
+    pub from: i32,
+    pub to: i32,
+    pub year: i32,
+    pub month: i32,
+    pub day: i32,
+    /// Otherwise the tasks lose their start and end times
+    pub keep_times: bool,
+}
+
+/// Where a task falls in each `TaskSort`, i.e. the columns that the SQL storages order by,
+/// with the NULL times replaced so that the keys compare like the calendar order
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct TaskKey {
+    pub date: i32,
+    /// The start time, or the end of the day for untimed tasks (so that they come last)
+    pub start: i32,
+    /// The negated end time (so that longer tasks come first), or before any end time if unset
+    pub neg_end: i32,
+    pub position: i32,
+    pub title: String,
+    pub task_id: i64,
+    /// The negated priority, so that higher priorities come first
+    pub neg_priority: i32,
+}
+
+/// Minutes in a day, which no start or end time reaches
+const DAY_MINS: i32 = 24 * 60;
+
+impl TaskKey {
+    pub fn of(task: &TaskDataWithId) -> TaskKey {
+        TaskKey {
+            date: date_number(task.year, task.month, task.day),
+            start: task.start_min.unwrap_or(DAY_MINS),
+            neg_end: -task.end_min.unwrap_or(DAY_MINS),
+            position: task.position,
+            title: task.title.clone(),
+            task_id: task.task_id,
+            neg_priority: -(task.priority as i32),
+        }
+    }
+}
+
+/// A date as one number (e.g. 20250804) that sorts like the date itself
+pub fn date_number(year: i32, month: i32, day: i32) -> i32 {
+    year * 10000 + month * 100 + day
+}
+
+/// Which of an account's tasks `search_tasks` looks through
+pub struct SearchFilter<'a> {
+    /// What the user typed, see `search_terms`
+    pub text: &'a str,
+    /// The first and last dates to include, as `date_number`s
+    pub from: Option<i32>,
+    pub to: Option<i32>,
+    pub completed: Option<bool>,
+    pub limit: u32,
+}
+
+/// Split a search into its words and "quoted phrases", for the storages without a query
+/// language of their own (Postgres parses the text itself)
+pub fn search_terms(text: &str) -> Vec<String> {
+    text.split('"')
+        .enumerate()
+        .flat_map(|(i, part)| {
+            // Every other part is inside quotes
+            if i % 2 == 1 {
+                vec![part.trim().to_string()]
+            } else {
+                part.split_whitespace().map(String::from).collect()
+            }
+        })
+        .filter(|term| !term.is_empty())
+        .collect()
+}
+
+/// The `date_number` of a project's due date, or `Invalid` if it has an empty name or its due
+/// date isn't a `YYYY-MM-DD` date
+pub fn project_due_date(project: &ProjectData) -> StorageResult<Option<i32>> {
+    if project.name.trim().is_empty() {
+        return Err(StorageError::Invalid);
+    }
+    match project.due.as_deref().map(RicalDate::parse_iso) {
+        Some(Some(due)) => Ok(Some(date_number(
+            due.year,
+            due.month as i32,
+            due.day as i32,
+        ))),
+        Some(None) => Err(StorageError::Invalid),
+        None => Ok(None),
+    }
+}
+
+/// A `date_number` as `YYYY-MM-DD`
+pub fn format_date_number(date: i32) -> String {
+    RicalDate {
+        year: date / 10000,
+        month: (date / 100 % 100) as u32,
+        day: (date % 100) as u32,
+    }
+    .format_iso()
+}
+
+/// The result of one of `run_batch`'s operations, given the ID of the task it changed
+/// or None if there is no such task
+/// Only failures of the storage itself fail the whole batch
+pub fn operation_result(outcome: StorageResult<Option<i64>>) -> StorageResult<OperationResult> {
+    match outcome {
+        Ok(Some(task_id)) => Ok(OperationResult::Ok { task_id }),
+        Ok(None) => Ok(OperationResult::NotFound),
+        Err(StorageError::Invalid | StorageError::Conflict) => Ok(OperationResult::Invalid),
+        Err(err) => Err(err),
+    }
+}
+
+/// A session, with what `/tracking/report` can group it by
+#[derive(Clone, Debug, PartialEq)]
+pub struct TrackedSession {
+    pub session: TimeSession,
+    /// The names of its task's tags
+    pub tags: Vec<String>,
+    /// The ID and name of its task's project, if the task is in one
+    pub project: Option<(i64, String)>,
+}
+
+pub struct NewApiTokenRecord<'a> {
+    pub account_id: i64,
+    pub name: &'a str,
+    pub hashed_token: &'a str,
+    pub scope: TokenScope,
+    pub created_at: i64,
+    pub expires_at: Option<i64>,
+}
+
+/// Every method that touches a task takes the account ID, and must only ever see that account's tasks
+#[async_trait]
+pub trait Storage: Send + Sync {
+    // Accounts
+
+    /// Create an account and return its ID, or `Conflict` if the username is taken
+    async fn create_account(&self, username: &str, hashed_password: &str) -> StorageResult<i64>;
+    async fn find_account(&self, username: &str) -> StorageResult<Option<Account>>;
+    async fn get_account(&self, account_id: i64) -> StorageResult<Option<Account>>;
+    /// Every account, by ID
+    async fn list_accounts(&self) -> StorageResult<Vec<AccountSummary>>;
+    /// Each of these returns whether the account exists
+    async fn set_password(&self, account_id: i64, hashed_password: &str) -> StorageResult<bool>;
+    async fn set_locked(&self, account_id: i64, locked: bool) -> StorageResult<bool>;
+    async fn revoke_sessions(&self, account_id: i64, revoked_at: i64) -> StorageResult<bool>;
+    /// Delete an account along with all of its tasks and tokens
+    async fn delete_account(&self, account_id: i64) -> StorageResult<bool>;
+    async fn get_settings(&self, account_id: i64) -> StorageResult<Option<AccountSettings>>;
+    /// Replace an account's settings and return whether it exists
+    async fn set_settings(
+        &self,
+        account_id: i64,
+        settings: &AccountSettings,
+    ) -> StorageResult<bool>;
+    /// Every account with auto-rollover on, by ID
+    async fn auto_rollover_accounts(&self) -> StorageResult<Vec<AutoRollover>>;
+    /// Record the `date_number` of the day that an account's overdue tasks were moved to
+    async fn set_rolled_over(&self, account_id: i64, date: i32) -> StorageResult<bool>;
+
+    // Tasks
+
+    async fn get_task(&self, account_id: i64, task_id: i64) -> StorageResult<Option<TaskData>>;
+    /// Saving a task with a new status (see `TaskData::resolved_status`) records the change
+    /// and when it happened
+    async fn create_task(&self, account_id: i64, task: &TaskData) -> StorageResult<i64>;
+    /// Replace a task and return the task as it was before, or None if it doesn't exist
+    async fn update_task(
+        &self,
+        account_id: i64,
+        task_id: i64,
+        task: &TaskData,
+    ) -> StorageResult<Option<TaskData>>;
+    /// Delete a task and return whether it existed
+    async fn delete_task(&self, account_id: i64, task_id: i64) -> StorageResult<bool>;
+    /// Every change of a task's status, oldest first, or None if the account has no such task
+    async fn status_history(
+        &self,
+        account_id: i64,
+        task_id: i64,
+    ) -> StorageResult<Option<Vec<StatusChange>>>;
+    /// All of a month's tasks, in calendar order: by day, then (with `DayOrder::Priority`)
+    /// highest priority, then start time (untimed last), then end time (latest first),
+    /// then position, then title
+    /// Tasks with a checklist come with its progress, and every task with what it's blocked by
+    /// Only the tasks with any of the `tags` and any of the `statuses` are included,
+    /// unless there are none
+    async fn get_month_tasks(
+        &self,
+        account_id: i64,
+        year: i32,
+        month: i32,
+        order: DayOrder,
+        tags: &[String],
+        statuses: &[TaskStatus],
+    ) -> StorageResult<Vec<TaskDataWithId>>;
+    /// Up to `filter.limit` tasks that match the filter, in its order
+    async fn query_tasks(
+        &self,
+        account_id: i64,
+        filter: &TaskFilter<'_>,
+    ) -> StorageResult<Vec<TaskDataWithId>>;
+    /// Up to `filter.limit` tasks that match the search, best match first
+    async fn search_tasks(
+        &self,
+        account_id: i64,
+        filter: &SearchFilter<'_>,
+    ) -> StorageResult<Vec<SearchHit>>;
+    /// Move the matching tasks to the new date and return how many there were
+    async fn reschedule_tasks(
+        &self,
+        account_id: i64,
+        reschedule: &Reschedule,
+    ) -> StorageResult<u64>;
+    /// Run the operations in order, in one transaction, and return the result of each
+    /// A failed operation changes nothing; if `atomic`, it also undoes the operations before it
+    /// and the ones after it are skipped
+    async fn run_batch(
+        &self,
+        account_id: i64,
+        operations: &[TaskOperation],
+        atomic: bool,
+    ) -> StorageResult<Vec<OperationResult>>;
+
+    // Checklists
+    // Changing a checklist also marks its task done or not done, if the task has `auto_complete`
+    // (and isn't cancelled)
+
+    /// A task's checklist items in order, or None if the account has no such task
+    async fn get_checklist(
+        &self,
+        account_id: i64,
+        task_id: i64,
+    ) -> StorageResult<Option<Vec<ChecklistItem>>>;
+    /// Add an item to a task's checklist and return its ID, or None if the account has no such task
+    async fn create_checklist_item(
+        &self,
+        account_id: i64,
+        task_id: i64,
+        item: &ChecklistItemData,
+    ) -> StorageResult<Option<i64>>;
+    /// Replace an item of a task's checklist and return whether it exists
+    async fn update_checklist_item(
+        &self,
+        account_id: i64,
+        task_id: i64,
+        item_id: i64,
+        item: &ChecklistItemData,
+    ) -> StorageResult<bool>;
+    /// Delete an item of a task's checklist and return whether it existed
+    async fn delete_checklist_item(
+        &self,
+        account_id: i64,
+        task_id: i64,
+        item_id: i64,
+    ) -> StorageResult<bool>;
+
+    // Dependencies
+    // Listed tasks (from `get_month_tasks`, `query_tasks` and `search_tasks`) say which of the tasks
+    // they depend on aren't done or cancelled yet, and which of those are on later dates
+
+    /// The tasks a task depends on and the tasks that depend on it, or None if the account has
+    /// no such task
+    async fn get_dependencies(
+        &self,
+        account_id: i64,
+        task_id: i64,
+    ) -> StorageResult<Option<Dependencies>>;
+    /// Make a task depend on another of the account's tasks and return whether both exist
+    /// `Conflict` if the other task already depends on it, directly or not (or is the same task)
+    async fn add_dependency(
+        &self,
+        account_id: i64,
+        task_id: i64,
+        depends_on: i64,
+    ) -> StorageResult<bool>;
+    /// Stop a task depending on another and return whether it did
+    async fn remove_dependency(
+        &self,
+        account_id: i64,
+        task_id: i64,
+        depends_on: i64,
+    ) -> StorageResult<bool>;
+
+    // Projects
+    // Tasks name their project by ID, and a project that isn't the account's is `Invalid`
+    // Progress counts the tasks dated before `today` (a `date_number`) that aren't done as overdue
+
+    /// All of an account's projects, the soonest due first (then by name)
+    async fn list_projects(&self, account_id: i64, today: i32) -> StorageResult<Vec<Project>>;
+    async fn get_project(
+        &self,
+        account_id: i64,
+        project_id: i64,
+        today: i32,
+    ) -> StorageResult<Option<Project>>;
+    /// Create a project and return its ID; see `project_due_date` for what's `Invalid`
+    async fn create_project(&self, account_id: i64, project: &ProjectData) -> StorageResult<i64>;
+    /// Replace a project and return whether it exists
+    async fn update_project(
+        &self,
+        account_id: i64,
+        project_id: i64,
+        project: &ProjectData,
+    ) -> StorageResult<bool>;
+    /// Delete a project (keeping its tasks, which aren't in a project anymore) and return
+    /// whether it existed
+    async fn delete_project(&self, account_id: i64, project_id: i64) -> StorageResult<bool>;
+    /// A project's tasks by date, then in calendar order, or None if the account has no such project
+    async fn project_tasks(
+        &self,
+        account_id: i64,
+        project_id: i64,
+    ) -> StorageResult<Option<Vec<TaskDataWithId>>>;
+
+    // Time tracking
+    // An account's timer runs at most one session at a time; times are unix seconds
+
+    /// Start a session on the account's task at `now`, stopping the running one first if there is
+    /// one, or return None if the account has no such task
+    async fn start_timer(
+        &self,
+        account_id: i64,
+        task_id: i64,
+        now: i64,
+    ) -> StorageResult<Option<TimeSession>>;
+    /// Stop the running session at `now` and return it, or None if the timer isn't running
+    async fn stop_timer(&self, account_id: i64, now: i64) -> StorageResult<Option<TimeSession>>;
+    async fn running_timer(&self, account_id: i64) -> StorageResult<Option<RunningTimer>>;
+    /// A task's sessions, oldest first, or None if the account has no such task
+    async fn task_sessions(
+        &self,
+        account_id: i64,
+        task_id: i64,
+    ) -> StorageResult<Option<Vec<TimeSession>>>;
+    /// The account's sessions that started from `from` until (not including) `to`, oldest first
+    async fn tracked_sessions(
+        &self,
+        account_id: i64,
+        from: i64,
+        to: i64,
+    ) -> StorageResult<Vec<TrackedSession>>;
+
+    // Tags
+    // Tasks name their tags, and creating or updating a task creates the tags it names that
+    // don't exist yet (with the default color); names that aren't `is_valid_tag_name` are `Invalid`
+
+    /// All of an account's tags, by name
+    async fn list_tags(&self, account_id: i64) -> StorageResult<Vec<Tag>>;
+    /// Create a tag and return its ID, or `Conflict` if the account already has one with the name
+    async fn create_tag(&self, account_id: i64, tag: &TagData) -> StorageResult<i64>;
+    /// Rename or recolor a tag and return whether it exists
+    async fn update_tag(&self, account_id: i64, tag_id: i64, tag: &TagData) -> StorageResult<bool>;
+    /// Delete a tag (taking it off its tasks) and return whether it existed
+    async fn delete_tag(&self, account_id: i64, tag_id: i64) -> StorageResult<bool>;
+
+    // Personal API tokens
+
+    async fn create_api_token(&self, token: NewApiTokenRecord<'_>) -> StorageResult<i64>;
+    async fn list_api_tokens(&self, account_id: i64) -> StorageResult<Vec<ApiTokenInfo>>;
+    async fn find_api_token(&self, hashed_token: &str) -> StorageResult<Option<ApiTokenAuth>>;
+    async fn touch_api_token(&self, token_id: i64, last_used_at: i64) -> StorageResult<()>;
+    /// Delete a token and return whether it existed
+    async fn revoke_api_token(&self, account_id: i64, token_id: i64) -> StorageResult<bool>;
+    /// Delete all of an account's tokens and return how many there were
+    async fn revoke_all_api_tokens(&self, account_id: i64) -> StorageResult<u64>;
+
+    // Maintenance
+
+    /// Delete tokens that expired before `now` and return how many there were
+    async fn purge_expired_api_tokens(&self, now: i64) -> StorageResult<u64>;
+    /// Give the space of deleted data back to the filesystem, where the database supports it
+    async fn compact(&self) -> StorageResult<()>;
+
+    // Health
+
+    /// Check that the database answers and that every migration has been applied
+    async fn check_ready(&self) -> StorageResult<()>;
+    fn pool_stats(&self) -> PoolStats;
+    /// Close every connection, waiting for the ones in use to be given back
+    async fn close(&self);
+}
+
+/// How the pool of database connections is sized
+#[derive(Clone, Debug)]
+pub struct PoolConfig {
+    pub max_connections: u32,
+    /// How long a request waits for a free connection before failing
+    pub acquire_timeout: Duration,
+}
+
+impl Default for PoolConfig {
+    fn default() -> PoolConfig {
+        PoolConfig {
+            max_connections: 10,
+            acquire_timeout: Duration::from_secs(30),
+        }
+    }
+}
+
+/// Connect to the database that `db_url` points to and bring its schema up to date
+/// `sqlite:` URLs use SQLite (with the `sqlite` feature), and anything else uses Postgres
+pub async fn connect(db_url: &str, pool: &PoolConfig) -> StorageResult<Arc<dyn Storage>> {
+    let backend_err = |err: &dyn std::error::Error| StorageError::Backend(err.to_string());
+    if db_url.starts_with("sqlite:") {
+        #[cfg(feature = "sqlite")]
+        {
+            let storage = sqlite::SqliteStorage::connect(db_url, pool)
+                .await
+                .map_err(|err| backend_err(&err))?;
+            storage.migrate().await.map_err(|err| backend_err(&err))?;
+            return Ok(Arc::new(storage));
+        }
+        #[cfg(not(feature = "sqlite"))]
+        return Err(StorageError::Backend(
+            "this build doesn't support SQLite (build with `--features sqlite`)".to_string(),
+        ));
+    }
+    let storage = postgres::PostgresStorage::connect(db_url, pool)
+        .await
+        .map_err(|err| backend_err(&err))?;
+    storage.migrate().await.map_err(|err| backend_err(&err))?;
+    Ok(Arc::new(storage))
+}
+
+/// Like `connect`, but try again with exponential backoff up to `retries` times,
+/// e.g. while the database is still starting up alongside the backend
+pub async fn connect_with_retries(
+    db_url: &str,
+    pool: &PoolConfig,
+    retries: u32,
+) -> StorageResult<Arc<dyn Storage>> {
+    let mut attempt = 0;
+    loop {
+        match connect(db_url, pool).await {
+            Ok(storage) => return Ok(storage),
+            Err(err) if attempt < retries => {
+                let delay = retry_delay(attempt);
+                tracing::warn!(
+                    error = %err,
+                    "Couldn't set up the database, retrying in {}s ({} of {})",
+                    delay.as_secs(),
+                    attempt + 1,
+                    retries
+                );
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+            }
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+/// 1s, 2s, 4s... up to 30s between attempts
+fn retry_delay(attempt: u32) -> Duration {
+    Duration::from_secs(2u64.saturating_pow(attempt).min(30))
+}
+
+/// Helpers to run the same tests against every storage
+#[cfg(test)]
+pub mod testing {
+    use super::*;
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    /// Every storage that can be tested here, named for assertion messages
+    /// Postgres is only included when `TEST_DATABASE_URL` is set, since it needs a running server
+    pub async fn test_storages() -> Vec<(&'static str, Arc<dyn Storage>)> {
+        let mut storages: Vec<(&'static str, Arc<dyn Storage>)> =
+            vec![("memory", Arc::new(memory::MemoryStorage::new()))];
+        #[cfg(feature = "sqlite")]
+        storages.push((
+            "sqlite",
+            connect("sqlite::memory:", &PoolConfig::default())
+                .await
+                .expect("Couldn't set up SQLite"),
+        ));
+        if let Ok(db_url) = std::env::var("TEST_DATABASE_URL") {
+            storages.push((
+                "postgres",
+                connect(&db_url, &PoolConfig::default())
+                    .await
+                    .expect("Couldn't set up Postgres"),
+            ));
+        }
+        storages
+    }
+
+    /// A name that's never been used before, even in a database that outlives the tests
+    pub fn unique(name: &str) -> String {
+        static COUNTER: AtomicU64 = AtomicU64::new(0);
+        format!(
+            "{}_{}_{}_{}",
+            name,
+            crate::utils::now_unix(),
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::SeqCst)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::routes::testing::task;
+    use rical_types::TaskData;
+
+    #[tokio::test]
+    async fn test_month_tasks_are_in_calendar_order() {
+        for (name, storage) in testing::test_storages().await {
+            let account_id = storage
+                .create_account(&testing::unique("user"), "hash")
+                .await
+                .unwrap();
+            for t in [
+                task(2, "untimed"),
+                TaskData {
+                    start_min: Some(60),
+                    end_min: Some(120),
+                    ..task(2, "short")
+                },
+                TaskData {
+                    start_min: Some(60),
+                    end_min: Some(180),
+                    ..task(2, "long")
+                },
+                TaskData {
+                    start_min: Some(60),
+                    ..task(2, "no end")
+                },
+                task(1, "b"),
+                task(1, "a"),
+            ] {
+                storage.create_task(account_id, &t).await.unwrap();
+            }
+
+            let titles: Vec<String> = storage
+                .get_month_tasks(account_id, 2025, 8, DayOrder::Time, &[], &[])
+                .await
+                .unwrap()
+                .into_iter()
+                .map(|t| t.title)
+                .collect();
+            assert_eq!(
+                titles,
+                ["a", "b", "no end", "long", "short", "untimed"].map(String::from),
+                "{}",
+                name
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rejects_what_the_schema_rejects() {
+        for (name, storage) in testing::test_storages().await {
+            let username = testing::unique("user");
+            let account_id = storage.create_account(&username, "hash").await.unwrap();
+            assert!(
+                matches!(
+                    storage.create_account(&username, "other").await,
+                    Err(StorageError::Conflict)
+                ),
+                "{}",
+                name
+            );
+            for invalid in [
+                TaskData {
+                    month: 13,
+                    ..task(1, "")
+                },
+                task(32, ""),
+                // Days past the end of the month and years are checked outside the schema
+                TaskData {
+                    month: 2,
+                    day: 30,
+                    ..task(1, "")
+                },
+                TaskData {
+                    year: 300000,
+                    ..task(1, "")
+                },
+                TaskData {
+                    start_min: Some(24 * 60),
+                    ..task(1, "")
+                },
+                TaskData {
+                    end_min: Some(60),
+                    ..task(1, "")
+                },
+            ] {
+                assert!(
+                    matches!(
+                        storage.create_task(account_id, &invalid).await,
+                        Err(StorageError::Invalid)
+                    ),
+                    "{}",
+                    name
+                );
+            }
+            let far_offset = AccountSettings {
+                auto_rollover: false,
+                utc_offset_min: 15 * 60,
+            };
+            assert!(
+                matches!(
+                    storage.set_settings(account_id, &far_offset).await,
+                    Err(StorageError::Invalid)
+                ),
+                "{}",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn test_search_terms() {
+        assert_eq!(
+            search_terms(r#" dentist  "last spring" x"" "unclosed"#),
+            ["dentist", "last spring", "x", "unclosed"]
+        );
+        assert!(search_terms("  \"\" ").is_empty());
+    }
+
+    #[test]
+    fn test_error_status_codes() {
+        assert_eq!(StorageError::Invalid.status_code(), StatusCode::BAD_REQUEST);
+        assert_eq!(StorageError::Conflict.status_code(), StatusCode::CONFLICT);
+        assert_eq!(
+            StorageError::Backend("unreachable".to_string()).status_code(),
+            StatusCode::INTERNAL_SERVER_ERROR
+        );
+    }
+
+    #[test]
+    fn test_retry_delay_backs_off() {
+        let delays: Vec<u64> = (0..7).map(|n| retry_delay(n).as_secs()).collect();
+        assert_eq!(delays, [1, 2, 4, 8, 16, 30, 30]);
+        assert_eq!(retry_delay(100).as_secs(), 30);
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn test_ready_only_once_migrated() {
+        let storage = sqlite::SqliteStorage::connect("sqlite::memory:", &PoolConfig::default())
+            .await
+            .unwrap();
+        assert!(storage.check_ready().await.is_err());
+        storage.migrate().await.unwrap();
+        assert!(storage.check_ready().await.is_ok());
+        assert_eq!(storage.pool_stats().open, 1);
+    }
+}
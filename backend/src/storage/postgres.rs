@@ -0,0 +1,58 @@
+use sqlx::migrate::{MigrateError, Migrator};
+use sqlx::postgres::{PgPool, PgPoolOptions};
+
+use crate::storage::PoolConfig;
+use crate::storage::sql::{SearchDialect, impl_sql_storage};
+
+// The production storage, in a Postgres database
+
+static MIGRATOR: Migrator = sqlx::migrate!("./migrations/postgres");
+
+/// Full-text search on the generated `search` column, in English (e.g. "appointments" finds
+/// "appointment"), with the syntax of `websearch_to_tsquery`
+static SEARCH: SearchDialect = SearchDialect {
+    sql: r#"
+        SELECT year, month, day,
+        start_min, end_min, title, description, (status = 4) AS complete, status,
+        priority, position, auto_complete, project_id, estimate_mins, task_id, status_changed_at, completed_at,
+        CAST(ts_rank(search, query) AS DOUBLE PRECISION) AS rank,
+        ts_headline('english', title, query,
+            'HighlightAll=true, StartSel=' || chr(2) || ', StopSel=' || chr(3)) AS title_snippet,
+        ts_headline('english', description, query,
+            'MaxFragments=2, MinWords=5, MaxWords=15, FragmentDelimiter=" … ", StartSel='
+            || chr(2) || ', StopSel=' || chr(3)) AS description_snippet
+        FROM task, websearch_to_tsquery('english', $2) AS query
+        WHERE account_id=$1 AND search @@ query
+        AND ($3 IS NULL OR (CAST(year AS BIGINT) * 10000 + month * 100 + day) >= $3)
+        AND ($4 IS NULL OR (CAST(year AS BIGINT) * 10000 + month * 100 + day) <= $4)
+        AND ($5 IS NULL OR (status = 4) = $5)
+        ORDER BY rank DESC, year DESC, month DESC, day DESC, task_id DESC
+        LIMIT $6;
+    "#,
+    search: |text| Some(text.to_string()).filter(|text| !text.trim().is_empty()),
+};
+
+pub struct PostgresStorage {
+    pool: PgPool,
+}
+
+impl PostgresStorage {
+    pub async fn connect(
+        db_url: &str,
+        config: &PoolConfig,
+    ) -> Result<PostgresStorage, sqlx::Error> {
+        let pool = PgPoolOptions::new()
+            .max_connections(config.max_connections)
+            .acquire_timeout(config.acquire_timeout)
+            .connect(db_url)
+            .await?;
+        Ok(PostgresStorage { pool })
+    }
+
+    /// Bring the schema up to date by running any migrations that haven't been run yet
+    pub async fn migrate(&self) -> Result<(), MigrateError> {
+        MIGRATOR.run(&self.pool).await
+    }
+}
+
+impl_sql_storage!(PostgresStorage, sqlx::Postgres, MIGRATOR, SEARCH);
@@ -0,0 +1,1641 @@
+use rical_types::{
+    Project, ProjectProgress, SearchHit, SnippetPart, TaskDataWithId, TaskSort, TaskStatus,
+    TokenScope,
+};
+
+use crate::storage::{StorageError, StorageResult, format_date_number};
+
+// What the SQL storages have in common
+// Postgres and SQLite understand the same queries, so they share one implementation of `Storage`
+
+impl From<sqlx::Error> for StorageError {
+    fn from(err: sqlx::Error) -> StorageError {
+        match &err {
+            sqlx::Error::Database(db_err) if db_err.is_unique_violation() => StorageError::Conflict,
+            sqlx::Error::Database(db_err)
+                if db_err.is_check_violation() || db_err.is_foreign_key_violation() =>
+            {
+                StorageError::Invalid
+            }
+            _ => StorageError::Backend(err.to_string()),
+        }
+    }
+}
+
+#[derive(sqlx::FromRow)]
+pub struct ApiTokenRow {
+    pub token_id: i64,
+    pub account_id: i64,
+    pub name: String,
+    pub scope: String,
+    pub created_at: i64,
+    pub expires_at: Option<i64>,
+    pub last_used_at: Option<i64>,
+}
+
+/// A project with its progress, as counted by `PROJECT_SQL`
+#[derive(sqlx::FromRow)]
+pub struct ProjectRow {
+    pub project_id: i64,
+    pub name: String,
+    pub description: Option<String>,
+    pub due_date: Option<i32>,
+    pub done: i64,
+    pub total: i64,
+    pub overdue: i64,
+}
+
+impl ProjectRow {
+    pub fn into_project(self) -> Project {
+        Project {
+            project_id: self.project_id,
+            name: self.name,
+            description: self.description,
+            due: self.due_date.map(format_date_number),
+            progress: ProjectProgress {
+                done: self.done as u32,
+                total: self.total as u32,
+                overdue: self.overdue as u32,
+            },
+        }
+    }
+}
+
+/// Selects the columns of `ProjectRow` for the account $1's projects, with the tasks dated before
+/// $2 that aren't done counted as overdue; the caller adds the `WHERE` and `GROUP BY`
+pub const PROJECT_SQL: &str = r#"
+    SELECT p.project_id, p.name, p.description, p.due_date,
+    COALESCE(SUM(CASE WHEN t.status = 4 THEN 1 ELSE 0 END), 0) AS done,
+    COUNT(t.task_id) AS total,
+    COALESCE(SUM(CASE WHEN t.status <> 4
+        AND (CAST(t.year AS BIGINT) * 10000 + t.month * 100 + t.day) < $2 THEN 1 ELSE 0 END), 0) AS overdue
+    FROM project p
+    LEFT JOIN task t ON t.project_id = p.project_id AND t.status <> 5
+    WHERE p.account_id = $1
+"#;
+
+pub fn parse_scope(scope: &str) -> StorageResult<TokenScope> {
+    TokenScope::from_db_str(scope)
+        .ok_or_else(|| StorageError::Backend(format!("Unknown token scope '{}'", scope)))
+}
+
+/// The parts of full-text search that differ between the SQL databases
+pub struct SearchDialect {
+    /// Selects the columns of `SearchRow` for the account $1 and the search $2,
+    /// filtered by the dates $3 and $4 and completion $5, best match first, up to $6
+    /// Matches in the snippets are wrapped in the `HIGHLIGHT_START` and `HIGHLIGHT_END` characters
+    pub sql: &'static str,
+    /// Turn what the user typed into the search bound as $2, or None if it can't match anything
+    pub search: fn(&str) -> Option<String>,
+}
+
+pub const HIGHLIGHT_START: char = '\u{2}';
+pub const HIGHLIGHT_END: char = '\u{3}';
+
+#[derive(sqlx::FromRow)]
+pub struct SearchRow {
+    #[sqlx(flatten)]
+    pub task: TaskDataWithId,
+    pub rank: f64,
+    pub title_snippet: String,
+    pub description_snippet: Option<String>,
+}
+
+impl SearchRow {
+    pub fn into_hit(self) -> SearchHit {
+        SearchHit {
+            task: self.task,
+            rank: self.rank,
+            title: snippet_parts(&self.title_snippet),
+            description: self
+                .description_snippet
+                .filter(|snippet| !snippet.is_empty())
+                .map(|snippet| snippet_parts(&snippet)),
+        }
+    }
+}
+
+/// Split a snippet up at its highlight characters
+fn snippet_parts(snippet: &str) -> Vec<SnippetPart> {
+    let mut parts = Vec::new();
+    let mut matched = false;
+    for piece in snippet.split([HIGHLIGHT_START, HIGHLIGHT_END]) {
+        if !piece.is_empty() {
+            parts.push(SnippetPart {
+                text: piece.to_string(),
+                matched,
+            });
+        }
+        matched = !matched;
+    }
+    parts
+}
+
+/// The columns of `TaskKey`, in the order of the SQL statement's parameters after the filters
+const TASK_KEY_COLUMNS: [&str; 7] = [
+    // In BIGINT, since tasks from before years were checked can be far enough out to overflow
+    "(CAST(year AS BIGINT) * 10000 + month * 100 + day)",
+    "COALESCE(start_min, 1440)",
+    "-COALESCE(end_min, 1440)",
+    "position",
+    "title",
+    "task_id",
+    "-priority",
+];
+
+/// The `ORDER BY` for a sort, and the condition that keeps only the tasks after the `TaskKey`
+/// bound as parameters $8 to $14
+pub fn task_sort_sql(sort: TaskSort) -> (String, String) {
+    // Which of the key's columns to order by, most significant first
+    let (positions, descending): ([usize; 7], bool) = match sort {
+        TaskSort::Date => ([0, 1, 2, 3, 4, 5, 6], false),
+        TaskSort::DateDesc => ([0, 1, 2, 3, 4, 5, 6], true),
+        TaskSort::Title => ([4, 0, 1, 2, 3, 5, 6], false),
+        TaskSort::Priority => ([0, 6, 1, 2, 3, 4, 5], false),
+    };
+    let columns = positions.map(|i| TASK_KEY_COLUMNS[i]);
+    let params = positions.map(|i| format!("${}", i + 8));
+    let order = columns.map(|column| {
+        if descending {
+            format!("{} DESC", column)
+        } else {
+            column.to_string()
+        }
+    });
+    let after = format!(
+        "AND ({}) {} ({})",
+        columns.join(", "),
+        if descending { "<" } else { ">" },
+        params.join(", ")
+    );
+    (order.join(", "), after)
+}
+
+/// The condition that keeps only the tasks with any of `count` tags, whose names are bound
+/// as the parameters from `$first` on (or nothing, without any tags)
+pub fn tag_filter_sql(first: usize, count: usize) -> String {
+    if count == 0 {
+        return String::new();
+    }
+    let params: Vec<String> = (first..first + count).map(|i| format!("${}", i)).collect();
+    format!(
+        "AND task_id IN (SELECT tt.task_id FROM task_tag tt JOIN tag g ON g.tag_id = tt.tag_id WHERE g.name IN ({}))",
+        params.join(", ")
+    )
+}
+
+/// The condition that keeps only the tasks with any of `count` statuses, which are bound
+/// as the parameters from `$first` on (or nothing, without any statuses)
+pub fn status_filter_sql(first: usize, count: usize) -> String {
+    if count == 0 {
+        return String::new();
+    }
+    let params: Vec<String> = (first..first + count).map(|i| format!("${}", i)).collect();
+    format!("AND status IN ({})", params.join(", "))
+}
+
+/// When a task that changes to `status` at `now` was completed, if it's done
+pub fn completed_at(status: TaskStatus, now: i64) -> Option<i64> {
+    (status == TaskStatus::Done).then_some(now)
+}
+
+/// A `LIKE ... ESCAPE '\'` pattern for lowercase text containing `text`
+pub fn like_pattern(text: &str) -> String {
+    let mut pattern = String::from("%");
+    for c in text.to_lowercase().chars() {
+        if matches!(c, '%' | '_' | '\\') {
+            pattern.push('\\');
+        }
+        pattern.push(c);
+    }
+    pattern.push('%');
+    pattern
+}
+
+/// Implement `Storage` for a struct with a `pool` field of any SQL database,
+/// given the database, the `Migrator` for its schema and its `SearchDialect`
+macro_rules! impl_sql_storage {
+    ($storage:ty, $db:ty, $migrator:expr, $search:expr) => {
+        // A block of its own, so that the imports don't clash with the caller's
+        const _: () = {
+            use async_trait::async_trait;
+            use rical_types::{AccountSettings, ApiTokenInfo, DayOrder, Dependencies, TaskData, TaskDataWithId};
+            use rical_types::{ChecklistItem, ChecklistItemData, ChecklistProgress};
+            use rical_types::{OperationResult, SearchHit, StatusChange, TaskOperation, TaskStatus};
+            use rical_types::{Project, ProjectData, Tag, TagData, is_valid_tag_name};
+            use rical_types::{RunningTimer, TimeSession};
+            use std::collections::HashMap;
+            use $crate::storage::sql::{
+                ApiTokenRow, PROJECT_SQL, ProjectRow, SearchRow, completed_at, like_pattern, parse_scope, status_filter_sql,
+                tag_filter_sql, task_sort_sql,
+            };
+            use $crate::storage::{
+                Account, AccountSummary, ApiTokenAuth, AutoRollover, NewApiTokenRecord, PoolStats,
+                Reschedule, SearchFilter, Storage, StorageError, StorageResult, TaskFilter, TrackedSession,
+                operation_result, project_due_date,
+            };
+
+            type Connection = <$db as sqlx::Database>::Connection;
+
+            // The statements that change a task, on a connection rather than the pool
+            // so that `run_batch` can run them inside its transaction
+
+            async fn insert_task(conn: &mut Connection, account_id: i64, task: &TaskData) -> StorageResult<i64> {
+                if !task.has_valid_date() {
+                    return Err(StorageError::Invalid);
+                }
+                check_project(conn, account_id, task).await?;
+                let status = task.resolved_status(None);
+                let now = $crate::utils::now_unix();
+                let task_id = sqlx::query_scalar(
+                    r#"
+                    INSERT INTO task
+                    (account_id, year, month, day, start_min, end_min, title, description, status,
+                        priority, position, auto_complete, project_id, estimate_mins, status_changed_at,
+                        completed_at)
+                    VALUES
+                    ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16)
+                    RETURNING task_id
+                "#,
+                )
+                .bind(account_id)
+                .bind(task.year)
+                .bind(task.month)
+                .bind(task.day)
+                .bind(task.start_min)
+                .bind(task.end_min)
+                .bind(&task.title)
+                .bind(&task.description)
+                .bind(status)
+                .bind(task.priority.unwrap_or_default())
+                .bind(task.position)
+                .bind(task.auto_complete)
+                .bind(task.project_id.flatten())
+                .bind(task.estimate_mins)
+                .bind(now)
+                .bind(completed_at(status, now))
+                .fetch_one(&mut *conn)
+                .await?;
+                record_status_change(conn, task_id, status, now).await?;
+                set_task_tags(conn, account_id, task_id, task.tags.as_deref().unwrap_or_default()).await?;
+                Ok(task_id)
+            }
+
+            /// Must run inside a transaction, since it reads then writes
+            /// (SQLite can't return the old row from an UPDATE)
+            async fn replace_task(
+                conn: &mut Connection,
+                account_id: i64,
+                task_id: i64,
+                task: &TaskData,
+            ) -> StorageResult<Option<TaskData>> {
+                if !task.has_valid_date() {
+                    return Err(StorageError::Invalid);
+                }
+                let original: Option<TaskData> = sqlx::query_as(
+                    r#"
+                    SELECT year, month, day,
+                    start_min, end_min, title, description, (status = 4) AS complete, status,
+                    priority, position, auto_complete, project_id, estimate_mins
+                    FROM task WHERE task_id=$1 AND account_id=$2;
+                "#,
+                )
+                .bind(task_id)
+                .bind(account_id)
+                .fetch_optional(&mut *conn)
+                .await?;
+                let Some(mut original) = original else {
+                    return Ok(None);
+                };
+                check_project(conn, account_id, task).await?;
+                // A NULL project decodes as if it were left out
+                original.project_id = Some(original.project_id.flatten());
+                original.tags = Some(task_tags(conn, task_id).await?);
+                sqlx::query(
+                    r#"
+                    UPDATE task
+                    SET year = $1, month = $2, day = $3, start_min = $4, end_min = $5, title = $6,
+                        description = $7, priority = $8, position = $9, auto_complete = $10,
+                        project_id = $11, estimate_mins = $12
+                    WHERE task_id = $13 AND account_id = $14;
+                "#,
+                )
+                .bind(task.year)
+                .bind(task.month)
+                .bind(task.day)
+                .bind(task.start_min)
+                .bind(task.end_min)
+                .bind(&task.title)
+                .bind(&task.description)
+                // What was left out stays as it was
+                .bind(task.priority.or(original.priority).unwrap_or_default())
+                .bind(task.position)
+                .bind(task.auto_complete)
+                .bind(task.project_id.unwrap_or(original.project_id.flatten()))
+                .bind(task.estimate_mins)
+                .bind(task_id)
+                .bind(account_id)
+                .execute(&mut *conn)
+                .await?;
+                set_status(conn, task_id, task.resolved_status(original.status)).await?;
+                if let Some(tags) = &task.tags {
+                    set_task_tags(conn, account_id, task_id, tags).await?;
+                }
+                Ok(Some(original))
+            }
+
+            /// Change a task's status, recording the change, unless it already has that status
+            async fn set_status(conn: &mut Connection, task_id: i64, status: TaskStatus) -> StorageResult<()> {
+                let now = $crate::utils::now_unix();
+                let res = sqlx::query(
+                    r#"
+                    UPDATE task SET status = $1, status_changed_at = $2, completed_at = $3
+                    WHERE task_id = $4 AND status <> $1;
+                "#,
+                )
+                .bind(status)
+                .bind(now)
+                .bind(completed_at(status, now))
+                .bind(task_id)
+                .execute(&mut *conn)
+                .await?;
+                if res.rows_affected() > 0 {
+                    record_status_change(conn, task_id, status, now).await?;
+                }
+                Ok(())
+            }
+
+            async fn record_status_change(
+                conn: &mut Connection,
+                task_id: i64,
+                status: TaskStatus,
+                changed_at: i64,
+            ) -> StorageResult<()> {
+                sqlx::query("INSERT INTO task_status_change (task_id, status, changed_at) VALUES ($1, $2, $3);")
+                    .bind(task_id)
+                    .bind(status)
+                    .bind(changed_at)
+                    .execute(&mut *conn)
+                    .await?;
+                Ok(())
+            }
+
+            async fn remove_task(conn: &mut Connection, account_id: i64, task_id: i64) -> StorageResult<bool> {
+                let res = sqlx::query("DELETE FROM task WHERE task_id = $1 AND account_id = $2;")
+                    .bind(task_id)
+                    .bind(account_id)
+                    .execute(&mut *conn)
+                    .await?;
+                Ok(res.rows_affected() > 0)
+            }
+
+            /// Give a task exactly these tags, creating the ones the account doesn't have yet
+            /// Must run inside a transaction, so that invalid names undo the changes before them
+            async fn set_task_tags(
+                conn: &mut Connection,
+                account_id: i64,
+                task_id: i64,
+                tags: &[String],
+            ) -> StorageResult<()> {
+                if !tags.iter().all(|name| is_valid_tag_name(name)) {
+                    return Err(StorageError::Invalid);
+                }
+                sqlx::query("DELETE FROM task_tag WHERE task_id = $1;")
+                    .bind(task_id)
+                    .execute(&mut *conn)
+                    .await?;
+                for (i, name) in tags.iter().enumerate() {
+                    if tags[..i].contains(name) {
+                        continue;
+                    }
+                    sqlx::query("INSERT INTO tag (account_id, name) VALUES ($1, $2) ON CONFLICT (account_id, name) DO NOTHING;")
+                        .bind(account_id)
+                        .bind(name)
+                        .execute(&mut *conn)
+                        .await?;
+                    sqlx::query(
+                        "INSERT INTO task_tag (task_id, tag_id) SELECT $1, tag_id FROM tag WHERE account_id = $2 AND name = $3;",
+                    )
+                    .bind(task_id)
+                    .bind(account_id)
+                    .bind(name)
+                    .execute(&mut *conn)
+                    .await?;
+                }
+                Ok(())
+            }
+
+            /// The names of a task's tags, alphabetically
+            async fn task_tags(conn: &mut Connection, task_id: i64) -> StorageResult<Vec<String>> {
+                let names = sqlx::query_scalar(
+                    r#"
+                    SELECT g.name FROM task_tag tt JOIN tag g ON g.tag_id = tt.tag_id
+                    WHERE tt.task_id = $1 ORDER BY g.name;
+                "#,
+                )
+                .bind(task_id)
+                .fetch_all(&mut *conn)
+                .await?;
+                Ok(names)
+            }
+
+            /// Fill in the tags of the account's tasks, with one query for all of their dates
+            async fn fill_tags(
+                conn: &mut Connection,
+                account_id: i64,
+                tasks: Vec<&mut TaskDataWithId>,
+            ) -> StorageResult<()> {
+                // In 64 bits, like `TASK_KEY_COLUMNS`
+                let dates = tasks
+                    .iter()
+                    .map(|task| i64::from(task.year) * 10000 + i64::from(task.month * 100 + task.day));
+                let (Some(from), Some(to)) = (dates.clone().min(), dates.max()) else {
+                    return Ok(());
+                };
+                let rows: Vec<(i64, String)> = sqlx::query_as(
+                    r#"
+                    SELECT tt.task_id, g.name
+                    FROM task_tag tt JOIN tag g ON g.tag_id = tt.tag_id JOIN task t ON t.task_id = tt.task_id
+                    WHERE t.account_id = $1
+                    AND (CAST(t.year AS BIGINT) * 10000 + t.month * 100 + t.day) BETWEEN $2 AND $3
+                    ORDER BY g.name;
+                "#,
+                )
+                .bind(account_id)
+                .bind(from)
+                .bind(to)
+                .fetch_all(&mut *conn)
+                .await?;
+                let mut names: HashMap<i64, Vec<String>> = HashMap::new();
+                for (task_id, name) in rows {
+                    names.entry(task_id).or_default().push(name);
+                }
+                for task in tasks {
+                    task.tags = names.remove(&task.task_id).unwrap_or_default();
+                }
+                Ok(())
+            }
+
+            /// Fill in what the account's tasks are blocked by, with one query for all of their dates
+            async fn fill_dependencies(
+                conn: &mut Connection,
+                account_id: i64,
+                tasks: Vec<&mut TaskDataWithId>,
+            ) -> StorageResult<()> {
+                // In 64 bits, like `TASK_KEY_COLUMNS`
+                let dates = tasks
+                    .iter()
+                    .map(|task| i64::from(task.year) * 10000 + i64::from(task.month * 100 + task.day));
+                let (Some(from), Some(to)) = (dates.clone().min(), dates.max()) else {
+                    return Ok(());
+                };
+                let rows: Vec<(i64, i64, bool)> = sqlx::query_as(
+                    r#"
+                    SELECT d.task_id, d.depends_on,
+                        (dep.year, dep.month, dep.day) > (t.year, t.month, t.day)
+                    FROM task_dependency d
+                    JOIN task t ON t.task_id = d.task_id JOIN task dep ON dep.task_id = d.depends_on
+                    WHERE t.account_id = $1
+                    AND (CAST(t.year AS BIGINT) * 10000 + t.month * 100 + t.day) BETWEEN $2 AND $3
+                    AND dep.status NOT IN (4, 5)
+                    ORDER BY d.depends_on;
+                "#,
+                )
+                .bind(account_id)
+                .bind(from)
+                .bind(to)
+                .fetch_all(&mut *conn)
+                .await?;
+                let mut blockers: HashMap<i64, Vec<(i64, bool)>> = HashMap::new();
+                for (task_id, depends_on, later) in rows {
+                    blockers.entry(task_id).or_default().push((depends_on, later));
+                }
+                for task in tasks {
+                    let blockers = blockers.remove(&task.task_id).unwrap_or_default();
+                    task.later_dependencies = blockers.iter().filter(|(_, later)| *later).map(|(id, _)| *id).collect();
+                    task.blocked_by = blockers.into_iter().map(|(id, _)| id).collect();
+                }
+                Ok(())
+            }
+
+            /// Whether the account has a task with this ID
+            async fn owns_task(conn: &mut Connection, account_id: i64, task_id: i64) -> StorageResult<bool> {
+                let found: Option<i64> =
+                    sqlx::query_scalar("SELECT task_id FROM task WHERE task_id=$1 AND account_id=$2;")
+                        .bind(task_id)
+                        .bind(account_id)
+                        .fetch_optional(&mut *conn)
+                        .await?;
+                Ok(found.is_some())
+            }
+
+            /// `Invalid` if the task names a project that isn't the account's, which the schema can't tell
+            async fn check_project(conn: &mut Connection, account_id: i64, task: &TaskData) -> StorageResult<()> {
+                let Some(project_id) = task.project_id.flatten() else {
+                    return Ok(());
+                };
+                let found: Option<i64> =
+                    sqlx::query_scalar("SELECT project_id FROM project WHERE project_id=$1 AND account_id=$2;")
+                        .bind(project_id)
+                        .bind(account_id)
+                        .fetch_optional(&mut *conn)
+                        .await?;
+                match found {
+                    Some(_) => Ok(()),
+                    None => Err(StorageError::Invalid),
+                }
+            }
+
+            /// If the task has `auto_complete` and a checklist, and isn't cancelled, mark it done
+            /// when every item is, and back to do when a done task has an item that isn't
+            async fn apply_auto_complete(conn: &mut Connection, task_id: i64) -> StorageResult<()> {
+                let (status, auto_complete, total, undone): (TaskStatus, bool, i64, i64) = sqlx::query_as(
+                    r#"
+                    SELECT t.status, t.auto_complete, COUNT(c.item_id),
+                        COALESCE(SUM(CASE WHEN c.done THEN 0 ELSE 1 END), 0)
+                    FROM task t LEFT JOIN checklist_item c ON c.task_id = t.task_id
+                    WHERE t.task_id = $1
+                    GROUP BY t.task_id, t.status, t.auto_complete;
+                "#,
+                )
+                .bind(task_id)
+                .fetch_one(&mut *conn)
+                .await?;
+                if !auto_complete || total == 0 || status == TaskStatus::Cancelled {
+                    return Ok(());
+                }
+                if undone == 0 {
+                    set_status(conn, task_id, TaskStatus::Done).await
+                } else if status == TaskStatus::Done {
+                    set_status(conn, task_id, TaskStatus::Todo).await
+                } else {
+                    Ok(())
+                }
+            }
+
+            /// Run one of a batch's operations and return the ID of its task, or None if there is no such task
+            async fn run_operation(
+                conn: &mut Connection,
+                account_id: i64,
+                operation: &TaskOperation,
+            ) -> StorageResult<Option<i64>> {
+                match operation {
+                    TaskOperation::Create { task } => insert_task(conn, account_id, task).await.map(Some),
+                    TaskOperation::Update { task_id, task } => Ok(replace_task(conn, account_id, *task_id, task)
+                        .await?
+                        .map(|_| *task_id)),
+                    TaskOperation::Delete { task_id } => {
+                        remove_task(conn, account_id, *task_id).await?;
+                        Ok(Some(*task_id))
+                    }
+                    TaskOperation::Complete { task_id, complete } => {
+                        let status: Option<TaskStatus> =
+                            sqlx::query_scalar("SELECT status FROM task WHERE task_id = $1 AND account_id = $2;")
+                                .bind(task_id)
+                                .bind(account_id)
+                                .fetch_optional(&mut *conn)
+                                .await?;
+                        let Some(status) = status else {
+                            return Ok(None);
+                        };
+                        set_status(conn, *task_id, status.marked(*complete)).await?;
+                        Ok(Some(*task_id))
+                    }
+                }
+            }
+
+            #[async_trait]
+            impl Storage for $storage {
+                async fn create_account(&self, username: &str, hashed_password: &str) -> StorageResult<i64> {
+                    let account_id = sqlx::query_scalar(
+                        "INSERT INTO account (username, hashed_password) VALUES ($1, $2) RETURNING account_id",
+                    )
+                    .bind(username)
+                    .bind(hashed_password)
+                    .fetch_one(&self.pool)
+                    .await?;
+                    Ok(account_id)
+                }
+
+                async fn find_account(&self, username: &str) -> StorageResult<Option<Account>> {
+                    let account = sqlx::query_as(
+                        r#"
+                        SELECT account_id, username, hashed_password, locked, sessions_revoked_at
+                        FROM account WHERE username=$1;
+                    "#,
+                    )
+                    .bind(username)
+                    .fetch_optional(&self.pool)
+                    .await?;
+                    Ok(account)
+                }
+
+                async fn get_account(&self, account_id: i64) -> StorageResult<Option<Account>> {
+                    let account = sqlx::query_as(
+                        r#"
+                        SELECT account_id, username, hashed_password, locked, sessions_revoked_at
+                        FROM account WHERE account_id=$1;
+                    "#,
+                    )
+                    .bind(account_id)
+                    .fetch_optional(&self.pool)
+                    .await?;
+                    Ok(account)
+                }
+
+                async fn list_accounts(&self) -> StorageResult<Vec<AccountSummary>> {
+                    let accounts = sqlx::query_as(
+                        r#"
+                        SELECT a.account_id, a.username, a.locked, COUNT(t.task_id) AS task_count
+                        FROM account a LEFT JOIN task t ON t.account_id = a.account_id
+                        GROUP BY a.account_id, a.username, a.locked
+                        ORDER BY a.account_id;
+                    "#,
+                    )
+                    .fetch_all(&self.pool)
+                    .await?;
+                    Ok(accounts)
+                }
+
+                async fn set_password(&self, account_id: i64, hashed_password: &str) -> StorageResult<bool> {
+                    let res = sqlx::query("UPDATE account SET hashed_password=$1 WHERE account_id=$2;")
+                        .bind(hashed_password)
+                        .bind(account_id)
+                        .execute(&self.pool)
+                        .await?;
+                    Ok(res.rows_affected() > 0)
+                }
+
+                async fn set_locked(&self, account_id: i64, locked: bool) -> StorageResult<bool> {
+                    let res = sqlx::query("UPDATE account SET locked=$1 WHERE account_id=$2;")
+                        .bind(locked)
+                        .bind(account_id)
+                        .execute(&self.pool)
+                        .await?;
+                    Ok(res.rows_affected() > 0)
+                }
+
+                async fn revoke_sessions(&self, account_id: i64, revoked_at: i64) -> StorageResult<bool> {
+                    let res = sqlx::query("UPDATE account SET sessions_revoked_at=$1 WHERE account_id=$2;")
+                        .bind(revoked_at)
+                        .bind(account_id)
+                        .execute(&self.pool)
+                        .await?;
+                    Ok(res.rows_affected() > 0)
+                }
+
+                async fn delete_account(&self, account_id: i64) -> StorageResult<bool> {
+                    let mut tx = self.pool.begin().await?;
+                    for query in [
+                        "DELETE FROM task WHERE account_id=$1;",
+                        "DELETE FROM tag WHERE account_id=$1;",
+                        "DELETE FROM project WHERE account_id=$1;",
+                        "DELETE FROM api_token WHERE account_id=$1;",
+                    ] {
+                        sqlx::query(query).bind(account_id).execute(&mut *tx).await?;
+                    }
+                    let res = sqlx::query("DELETE FROM account WHERE account_id=$1;")
+                        .bind(account_id)
+                        .execute(&mut *tx)
+                        .await?;
+                    tx.commit().await?;
+                    Ok(res.rows_affected() > 0)
+                }
+
+                async fn get_settings(&self, account_id: i64) -> StorageResult<Option<AccountSettings>> {
+                    let settings = sqlx::query_as(
+                        "SELECT auto_rollover, utc_offset_min FROM account WHERE account_id=$1;",
+                    )
+                    .bind(account_id)
+                    .fetch_optional(&self.pool)
+                    .await?;
+                    Ok(settings)
+                }
+
+                async fn set_settings(
+                    &self,
+                    account_id: i64,
+                    settings: &AccountSettings,
+                ) -> StorageResult<bool> {
+                    let res = sqlx::query(
+                        "UPDATE account SET auto_rollover=$1, utc_offset_min=$2 WHERE account_id=$3;",
+                    )
+                    .bind(settings.auto_rollover)
+                    .bind(settings.utc_offset_min)
+                    .bind(account_id)
+                    .execute(&self.pool)
+                    .await?;
+                    Ok(res.rows_affected() > 0)
+                }
+
+                async fn auto_rollover_accounts(&self) -> StorageResult<Vec<AutoRollover>> {
+                    let accounts = sqlx::query_as(
+                        r#"
+                        SELECT account_id, utc_offset_min, rolled_over_on
+                        FROM account WHERE auto_rollover
+                        ORDER BY account_id;
+                    "#,
+                    )
+                    .fetch_all(&self.pool)
+                    .await?;
+                    Ok(accounts)
+                }
+
+                async fn set_rolled_over(&self, account_id: i64, date: i32) -> StorageResult<bool> {
+                    let res = sqlx::query("UPDATE account SET rolled_over_on=$1 WHERE account_id=$2;")
+                        .bind(date)
+                        .bind(account_id)
+                        .execute(&self.pool)
+                        .await?;
+                    Ok(res.rows_affected() > 0)
+                }
+
+                async fn get_task(&self, account_id: i64, task_id: i64) -> StorageResult<Option<TaskData>> {
+                    let mut conn = self.pool.acquire().await?;
+                    let task: Option<TaskData> = sqlx::query_as(
+                        r#"
+                        SELECT year, month, day,
+                        start_min, end_min, title, description, (status = 4) AS complete, status,
+                    priority, position, auto_complete, project_id, estimate_mins
+                        FROM task WHERE task_id=$1 AND account_id=$2;
+                    "#,
+                    )
+                    .bind(task_id)
+                    .bind(account_id)
+                    .fetch_optional(&mut *conn)
+                    .await?;
+                    let Some(mut task) = task else {
+                        return Ok(None);
+                    };
+                    // A NULL project decodes as if it were left out
+                    task.project_id = Some(task.project_id.flatten());
+                    task.tags = Some(task_tags(&mut conn, task_id).await?);
+                    Ok(Some(task))
+                }
+
+                async fn create_task(&self, account_id: i64, task: &TaskData) -> StorageResult<i64> {
+                    let mut tx = self.pool.begin().await?;
+                    let task_id = insert_task(&mut tx, account_id, task).await?;
+                    tx.commit().await?;
+                    Ok(task_id)
+                }
+
+                async fn update_task(
+                    &self,
+                    account_id: i64,
+                    task_id: i64,
+                    task: &TaskData,
+                ) -> StorageResult<Option<TaskData>> {
+                    let mut tx = self.pool.begin().await?;
+                    let original = replace_task(&mut tx, account_id, task_id, task).await?;
+                    tx.commit().await?;
+                    Ok(original)
+                }
+
+                async fn delete_task(&self, account_id: i64, task_id: i64) -> StorageResult<bool> {
+                    let mut conn = self.pool.acquire().await?;
+                    remove_task(&mut conn, account_id, task_id).await
+                }
+
+                async fn status_history(
+                    &self,
+                    account_id: i64,
+                    task_id: i64,
+                ) -> StorageResult<Option<Vec<StatusChange>>> {
+                    let mut conn = self.pool.acquire().await?;
+                    if !owns_task(&mut conn, account_id, task_id).await? {
+                        return Ok(None);
+                    }
+                    let changes = sqlx::query_as(
+                        "SELECT status, changed_at FROM task_status_change WHERE task_id = $1 ORDER BY changed_at, change_id;",
+                    )
+                    .bind(task_id)
+                    .fetch_all(&mut *conn)
+                    .await?;
+                    Ok(Some(changes))
+                }
+
+                async fn get_month_tasks(
+                    &self,
+                    account_id: i64,
+                    year: i32,
+                    month: i32,
+                    order: DayOrder,
+                    tags: &[String],
+                    statuses: &[TaskStatus],
+                ) -> StorageResult<Vec<TaskDataWithId>> {
+                    let sql = format!(
+                        r#"
+                        SELECT year, month, day,
+                        start_min, end_min, title, description, (status = 4) AS complete, status,
+                        priority, position, auto_complete, project_id, estimate_mins, task_id, status_changed_at, completed_at
+                        FROM task WHERE year=$1 AND month=$2 AND account_id=$3
+                        {}
+                        {}
+                        ORDER BY day, {}start_min NULLS LAST, end_min DESC NULLS FIRST, position, title;
+                    "#,
+                        tag_filter_sql(4, tags.len()),
+                        status_filter_sql(4 + tags.len(), statuses.len()),
+                        match order {
+                            DayOrder::Time => "",
+                            DayOrder::Priority => "priority DESC, ",
+                        }
+                    );
+                    let mut conn = self.pool.acquire().await?;
+                    let mut query = sqlx::query_as(&sql).bind(year).bind(month).bind(account_id);
+                    for name in tags {
+                        query = query.bind(name);
+                    }
+                    for status in statuses {
+                        query = query.bind(status);
+                    }
+                    let mut tasks: Vec<TaskDataWithId> = query.fetch_all(&mut *conn).await?;
+                    let progress: Vec<(i64, i64, i64)> = sqlx::query_as(
+                        r#"
+                        SELECT c.task_id, COUNT(*), SUM(CASE WHEN c.done THEN 1 ELSE 0 END)
+                        FROM checklist_item c JOIN task t ON t.task_id = c.task_id
+                        WHERE t.year=$1 AND t.month=$2 AND t.account_id=$3
+                        GROUP BY c.task_id;
+                    "#,
+                    )
+                    .bind(year)
+                    .bind(month)
+                    .bind(account_id)
+                    .fetch_all(&mut *conn)
+                    .await?;
+                    let progress: HashMap<i64, ChecklistProgress> = progress
+                        .into_iter()
+                        .map(|(task_id, total, done)| {
+                            (task_id, ChecklistProgress { done: done as u32, total: total as u32 })
+                        })
+                        .collect();
+                    for task in &mut tasks {
+                        task.checklist = progress.get(&task.task_id).copied();
+                    }
+                    fill_tags(&mut conn, account_id, tasks.iter_mut().collect()).await?;
+                    fill_dependencies(&mut conn, account_id, tasks.iter_mut().collect()).await?;
+                    Ok(tasks)
+                }
+
+                async fn query_tasks(
+                    &self,
+                    account_id: i64,
+                    filter: &TaskFilter<'_>,
+                ) -> StorageResult<Vec<TaskDataWithId>> {
+                    let (order, after) = task_sort_sql(filter.sort);
+                    let tags_first = if filter.after.is_some() { 15 } else { 8 };
+                    let sql = format!(
+                        r#"
+                        SELECT year, month, day,
+                        start_min, end_min, title, description, (status = 4) AS complete, status,
+                        priority, position, auto_complete, project_id, estimate_mins, task_id, status_changed_at, completed_at
+                        FROM task WHERE account_id=$1
+                        AND (CAST(year AS BIGINT) * 10000 + month * 100 + day) BETWEEN $2 AND $3
+                        AND ($4 IS NULL OR (status = 4) = $4)
+                        AND ($5 IS NULL OR (start_min IS NOT NULL) = $5)
+                        AND ($6 IS NULL OR LOWER(title) LIKE $6 ESCAPE '\'
+                            OR LOWER(COALESCE(description, '')) LIKE $6 ESCAPE '\')
+                        {}
+                        {}
+                        {}
+                        ORDER BY {}
+                        LIMIT $7;
+                    "#,
+                        if filter.after.is_some() { after.as_str() } else { "" },
+                        // The tags' names come after the key's columns, if there's a key,
+                        // and the statuses after the tags
+                        tag_filter_sql(tags_first, filter.tags.len()),
+                        status_filter_sql(tags_first + filter.tags.len(), filter.statuses.len()),
+                        order
+                    );
+                    let mut query = sqlx::query_as(&sql)
+                        .bind(account_id)
+                        .bind(filter.from)
+                        .bind(filter.to)
+                        .bind(filter.completed)
+                        .bind(filter.timed)
+                        .bind(filter.text.map(like_pattern))
+                        .bind(i64::from(filter.limit));
+                    if let Some(key) = filter.after {
+                        query = query
+                            .bind(key.date)
+                            .bind(key.start)
+                            .bind(key.neg_end)
+                            .bind(key.position)
+                            .bind(&key.title)
+                            .bind(key.task_id)
+                            .bind(key.neg_priority);
+                    }
+                    for name in filter.tags {
+                        query = query.bind(name);
+                    }
+                    for status in filter.statuses {
+                        query = query.bind(status);
+                    }
+                    let mut conn = self.pool.acquire().await?;
+                    let mut tasks: Vec<TaskDataWithId> = query.fetch_all(&mut *conn).await?;
+                    fill_tags(&mut conn, account_id, tasks.iter_mut().collect()).await?;
+                    fill_dependencies(&mut conn, account_id, tasks.iter_mut().collect()).await?;
+                    Ok(tasks)
+                }
+
+                async fn search_tasks(
+                    &self,
+                    account_id: i64,
+                    filter: &SearchFilter<'_>,
+                ) -> StorageResult<Vec<SearchHit>> {
+                    let Some(search) = ($search.search)(filter.text) else {
+                        return Ok(Vec::new());
+                    };
+                    let mut conn = self.pool.acquire().await?;
+                    let rows: Vec<SearchRow> = sqlx::query_as($search.sql)
+                        .bind(account_id)
+                        .bind(search)
+                        .bind(filter.from)
+                        .bind(filter.to)
+                        .bind(filter.completed)
+                        .bind(i64::from(filter.limit))
+                        .fetch_all(&mut *conn)
+                        .await?;
+                    let mut hits: Vec<SearchHit> = rows.into_iter().map(SearchRow::into_hit).collect();
+                    fill_tags(&mut conn, account_id, hits.iter_mut().map(|hit| &mut hit.task).collect()).await?;
+                    fill_dependencies(&mut conn, account_id, hits.iter_mut().map(|hit| &mut hit.task).collect())
+                        .await?;
+                    Ok(hits)
+                }
+
+                async fn reschedule_tasks(
+                    &self,
+                    account_id: i64,
+                    reschedule: &Reschedule,
+                ) -> StorageResult<u64> {
+                    let res = sqlx::query(
+                        r#"
+                        UPDATE task
+                        SET year = $1, month = $2, day = $3,
+                            start_min = CASE WHEN $4 THEN start_min END,
+                            end_min = CASE WHEN $4 THEN end_min END
+                        WHERE account_id = $5 AND status NOT IN (4, 5)
+                        AND (CAST(year AS BIGINT) * 10000 + month * 100 + day) BETWEEN $6 AND $7;
+                    "#,
+                    )
+                    .bind(reschedule.year)
+                    .bind(reschedule.month)
+                    .bind(reschedule.day)
+                    .bind(reschedule.keep_times)
+                    .bind(account_id)
+                    .bind(reschedule.from)
+                    .bind(reschedule.to)
+                    .execute(&self.pool)
+                    .await?;
+                    Ok(res.rows_affected())
+                }
+
+                async fn run_batch(
+                    &self,
+                    account_id: i64,
+                    operations: &[TaskOperation],
+                    atomic: bool,
+                ) -> StorageResult<Vec<OperationResult>> {
+                    let mut tx = self.pool.begin().await?;
+                    let mut results = Vec::with_capacity(operations.len());
+                    for operation in operations {
+                        // A savepoint, so that a failed operation can be undone on its own
+                        // (Postgres refuses to go on with a transaction after an error)
+                        let mut savepoint = sqlx::Connection::begin(&mut *tx).await?;
+                        let result = operation_result(run_operation(&mut savepoint, account_id, operation).await)?;
+                        if result.is_ok() {
+                            savepoint.commit().await?;
+                        } else {
+                            savepoint.rollback().await?;
+                            if atomic {
+                                tx.rollback().await?;
+                                results.push(result);
+                                results.resize(operations.len(), OperationResult::Skipped);
+                                return Ok(results);
+                            }
+                        }
+                        results.push(result);
+                    }
+                    tx.commit().await?;
+                    Ok(results)
+                }
+
+                async fn get_checklist(
+                    &self,
+                    account_id: i64,
+                    task_id: i64,
+                ) -> StorageResult<Option<Vec<ChecklistItem>>> {
+                    let mut conn = self.pool.acquire().await?;
+                    if !owns_task(&mut conn, account_id, task_id).await? {
+                        return Ok(None);
+                    }
+                    let items = sqlx::query_as(
+                        r#"
+                        SELECT item_id, text, done, position FROM checklist_item
+                        WHERE task_id=$1 ORDER BY position, item_id;
+                    "#,
+                    )
+                    .bind(task_id)
+                    .fetch_all(&mut *conn)
+                    .await?;
+                    Ok(Some(items))
+                }
+
+                async fn create_checklist_item(
+                    &self,
+                    account_id: i64,
+                    task_id: i64,
+                    item: &ChecklistItemData,
+                ) -> StorageResult<Option<i64>> {
+                    let mut tx = self.pool.begin().await?;
+                    if !owns_task(&mut tx, account_id, task_id).await? {
+                        return Ok(None);
+                    }
+                    let item_id = sqlx::query_scalar(
+                        r#"
+                        INSERT INTO checklist_item (task_id, text, done, position)
+                        VALUES ($1, $2, $3, COALESCE($4,
+                            (SELECT COALESCE(MAX(position) + 1, 0) FROM checklist_item WHERE task_id = $1)))
+                        RETURNING item_id
+                    "#,
+                    )
+                    .bind(task_id)
+                    .bind(&item.text)
+                    .bind(item.done)
+                    .bind(item.position)
+                    .fetch_one(&mut *tx)
+                    .await?;
+                    apply_auto_complete(&mut tx, task_id).await?;
+                    tx.commit().await?;
+                    Ok(Some(item_id))
+                }
+
+                async fn update_checklist_item(
+                    &self,
+                    account_id: i64,
+                    task_id: i64,
+                    item_id: i64,
+                    item: &ChecklistItemData,
+                ) -> StorageResult<bool> {
+                    let mut tx = self.pool.begin().await?;
+                    let res = sqlx::query(
+                        r#"
+                        UPDATE checklist_item
+                        SET text = $1, done = $2, position = COALESCE($3, position)
+                        WHERE item_id = $4 AND task_id = $5
+                        AND task_id IN (SELECT task_id FROM task WHERE account_id = $6);
+                    "#,
+                    )
+                    .bind(&item.text)
+                    .bind(item.done)
+                    .bind(item.position)
+                    .bind(item_id)
+                    .bind(task_id)
+                    .bind(account_id)
+                    .execute(&mut *tx)
+                    .await?;
+                    if res.rows_affected() == 0 {
+                        return Ok(false);
+                    }
+                    apply_auto_complete(&mut tx, task_id).await?;
+                    tx.commit().await?;
+                    Ok(true)
+                }
+
+                async fn delete_checklist_item(
+                    &self,
+                    account_id: i64,
+                    task_id: i64,
+                    item_id: i64,
+                ) -> StorageResult<bool> {
+                    let mut tx = self.pool.begin().await?;
+                    let res = sqlx::query(
+                        r#"
+                        DELETE FROM checklist_item
+                        WHERE item_id = $1 AND task_id = $2
+                        AND task_id IN (SELECT task_id FROM task WHERE account_id = $3);
+                    "#,
+                    )
+                    .bind(item_id)
+                    .bind(task_id)
+                    .bind(account_id)
+                    .execute(&mut *tx)
+                    .await?;
+                    if res.rows_affected() == 0 {
+                        return Ok(false);
+                    }
+                    apply_auto_complete(&mut tx, task_id).await?;
+                    tx.commit().await?;
+                    Ok(true)
+                }
+
+                async fn get_dependencies(
+                    &self,
+                    account_id: i64,
+                    task_id: i64,
+                ) -> StorageResult<Option<Dependencies>> {
+                    let mut conn = self.pool.acquire().await?;
+                    if !owns_task(&mut conn, account_id, task_id).await? {
+                        return Ok(None);
+                    }
+                    let depends_on =
+                        sqlx::query_scalar("SELECT depends_on FROM task_dependency WHERE task_id = $1 ORDER BY depends_on;")
+                            .bind(task_id)
+                            .fetch_all(&mut *conn)
+                            .await?;
+                    let dependents =
+                        sqlx::query_scalar("SELECT task_id FROM task_dependency WHERE depends_on = $1 ORDER BY task_id;")
+                            .bind(task_id)
+                            .fetch_all(&mut *conn)
+                            .await?;
+                    Ok(Some(Dependencies { depends_on, dependents }))
+                }
+
+                async fn add_dependency(
+                    &self,
+                    account_id: i64,
+                    task_id: i64,
+                    depends_on: i64,
+                ) -> StorageResult<bool> {
+                    let mut tx = self.pool.begin().await?;
+                    if !owns_task(&mut tx, account_id, task_id).await? || !owns_task(&mut tx, account_id, depends_on).await? {
+                        return Ok(false);
+                    }
+                    // Every task `depends_on` depends on, directly or not, including itself
+                    let cycle: bool = sqlx::query_scalar(
+                        r#"
+                        WITH RECURSIVE reachable(task_id) AS (
+                            SELECT CAST($1 AS BIGINT)
+                            UNION
+                            SELECT d.depends_on FROM task_dependency d JOIN reachable r ON d.task_id = r.task_id
+                        )
+                        SELECT EXISTS (SELECT 1 FROM reachable WHERE task_id = $2);
+                    "#,
+                    )
+                    .bind(depends_on)
+                    .bind(task_id)
+                    .fetch_one(&mut *tx)
+                    .await?;
+                    if cycle {
+                        return Err(StorageError::Conflict);
+                    }
+                    sqlx::query(
+                        "INSERT INTO task_dependency (task_id, depends_on) VALUES ($1, $2) ON CONFLICT (task_id, depends_on) DO NOTHING;",
+                    )
+                    .bind(task_id)
+                    .bind(depends_on)
+                    .execute(&mut *tx)
+                    .await?;
+                    tx.commit().await?;
+                    Ok(true)
+                }
+
+                async fn remove_dependency(
+                    &self,
+                    account_id: i64,
+                    task_id: i64,
+                    depends_on: i64,
+                ) -> StorageResult<bool> {
+                    let res = sqlx::query(
+                        r#"
+                        DELETE FROM task_dependency WHERE task_id = $1 AND depends_on = $2
+                        AND task_id IN (SELECT task_id FROM task WHERE account_id = $3);
+                    "#,
+                    )
+                    .bind(task_id)
+                    .bind(depends_on)
+                    .bind(account_id)
+                    .execute(&self.pool)
+                    .await?;
+                    Ok(res.rows_affected() > 0)
+                }
+
+                async fn start_timer(
+                    &self,
+                    account_id: i64,
+                    task_id: i64,
+                    now: i64,
+                ) -> StorageResult<Option<TimeSession>> {
+                    let mut tx = self.pool.begin().await?;
+                    if !owns_task(&mut tx, account_id, task_id).await? {
+                        return Ok(None);
+                    }
+                    sqlx::query("UPDATE time_session SET stopped_at=$1 WHERE account_id=$2 AND stopped_at IS NULL;")
+                        .bind(now)
+                        .bind(account_id)
+                        .execute(&mut *tx)
+                        .await?;
+                    let session_id = sqlx::query_scalar(
+                        "INSERT INTO time_session (task_id, account_id, started_at) VALUES ($1, $2, $3) RETURNING session_id",
+                    )
+                    .bind(task_id)
+                    .bind(account_id)
+                    .bind(now)
+                    .fetch_one(&mut *tx)
+                    .await?;
+                    tx.commit().await?;
+                    Ok(Some(TimeSession { session_id, task_id, started_at: now, stopped_at: None }))
+                }
+
+                async fn stop_timer(&self, account_id: i64, now: i64) -> StorageResult<Option<TimeSession>> {
+                    let mut tx = self.pool.begin().await?;
+                    let running: Option<TimeSession> = sqlx::query_as(
+                        "SELECT session_id, task_id, started_at, stopped_at FROM time_session WHERE account_id=$1 AND stopped_at IS NULL;",
+                    )
+                    .bind(account_id)
+                    .fetch_optional(&mut *tx)
+                    .await?;
+                    let Some(mut session) = running else {
+                        return Ok(None);
+                    };
+                    sqlx::query("UPDATE time_session SET stopped_at=$1 WHERE session_id=$2;")
+                        .bind(now)
+                        .bind(session.session_id)
+                        .execute(&mut *tx)
+                        .await?;
+                    tx.commit().await?;
+                    session.stopped_at = Some(now);
+                    Ok(Some(session))
+                }
+
+                async fn running_timer(&self, account_id: i64) -> StorageResult<Option<RunningTimer>> {
+                    let running = sqlx::query_as(
+                        r#"
+                        SELECT s.session_id, s.task_id, t.title, s.started_at
+                        FROM time_session s JOIN task t ON t.task_id = s.task_id
+                        WHERE s.account_id=$1 AND s.stopped_at IS NULL;
+                    "#,
+                    )
+                    .bind(account_id)
+                    .fetch_optional(&self.pool)
+                    .await?;
+                    Ok(running)
+                }
+
+                async fn task_sessions(
+                    &self,
+                    account_id: i64,
+                    task_id: i64,
+                ) -> StorageResult<Option<Vec<TimeSession>>> {
+                    let mut conn = self.pool.acquire().await?;
+                    if !owns_task(&mut conn, account_id, task_id).await? {
+                        return Ok(None);
+                    }
+                    let sessions = sqlx::query_as(
+                        "SELECT session_id, task_id, started_at, stopped_at FROM time_session WHERE task_id=$1 ORDER BY started_at, session_id;",
+                    )
+                    .bind(task_id)
+                    .fetch_all(&mut *conn)
+                    .await?;
+                    Ok(Some(sessions))
+                }
+
+                async fn tracked_sessions(
+                    &self,
+                    account_id: i64,
+                    from: i64,
+                    to: i64,
+                ) -> StorageResult<Vec<TrackedSession>> {
+                    let mut conn = self.pool.acquire().await?;
+                    let rows: Vec<(i64, i64, i64, Option<i64>, Option<i64>, Option<String>)> = sqlx::query_as(
+                        r#"
+                        SELECT s.session_id, s.task_id, s.started_at, s.stopped_at, p.project_id, p.name
+                        FROM time_session s JOIN task t ON t.task_id = s.task_id
+                        LEFT JOIN project p ON p.project_id = t.project_id
+                        WHERE s.account_id=$1 AND s.started_at >= $2 AND s.started_at < $3
+                        ORDER BY s.started_at, s.session_id;
+                    "#,
+                    )
+                    .bind(account_id)
+                    .bind(from)
+                    .bind(to)
+                    .fetch_all(&mut *conn)
+                    .await?;
+                    let tag_rows: Vec<(i64, String)> = sqlx::query_as(
+                        r#"
+                        SELECT DISTINCT tt.task_id, g.name
+                        FROM task_tag tt JOIN tag g ON g.tag_id = tt.tag_id
+                        JOIN time_session s ON s.task_id = tt.task_id
+                        WHERE s.account_id=$1 AND s.started_at >= $2 AND s.started_at < $3
+                        ORDER BY g.name;
+                    "#,
+                    )
+                    .bind(account_id)
+                    .bind(from)
+                    .bind(to)
+                    .fetch_all(&mut *conn)
+                    .await?;
+                    let mut tags: HashMap<i64, Vec<String>> = HashMap::new();
+                    for (task_id, name) in tag_rows {
+                        tags.entry(task_id).or_default().push(name);
+                    }
+                    Ok(rows
+                        .into_iter()
+                        .map(|(session_id, task_id, started_at, stopped_at, project_id, project_name)| TrackedSession {
+                            session: TimeSession { session_id, task_id, started_at, stopped_at },
+                            tags: tags.get(&task_id).cloned().unwrap_or_default(),
+                            project: project_id.zip(project_name),
+                        })
+                        .collect())
+                }
+
+                async fn list_tags(&self, account_id: i64) -> StorageResult<Vec<Tag>> {
+                    let tags = sqlx::query_as(
+                        "SELECT tag_id, name, color FROM tag WHERE account_id=$1 ORDER BY name;",
+                    )
+                    .bind(account_id)
+                    .fetch_all(&self.pool)
+                    .await?;
+                    Ok(tags)
+                }
+
+                async fn create_tag(&self, account_id: i64, tag: &TagData) -> StorageResult<i64> {
+                    if !is_valid_tag_name(&tag.name) {
+                        return Err(StorageError::Invalid);
+                    }
+                    let tag_id = sqlx::query_scalar(
+                        "INSERT INTO tag (account_id, name, color) VALUES ($1, $2, $3) RETURNING tag_id",
+                    )
+                    .bind(account_id)
+                    .bind(&tag.name)
+                    .bind(tag.color)
+                    .fetch_one(&self.pool)
+                    .await?;
+                    Ok(tag_id)
+                }
+
+                async fn update_tag(
+                    &self,
+                    account_id: i64,
+                    tag_id: i64,
+                    tag: &TagData,
+                ) -> StorageResult<bool> {
+                    if !is_valid_tag_name(&tag.name) {
+                        return Err(StorageError::Invalid);
+                    }
+                    let res = sqlx::query("UPDATE tag SET name=$1, color=$2 WHERE tag_id=$3 AND account_id=$4;")
+                        .bind(&tag.name)
+                        .bind(tag.color)
+                        .bind(tag_id)
+                        .bind(account_id)
+                        .execute(&self.pool)
+                        .await?;
+                    Ok(res.rows_affected() > 0)
+                }
+
+                async fn delete_tag(&self, account_id: i64, tag_id: i64) -> StorageResult<bool> {
+                    let res = sqlx::query("DELETE FROM tag WHERE tag_id=$1 AND account_id=$2;")
+                        .bind(tag_id)
+                        .bind(account_id)
+                        .execute(&self.pool)
+                        .await?;
+                    Ok(res.rows_affected() > 0)
+                }
+
+                async fn list_projects(&self, account_id: i64, today: i32) -> StorageResult<Vec<Project>> {
+                    let sql = format!(
+                        "{} GROUP BY p.project_id, p.name, p.description, p.due_date ORDER BY p.due_date NULLS LAST, p.name, p.project_id;",
+                        PROJECT_SQL
+                    );
+                    let rows: Vec<ProjectRow> = sqlx::query_as(&sql)
+                        .bind(account_id)
+                        .bind(today)
+                        .fetch_all(&self.pool)
+                        .await?;
+                    Ok(rows.into_iter().map(ProjectRow::into_project).collect())
+                }
+
+                async fn get_project(
+                    &self,
+                    account_id: i64,
+                    project_id: i64,
+                    today: i32,
+                ) -> StorageResult<Option<Project>> {
+                    let sql = format!(
+                        "{} AND p.project_id = $3 GROUP BY p.project_id, p.name, p.description, p.due_date;",
+                        PROJECT_SQL
+                    );
+                    let row: Option<ProjectRow> = sqlx::query_as(&sql)
+                        .bind(account_id)
+                        .bind(today)
+                        .bind(project_id)
+                        .fetch_optional(&self.pool)
+                        .await?;
+                    Ok(row.map(ProjectRow::into_project))
+                }
+
+                async fn create_project(&self, account_id: i64, project: &ProjectData) -> StorageResult<i64> {
+                    let due_date = project_due_date(project)?;
+                    let project_id = sqlx::query_scalar(
+                        "INSERT INTO project (account_id, name, description, due_date) VALUES ($1, $2, $3, $4) RETURNING project_id",
+                    )
+                    .bind(account_id)
+                    .bind(&project.name)
+                    .bind(&project.description)
+                    .bind(due_date)
+                    .fetch_one(&self.pool)
+                    .await?;
+                    Ok(project_id)
+                }
+
+                async fn update_project(
+                    &self,
+                    account_id: i64,
+                    project_id: i64,
+                    project: &ProjectData,
+                ) -> StorageResult<bool> {
+                    let due_date = project_due_date(project)?;
+                    let res = sqlx::query(
+                        "UPDATE project SET name=$1, description=$2, due_date=$3 WHERE project_id=$4 AND account_id=$5;",
+                    )
+                    .bind(&project.name)
+                    .bind(&project.description)
+                    .bind(due_date)
+                    .bind(project_id)
+                    .bind(account_id)
+                    .execute(&self.pool)
+                    .await?;
+                    Ok(res.rows_affected() > 0)
+                }
+
+                async fn delete_project(&self, account_id: i64, project_id: i64) -> StorageResult<bool> {
+                    // The schema takes the project off its tasks
+                    let res = sqlx::query("DELETE FROM project WHERE project_id=$1 AND account_id=$2;")
+                        .bind(project_id)
+                        .bind(account_id)
+                        .execute(&self.pool)
+                        .await?;
+                    Ok(res.rows_affected() > 0)
+                }
+
+                async fn project_tasks(
+                    &self,
+                    account_id: i64,
+                    project_id: i64,
+                ) -> StorageResult<Option<Vec<TaskDataWithId>>> {
+                    let mut conn = self.pool.acquire().await?;
+                    let found: Option<i64> =
+                        sqlx::query_scalar("SELECT project_id FROM project WHERE project_id=$1 AND account_id=$2;")
+                            .bind(project_id)
+                            .bind(account_id)
+                            .fetch_optional(&mut *conn)
+                            .await?;
+                    if found.is_none() {
+                        return Ok(None);
+                    }
+                    let mut tasks: Vec<TaskDataWithId> = sqlx::query_as(
+                        r#"
+                        SELECT year, month, day,
+                        start_min, end_min, title, description, (status = 4) AS complete, status,
+                        priority, position, auto_complete, project_id, estimate_mins, task_id, status_changed_at, completed_at
+                        FROM task WHERE project_id=$1 AND account_id=$2
+                        ORDER BY year, month, day, start_min NULLS LAST, end_min DESC NULLS FIRST, position, title,
+                            task_id;
+                    "#,
+                    )
+                    .bind(project_id)
+                    .bind(account_id)
+                    .fetch_all(&mut *conn)
+                    .await?;
+                    fill_tags(&mut conn, account_id, tasks.iter_mut().collect()).await?;
+                    fill_dependencies(&mut conn, account_id, tasks.iter_mut().collect()).await?;
+                    Ok(Some(tasks))
+                }
+
+                async fn create_api_token(&self, token: NewApiTokenRecord<'_>) -> StorageResult<i64> {
+                    let token_id = sqlx::query_scalar(
+                        r#"
+                        INSERT INTO api_token
+                        (account_id, name, hashed_token, scope, created_at, expires_at)
+                        VALUES
+                        ($1, $2, $3, $4, $5, $6)
+                        RETURNING token_id
+                    "#,
+                    )
+                    .bind(token.account_id)
+                    .bind(token.name)
+                    .bind(token.hashed_token)
+                    .bind(token.scope.as_str())
+                    .bind(token.created_at)
+                    .bind(token.expires_at)
+                    .fetch_one(&self.pool)
+                    .await?;
+                    Ok(token_id)
+                }
+
+                async fn list_api_tokens(&self, account_id: i64) -> StorageResult<Vec<ApiTokenInfo>> {
+                    let rows: Vec<ApiTokenRow> = sqlx::query_as(
+                        r#"
+                        SELECT token_id, account_id, name, scope, created_at, expires_at, last_used_at
+                        FROM api_token WHERE account_id=$1
+                        ORDER BY created_at, token_id;
+                    "#,
+                    )
+                    .bind(account_id)
+                    .fetch_all(&self.pool)
+                    .await?;
+                    rows.into_iter()
+                        .map(|row| {
+                            Ok(ApiTokenInfo {
+                                token_id: row.token_id,
+                                name: row.name,
+                                scope: parse_scope(&row.scope)?,
+                                created_at: row.created_at,
+                                expires_at: row.expires_at,
+                                last_used_at: row.last_used_at,
+                            })
+                        })
+                        .collect()
+                }
+
+                async fn find_api_token(&self, hashed_token: &str) -> StorageResult<Option<ApiTokenAuth>> {
+                    let row: Option<ApiTokenRow> = sqlx::query_as(
+                        r#"
+                        SELECT token_id, account_id, name, scope, created_at, expires_at, last_used_at
+                        FROM api_token WHERE hashed_token=$1;
+                    "#,
+                    )
+                    .bind(hashed_token)
+                    .fetch_optional(&self.pool)
+                    .await?;
+                    row.map(|row| {
+                        Ok(ApiTokenAuth {
+                            token_id: row.token_id,
+                            account_id: row.account_id,
+                            scope: parse_scope(&row.scope)?,
+                            expires_at: row.expires_at,
+                        })
+                    })
+                    .transpose()
+                }
+
+                async fn touch_api_token(&self, token_id: i64, last_used_at: i64) -> StorageResult<()> {
+                    sqlx::query("UPDATE api_token SET last_used_at=$1 WHERE token_id=$2;")
+                        .bind(last_used_at)
+                        .bind(token_id)
+                        .execute(&self.pool)
+                        .await?;
+                    Ok(())
+                }
+
+                async fn revoke_api_token(&self, account_id: i64, token_id: i64) -> StorageResult<bool> {
+                    let res = sqlx::query("DELETE FROM api_token WHERE token_id=$1 AND account_id=$2;")
+                        .bind(token_id)
+                        .bind(account_id)
+                        .execute(&self.pool)
+                        .await?;
+                    Ok(res.rows_affected() > 0)
+                }
+
+                async fn revoke_all_api_tokens(&self, account_id: i64) -> StorageResult<u64> {
+                    let res = sqlx::query("DELETE FROM api_token WHERE account_id=$1;")
+                        .bind(account_id)
+                        .execute(&self.pool)
+                        .await?;
+                    Ok(res.rows_affected())
+                }
+
+                async fn purge_expired_api_tokens(&self, now: i64) -> StorageResult<u64> {
+                    let res = sqlx::query("DELETE FROM api_token WHERE expires_at <= $1;")
+                        .bind(now)
+                        .execute(&self.pool)
+                        .await?;
+                    Ok(res.rows_affected())
+                }
+
+                async fn compact(&self) -> StorageResult<()> {
+                    sqlx::query("VACUUM;").execute(&self.pool).await?;
+                    Ok(())
+                }
+
+                async fn check_ready(&self) -> StorageResult<()> {
+                    let applied: Vec<i64> =
+                        sqlx::query_scalar("SELECT version FROM _sqlx_migrations WHERE success;")
+                            .fetch_all(&self.pool)
+                            .await?;
+                    match $migrator.iter().find(|m| !applied.contains(&m.version)) {
+                        Some(pending) => Err(StorageError::Backend(format!(
+                            "migration {} ({}) hasn't been applied",
+                            pending.version, pending.description
+                        ))),
+                        None => Ok(()),
+                    }
+                }
+
+                fn pool_stats(&self) -> PoolStats {
+                    PoolStats {
+                        open: self.pool.size(),
+                        idle: self.pool.num_idle() as u32,
+                    }
+                }
+
+                async fn close(&self) {
+                    self.pool.close().await;
+                }
+            }
+        };
+    };
+}
+
+pub(crate) use impl_sql_storage;
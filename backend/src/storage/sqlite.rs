@@ -0,0 +1,84 @@
+use sqlx::migrate::{MigrateError, Migrator};
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePool, SqlitePoolOptions};
+use std::str::FromStr;
+
+use crate::storage::sql::{SearchDialect, impl_sql_storage};
+use crate::storage::{PoolConfig, search_terms};
+
+// Storage in a single SQLite file, for personal instances that don't want to run Postgres
+
+static MIGRATOR: Migrator = sqlx::migrate!("./migrations/sqlite");
+
+/// Full-text search on the FTS5 table `task_search`, with English stemming like Postgres
+static SEARCH: SearchDialect = SearchDialect {
+    sql: r#"
+        SELECT t.year, t.month, t.day,
+        t.start_min, t.end_min, t.title, t.description, (t.status = 4) AS complete, t.status,
+        t.priority, t.position, t.auto_complete, t.project_id, t.estimate_mins, t.task_id, t.status_changed_at, t.completed_at,
+        -bm25(task_search, 10.0, 1.0) AS rank,
+        highlight(task_search, 0, char(2), char(3)) AS title_snippet,
+        CASE WHEN t.description IS NULL THEN NULL
+            ELSE snippet(task_search, 1, char(2), char(3), '…', 15) END AS description_snippet
+        FROM task_search JOIN task t ON t.task_id = task_search.rowid
+        WHERE task_search MATCH $2 AND t.account_id=$1
+        AND ($3 IS NULL OR (CAST(t.year AS BIGINT) * 10000 + t.month * 100 + t.day) >= $3)
+        AND ($4 IS NULL OR (CAST(t.year AS BIGINT) * 10000 + t.month * 100 + t.day) <= $4)
+        AND ($5 IS NULL OR (t.status = 4) = $5)
+        ORDER BY rank DESC, t.year DESC, t.month DESC, t.day DESC, t.task_id DESC
+        LIMIT $6;
+    "#,
+    search: fts5_query,
+};
+
+/// Every term as an FTS5 string (so that nothing the user types is taken as syntax),
+/// which must all match
+fn fts5_query(text: &str) -> Option<String> {
+    let terms: Vec<String> = search_terms(text)
+        .iter()
+        .map(|term| format!("\"{}\"", term.replace('"', "\"\"")))
+        .collect();
+    if terms.is_empty() {
+        None
+    } else {
+        Some(terms.join(" "))
+    }
+}
+
+pub struct SqliteStorage {
+    pool: SqlitePool,
+}
+
+impl SqliteStorage {
+    /// Connect to e.g. `sqlite://rical.db`, creating the file if needed
+    /// `sqlite::memory:` gives a database that's gone when the process exits
+    pub async fn connect(db_url: &str, config: &PoolConfig) -> Result<SqliteStorage, sqlx::Error> {
+        let options = SqliteConnectOptions::from_str(db_url)?
+            .create_if_missing(true)
+            .foreign_keys(true);
+        let pool = if db_url.contains(":memory:") {
+            // Each connection to an in-memory database gets a database of its own,
+            // so keep exactly one connection around for good
+            SqlitePoolOptions::new()
+                .max_connections(1)
+                .acquire_timeout(config.acquire_timeout)
+                .idle_timeout(None)
+                .max_lifetime(None)
+                .connect_with(options)
+                .await?
+        } else {
+            SqlitePoolOptions::new()
+                .max_connections(config.max_connections)
+                .acquire_timeout(config.acquire_timeout)
+                .connect_with(options)
+                .await?
+        };
+        Ok(SqliteStorage { pool })
+    }
+
+    /// Bring the schema up to date by running any migrations that haven't been run yet
+    pub async fn migrate(&self) -> Result<(), MigrateError> {
+        MIGRATOR.run(&self.pool).await
+    }
+}
+
+impl_sql_storage!(SqliteStorage, sqlx::Sqlite, MIGRATOR, SEARCH);
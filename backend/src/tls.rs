@@ -0,0 +1,126 @@
+use axum::{
+    Router,
+    http::{HeaderMap, StatusCode, Uri, header},
+    response::{IntoResponse, Redirect, Response},
+};
+use axum_server::tls_rustls::RustlsConfig;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+use crate::config::TlsConfig;
+
+// Serving HTTPS directly, for instances without a reverse proxy in front
+
+/// How often the certificate files are checked for changes
+const RELOAD_CHECK_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Load the certificate (chain) and private key, both PEM files
+pub async fn load(cert_path: &Path, key_path: &Path) -> std::io::Result<RustlsConfig> {
+    // Only fails if another provider was installed first, which is just as good
+    let _ = rustls::crypto::ring::default_provider().install_default();
+    RustlsConfig::from_pem_file(cert_path, key_path).await
+}
+
+/// Keep checking the certificate files, and switch to them when they change
+/// (e.g. when certbot renews them), without dropping any connection
+pub fn reload_on_change(rustls: RustlsConfig, tls: &TlsConfig) {
+    let Some((cert_path, key_path)) = tls.paths() else {
+        return;
+    };
+    let (cert_path, key_path) = (cert_path.to_path_buf(), key_path.to_path_buf());
+    tokio::spawn(async move {
+        let modified = || {
+            [&cert_path, &key_path].map(|path| {
+                std::fs::metadata(path)
+                    .and_then(|meta| meta.modified())
+                    .ok()
+            })
+        };
+        let mut last_modified: [Option<SystemTime>; 2] = modified();
+        loop {
+            tokio::time::sleep(RELOAD_CHECK_INTERVAL).await;
+            let now_modified = modified();
+            if now_modified == last_modified {
+                continue;
+            }
+            last_modified = now_modified;
+            match rustls.reload_from_pem_file(&cert_path, &key_path).await {
+                Ok(()) => tracing::info!("Reloaded the TLS certificate"),
+                // Keep serving the old one until the files are fixed
+                Err(err) => tracing::error!(error = %err, "Couldn't reload the TLS certificate"),
+            }
+        }
+    });
+}
+
+/// An app that sends every plain HTTP request to the same URL over HTTPS
+pub fn redirect_app(https_port: u16) -> Router {
+    Router::new().fallback(move |headers: HeaderMap, uri: Uri| async move {
+        redirect(&headers, &uri, https_port)
+    })
+}
+
+fn redirect(headers: &HeaderMap, uri: &Uri, https_port: u16) -> Response {
+    let Some(host) = headers
+        .get(header::HOST)
+        .and_then(|host| host.to_str().ok())
+    else {
+        return StatusCode::BAD_REQUEST.into_response();
+    };
+    let path = uri.path_and_query().map_or("/", |path| path.as_str());
+    // Permanent, and keeping the method so that e.g. a POST isn't turned into a GET
+    Redirect::permanent(&https_url(host, https_port, path)).into_response()
+}
+
+fn https_url(host: &str, https_port: u16, path: &str) -> String {
+    // Drop the HTTP port, keeping IPv6 addresses like `[::1]` whole
+    let hostname = match host.rsplit_once(':') {
+        Some((hostname, port)) if !port.contains(']') => hostname,
+        _ => host,
+    };
+    if https_port == 443 {
+        format!("https://{}{}", hostname, path)
+    } else {
+        format!("https://{}:{}{}", hostname, https_port, path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::Request;
+    use tower::ServiceExt;
+
+    #[test]
+    fn test_https_url() {
+        assert_eq!(
+            https_url("rical.example.com", 443, "/calendar/2025/8?x=1"),
+            "https://rical.example.com/calendar/2025/8?x=1"
+        );
+        assert_eq!(
+            https_url("rical.example.com:80", 8443, "/"),
+            "https://rical.example.com:8443/"
+        );
+        assert_eq!(https_url("[::1]:8080", 443, "/"), "https://[::1]/");
+        assert_eq!(https_url("[::1]", 3001, "/"), "https://[::1]:3001/");
+    }
+
+    #[tokio::test]
+    async fn test_redirects_to_https() {
+        let res = redirect_app(443)
+            .oneshot(
+                Request::post("/account/login")
+                    .header(header::HOST, "rical.example.com")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::PERMANENT_REDIRECT);
+        assert_eq!(
+            res.headers()[header::LOCATION],
+            "https://rical.example.com/account/login"
+        );
+    }
+}
@@ -1,21 +1,30 @@
 use argon2::{
     Argon2,
-    password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString, rand_core::OsRng},
+    password_hash::{
+        PasswordHash, PasswordHasher, PasswordVerifier, SaltString,
+        rand_core::{OsRng, RngCore},
+    },
 };
+use axum::http::StatusCode;
 
 use hmac::{Hmac, Mac};
 use jwt::{SignWithKey, VerifyWithKey};
-use sha2::Sha256;
+use sha2::{Digest, Sha256};
 use std::collections::BTreeMap;
+use std::time::{SystemTime, UNIX_EPOCH};
 
-use crate::config;
+use rical_types::TokenScope;
+
+use crate::AppState;
+use crate::logging;
+use crate::storage::StorageResult;
 
 /// Hash a password
 pub fn hash_password(password: &str) -> String {
     // See docs: https://docs.rs/argon2/latest/argon2/
     let salt = SaltString::generate(&mut OsRng);
     let argon2 = Argon2::default();
-    
+
     argon2
         .hash_password(password.as_bytes(), &salt)
         .expect("Could not hash password")
@@ -30,29 +39,158 @@ pub fn verify_password(incoming: &str, stored_hash: &str) -> bool {
         .is_ok()
 }
 
-fn create_hmac_key() -> Hmac<Sha256> {
-    let jwt_secret = &config::get_config()["JWT_SECRET"];
+fn create_hmac_key(jwt_secret: &str) -> Hmac<Sha256> {
     Hmac::new_from_slice(jwt_secret.as_bytes()).expect("Could not generate key")
 }
 
-/// Create and sign a JWT for auth with the user ID
-pub fn create_jwt(user_id: i64) -> String {
+/// Create and sign a JWT for auth with the user ID, which expires after `lifetime_secs` if given
+pub fn create_jwt(jwt_secret: &str, user_id: i64, lifetime_secs: Option<u64>) -> String {
     // See docs: https://docs.rs/jwt/latest/jwt/
-    // TODO: expiration?
-    let key = create_hmac_key();
+    let key = create_hmac_key(jwt_secret);
+    let now = now_unix();
     let mut claims = BTreeMap::new();
     claims.insert("sub", user_id);
-    
+    claims.insert("iat", now);
+    if let Some(lifetime_secs) = lifetime_secs {
+        claims.insert("exp", now + lifetime_secs as i64);
+    }
+
     claims.sign_with_key(&key).expect("Could not sign")
 }
 
-/// Verify a JWT and return the sub claim with the ID
-pub fn verify_jwt(incoming_token: &str) -> Option<i64> {
-    let key = create_hmac_key();
+/// The claims of a verified JWT
+pub struct JwtClaims {
+    /// The account ID
+    pub sub: i64,
+    /// When the token was issued (tokens from before this claim existed don't have it)
+    pub iat: Option<i64>,
+}
+
+/// Verify a JWT that hasn't expired and return its claims
+pub fn verify_jwt(jwt_secret: &str, incoming_token: &str) -> Option<JwtClaims> {
+    let key = create_hmac_key(jwt_secret);
 
     let verif_res: Result<BTreeMap<String, i64>, jwt::Error> = incoming_token.verify_with_key(&key);
     match verif_res {
-        Ok(claims) => claims.get("sub").copied(),
+        Ok(claims) if claims.get("exp").is_some_and(|exp| *exp <= now_unix()) => None,
+        Ok(claims) => Some(JwtClaims {
+            sub: *claims.get("sub")?,
+            iat: claims.get("iat").copied(),
+        }),
         Err(_) => None,
     }
 }
+
+/// The prefix that distinguishes personal API tokens from login JWTs
+pub const API_TOKEN_PREFIX: &str = "rical_pat_";
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// The current time in unix seconds
+pub fn now_unix() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("System time is before the unix epoch")
+        .as_secs() as i64
+}
+
+/// Generate a new random personal API token
+/// This is the only time the plaintext exists, so it must be shown to the user right away
+pub fn generate_api_token() -> String {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    format!("{}{}", API_TOKEN_PREFIX, to_hex(&bytes))
+}
+
+/// Hash a personal API token for storage and lookup
+/// Unlike passwords, tokens are long and random, so a fast unsalted hash is enough
+pub fn hash_api_token(token: &str) -> String {
+    to_hex(&Sha256::digest(token.as_bytes()))
+}
+
+/// Verify a login JWT and return the ID of the account it belongs to
+/// The account must still exist, not be locked, and not have had its sessions revoked since
+pub async fn authenticate_login(state: &AppState, incoming_token: &str) -> Option<i64> {
+    let claims = verify_jwt(&state.config.auth.jwt_secret, incoming_token)?;
+    let account = state
+        .storage
+        .get_account(claims.sub)
+        .await
+        .inspect_err(|err| err.log("look up the token's account"))
+        .ok()??;
+    if account.locked {
+        return None;
+    }
+    if let Some(revoked_at) = account.sessions_revoked_at {
+        // `iat` only has second precision, so a token issued in the second of the revocation may
+        // predate it; tokens from before `iat` existed can't prove when they were issued at all
+        if claims.iat.is_none_or(|iat| iat <= revoked_at) {
+            return None;
+        }
+    }
+    logging::record_account_id(account.account_id);
+    Some(account.account_id)
+}
+
+/// Verify a bearer token and return the ID of the account it belongs to, or the status to answer
+/// with: 401 if the token isn't (or is no longer) valid, or 403 if it's a working API token whose
+/// scope doesn't allow the request, so that clients know to keep it
+/// Login JWTs may do anything; personal API tokens are limited by their scope and expiry
+pub async fn authenticate(
+    state: &AppState,
+    incoming_token: &str,
+    required_scope: TokenScope,
+) -> Result<i64, StatusCode> {
+    if !incoming_token.starts_with(API_TOKEN_PREFIX) {
+        return authenticate_login(state, incoming_token)
+            .await
+            .ok_or(StatusCode::UNAUTHORIZED);
+    }
+
+    let token = state
+        .storage
+        .find_api_token(&hash_api_token(incoming_token))
+        .await
+        .inspect_err(|err| err.log("look up the API token"))
+        .ok()
+        .flatten()
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+
+    let now = now_unix();
+    if token.expires_at.is_some_and(|expires_at| expires_at <= now) {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+    let account = state
+        .storage
+        .get_account(token.account_id)
+        .await
+        .inspect_err(|err| err.log("look up the token's account"))
+        .ok()
+        .flatten()
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+    if account.locked {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+    if !token.scope.allows(required_scope) {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    // Failing to record usage shouldn't block the request
+    if let Err(err) = state.storage.touch_api_token(token.token_id, now).await {
+        err.log("record the API token's use");
+    }
+
+    logging::record_account_id(token.account_id);
+    Ok(token.account_id)
+}
+
+/// How many minutes ahead of UTC the account's days start (0 until it's set)
+pub async fn utc_offset_min(state: &AppState, account_id: i64) -> StorageResult<i32> {
+    Ok(state
+        .storage
+        .get_settings(account_id)
+        .await?
+        .map_or(0, |settings| settings.utc_offset_min))
+}
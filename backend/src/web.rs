@@ -0,0 +1,203 @@
+use axum::{
+    Router,
+    http::{HeaderValue, Method, header},
+};
+use std::path::PathBuf;
+use tower_http::cors::{AllowOrigin, CorsLayer};
+use tower_http::services::{ServeDir, ServeFile};
+
+use crate::config::{CorsConfig, WebConfig};
+
+// Everything needed for browsers to use the API: CORS, and optionally hosting the web client itself
+
+/// Allow the configured origins to call the API from browsers
+/// The config has been validated by now, so any bad value is a bug
+pub fn cors_layer(cors: &CorsConfig) -> CorsLayer {
+    let allow_origin = if cors.allowed_origins.iter().any(|origin| origin == "*") {
+        AllowOrigin::any()
+    } else {
+        AllowOrigin::list(cors.allowed_origins.iter().map(|origin| {
+            HeaderValue::from_str(origin).expect("Origins are validated with the config")
+        }))
+    };
+    let allowed_methods: Vec<Method> = cors
+        .allowed_methods
+        .iter()
+        .map(|method| {
+            Method::from_bytes(method.to_ascii_uppercase().as_bytes())
+                .expect("Methods are validated with the config")
+        })
+        .collect();
+    CorsLayer::new()
+        .allow_origin(allow_origin)
+        .allow_methods(allowed_methods)
+        .allow_headers([header::AUTHORIZATION, header::CONTENT_TYPE])
+        .allow_credentials(cors.allow_credentials)
+}
+
+/// Where the web client's files come from, if the backend hosts it
+#[derive(Clone, Debug)]
+pub enum StaticFiles {
+    /// A directory on disk, e.g. `rical_web/dist`
+    Dir(PathBuf),
+    /// The files baked in at compile time
+    #[cfg(feature = "embed-web")]
+    Embedded,
+}
+
+impl StaticFiles {
+    /// The configured directory if there is one, otherwise the embedded files if they were built in
+    pub fn from_config(web: &WebConfig) -> Option<StaticFiles> {
+        if let Some(dir) = &web.static_dir {
+            return Some(StaticFiles::Dir(dir.clone()));
+        }
+        #[cfg(feature = "embed-web")]
+        return Some(StaticFiles::Embedded);
+        #[cfg(not(feature = "embed-web"))]
+        None
+    }
+}
+
+/// Add CORS (if any origins are allowed) and the web client to the API routes
+/// The web client is served at `/`, and any other path that isn't part of the API
+/// gets its `index.html` so that its own routing can take over (SPA fallback)
+pub fn with_web(router: Router, cors: &CorsConfig, static_files: Option<&StaticFiles>) -> Router {
+    let router = match static_files {
+        Some(StaticFiles::Dir(dir)) => router
+            .fallback_service(ServeDir::new(dir).fallback(ServeFile::new(dir.join("index.html")))),
+        #[cfg(feature = "embed-web")]
+        Some(StaticFiles::Embedded) => router.fallback(embedded::serve),
+        None => router,
+    };
+    if cors.allowed_origins.is_empty() {
+        router
+    } else {
+        router.layer(cors_layer(cors))
+    }
+}
+
+#[cfg(feature = "embed-web")]
+mod embedded {
+    use axum::{
+        http::{StatusCode, Uri, header},
+        response::{IntoResponse, Response},
+    };
+    use rust_embed::RustEmbed;
+
+    #[derive(RustEmbed)]
+    #[folder = "../rical_web/dist/"]
+    struct Assets;
+
+    pub async fn serve(uri: Uri) -> Response {
+        let path = uri.path().trim_start_matches('/');
+        let file = match Assets::get(path) {
+            Some(file) if !path.is_empty() => file,
+            // The SPA fallback
+            _ => match Assets::get("index.html") {
+                Some(file) => file,
+                None => return StatusCode::NOT_FOUND.into_response(),
+            },
+        };
+        (
+            [(header::CONTENT_TYPE, file.metadata.mimetype().to_string())],
+            file.data,
+        )
+            .into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, http::Request, http::StatusCode, routing::get};
+    use http_body_util::BodyExt;
+    use tower::ServiceExt;
+
+    fn api() -> Router {
+        Router::new().route("/calendar/{year}/{month}", get(|| async { "api" }))
+    }
+
+    async fn get_body(router: &Router, uri: &str) -> (StatusCode, String) {
+        let res = router
+            .clone()
+            .oneshot(Request::get(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let status = res.status();
+        let bytes = res.into_body().collect().await.unwrap().to_bytes();
+        (status, String::from_utf8(bytes.to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_cors_only_allows_listed_origins() {
+        let cors = CorsConfig {
+            allowed_origins: vec!["https://rical.example.com".to_string()],
+            allow_credentials: true,
+            allowed_methods: vec!["GET".to_string()],
+        };
+        let router = with_web(api(), &cors, None);
+        let preflight = |origin: &str| {
+            Request::builder()
+                .method(Method::OPTIONS)
+                .uri("/calendar/2025/8")
+                .header(header::ORIGIN, origin)
+                .header(header::ACCESS_CONTROL_REQUEST_METHOD, "GET")
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        let res = router
+            .clone()
+            .oneshot(preflight("https://rical.example.com"))
+            .await
+            .unwrap();
+        let headers = res.headers();
+        assert_eq!(
+            headers[header::ACCESS_CONTROL_ALLOW_ORIGIN],
+            "https://rical.example.com"
+        );
+        assert_eq!(headers[header::ACCESS_CONTROL_ALLOW_CREDENTIALS], "true");
+
+        let res = router
+            .oneshot(preflight("https://evil.example.com"))
+            .await
+            .unwrap();
+        assert!(
+            !res.headers()
+                .contains_key(header::ACCESS_CONTROL_ALLOW_ORIGIN)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_static_dir_with_spa_fallback() {
+        let dir = std::env::temp_dir().join(format!("rical_static_test_{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("assets")).unwrap();
+        std::fs::write(dir.join("index.html"), "index").unwrap();
+        std::fs::write(dir.join("assets/app.js"), "app").unwrap();
+        let router = with_web(
+            api(),
+            &CorsConfig::default(),
+            Some(&StaticFiles::Dir(dir.clone())),
+        );
+
+        assert_eq!(
+            get_body(&router, "/").await,
+            (StatusCode::OK, "index".to_string())
+        );
+        assert_eq!(
+            get_body(&router, "/assets/app.js").await,
+            (StatusCode::OK, "app".to_string())
+        );
+        assert_eq!(
+            get_body(&router, "/some/client/route").await,
+            (StatusCode::OK, "index".to_string())
+        );
+        // The API still takes precedence
+        assert_eq!(
+            get_body(&router, "/calendar/2025/8").await,
+            (StatusCode::OK, "api".to_string())
+        );
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,280 @@
+use std::sync::Arc;
+
+use rical_types::{
+    AccountSettings, ApiTokenInfo, BatchResults, CalendarQuery, CalendarTasks, Checklist,
+    ChecklistItemData, CreatedApiToken, Dependencies, NewApiToken, Project, ProjectData,
+    ReportQuery, RescheduleTasks, RescheduledTasks, RunningTimer, SearchQuery, SearchResults,
+    StatusHistory, Tag, TagData, TaskBatch, TaskData, TaskDataWithId, TaskPage, TaskQuery,
+    TimeReport, TimeSession,
+};
+use tokio::runtime::{Builder, Runtime};
+
+use crate::{ClientConfig, ClientError, TokenStore};
+
+/// A synchronous client for the Rical API
+/// Wraps the async `Client`, running each call to completion on its own small runtime
+pub struct Client {
+    inner: crate::Client,
+    runtime: Runtime,
+}
+
+impl Client {
+    /// Create a client that keeps its token in memory
+    pub fn new(config: ClientConfig) -> Result<Client, ClientError> {
+        Client::from_async(crate::Client::new(config)?)
+    }
+
+    pub fn with_token_store(
+        config: ClientConfig,
+        token_store: Arc<dyn TokenStore>,
+    ) -> Result<Client, ClientError> {
+        Client::from_async(crate::Client::with_token_store(config, token_store)?)
+    }
+
+    fn from_async(inner: crate::Client) -> Result<Client, ClientError> {
+        let runtime = Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .map_err(|err| ClientError::Request(err.to_string()))?;
+        Ok(Client { inner, runtime })
+    }
+
+    pub fn config(&self) -> &ClientConfig {
+        self.inner.config()
+    }
+
+    /// Log in and store the auth token
+    pub fn login(&self, username: String, password: String) -> Result<(), ClientError> {
+        self.runtime.block_on(self.inner.login(username, password))
+    }
+
+    /// Forget the stored auth token
+    pub fn logout(&self) -> Result<(), ClientError> {
+        self.inner.logout()
+    }
+
+    /// Whether a token is stored (it may still have expired)
+    pub fn is_logged_in(&self) -> bool {
+        self.inner.is_logged_in()
+    }
+
+    /// Sign up a new account
+    pub fn signup(&self, username: String, password: String) -> Result<(), ClientError> {
+        self.runtime.block_on(self.inner.signup(username, password))
+    }
+
+    /// Fetch all of a month's tasks, grouped by day
+    pub fn fetch_calendar(&self, year: i32, month: i32) -> Result<CalendarTasks, ClientError> {
+        self.runtime
+            .block_on(self.inner.fetch_calendar(year, month))
+    }
+
+    /// Fetch a month's tasks, ordered and filtered by a query
+    pub fn fetch_calendar_with(
+        &self,
+        year: i32,
+        month: i32,
+        query: &CalendarQuery,
+    ) -> Result<CalendarTasks, ClientError> {
+        self.runtime
+            .block_on(self.inner.fetch_calendar_with(year, month, query))
+    }
+
+    /// Fetch a page of the tasks in a range of dates; pass its `next_cursor` back in the
+    /// query's `cursor` to fetch the next page
+    pub fn query_tasks(&self, query: &TaskQuery) -> Result<TaskPage, ClientError> {
+        self.runtime.block_on(self.inner.query_tasks(query))
+    }
+
+    /// Search all of the tasks' titles and descriptions, best match first
+    pub fn search_tasks(&self, query: &SearchQuery) -> Result<SearchResults, ClientError> {
+        self.runtime.block_on(self.inner.search_tasks(query))
+    }
+
+    pub fn get_task(&self, task_id: i64) -> Result<TaskData, ClientError> {
+        self.runtime.block_on(self.inner.get_task(task_id))
+    }
+
+    /// Create a task and return its new ID
+    pub fn create_task(&self, task: &TaskData) -> Result<i64, ClientError> {
+        self.runtime.block_on(self.inner.create_task(task))
+    }
+
+    /// Replace a task and return the task as it was before
+    pub fn update_task(&self, task_id: i64, task: &TaskData) -> Result<TaskData, ClientError> {
+        self.runtime.block_on(self.inner.update_task(task_id, task))
+    }
+
+    pub fn delete_task(&self, task_id: i64) -> Result<(), ClientError> {
+        self.runtime.block_on(self.inner.delete_task(task_id))
+    }
+
+    /// Every change of a task's status, oldest first
+    pub fn status_history(&self, task_id: i64) -> Result<StatusHistory, ClientError> {
+        self.runtime.block_on(self.inner.status_history(task_id))
+    }
+
+    pub fn get_dependencies(&self, task_id: i64) -> Result<Dependencies, ClientError> {
+        self.runtime.block_on(self.inner.get_dependencies(task_id))
+    }
+
+    /// Make a task depend on another, which has to be done first
+    pub fn add_dependency(&self, task_id: i64, depends_on: i64) -> Result<(), ClientError> {
+        self.runtime
+            .block_on(self.inner.add_dependency(task_id, depends_on))
+    }
+
+    pub fn remove_dependency(&self, task_id: i64, depends_on: i64) -> Result<(), ClientError> {
+        self.runtime
+            .block_on(self.inner.remove_dependency(task_id, depends_on))
+    }
+
+    pub fn get_checklist(&self, task_id: i64) -> Result<Checklist, ClientError> {
+        self.runtime.block_on(self.inner.get_checklist(task_id))
+    }
+
+    /// Add an item to a task's checklist and return its new ID
+    pub fn create_checklist_item(
+        &self,
+        task_id: i64,
+        item: &ChecklistItemData,
+    ) -> Result<i64, ClientError> {
+        self.runtime
+            .block_on(self.inner.create_checklist_item(task_id, item))
+    }
+
+    /// Replace an item of a task's checklist
+    pub fn update_checklist_item(
+        &self,
+        task_id: i64,
+        item_id: i64,
+        item: &ChecklistItemData,
+    ) -> Result<(), ClientError> {
+        self.runtime
+            .block_on(self.inner.update_checklist_item(task_id, item_id, item))
+    }
+
+    pub fn delete_checklist_item(&self, task_id: i64, item_id: i64) -> Result<(), ClientError> {
+        self.runtime
+            .block_on(self.inner.delete_checklist_item(task_id, item_id))
+    }
+
+    /// Run many task operations in one transaction and return the result of each;
+    /// check `committed` to know whether an atomic batch was kept
+    pub fn run_batch(&self, batch: &TaskBatch) -> Result<BatchResults, ClientError> {
+        self.runtime.block_on(self.inner.run_batch(batch))
+    }
+
+    /// Move every incomplete task in a range of dates to one date
+    pub fn reschedule_tasks(
+        &self,
+        reschedule: &RescheduleTasks,
+    ) -> Result<RescheduledTasks, ClientError> {
+        self.runtime
+            .block_on(self.inner.reschedule_tasks(reschedule))
+    }
+
+    /// Fetch the account's tags, by name
+    pub fn list_tags(&self) -> Result<Vec<Tag>, ClientError> {
+        self.runtime.block_on(self.inner.list_tags())
+    }
+
+    /// Create a tag and return its new ID
+    pub fn create_tag(&self, tag: &TagData) -> Result<i64, ClientError> {
+        self.runtime.block_on(self.inner.create_tag(tag))
+    }
+
+    /// Rename or recolor a tag
+    pub fn update_tag(&self, tag_id: i64, tag: &TagData) -> Result<(), ClientError> {
+        self.runtime.block_on(self.inner.update_tag(tag_id, tag))
+    }
+
+    /// Delete a tag, taking it off every task that has it
+    pub fn delete_tag(&self, tag_id: i64) -> Result<(), ClientError> {
+        self.runtime.block_on(self.inner.delete_tag(tag_id))
+    }
+
+    /// Fetch the account's projects with their progress, the soonest due first
+    pub fn list_projects(&self) -> Result<Vec<Project>, ClientError> {
+        self.runtime.block_on(self.inner.list_projects())
+    }
+
+    pub fn get_project(&self, project_id: i64) -> Result<Project, ClientError> {
+        self.runtime.block_on(self.inner.get_project(project_id))
+    }
+
+    /// Create a project and return its new ID
+    pub fn create_project(&self, project: &ProjectData) -> Result<i64, ClientError> {
+        self.runtime.block_on(self.inner.create_project(project))
+    }
+
+    pub fn update_project(
+        &self,
+        project_id: i64,
+        project: &ProjectData,
+    ) -> Result<(), ClientError> {
+        self.runtime
+            .block_on(self.inner.update_project(project_id, project))
+    }
+
+    /// Delete a project, keeping its tasks
+    pub fn delete_project(&self, project_id: i64) -> Result<(), ClientError> {
+        self.runtime.block_on(self.inner.delete_project(project_id))
+    }
+
+    /// Fetch a project's tasks, by date
+    pub fn project_tasks(&self, project_id: i64) -> Result<Vec<TaskDataWithId>, ClientError> {
+        self.runtime.block_on(self.inner.project_tasks(project_id))
+    }
+
+    /// Fetch the session the account's timer is running, if any
+    pub fn running_timer(&self) -> Result<Option<RunningTimer>, ClientError> {
+        self.runtime.block_on(self.inner.running_timer())
+    }
+
+    /// Start the timer on a task, stopping the session that was running
+    pub fn start_timer(&self, task_id: i64) -> Result<TimeSession, ClientError> {
+        self.runtime.block_on(self.inner.start_timer(task_id))
+    }
+
+    /// Stop the timer and return the session that was running
+    pub fn stop_timer(&self) -> Result<TimeSession, ClientError> {
+        self.runtime.block_on(self.inner.stop_timer())
+    }
+
+    /// Fetch a task's time tracking sessions, oldest first
+    pub fn task_sessions(&self, task_id: i64) -> Result<Vec<TimeSession>, ClientError> {
+        self.runtime.block_on(self.inner.task_sessions(task_id))
+    }
+
+    /// Add up the time tracked between two dates by day, week, tag or project
+    pub fn time_report(&self, query: &ReportQuery) -> Result<TimeReport, ClientError> {
+        self.runtime.block_on(self.inner.time_report(query))
+    }
+
+    pub fn get_settings(&self) -> Result<AccountSettings, ClientError> {
+        self.runtime.block_on(self.inner.get_settings())
+    }
+
+    /// Replace the account's settings
+    pub fn set_settings(&self, settings: &AccountSettings) -> Result<(), ClientError> {
+        self.runtime.block_on(self.inner.set_settings(settings))
+    }
+
+    /// Create a personal API token (requires being logged in with a password, not another API token)
+    pub fn create_api_token(
+        &self,
+        new_token: &NewApiToken,
+    ) -> Result<CreatedApiToken, ClientError> {
+        self.runtime
+            .block_on(self.inner.create_api_token(new_token))
+    }
+
+    pub fn list_api_tokens(&self) -> Result<Vec<ApiTokenInfo>, ClientError> {
+        self.runtime.block_on(self.inner.list_api_tokens())
+    }
+
+    pub fn revoke_api_token(&self, token_id: i64) -> Result<(), ClientError> {
+        self.runtime.block_on(self.inner.revoke_api_token(token_id))
+    }
+}
@@ -0,0 +1,177 @@
+use std::collections::HashMap;
+
+use rical_types::{
+    BatchResults, CalendarQuery, CalendarTasks, ChecklistItem, ChecklistItemData, RescheduleTasks,
+    RicalDate, TaskBatch, TaskData, TaskDataWithId, TaskStatus,
+};
+
+use crate::ClientError;
+use crate::blocking::Client;
+
+pub enum CacheType {
+    /// If the matching parameters are found in the cache, use that instead of calling the API
+    PreferCache,
+    /// Call the API with these parameters and update the cache with the new results
+    RefreshOne,
+}
+
+/// A blocking client that remembers every month it has fetched
+/// Writes through this client refresh the months they affect, so the cache stays in sync
+/// with this client's own changes (but not with changes made elsewhere)
+pub struct CachedClient {
+    client: Client,
+    cached_calendar_tasks: HashMap<(i32, i32), CalendarTasks>,
+    /// Only tasks with any of these tags are fetched, unless it's empty
+    tag_filter: Vec<String>,
+}
+
+impl CachedClient {
+    pub fn new(client: Client) -> CachedClient {
+        CachedClient {
+            client,
+            cached_calendar_tasks: HashMap::new(),
+            tag_filter: Vec::new(),
+        }
+    }
+
+    /// The underlying client, for calls that don't involve the cache
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+
+    /// Forget every cached month (e.g. when logging in as someone else)
+    pub fn clear(&mut self) {
+        self.cached_calendar_tasks.clear();
+    }
+
+    pub fn tag_filter(&self) -> &[String] {
+        &self.tag_filter
+    }
+
+    /// Only fetch the tasks with any of these tags (or every task if it's empty)
+    /// The cached months were fetched with the old filter, so they're forgotten
+    pub fn set_tag_filter(&mut self, tags: Vec<String>) {
+        if tags != self.tag_filter {
+            self.tag_filter = tags;
+            self.clear();
+        }
+    }
+
+    /// Fetch a calendar from the API. If this year/month calendar was already fetched, just return that one
+    /// Only calling this method with `CacheType::PreferCache` could lead to data being out of sync
+    pub fn fetch_calendar_tasks(
+        &mut self,
+        year: i32,
+        month: i32,
+        cache_type: CacheType,
+    ) -> Result<CalendarTasks, ClientError> {
+        let identifier = (year, month);
+        if let CacheType::PreferCache = cache_type
+            && let Some(cached) = self.cached_calendar_tasks.get(&identifier)
+        {
+            return Ok(cached.clone());
+        }
+
+        let query = CalendarQuery {
+            tags: (!self.tag_filter.is_empty()).then(|| self.tag_filter.join(",")),
+            ..CalendarQuery::default()
+        };
+        let calendar_tasks = self.client.fetch_calendar_with(year, month, &query)?;
+        self.cached_calendar_tasks
+            .insert(identifier, calendar_tasks.clone());
+
+        Ok(calendar_tasks)
+    }
+
+    pub fn fetch_tasks_at_date(
+        &mut self,
+        date: &RicalDate,
+        cache_type: CacheType,
+    ) -> Result<Vec<TaskDataWithId>, ClientError> {
+        let calendar_tasks = self.fetch_calendar_tasks(date.year, date.month as i32, cache_type)?;
+        Ok(calendar_tasks
+            .days
+            .get(date.day as usize - 1)
+            .cloned()
+            .unwrap_or_default())
+    }
+
+    /// Post a task and refresh the calendar data from the API accordingly
+    pub fn post_new_task(&mut self, task: &TaskData) -> Result<(), ClientError> {
+        self.client.create_task(task)?;
+        self.fetch_calendar_tasks(task.year, task.month, CacheType::RefreshOne)?;
+
+        Ok(())
+    }
+
+    /// Update an existing task and refresh the calendar accordingly; return whether the date changed
+    pub fn update_task(&mut self, task: &TaskDataWithId) -> Result<bool, ClientError> {
+        let original = self.client.update_task(task.task_id, &task.without_id())?;
+
+        // Must update the previously designated month AND the newly designated month if both have changed
+        self.fetch_calendar_tasks(original.year, original.month, CacheType::RefreshOne)?;
+        let calendar_frame_changed = original.year != task.year || original.month != task.month;
+        if calendar_frame_changed {
+            self.fetch_calendar_tasks(task.year, task.month, CacheType::RefreshOne)?;
+        }
+        let date_changed = calendar_frame_changed || original.day != task.day;
+
+        Ok(date_changed)
+    }
+
+    /// Change a task's status and refresh the calendar accordingly
+    pub fn set_status(
+        &mut self,
+        task: &TaskDataWithId,
+        status: TaskStatus,
+    ) -> Result<(), ClientError> {
+        self.update_task(&task.clone().with_status(status))?;
+
+        Ok(())
+    }
+
+    /// Delete a task and refresh the calendar accordingly
+    pub fn delete_task(&mut self, task: &TaskDataWithId) -> Result<(), ClientError> {
+        self.client.delete_task(task.task_id)?;
+        self.fetch_calendar_tasks(task.year, task.month, CacheType::RefreshOne)?;
+
+        Ok(())
+    }
+
+    /// Mark an item of a task's checklist as done or not done, and refresh the calendar
+    /// accordingly (the task's progress, and whether it's done if it has `auto_complete`)
+    pub fn toggle_checklist_item(
+        &mut self,
+        task: &TaskDataWithId,
+        item: &ChecklistItem,
+    ) -> Result<(), ClientError> {
+        let updated = ChecklistItemData {
+            text: item.text.clone(),
+            done: !item.done,
+            position: Some(item.position),
+        };
+        self.client
+            .update_checklist_item(task.task_id, item.item_id, &updated)?;
+        self.fetch_calendar_tasks(task.year, task.month, CacheType::RefreshOne)?;
+
+        Ok(())
+    }
+
+    /// Run a batch of task changes
+    /// Its tasks can be in any number of months, so every cached month is forgotten
+    pub fn run_batch(&mut self, batch: &TaskBatch) -> Result<BatchResults, ClientError> {
+        let results = self.client.run_batch(batch)?;
+        self.clear();
+
+        Ok(results)
+    }
+
+    /// Move the incomplete tasks of a range of dates to one date and return how many moved
+    /// The range can span any number of months, so every cached month is forgotten
+    pub fn reschedule_tasks(&mut self, reschedule: &RescheduleTasks) -> Result<u64, ClientError> {
+        let moved = self.client.reschedule_tasks(reschedule)?.moved;
+        self.clear();
+
+        Ok(moved)
+    }
+}
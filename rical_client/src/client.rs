@@ -0,0 +1,677 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use reqwest::{Method, RequestBuilder, Response};
+use rical_types::{
+    AccountSettings, ApiTokenInfo, AuthToken, BatchResults, CalendarQuery, CalendarTasks,
+    Checklist, ChecklistItemData, ChecklistItemId, CreatedApiToken, Dependencies, DependencyData,
+    NewApiToken, Project, ProjectData, ProjectId, ProjectList, ProjectTasks, ReportQuery,
+    RescheduleTasks, RescheduledTasks, RunningTimer, SearchQuery, SearchResults, SessionList,
+    SessionQuery, StartTimer, StatusHistory, Tag, TagData, TagId, TagList, TaskBatch, TaskData,
+    TaskDataWithId, TaskId, TaskPage, TaskQuery, TimeReport, TimeSession, Timer, UserCredentials,
+};
+
+use crate::{ClientError, MemoryTokenStore, TlsTrust, TokenStore};
+
+/// How failed requests are retried
+/// The delay starts at `initial_backoff` and doubles after each attempt, up to `max_backoff`
+#[derive(Clone, Debug)]
+pub struct RetryPolicy {
+    /// The number of retries after the first attempt (0 never retries)
+    pub max_retries: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl RetryPolicy {
+    pub fn new() -> RetryPolicy {
+        RetryPolicy {
+            max_retries: 2,
+            initial_backoff: Duration::from_millis(200),
+            max_backoff: Duration::from_secs(2),
+        }
+    }
+
+    /// Never retry
+    pub fn none() -> RetryPolicy {
+        RetryPolicy {
+            max_retries: 0,
+            ..RetryPolicy::new()
+        }
+    }
+
+    /// The delay before the retry following attempt number `attempt` (0-indexed)
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        self.initial_backoff
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(self.max_backoff)
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy::new()
+    }
+}
+
+/// Everything needed to connect to a Rical server
+/// Can be constructed like the TUI's styles, e.g.
+/// ```
+/// # use rical_client::ClientConfig;
+/// # use std::time::Duration;
+/// ClientConfig {
+///     timeout: Duration::from_secs(5),
+///     ..ClientConfig::new("http://localhost:3001")
+/// };
+/// ```
+#[derive(Clone, Debug)]
+pub struct ClientConfig {
+    /// The base URL of the server, without a trailing slash
+    pub api_url: String,
+    /// How long to wait for a whole request (per attempt)
+    pub timeout: Duration,
+    /// How long to wait for the connection to be established (per attempt)
+    pub connect_timeout: Duration,
+    pub retry_policy: RetryPolicy,
+    /// Which servers to trust over HTTPS, e.g. to allow a self-signed certificate
+    pub tls: TlsTrust,
+}
+
+impl ClientConfig {
+    pub fn new(api_url: &str) -> ClientConfig {
+        ClientConfig {
+            api_url: api_url.trim_end_matches('/').to_string(),
+            timeout: Duration::from_secs(10),
+            connect_timeout: Duration::from_secs(5),
+            retry_policy: RetryPolicy::new(),
+            tls: TlsTrust::System,
+        }
+    }
+}
+
+/// An async client for the Rical API
+#[derive(Clone)]
+pub struct Client {
+    http: reqwest::Client,
+    config: ClientConfig,
+    token_store: Arc<dyn TokenStore>,
+}
+
+impl Client {
+    /// Create a client that keeps its token in memory
+    pub fn new(config: ClientConfig) -> Result<Client, ClientError> {
+        Client::with_token_store(config, Arc::new(MemoryTokenStore::new()))
+    }
+
+    pub fn with_token_store(
+        config: ClientConfig,
+        token_store: Arc<dyn TokenStore>,
+    ) -> Result<Client, ClientError> {
+        let builder = reqwest::Client::builder()
+            .timeout(config.timeout)
+            .connect_timeout(config.connect_timeout);
+        let http = config.tls.apply(builder)?.build()?;
+        Ok(Client {
+            http,
+            config,
+            token_store,
+        })
+    }
+
+    pub fn config(&self) -> &ClientConfig {
+        &self.config
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}{}", self.config.api_url, path)
+    }
+
+    fn expect_auth_token(&self) -> Result<String, ClientError> {
+        self.token_store.load()?.ok_or(ClientError::NotLoggedIn)
+    }
+
+    fn authed(&self, method: Method, path: &str) -> Result<RequestBuilder, ClientError> {
+        Ok(self
+            .http
+            .request(method, self.url(path))
+            .bearer_auth(self.expect_auth_token()?))
+    }
+
+    /// Send a request, retrying according to the retry policy, and turn error statuses into errors
+    async fn send(&self, builder: RequestBuilder) -> Result<Response, ClientError> {
+        let request = builder.build()?;
+        let idempotent = request.method().is_idempotent();
+        let mut attempt = 0;
+        loop {
+            // Bodies are always in memory (JSON), so requests can always be cloned
+            let this_try = request
+                .try_clone()
+                .expect("Request bodies should be clonable");
+            let res = match self.http.execute(this_try).await {
+                Ok(res) => res.error_for_status().map_err(ClientError::from),
+                Err(err) => Err(ClientError::from(err)),
+            };
+            match res {
+                Err(err)
+                    if attempt < self.config.retry_policy.max_retries
+                        && err.is_retryable(idempotent) =>
+                {
+                    tokio::time::sleep(self.config.retry_policy.backoff(attempt)).await;
+                    attempt += 1;
+                }
+                other => return other,
+            }
+        }
+    }
+
+    /// Log in and store the auth token
+    pub async fn login(&self, username: String, password: String) -> Result<(), ClientError> {
+        let res = self
+            .send(
+                self.http
+                    .post(self.url("/account/login"))
+                    .json(&UserCredentials { username, password }),
+            )
+            .await?;
+        let token = res.json::<AuthToken>().await?.token;
+        self.token_store.save(Some(&token))
+    }
+
+    /// Forget the stored auth token
+    pub fn logout(&self) -> Result<(), ClientError> {
+        self.token_store.save(None)
+    }
+
+    /// Whether a token is stored (it may still have expired)
+    pub fn is_logged_in(&self) -> bool {
+        matches!(self.token_store.load(), Ok(Some(_)))
+    }
+
+    /// Sign up a new account
+    pub async fn signup(&self, username: String, password: String) -> Result<(), ClientError> {
+        self.send(
+            self.http
+                .post(self.url("/account/signup"))
+                .json(&UserCredentials { username, password }),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Fetch all of a month's tasks, grouped by day
+    pub async fn fetch_calendar(
+        &self,
+        year: i32,
+        month: i32,
+    ) -> Result<CalendarTasks, ClientError> {
+        self.fetch_calendar_with(year, month, &CalendarQuery::default())
+            .await
+    }
+
+    /// Fetch a month's tasks, ordered and filtered by a query
+    pub async fn fetch_calendar_with(
+        &self,
+        year: i32,
+        month: i32,
+        query: &CalendarQuery,
+    ) -> Result<CalendarTasks, ClientError> {
+        let res = self
+            .send(
+                self.authed(Method::GET, &format!("/calendar/{}/{}", year, month))?
+                    .query(query),
+            )
+            .await?;
+        Ok(res.json::<CalendarTasks>().await?)
+    }
+
+    /// Fetch a page of the tasks in a range of dates; pass its `next_cursor` back in the
+    /// query's `cursor` to fetch the next page
+    pub async fn query_tasks(&self, query: &TaskQuery) -> Result<TaskPage, ClientError> {
+        let res = self
+            .send(self.authed(Method::GET, "/tasks")?.query(query))
+            .await?;
+        Ok(res.json::<TaskPage>().await?)
+    }
+
+    /// Search all of the tasks' titles and descriptions, best match first
+    pub async fn search_tasks(&self, query: &SearchQuery) -> Result<SearchResults, ClientError> {
+        let res = self
+            .send(self.authed(Method::GET, "/search")?.query(query))
+            .await?;
+        Ok(res.json::<SearchResults>().await?)
+    }
+
+    pub async fn get_task(&self, task_id: i64) -> Result<TaskData, ClientError> {
+        let res = self
+            .send(self.authed(Method::GET, &format!("/task/{}", task_id))?)
+            .await?;
+        Ok(res.json::<TaskData>().await?)
+    }
+
+    /// Create a task and return its new ID
+    pub async fn create_task(&self, task: &TaskData) -> Result<i64, ClientError> {
+        let res = self
+            .send(self.authed(Method::POST, "/task")?.json(task))
+            .await?;
+        Ok(res.json::<TaskId>().await?.task_id)
+    }
+
+    /// Replace a task and return the task as it was before
+    pub async fn update_task(
+        &self,
+        task_id: i64,
+        task: &TaskData,
+    ) -> Result<TaskData, ClientError> {
+        let res = self
+            .send(
+                self.authed(Method::PUT, &format!("/task/{}", task_id))?
+                    .json(task),
+            )
+            .await?;
+        Ok(res.json::<TaskData>().await?)
+    }
+
+    pub async fn delete_task(&self, task_id: i64) -> Result<(), ClientError> {
+        self.send(self.authed(Method::DELETE, &format!("/task/{}", task_id))?)
+            .await?;
+        Ok(())
+    }
+
+    /// Every change of a task's status, oldest first
+    pub async fn status_history(&self, task_id: i64) -> Result<StatusHistory, ClientError> {
+        let res = self
+            .send(self.authed(Method::GET, &format!("/task/{}/history", task_id))?)
+            .await?;
+        Ok(res.json::<StatusHistory>().await?)
+    }
+
+    pub async fn get_dependencies(&self, task_id: i64) -> Result<Dependencies, ClientError> {
+        let res = self
+            .send(self.authed(Method::GET, &format!("/task/{}/dependencies", task_id))?)
+            .await?;
+        Ok(res.json::<Dependencies>().await?)
+    }
+
+    /// Make a task depend on another, which has to be done first
+    pub async fn add_dependency(&self, task_id: i64, depends_on: i64) -> Result<(), ClientError> {
+        self.send(
+            self.authed(Method::POST, &format!("/task/{}/dependencies", task_id))?
+                .json(&DependencyData { depends_on }),
+        )
+        .await?;
+        Ok(())
+    }
+
+    pub async fn remove_dependency(
+        &self,
+        task_id: i64,
+        depends_on: i64,
+    ) -> Result<(), ClientError> {
+        self.send(self.authed(
+            Method::DELETE,
+            &format!("/task/{}/dependencies/{}", task_id, depends_on),
+        )?)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn get_checklist(&self, task_id: i64) -> Result<Checklist, ClientError> {
+        let res = self
+            .send(self.authed(Method::GET, &format!("/task/{}/checklist", task_id))?)
+            .await?;
+        Ok(res.json::<Checklist>().await?)
+    }
+
+    /// Add an item to a task's checklist and return its new ID
+    pub async fn create_checklist_item(
+        &self,
+        task_id: i64,
+        item: &ChecklistItemData,
+    ) -> Result<i64, ClientError> {
+        let res = self
+            .send(
+                self.authed(Method::POST, &format!("/task/{}/checklist", task_id))?
+                    .json(item),
+            )
+            .await?;
+        Ok(res.json::<ChecklistItemId>().await?.item_id)
+    }
+
+    /// Replace an item of a task's checklist
+    pub async fn update_checklist_item(
+        &self,
+        task_id: i64,
+        item_id: i64,
+        item: &ChecklistItemData,
+    ) -> Result<(), ClientError> {
+        self.send(
+            self.authed(
+                Method::PUT,
+                &format!("/task/{}/checklist/{}", task_id, item_id),
+            )?
+            .json(item),
+        )
+        .await?;
+        Ok(())
+    }
+
+    pub async fn delete_checklist_item(
+        &self,
+        task_id: i64,
+        item_id: i64,
+    ) -> Result<(), ClientError> {
+        self.send(self.authed(
+            Method::DELETE,
+            &format!("/task/{}/checklist/{}", task_id, item_id),
+        )?)
+        .await?;
+        Ok(())
+    }
+
+    /// Run many task operations in one transaction and return the result of each;
+    /// check `committed` to know whether an atomic batch was kept
+    pub async fn run_batch(&self, batch: &TaskBatch) -> Result<BatchResults, ClientError> {
+        let res = self
+            .send(self.authed(Method::POST, "/tasks/batch")?.json(batch))
+            .await?;
+        Ok(res.json::<BatchResults>().await?)
+    }
+
+    /// Move every incomplete task in a range of dates to one date
+    pub async fn reschedule_tasks(
+        &self,
+        reschedule: &RescheduleTasks,
+    ) -> Result<RescheduledTasks, ClientError> {
+        let res = self
+            .send(
+                self.authed(Method::POST, "/tasks/reschedule")?
+                    .json(reschedule),
+            )
+            .await?;
+        Ok(res.json::<RescheduledTasks>().await?)
+    }
+
+    /// Fetch the account's tags, by name
+    pub async fn list_tags(&self) -> Result<Vec<Tag>, ClientError> {
+        let res = self.send(self.authed(Method::GET, "/tags")?).await?;
+        Ok(res.json::<TagList>().await?.tags)
+    }
+
+    /// Create a tag and return its new ID
+    pub async fn create_tag(&self, tag: &TagData) -> Result<i64, ClientError> {
+        let res = self
+            .send(self.authed(Method::POST, "/tags")?.json(tag))
+            .await?;
+        Ok(res.json::<TagId>().await?.tag_id)
+    }
+
+    /// Rename or recolor a tag
+    pub async fn update_tag(&self, tag_id: i64, tag: &TagData) -> Result<(), ClientError> {
+        self.send(
+            self.authed(Method::PUT, &format!("/tags/{}", tag_id))?
+                .json(tag),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Delete a tag, taking it off every task that has it
+    pub async fn delete_tag(&self, tag_id: i64) -> Result<(), ClientError> {
+        self.send(self.authed(Method::DELETE, &format!("/tags/{}", tag_id))?)
+            .await?;
+        Ok(())
+    }
+
+    /// Fetch the account's projects with their progress, the soonest due first
+    pub async fn list_projects(&self) -> Result<Vec<Project>, ClientError> {
+        let res = self.send(self.authed(Method::GET, "/projects")?).await?;
+        Ok(res.json::<ProjectList>().await?.projects)
+    }
+
+    pub async fn get_project(&self, project_id: i64) -> Result<Project, ClientError> {
+        let res = self
+            .send(self.authed(Method::GET, &format!("/projects/{}", project_id))?)
+            .await?;
+        Ok(res.json::<Project>().await?)
+    }
+
+    /// Create a project and return its new ID
+    pub async fn create_project(&self, project: &ProjectData) -> Result<i64, ClientError> {
+        let res = self
+            .send(self.authed(Method::POST, "/projects")?.json(project))
+            .await?;
+        Ok(res.json::<ProjectId>().await?.project_id)
+    }
+
+    pub async fn update_project(
+        &self,
+        project_id: i64,
+        project: &ProjectData,
+    ) -> Result<(), ClientError> {
+        self.send(
+            self.authed(Method::PUT, &format!("/projects/{}", project_id))?
+                .json(project),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Delete a project, keeping its tasks
+    pub async fn delete_project(&self, project_id: i64) -> Result<(), ClientError> {
+        self.send(self.authed(Method::DELETE, &format!("/projects/{}", project_id))?)
+            .await?;
+        Ok(())
+    }
+
+    /// Fetch a project's tasks, by date
+    pub async fn project_tasks(&self, project_id: i64) -> Result<Vec<TaskDataWithId>, ClientError> {
+        let res = self
+            .send(self.authed(Method::GET, &format!("/projects/{}/tasks", project_id))?)
+            .await?;
+        Ok(res.json::<ProjectTasks>().await?.tasks)
+    }
+
+    /// Fetch the session the account's timer is running, if any
+    pub async fn running_timer(&self) -> Result<Option<RunningTimer>, ClientError> {
+        let res = self
+            .send(self.authed(Method::GET, "/tracking/timer")?)
+            .await?;
+        Ok(res.json::<Timer>().await?.running)
+    }
+
+    /// Start the timer on a task, stopping the session that was running
+    pub async fn start_timer(&self, task_id: i64) -> Result<TimeSession, ClientError> {
+        let res = self
+            .send(
+                self.authed(Method::POST, "/tracking/timer/start")?
+                    .json(&StartTimer { task_id }),
+            )
+            .await?;
+        Ok(res.json::<TimeSession>().await?)
+    }
+
+    /// Stop the timer and return the session that was running
+    pub async fn stop_timer(&self) -> Result<TimeSession, ClientError> {
+        let res = self
+            .send(self.authed(Method::POST, "/tracking/timer/stop")?)
+            .await?;
+        Ok(res.json::<TimeSession>().await?)
+    }
+
+    /// Fetch a task's time tracking sessions, oldest first
+    pub async fn task_sessions(&self, task_id: i64) -> Result<Vec<TimeSession>, ClientError> {
+        let res = self
+            .send(
+                self.authed(Method::GET, "/tracking/sessions")?
+                    .query(&SessionQuery { task_id }),
+            )
+            .await?;
+        Ok(res.json::<SessionList>().await?.sessions)
+    }
+
+    /// Add up the time tracked between two dates by day, week, tag or project
+    pub async fn time_report(&self, query: &ReportQuery) -> Result<TimeReport, ClientError> {
+        let res = self
+            .send(self.authed(Method::GET, "/tracking/report")?.query(query))
+            .await?;
+        Ok(res.json::<TimeReport>().await?)
+    }
+
+    pub async fn get_settings(&self) -> Result<AccountSettings, ClientError> {
+        let res = self
+            .send(self.authed(Method::GET, "/account/settings")?)
+            .await?;
+        Ok(res.json::<AccountSettings>().await?)
+    }
+
+    /// Replace the account's settings
+    pub async fn set_settings(&self, settings: &AccountSettings) -> Result<(), ClientError> {
+        self.send(
+            self.authed(Method::PUT, "/account/settings")?
+                .json(settings),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Create a personal API token (requires being logged in with a password, not another API token)
+    pub async fn create_api_token(
+        &self,
+        new_token: &NewApiToken,
+    ) -> Result<CreatedApiToken, ClientError> {
+        let res = self
+            .send(
+                self.authed(Method::POST, "/account/tokens")?
+                    .json(new_token),
+            )
+            .await?;
+        Ok(res.json::<CreatedApiToken>().await?)
+    }
+
+    pub async fn list_api_tokens(&self) -> Result<Vec<ApiTokenInfo>, ClientError> {
+        let res = self
+            .send(self.authed(Method::GET, "/account/tokens")?)
+            .await?;
+        Ok(res.json::<Vec<ApiTokenInfo>>().await?)
+    }
+
+    pub async fn revoke_api_token(&self, token_id: i64) -> Result<(), ClientError> {
+        self.send(self.authed(Method::DELETE, &format!("/account/tokens/{}", token_id))?)
+            .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{Json, Router, http::StatusCode, routing::get};
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    /// Serve a router on a random local port and return its URL
+    async fn serve(app: Router) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        format!("http://{}", addr)
+    }
+
+    fn fast_config(api_url: &str) -> ClientConfig {
+        ClientConfig {
+            retry_policy: RetryPolicy {
+                max_retries: 2,
+                initial_backoff: Duration::from_millis(1),
+                max_backoff: Duration::from_millis(1),
+            },
+            ..ClientConfig::new(api_url)
+        }
+    }
+
+    #[test]
+    fn test_backoff() {
+        let policy = RetryPolicy {
+            max_retries: 5,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_millis(500),
+        };
+        assert_eq!(policy.backoff(0), Duration::from_millis(100));
+        assert_eq!(policy.backoff(1), Duration::from_millis(200));
+        assert_eq!(policy.backoff(2), Duration::from_millis(400));
+        assert_eq!(policy.backoff(3), Duration::from_millis(500));
+        assert_eq!(policy.backoff(40), Duration::from_millis(500));
+    }
+
+    #[tokio::test]
+    async fn test_requires_login() {
+        let client = Client::new(fast_config("http://127.0.0.1:1")).unwrap();
+        assert!(matches!(
+            client.fetch_calendar(2025, 1).await,
+            Err(ClientError::NotLoggedIn)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_retries_server_errors_on_idempotent_requests() {
+        let calls = Arc::new(AtomicU32::new(0));
+        let handler_calls = calls.clone();
+        let app = Router::new().route(
+            "/calendar/{year}/{month}",
+            get(move || async move {
+                if handler_calls.fetch_add(1, Ordering::SeqCst) == 0 {
+                    Err(StatusCode::SERVICE_UNAVAILABLE)
+                } else {
+                    Ok(Json(CalendarTasks { days: vec![] }))
+                }
+            }),
+        );
+        let client = Client::with_token_store(
+            fast_config(&serve(app).await),
+            Arc::new(MemoryTokenStore::with_token("token".to_string())),
+        )
+        .unwrap();
+
+        assert!(client.fetch_calendar(2025, 1).await.is_ok());
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_does_not_retry_non_idempotent_requests() {
+        let calls = Arc::new(AtomicU32::new(0));
+        let handler_calls = calls.clone();
+        let app = Router::new().route(
+            "/task",
+            axum::routing::post(move || async move {
+                handler_calls.fetch_add(1, Ordering::SeqCst);
+                StatusCode::SERVICE_UNAVAILABLE
+            }),
+        );
+        let client = Client::with_token_store(
+            fast_config(&serve(app).await),
+            Arc::new(MemoryTokenStore::with_token("token".to_string())),
+        )
+        .unwrap();
+
+        let task = TaskData {
+            year: 2025,
+            month: 1,
+            day: 1,
+            start_min: None,
+            end_min: None,
+            title: "Task".to_string(),
+            description: None,
+            complete: None,
+            status: None,
+            priority: None,
+            position: 0,
+            auto_complete: false,
+            project_id: None,
+            estimate_mins: None,
+            tags: None,
+        };
+        assert!(matches!(
+            client.create_task(&task).await,
+            Err(ClientError::Status(StatusCode::SERVICE_UNAVAILABLE))
+        ));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+}
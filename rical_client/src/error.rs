@@ -0,0 +1,71 @@
+use std::fmt;
+
+use reqwest::StatusCode;
+
+/// Everything that can go wrong when calling the Rical API
+#[derive(Debug)]
+pub enum ClientError {
+    /// The action requires logging in first
+    NotLoggedIn,
+    /// The server took too long to respond
+    Timeout,
+    /// The server could not be reached
+    Connect,
+    /// The server responded, but with an error status
+    Status(StatusCode),
+    /// The server's response could not be understood
+    Decode(String),
+    /// The request could not be built or sent
+    Request(String),
+    /// The auth token could not be loaded or saved
+    TokenStore(String),
+    /// The certificates to trust could not be loaded
+    Tls(String),
+}
+
+impl ClientError {
+    /// Whether sending the same request again might succeed
+    /// Requests that may have reached the server are only retried if they're idempotent,
+    /// so that e.g. creating a task can never create it twice
+    pub fn is_retryable(&self, idempotent: bool) -> bool {
+        match self {
+            ClientError::Connect => true,
+            ClientError::Timeout => idempotent,
+            ClientError::Status(status) => idempotent && status.is_server_error(),
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for ClientError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClientError::NotLoggedIn => write!(f, "Must be logged in to perform this action"),
+            ClientError::Timeout => write!(f, "Request timed out"),
+            ClientError::Connect => write!(f, "Could not connect to the server"),
+            ClientError::Status(status) => write!(f, "{}", status),
+            ClientError::Decode(_) => write!(f, "Invalid response from the server"),
+            ClientError::Request(_) => write!(f, "Invalid request"),
+            ClientError::TokenStore(msg) => write!(f, "Could not access the saved login: {}", msg),
+            ClientError::Tls(msg) => write!(f, "Invalid TLS settings: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for ClientError {}
+
+impl From<reqwest::Error> for ClientError {
+    fn from(err: reqwest::Error) -> ClientError {
+        if err.is_timeout() {
+            ClientError::Timeout
+        } else if err.is_connect() {
+            ClientError::Connect
+        } else if let Some(status) = err.status() {
+            ClientError::Status(status)
+        } else if err.is_decode() {
+            ClientError::Decode(err.to_string())
+        } else {
+            ClientError::Request(err.to_string())
+        }
+    }
+}
@@ -0,0 +1,18 @@
+//! A client for the Rical HTTP API, shared by the Rical frontends
+//!
+//! `Client` is async; enable the `blocking` feature for a synchronous `blocking::Client`, and the
+//! `cache` feature for `cache::CachedClient`, which keeps fetched months around between calls
+
+#[cfg(feature = "blocking")]
+pub mod blocking;
+#[cfg(feature = "cache")]
+pub mod cache;
+mod client;
+mod error;
+mod tls;
+mod token;
+
+pub use client::{Client, ClientConfig, RetryPolicy};
+pub use error::ClientError;
+pub use tls::TlsTrust;
+pub use token::{FileTokenStore, MemoryTokenStore, TokenStore};
@@ -0,0 +1,232 @@
+use std::path::Path;
+use std::sync::Arc;
+
+use reqwest::{Certificate, ClientBuilder};
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::crypto::WebPkiSupportedAlgorithms;
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+use rustls::{CertificateError, DigitallySignedStruct, SignatureScheme};
+
+use crate::ClientError;
+
+/// Which servers the client trusts over HTTPS
+#[derive(Clone, Debug, Default)]
+pub enum TlsTrust {
+    /// Servers with a certificate from one of the operating system's trusted CAs
+    #[default]
+    System,
+    /// Also servers with a certificate from one of these CAs (PEM), e.g. a self-hosted CA
+    CaBundle(Vec<u8>),
+    /// Only a server with exactly this certificate (PEM), whoever signed it and whichever
+    /// names it's for, e.g. a self-signed one
+    Pinned(Vec<u8>),
+}
+
+impl TlsTrust {
+    /// Trust the CAs in a PEM file
+    pub fn ca_bundle_file(path: impl AsRef<Path>) -> Result<TlsTrust, ClientError> {
+        Ok(TlsTrust::CaBundle(read(path.as_ref())?))
+    }
+
+    /// Trust only the certificate in a PEM file
+    pub fn pinned_file(path: impl AsRef<Path>) -> Result<TlsTrust, ClientError> {
+        Ok(TlsTrust::Pinned(read(path.as_ref())?))
+    }
+
+    pub(crate) fn apply(&self, builder: ClientBuilder) -> Result<ClientBuilder, ClientError> {
+        match self {
+            TlsTrust::System => Ok(builder),
+            TlsTrust::CaBundle(pem) => {
+                let certs = Certificate::from_pem_bundle(pem)
+                    .map_err(|err| ClientError::Tls(err.to_string()))?;
+                if certs.is_empty() {
+                    return Err(ClientError::Tls(
+                        "The CA bundle has no certificates".to_string(),
+                    ));
+                }
+                Ok(certs
+                    .into_iter()
+                    .fold(builder, ClientBuilder::add_root_certificate))
+            }
+            TlsTrust::Pinned(pem) => {
+                let pinned = CertificateDer::from_pem_slice(pem)
+                    .map_err(|err| ClientError::Tls(err.to_string()))?;
+                let provider = Arc::new(rustls::crypto::ring::default_provider());
+                let verifier = PinnedVerifier {
+                    pinned,
+                    algorithms: provider.signature_verification_algorithms,
+                };
+                let config = rustls::ClientConfig::builder_with_provider(provider)
+                    .with_safe_default_protocol_versions()
+                    .map_err(|err| ClientError::Tls(err.to_string()))?
+                    .dangerous()
+                    .with_custom_certificate_verifier(Arc::new(verifier))
+                    .with_no_client_auth();
+                Ok(builder.use_preconfigured_tls(config))
+            }
+        }
+    }
+}
+
+fn read(path: &Path) -> Result<Vec<u8>, ClientError> {
+    std::fs::read(path)
+        .map_err(|err| ClientError::Tls(format!("Could not read {}: {}", path.display(), err)))
+}
+
+/// Accepts the pinned certificate and nothing else
+/// The handshake's signatures are still checked, so the server must hold the certificate's key
+#[derive(Debug)]
+struct PinnedVerifier {
+    pinned: CertificateDer<'static>,
+    algorithms: WebPkiSupportedAlgorithms,
+}
+
+impl ServerCertVerifier for PinnedVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        if end_entity.as_ref() == self.pinned.as_ref() {
+            Ok(ServerCertVerified::assertion())
+        } else {
+            Err(rustls::Error::InvalidCertificate(
+                CertificateError::ApplicationVerificationFailure,
+            ))
+        }
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls12_signature(message, cert, dss, &self.algorithms)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls13_signature(message, cert, dss, &self.algorithms)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.algorithms.supported_schemes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Client, ClientConfig, RetryPolicy};
+    use axum::{Router, http::StatusCode, routing::post};
+    use axum_server::tls_rustls::RustlsConfig;
+    use rcgen::{BasicConstraints, CertificateParams, CertifiedIssuer, IsCa, KeyPair};
+
+    /// A certificate for `localhost`, with its key, both PEM
+    struct TestCert {
+        cert: String,
+        key: String,
+    }
+
+    /// A CA, and a certificate for `localhost` that it signed
+    fn ca_and_cert() -> (String, TestCert) {
+        let mut ca_params = CertificateParams::new(Vec::<String>::new()).unwrap();
+        ca_params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+        let ca = CertifiedIssuer::self_signed(ca_params, KeyPair::generate().unwrap()).unwrap();
+        let key = KeyPair::generate().unwrap();
+        let cert = CertificateParams::new(vec!["localhost".to_string()])
+            .unwrap()
+            .signed_by(&key, &ca)
+            .unwrap();
+        (
+            ca.pem(),
+            TestCert {
+                cert: cert.pem(),
+                key: key.serialize_pem(),
+            },
+        )
+    }
+
+    fn self_signed() -> TestCert {
+        let certified = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+        TestCert {
+            cert: certified.cert.pem(),
+            key: certified.signing_key.serialize_pem(),
+        }
+    }
+
+    /// Serve over HTTPS on a random local port and return the port
+    async fn serve_tls(cert: &TestCert) -> u16 {
+        let _ = rustls::crypto::ring::default_provider().install_default();
+        let config = RustlsConfig::from_pem(cert.cert.clone().into(), cert.key.clone().into())
+            .await
+            .unwrap();
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        listener.set_nonblocking(true).unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let app = Router::new().route("/account/signup", post(|| async { StatusCode::CREATED }));
+        tokio::spawn(async move {
+            axum_server::from_tcp_rustls(listener, config)
+                .unwrap()
+                .serve(app.into_make_service())
+                .await
+                .unwrap()
+        });
+        port
+    }
+
+    /// Whether a request to the server gets through the TLS handshake
+    async fn connects(url: &str, tls: TlsTrust) -> bool {
+        let client = Client::new(ClientConfig {
+            tls,
+            retry_policy: RetryPolicy::none(),
+            ..ClientConfig::new(url)
+        })
+        .unwrap();
+        client
+            .signup("alice".to_string(), "password".to_string())
+            .await
+            .is_ok()
+    }
+
+    #[tokio::test]
+    async fn test_ca_bundle() {
+        let (ca, cert) = ca_and_cert();
+        let port = serve_tls(&cert).await;
+        let url = format!("https://localhost:{}", port);
+
+        assert!(!connects(&url, TlsTrust::System).await);
+        assert!(connects(&url, TlsTrust::CaBundle(ca.into_bytes())).await);
+    }
+
+    #[tokio::test]
+    async fn test_pinned_certificate() {
+        let cert = self_signed();
+        let port = serve_tls(&cert).await;
+        // The pin is all that matters, not the name the certificate is for
+        let url = format!("https://127.0.0.1:{}", port);
+
+        assert!(!connects(&url, TlsTrust::System).await);
+        assert!(connects(&url, TlsTrust::Pinned(cert.cert.clone().into_bytes())).await);
+        let other = self_signed();
+        assert!(!connects(&url, TlsTrust::Pinned(other.cert.into_bytes())).await);
+    }
+
+    #[test]
+    fn test_invalid_pem() {
+        let builder = reqwest::Client::builder();
+        assert!(matches!(
+            TlsTrust::CaBundle(b"not a certificate".to_vec()).apply(builder),
+            Err(ClientError::Tls(_))
+        ));
+    }
+}
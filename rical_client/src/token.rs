@@ -0,0 +1,92 @@
+use std::fs;
+use std::io::{self, Write};
+#[cfg(unix)]
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use crate::ClientError;
+
+/// Where the client keeps the token it authenticates with
+/// A login stores the new token here, and every authenticated request loads it
+pub trait TokenStore: Send + Sync {
+    fn load(&self) -> Result<Option<String>, ClientError>;
+    /// Replace the stored token, or forget it if None
+    fn save(&self, token: Option<&str>) -> Result<(), ClientError>;
+}
+
+/// Keep the token only for as long as the client lives
+#[derive(Default)]
+pub struct MemoryTokenStore {
+    token: Mutex<Option<String>>,
+}
+
+impl MemoryTokenStore {
+    pub fn new() -> MemoryTokenStore {
+        MemoryTokenStore::default()
+    }
+
+    /// Start out with a known token, such as a personal API token
+    pub fn with_token(token: String) -> MemoryTokenStore {
+        MemoryTokenStore {
+            token: Mutex::new(Some(token)),
+        }
+    }
+}
+
+impl TokenStore for MemoryTokenStore {
+    fn load(&self) -> Result<Option<String>, ClientError> {
+        Ok(self
+            .token
+            .lock()
+            .expect("Token store lock poisoned")
+            .clone())
+    }
+
+    fn save(&self, token: Option<&str>) -> Result<(), ClientError> {
+        *self.token.lock().expect("Token store lock poisoned") = token.map(str::to_string);
+        Ok(())
+    }
+}
+
+/// Keep the token in a file, so that it survives restarts (e.g. for a daemon)
+/// On unix, the file is only readable by its owner
+pub struct FileTokenStore {
+    path: PathBuf,
+}
+
+impl FileTokenStore {
+    pub fn new(path: impl Into<PathBuf>) -> FileTokenStore {
+        FileTokenStore { path: path.into() }
+    }
+}
+
+impl TokenStore for FileTokenStore {
+    fn load(&self) -> Result<Option<String>, ClientError> {
+        match fs::read_to_string(&self.path) {
+            Ok(contents) if contents.trim().is_empty() => Ok(None),
+            Ok(contents) => Ok(Some(contents.trim().to_string())),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(ClientError::TokenStore(err.to_string())),
+        }
+    }
+
+    fn save(&self, token: Option<&str>) -> Result<(), ClientError> {
+        let res = match token {
+            Some(token) => {
+                let mut options = fs::OpenOptions::new();
+                options.write(true).create(true).truncate(true);
+                #[cfg(unix)]
+                options.mode(0o600);
+                options
+                    .open(&self.path)
+                    .and_then(|mut file| file.write_all(token.as_bytes()))
+            }
+            None => match fs::remove_file(&self.path) {
+                Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+                other => other,
+            },
+        };
+        res.map_err(|err| ClientError::TokenStore(err.to_string()))
+    }
+}
@@ -1,40 +1,35 @@
-use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use rical_client::cache::CachedClient;
+use rical_client::{ClientConfig, ClientError, TlsTrust, blocking};
 use std::env;
 
-use crate::types;
 use crate::utils;
 
-#[derive(Serialize)]
-struct Credentials {
-    username: String,
-    password: String,
-}
-
-#[derive(Deserialize)]
-struct LoginResult {
-    token: String,
-}
-
-pub enum CacheType {
-    /// If the matching parameters are found in the cache, use that instead of calling the API
-    PreferCache,
-    /// Call the API with these parameters and update the cache with the new results
-    RefreshOne,
-}
+pub use rical_client::cache::CacheType;
 
+/// The TUI's connection to the API
+/// Fetches used while rendering can't report errors, so they fall back to empty results
+/// and remember the error so that it can be displayed instead
 pub struct ApiHandler {
-    blocking_client: reqwest::blocking::Client,
-    auth_token: Option<String>,
-    cached_calendar_tasks: HashMap<(i32, i32), types::CalendarTasks>,
+    client: CachedClient,
+    last_error: Option<ClientError>,
+    /// The account's tags, for their colors; None until fetched or after tasks may have added some
+    tags: Option<Vec<rical_types::Tag>>,
+    /// The session the account's timer is running; None until fetched
+    timer: Option<Option<rical_types::RunningTimer>>,
 }
 
 impl ApiHandler {
     pub fn new() -> ApiHandler {
+        let config = ClientConfig {
+            tls: Self::tls_trust(),
+            ..ClientConfig::new(&Self::api_url())
+        };
+        let client = blocking::Client::new(config).expect("Could not create the API client");
         ApiHandler {
-            blocking_client: reqwest::blocking::Client::new(),
-            auth_token: None,
-            cached_calendar_tasks: HashMap::new(),
+            client: CachedClient::new(client),
+            last_error: None,
+            tags: None,
+            timer: None,
         }
     }
 
@@ -43,50 +38,56 @@ impl ApiHandler {
         env::var("API_URL").expect("API_URL must be set")
     }
 
-    fn expect_auth_token(&self) -> String {
-        self.auth_token
-            .clone()
-            .expect("Must be logged in to perform this action")
+    /// `API_PINNED_CERT` trusts only that certificate (e.g. a self-signed one),
+    /// and `API_CA_BUNDLE` also trusts the CAs in that file
+    fn tls_trust() -> TlsTrust {
+        let path = |key| env::var(key).ok().filter(|path| !path.trim().is_empty());
+        let trust = if let Some(cert) = path("API_PINNED_CERT") {
+            TlsTrust::pinned_file(cert)
+        } else if let Some(bundle) = path("API_CA_BUNDLE") {
+            TlsTrust::ca_bundle_file(bundle)
+        } else {
+            Ok(TlsTrust::System)
+        };
+        trust.unwrap_or_else(|err| panic!("{}", err))
     }
 
-    /// Log in and store the auth token
-    pub fn try_login(&mut self, username: String, password: String) -> Result<(), reqwest::Error> {
-        let res = self
-            .blocking_client
-            .post(format!("{}/account/login", Self::api_url()))
-            .json(&Credentials { username, password })
-            .send()?;
-        let token = res.json::<LoginResult>()?.token;
-
-        self.auth_token = Some(token.clone());
+    /// The error from the most recent fetch, if it failed
+    pub fn last_error(&self) -> Option<&ClientError> {
+        self.last_error.as_ref()
+    }
 
-        Ok(())
+    /// Log in and store the auth token
+    pub fn try_login(&mut self, username: String, password: String) -> Result<(), ClientError> {
+        // Don't show a previous account's tasks or tags
+        self.client.clear();
+        self.client.set_tag_filter(Vec::new());
+        self.tags = None;
+        self.timer = None;
+        self.client.client().login(username, password)
     }
 
     /// Sign up a new account
-    pub fn try_signup(&mut self, username: String, password: String) -> Result<(), reqwest::Error> {
-        let res = self
-            .blocking_client
-            .post(format!("{}/account/signup", Self::api_url()))
-            .json(&Credentials { username, password })
-            .send()?;
-        res.error_for_status()?;
-
-        Ok(())
+    pub fn try_signup(&mut self, username: String, password: String) -> Result<(), ClientError> {
+        self.client.client().signup(username, password)
     }
 
     pub fn fetch_tasks_at_date(
         &mut self,
         date: &utils::RicalDate,
         cache_type: CacheType,
-    ) -> Vec<types::TaskDataWithId> {
-        let calendar_tasks = self.fetch_calendar_tasks(date.year, date.month as i32, cache_type);
-        let empty_res: Vec<types::TaskDataWithId> = vec![];
-        calendar_tasks
-            .days
-            .get(date.day as usize - 1)
-            .unwrap_or(&empty_res)
-            .clone()
+    ) -> Vec<rical_types::TaskDataWithId> {
+        let res = self.client.fetch_tasks_at_date(date, cache_type);
+        self.remember_error(res).unwrap_or_default()
+    }
+
+    /// The position that puts a new task after a date's other untimed tasks
+    pub fn next_position(&mut self, date: &utils::RicalDate) -> i32 {
+        self.fetch_tasks_at_date(date, CacheType::PreferCache)
+            .iter()
+            .map(|task| task.position + 1)
+            .max()
+            .unwrap_or(0)
     }
 
     /// Fetch a calendar from the API. If this year/month calendar was already fetched, just return that one
@@ -96,87 +97,198 @@ impl ApiHandler {
         year: i32,
         month: i32,
         cache_type: CacheType,
-    ) -> types::CalendarTasks {
-        let identifier = (year, month);
-        match cache_type {
-            CacheType::PreferCache => if let Some(cached) = self.cached_calendar_tasks.get(&identifier) {
-                return cached.clone();
-            },
-            CacheType::RefreshOne => (),
+    ) -> rical_types::CalendarTasks {
+        let res = self.client.fetch_calendar_tasks(year, month, cache_type);
+        self.remember_error(res)
+            .unwrap_or(rical_types::CalendarTasks { days: vec![] })
+    }
+
+    /// A task on any date, as its month's calendar has it; None if it can't be fetched,
+    /// or the tag filter hides it
+    pub fn fetch_task(&mut self, task_id: i64) -> Option<rical_types::TaskDataWithId> {
+        let res = self.client.client().get_task(task_id);
+        let task = self.remember_error(res)?;
+        self.fetch_calendar_tasks(task.year, task.month, CacheType::PreferCache)
+            .days
+            .get(task.day as usize - 1)?
+            .iter()
+            .find(|other| other.task_id == task_id)
+            .cloned()
+    }
+
+    fn remember_error<T>(&mut self, res: Result<T, ClientError>) -> Option<T> {
+        match res {
+            Ok(value) => {
+                self.last_error = None;
+                Some(value)
+            }
+            Err(err) => {
+                self.last_error = Some(err);
+                None
+            }
         }
+    }
 
-        let res = self
-            .blocking_client
-            .get(format!("{}/calendar/{}/{}", Self::api_url(), year, month))
-            .bearer_auth(self.expect_auth_token())
-            .send()
-            .unwrap();
+    /// The account's tags, by name; empty if they can't be fetched
+    pub fn fetch_tags(&mut self, cache_type: CacheType) -> Vec<rical_types::Tag> {
+        if let CacheType::PreferCache = cache_type
+            && let Some(tags) = &self.tags
+        {
+            return tags.clone();
+        }
+        // Not remembered as the last error, which is about the tasks on screen
+        match self.client.client().list_tags() {
+            Ok(tags) => {
+                self.tags = Some(tags.clone());
+                tags
+            }
+            Err(_) => Vec::new(),
+        }
+    }
 
-        let calendar_tasks = res.json::<types::CalendarTasks>().unwrap();
-        self.cached_calendar_tasks
-            .insert(identifier, calendar_tasks.clone());
+    /// The names of the tags whose tasks are shown, or empty if every task is
+    pub fn tag_filter(&self) -> &[String] {
+        self.client.tag_filter()
+    }
 
-        calendar_tasks
+    /// Only show the tasks with any of these tags (or every task if it's empty)
+    pub fn set_tag_filter(&mut self, tags: Vec<String>) {
+        self.client.set_tag_filter(tags);
     }
 
-    /// Post a task and refresh the calendar data from the API accordingly
-    pub fn post_new_task(&mut self, task: &types::TaskData) -> Result<(), reqwest::Error> {
-        let res = self
-            .blocking_client
-            .post(format!("{}/task", Self::api_url()))
-            .bearer_auth(self.expect_auth_token())
-            .json(&task)
-            .send()?;
-        res.error_for_status()?;
+    /// The account's projects with their progress, the soonest due first
+    pub fn fetch_projects(&mut self) -> Result<Vec<rical_types::Project>, ClientError> {
+        self.client.client().list_projects()
+    }
 
-        self.fetch_calendar_tasks(task.year, task.month, CacheType::RefreshOne);
+    /// A project's tasks, by date
+    pub fn fetch_project_tasks(
+        &mut self,
+        project_id: i64,
+    ) -> Result<Vec<rical_types::TaskDataWithId>, ClientError> {
+        self.client.client().project_tasks(project_id)
+    }
 
+    /// Create a project without a description or due date
+    pub fn create_project(&mut self, name: &str) -> Result<(), ClientError> {
+        let project = rical_types::ProjectData {
+            name: name.to_string(),
+            description: None,
+            due: None,
+        };
+        self.client.client().create_project(&project)?;
         Ok(())
     }
 
-    /// Update an existing task and refresh the calendar accordingly; return whether the date changed
-    pub fn update_task(&mut self, task: &types::TaskDataWithId) -> Result<bool, reqwest::Error> {
-        let res = self
-            .blocking_client
-            .put(format!("{}/task/{}", Self::api_url(), task.task_id))
-            .bearer_auth(self.expect_auth_token())
-            .json(&task.without_id())
-            .send()?;
-        let res = res.error_for_status()?;
-        let original = res.json::<types::TaskData>().unwrap();
-
-        // Must update the previously designated month AND the newly designated month if both have changed
-        self.fetch_calendar_tasks(original.year, original.month, CacheType::RefreshOne);
-        let calendar_frame_changed = original.year != task.year || original.month != task.month;
-        if calendar_frame_changed {
-            self.fetch_calendar_tasks(task.year, task.month, CacheType::RefreshOne);
+    /// The session the account's timer is running, if any
+    pub fn fetch_timer(&mut self) -> Option<rical_types::RunningTimer> {
+        if let Some(timer) = &self.timer {
+            return timer.clone();
         }
-        let date_changed = calendar_frame_changed || original.day != task.day;
-
-        Ok(date_changed)
+        // Not remembered as the last error, like the tags
+        let timer = self.client.client().running_timer().ok()?;
+        self.timer = Some(timer.clone());
+        timer
     }
 
-    /// Toggle whether a task is completed and refresh the calendar accordingly
-    pub fn toggle_completed(&mut self, task: &types::TaskDataWithId) -> Result<(), reqwest::Error> {
-        let mut updated = task.clone();
-        updated.complete = !updated.complete;
-        self.update_task(&updated)?;
-        self.fetch_calendar_tasks(task.year, task.month, CacheType::RefreshOne);
+    /// Start tracking a task, or stop the timer if it's already tracking it
+    pub fn toggle_timer(&mut self, task_id: i64) -> Result<(), ClientError> {
+        let tracking = self
+            .fetch_timer()
+            .is_some_and(|timer| timer.task_id == task_id);
+        self.timer = None;
+        if tracking {
+            self.client.client().stop_timer()?;
+        } else {
+            self.client.client().start_timer(task_id)?;
+        }
+        Ok(())
+    }
 
+    pub fn stop_timer(&mut self) -> Result<(), ClientError> {
+        self.timer = None;
+        self.client.client().stop_timer()?;
         Ok(())
     }
 
+    /// Search all of the tasks, best match first
+    pub fn search_tasks(&mut self, text: &str) -> Result<Vec<rical_types::SearchHit>, ClientError> {
+        let query = rical_types::SearchQuery::new(text);
+        Ok(self.client.client().search_tasks(&query)?.hits)
+    }
+
+    /// Post a task and refresh the calendar data from the API accordingly
+    pub fn post_new_task(&mut self, task: &rical_types::TaskData) -> Result<(), ClientError> {
+        // The task may have added tags
+        self.tags = None;
+        self.client.post_new_task(task)
+    }
+
+    /// Update an existing task and refresh the calendar accordingly; return whether the date changed
+    pub fn update_task(&mut self, task: &rical_types::TaskDataWithId) -> Result<bool, ClientError> {
+        self.tags = None;
+        self.client.update_task(task)
+    }
+
+    /// Change a task's status and refresh the calendar accordingly
+    pub fn set_status(
+        &mut self,
+        task: &rical_types::TaskDataWithId,
+        status: rical_types::TaskStatus,
+    ) -> Result<(), ClientError> {
+        self.client.set_status(task, status)
+    }
+
     /// Delete a task and refresh the calendar accordingly
-    pub fn delete_task(&mut self, task: &types::TaskDataWithId) -> Result<(), reqwest::Error> {
-        let res = self
-            .blocking_client
-            .delete(format!("{}/task/{}", Self::api_url(), task.task_id))
-            .bearer_auth(self.expect_auth_token())
-            .send()?;
-        res.error_for_status()?;
+    pub fn delete_task(&mut self, task: &rical_types::TaskDataWithId) -> Result<(), ClientError> {
+        self.client.delete_task(task)
+    }
 
-        self.fetch_calendar_tasks(task.year, task.month, CacheType::RefreshOne);
+    /// A task's checklist items, in order
+    pub fn fetch_checklist(
+        &mut self,
+        task_id: i64,
+    ) -> Result<Vec<rical_types::ChecklistItem>, ClientError> {
+        Ok(self.client.client().get_checklist(task_id)?.items)
+    }
 
+    /// Tick or untick an item of a task's checklist and refresh the calendar accordingly
+    pub fn toggle_checklist_item(
+        &mut self,
+        task: &rical_types::TaskDataWithId,
+        item: &rical_types::ChecklistItem,
+    ) -> Result<(), ClientError> {
+        self.client.toggle_checklist_item(task, item)
+    }
+
+    /// Give tasks the positions 0, 1, 2... in the order they're listed, all at once,
+    /// and refresh the calendar accordingly
+    pub fn save_task_order(
+        &mut self,
+        tasks: &[rical_types::TaskDataWithId],
+    ) -> Result<(), ClientError> {
+        let operations = tasks
+            .iter()
+            .enumerate()
+            .map(|(position, task)| rical_types::TaskOperation::Update {
+                task_id: task.task_id,
+                task: rical_types::TaskData {
+                    position: position as i32,
+                    ..task.without_id()
+                },
+            })
+            .collect();
+        self.client.run_batch(&rical_types::TaskBatch {
+            operations,
+            atomic: true,
+        })?;
         Ok(())
     }
+
+    /// Move every incomplete task before `today` to `today` and refresh the calendar accordingly;
+    /// return how many moved
+    pub fn roll_overdue_to(&mut self, today: &utils::RicalDate) -> Result<u64, ClientError> {
+        self.client
+            .reschedule_tasks(&rical_types::RescheduleTasks::overdue_to(today))
+    }
 }
@@ -1,3 +1,4 @@
+use chrono::TimeZone;
 use crossterm::{
     cursor,
     event::{KeyCode, KeyModifiers},
@@ -11,9 +12,9 @@ use crate::api::{ApiHandler, CacheType};
 use crate::state;
 use crate::utils::{self, KeyInfo, fmt_mins, fmt_twodigit, get_calendar_frame, key_pressed};
 
-use crate::types;
-
-use crate::components::{edit_task_form, new_task_form, text};
+use crate::components::{
+    checklist, edit_task_form, new_task_form, projects, search, tag_filter, text,
+};
 
 // The main calendar screen
 
@@ -25,14 +26,43 @@ enum CalAction {
     SelectTaskDown,
     StartNewTask,
     EditSelectedTask,
-    ToggleCompleted,
+    /// Move the selected task along the usual workflow (to do, in progress, done)
+    CycleSelectedStatus,
+    /// Move the selected task through the statuses off that workflow (blocked, deferred, cancelled)
+    CycleSelectedHeldStatus,
     DeleteSelectedTask,
     PasteTask,
+    StartSearch,
+    RollOverdueToToday,
+    /// Swap the selected untimed task with the one above or below it
+    MoveSelectedTask(utils::GridDirection),
+    CycleSelectedPriority,
+    /// Show the selected task's checklist beneath it
+    ExpandSelectedTask,
+    StartTagFilter,
+    OpenProjects,
+    /// Select the first of the tasks the selected task is waiting on
+    JumpToBlockingTask,
+    /// Start tracking time on the selected task, or stop if the timer is already on it
+    ToggleSelectedTimer,
+    StopTimer,
     None,
 }
 
+/// The checkbox that shows a task's status, and the color it's drawn in (none for to do)
+pub fn status_checkbox(status: rical_types::TaskStatus) -> (&'static str, Option<style::Color>) {
+    match status {
+        rical_types::TaskStatus::Todo => ("[ ]", None),
+        rical_types::TaskStatus::InProgress => ("[~]", Some(style::Color::Yellow)),
+        rical_types::TaskStatus::Blocked => ("[!]", Some(style::Color::Red)),
+        rical_types::TaskStatus::Deferred => ("[>]", Some(style::Color::DarkCyan)),
+        rical_types::TaskStatus::Done => ("[x]", Some(style::Color::Green)),
+        rical_types::TaskStatus::Cancelled => ("[-]", Some(style::Color::DarkGrey)),
+    }
+}
+
 pub fn get_task_index_by_id(
-    date_tasks: &[types::TaskDataWithId],
+    date_tasks: &[rical_types::TaskDataWithId],
     task_id: i64,
 ) -> Option<usize> {
     date_tasks.iter().position(|task| task.task_id == task_id)
@@ -42,7 +72,7 @@ pub fn get_selected_task(
     api_handler: &mut ApiHandler,
     selected_date: &utils::RicalDate,
     task_id: Option<i64>,
-) -> Option<types::TaskDataWithId> {
+) -> Option<rical_types::TaskDataWithId> {
     let date_tasks = api_handler.fetch_tasks_at_date(selected_date, CacheType::PreferCache);
 
     match task_id {
@@ -51,9 +81,14 @@ pub fn get_selected_task(
     }
 }
 
-pub fn edit_task_state_from_task(task: &types::TaskDataWithId) -> state::EditTaskState {
+pub fn edit_task_state_from_task(task: &rical_types::TaskDataWithId) -> state::EditTaskState {
     state::EditTaskState {
         task_id: task.task_id,
+        priority: task.priority,
+        position: task.position,
+        auto_complete: task.auto_complete,
+        project_id: task.project_id,
+        estimate_mins: task.estimate_mins,
         form: state::FormState::<8>::from_field_contents(
             5,
             [
@@ -62,13 +97,12 @@ pub fn edit_task_state_from_task(task: &types::TaskDataWithId) -> state::EditTas
                 task.day.to_string(),
                 fmt_mins(task.start_min),
                 fmt_mins(task.end_min),
-                task.title.clone(),
+                // The tags are edited as `#tag`s in the title, like when making a task
+                task.tags.iter().fold(task.title.clone(), |title, tag| {
+                    format!("{} #{}", title, tag)
+                }),
                 task.description.clone().unwrap_or_default(),
-                if task.complete {
-                    "Yes".to_string()
-                } else {
-                    "No".to_string()
-                },
+                task.status.label().to_string(),
             ],
         ),
     }
@@ -85,6 +119,18 @@ pub fn handle_input(
     if currstate.editing_task.is_some() {
         return edit_task_form::handle_input(currstate, key, api_handler);
     }
+    if currstate.searching.is_some() {
+        return search::handle_input(currstate, key, api_handler);
+    }
+    if currstate.expanded.is_some() {
+        return checklist::handle_input(currstate, key, api_handler);
+    }
+    if currstate.filtering_tags.is_some() {
+        return tag_filter::handle_input(currstate, key, api_handler);
+    }
+    if currstate.viewing_projects.is_some() {
+        return projects::handle_input(currstate, key, api_handler);
+    }
 
     if key_pressed(key, KeyModifiers::CONTROL, KeyCode::Char('m')) {
         return state::ScreenState::Menu(state::MenuState::MainMenu);
@@ -105,6 +151,16 @@ pub fn handle_input(
                 CalAction::StartNewTask
             } else if key_pressed(key, KeyModifiers::NONE, KeyCode::Char('p')) {
                 CalAction::PasteTask
+            } else if key_pressed(key, KeyModifiers::NONE, KeyCode::Char('/')) {
+                CalAction::StartSearch
+            } else if key_pressed(key, KeyModifiers::NONE, KeyCode::Char('r')) {
+                CalAction::RollOverdueToToday
+            } else if key_pressed(key, KeyModifiers::NONE, KeyCode::Char('t')) {
+                CalAction::StartTagFilter
+            } else if key_pressed(key, KeyModifiers::NONE, KeyCode::Char('g')) {
+                CalAction::OpenProjects
+            } else if key_pressed(key, KeyModifiers::SHIFT, KeyCode::Char('S')) {
+                CalAction::StopTimer
             } else if key_pressed(key, KeyModifiers::NONE, KeyCode::Enter) {
                 CalAction::SwitchToTasks
             } else {
@@ -129,9 +185,33 @@ pub fn handle_input(
             } else if key_pressed(key, KeyModifiers::NONE, KeyCode::Char('e')) {
                 CalAction::EditSelectedTask
             } else if key_pressed(key, KeyModifiers::NONE, KeyCode::Char('x')) {
-                CalAction::ToggleCompleted
+                CalAction::CycleSelectedStatus
+            } else if key_pressed(key, KeyModifiers::SHIFT, KeyCode::Char('X')) {
+                CalAction::CycleSelectedHeldStatus
             } else if key_pressed(key, KeyModifiers::SHIFT, KeyCode::Char('D')) {
                 CalAction::DeleteSelectedTask
+            } else if key_pressed(key, KeyModifiers::SHIFT, KeyCode::Char('J')) {
+                CalAction::MoveSelectedTask(utils::GridDirection::Down)
+            } else if key_pressed(key, KeyModifiers::SHIFT, KeyCode::Char('K')) {
+                CalAction::MoveSelectedTask(utils::GridDirection::Up)
+            } else if key_pressed(key, KeyModifiers::SHIFT, KeyCode::Char('P')) {
+                CalAction::CycleSelectedPriority
+            } else if key_pressed(key, KeyModifiers::SHIFT, KeyCode::Char('B')) {
+                CalAction::JumpToBlockingTask
+            } else if key_pressed(key, KeyModifiers::NONE, KeyCode::Char('s')) {
+                CalAction::ToggleSelectedTimer
+            } else if key_pressed(key, KeyModifiers::SHIFT, KeyCode::Char('S')) {
+                CalAction::StopTimer
+            } else if key_pressed(key, KeyModifiers::NONE, KeyCode::Char('/')) {
+                CalAction::StartSearch
+            } else if key_pressed(key, KeyModifiers::NONE, KeyCode::Char('r')) {
+                CalAction::RollOverdueToToday
+            } else if key_pressed(key, KeyModifiers::NONE, KeyCode::Char('t')) {
+                CalAction::StartTagFilter
+            } else if key_pressed(key, KeyModifiers::NONE, KeyCode::Char('g')) {
+                CalAction::OpenProjects
+            } else if key_pressed(key, KeyModifiers::NONE, KeyCode::Enter) {
+                CalAction::ExpandSelectedTask
             } else if key_pressed(key, KeyModifiers::NONE, KeyCode::Esc) {
                 CalAction::SwitchToMonth
             } else {
@@ -165,6 +245,25 @@ pub fn handle_input(
             making_new_task: Some(state::FormState::<4>::new()),
             ..currstate.clone()
         },
+        CalAction::StartSearch => state::CalendarState {
+            searching: Some(state::SearchState::new()),
+            ..currstate.clone()
+        },
+        CalAction::StartTagFilter => tag_filter::open(currstate, api_handler),
+        CalAction::OpenProjects => projects::open(currstate, api_handler),
+        CalAction::RollOverdueToToday => {
+            let today = utils::RicalDate::today();
+            match api_handler.roll_overdue_to(&today) {
+                Ok(_) => state::CalendarState {
+                    year: today.year,
+                    month: today.month,
+                    day: today.day,
+                    task_id: None,
+                    ..currstate.clone()
+                },
+                Err(_) => currstate.clone(),
+            }
+        }
         CalAction::EditSelectedTask => {
             match get_selected_task(api_handler, &selected_date, currstate.task_id) {
                 Some(task) => state::CalendarState {
@@ -174,12 +273,79 @@ pub fn handle_input(
                 None => currstate.clone(),
             }
         }
-        CalAction::ToggleCompleted => {
-            if let Some(task) = get_selected_task(api_handler, &selected_date, currstate.task_id)
-                && api_handler.toggle_completed(&task).is_ok() {  };
+        CalAction::CycleSelectedStatus => {
+            if let Some(task) = get_selected_task(api_handler, &selected_date, currstate.task_id) {
+                let _ = api_handler.set_status(&task, task.status.cycle());
+            }
 
             currstate.clone()
         }
+        CalAction::CycleSelectedHeldStatus => {
+            if let Some(task) = get_selected_task(api_handler, &selected_date, currstate.task_id) {
+                let _ = api_handler.set_status(&task, task.status.cycle_held());
+            }
+
+            currstate.clone()
+        }
+        CalAction::MoveSelectedTask(dir) => {
+            // Only untimed tasks have an order of their own; timed ones go by their times
+            let mut untimed: Vec<rical_types::TaskDataWithId> = api_handler
+                .fetch_tasks_at_date(&selected_date, CacheType::PreferCache)
+                .into_iter()
+                .filter(|task| task.start_min.is_none())
+                .collect();
+            let index = currstate
+                .task_id
+                .and_then(|id| get_task_index_by_id(&untimed, id));
+            let other_index = match (index, dir) {
+                (Some(index), utils::GridDirection::Up) => index.checked_sub(1),
+                (Some(index), utils::GridDirection::Down) => {
+                    Some(index + 1).filter(|other| *other < untimed.len())
+                }
+                _ => None,
+            };
+            if let (Some(index), Some(other_index)) = (index, other_index) {
+                untimed.swap(index, other_index);
+                // The batch is atomic, so if it fails, the order is left as it was
+                let _ = api_handler.save_task_order(&untimed);
+            }
+
+            currstate.clone()
+        }
+        CalAction::CycleSelectedPriority => {
+            if let Some(task) = get_selected_task(api_handler, &selected_date, currstate.task_id) {
+                let updated = rical_types::TaskDataWithId {
+                    priority: task.priority.cycle(),
+                    ..task
+                };
+                let _ = api_handler.update_task(&updated);
+            }
+
+            currstate.clone()
+        }
+        CalAction::JumpToBlockingTask => {
+            let blocker = get_selected_task(api_handler, &selected_date, currstate.task_id)
+                .and_then(|task| task.blocked_by.first().copied())
+                .and_then(|task_id| api_handler.fetch_task(task_id));
+            match blocker {
+                Some(blocker) => return search::jump_to_task(currstate, &blocker),
+                None => currstate.clone(),
+            }
+        }
+        CalAction::ToggleSelectedTimer => {
+            if let Some(task_id) = currstate.task_id {
+                let _ = api_handler.toggle_timer(task_id);
+            }
+            currstate.clone()
+        }
+        CalAction::StopTimer => {
+            let _ = api_handler.stop_timer();
+            currstate.clone()
+        }
+        CalAction::ExpandSelectedTask => match currstate.task_id {
+            Some(task_id) => checklist::expand(currstate, task_id, api_handler),
+            None => currstate.clone(),
+        },
         CalAction::DeleteSelectedTask => {
             match get_selected_task(api_handler, &selected_date, currstate.task_id) {
                 Some(task) => {
@@ -198,7 +364,7 @@ pub fn handle_input(
         }
         CalAction::PasteTask => match &currstate.task_clipboard {
             Some(task) => {
-                let new_task = types::TaskData {
+                let new_task = rical_types::TaskData {
                     year: currstate.year,
                     month: currstate.month as i32,
                     day: currstate.day as i32,
@@ -207,6 +373,13 @@ pub fn handle_input(
                     title: task.title.clone(),
                     description: task.description.clone(),
                     complete: task.complete,
+                    status: task.status,
+                    priority: task.priority,
+                    position: api_handler.next_position(&selected_date),
+                    auto_complete: task.auto_complete,
+                    project_id: task.project_id,
+                    estimate_mins: task.estimate_mins,
+                    tags: task.tags.clone(),
                 };
                 match api_handler.post_new_task(&new_task) {
                     Ok(_) => currstate.clone(),
@@ -345,8 +518,9 @@ fn is_mini_mode() -> io::Result<bool> {
 pub fn render_task_candy(
     x: u16,
     y: u16,
-    task: &types::TaskDataWithId,
+    task: &rical_types::TaskDataWithId,
     overdue: bool,
+    tag_color: Option<style::Color>,
 ) -> io::Result<()> {
     let mut stdout = io::stdout();
 
@@ -378,25 +552,45 @@ pub fn render_task_candy(
     queue!(
         stdout,
         cursor::MoveTo(x, y),
-        style::PrintStyledContent(if task.complete {
+        style::PrintStyledContent(if task.status == rical_types::TaskStatus::Done {
             task_char.dark_green()
+        } else if task.status == rical_types::TaskStatus::Cancelled {
+            task_char.dark_grey()
         } else if overdue {
             task_char.dark_yellow()
+        } else if let Some(color) = tag_color {
+            task_char.with(color)
         } else {
-            task_char.dark_blue()
+            match task.priority {
+                rical_types::Priority::Urgent => task_char.red(),
+                rical_types::Priority::High => task_char.magenta(),
+                _ => task_char.dark_blue(),
+            }
         })
     )?;
     Ok(())
 }
 
+/// A task's priority as a row of `!`s, colored by how important it is
+fn priority_marker(priority: rical_types::Priority) -> Option<style::StyledContent<&'static str>> {
+    match priority {
+        rical_types::Priority::None => None,
+        rical_types::Priority::Low => Some("!".dark_grey()),
+        rical_types::Priority::Medium => Some("!!".dark_yellow()),
+        rical_types::Priority::High => Some("!!!".magenta()),
+        rical_types::Priority::Urgent => Some("!!!!".red()),
+    }
+}
+
 // TODO: use styles instead
 pub fn render_date_square(
     date: Option<utils::RicalDate>,
     x: u16,
     y: u16,
     is_selected: bool,
-    tasks: &[types::TaskDataWithId],
+    tasks: &[rical_types::TaskDataWithId],
     pane: &state::CalendarPane,
+    tags: &[rical_types::Tag],
 ) -> io::Result<()> {
     let mut stdout = io::stdout();
 
@@ -443,7 +637,13 @@ pub fn render_date_square(
         let task_y = y + 1 + i / 2;
         match tasks.get(i as usize) {
             Some(task) => {
-                render_task_candy(task_x, task_y, task, is_overdue)?;
+                render_task_candy(
+                    task_x,
+                    task_y,
+                    task,
+                    is_overdue,
+                    tag_filter::task_color(task, tags),
+                )?;
             }
             None => {
                 queue!(stdout, cursor::MoveTo(task_x, task_y), style::Print(" "))?;
@@ -468,6 +668,7 @@ pub fn render_date_square(
 }
 
 /// Render a date and its tasks in the tasks menu, returning its height in number of rows
+#[allow(clippy::too_many_arguments)]
 pub fn render_tasks_date(
     date: utils::RicalDate,
     x: u16,
@@ -476,9 +677,11 @@ pub fn render_tasks_date(
     is_selected: bool,
     selected_task_id: Option<i64>,
     is_today: bool,
-    tasks: &[types::TaskDataWithId],
+    tasks: &[rical_types::TaskDataWithId],
     pane: &state::CalendarPane,
     key_help: &str,
+    expanded: Option<&state::ExpandedTask>,
+    tags: &[rical_types::Tag],
 ) -> io::Result<u16> {
     let mut stdout = io::stdout();
 
@@ -524,12 +727,13 @@ pub fn render_tasks_date(
         queue!(stdout, cursor::MoveTo(x, cursory), style::Print(" "))?;
         // Candy column
         let overdue = date < utils::RicalDate::today();
-        render_task_candy(x + 1, cursory, task, overdue)?;
+        let tag_color = tag_filter::task_color(task, tags);
+        render_task_candy(x + 1, cursory, task, overdue, tag_color)?;
         // Time column
         const COL_TIME_WIDTH: u16 = 13;
         let timerange_text = format!(" {}", utils::fmt_timerange(task.start_min, task.end_min));
         text::padded_text_styled(
-            if task.complete {
+            if task.status.is_closed() || task.is_blocked() {
                 (&timerange_text as &str).dark_grey()
             } else {
                 (&timerange_text as &str).reset()
@@ -538,52 +742,91 @@ pub fn render_tasks_date(
             " ".reset(),
         )?;
         // Checkbox column
+        let is_task_expanded = is_selected && expanded.is_some_and(|e| e.task_id == task.task_id);
+        // While one of an expanded task's items is selected, the task itself isn't
         let is_task_selected = is_selected
             && match selected_task_id {
                 Some(id) => task.task_id == id,
                 None => false,
-            };
+            }
+            && expanded.is_none_or(|e| e.selected.is_none());
+        let (checkbox, status_color) = status_checkbox(task.status);
         queue!(
             stdout,
-            if task.complete {
-                let checkbox = "[x]";
-                style::PrintStyledContent(if is_task_selected {
-                    match pane {
-                        state::CalendarPane::Month => checkbox.green().on_dark_grey(),
-                        state::CalendarPane::Tasks => checkbox.black().on_dark_green(),
-                    }
-                } else {
-                    checkbox.green()
-                })
-            } else {
-                let checkbox = "[ ]";
-                style::PrintStyledContent(if is_task_selected {
-                    match pane {
-                        state::CalendarPane::Month => checkbox.black().on_dark_grey(),
-                        state::CalendarPane::Tasks => checkbox.black().on_white(),
-                    }
-                } else {
-                    checkbox.reset()
-                })
-            },
+            style::PrintStyledContent(match (is_task_selected, status_color) {
+                (false, Some(color)) => checkbox.with(color),
+                (false, None) => checkbox.reset(),
+                (true, _) => match pane {
+                    state::CalendarPane::Month => match status_color {
+                        Some(color) if color != style::Color::DarkGrey => {
+                            checkbox.with(color).on_dark_grey()
+                        }
+                        _ => checkbox.black().on_dark_grey(),
+                    },
+                    state::CalendarPane::Tasks => checkbox
+                        .black()
+                        .on(status_color.unwrap_or(style::Color::White)),
+                },
+            }),
             style::Print(" ")
         )?;
-        // Task text column
+        // Priority column, only as wide as the marker
+        let mut title_width = tasks_pane_width - COL_TIME_WIDTH - 6;
+        if let Some(marker) = priority_marker(task.priority) {
+            let marker_text = *marker.content();
+            queue!(
+                stdout,
+                style::PrintStyledContent(if task.status.is_closed() {
+                    marker_text.dark_grey()
+                } else {
+                    marker
+                }),
+                style::Print(" ")
+            )?;
+            title_width = title_width.saturating_sub(marker_text.len() as u16 + 1);
+        }
+        // A warning when the task is on an earlier date than a task it's waiting on
+        if !task.later_dependencies.is_empty() {
+            queue!(
+                stdout,
+                style::PrintStyledContent("⚠".dark_yellow()),
+                style::Print(" ")
+            )?;
+            title_width = title_width.saturating_sub(2);
+        }
+        // Task text column, with how much of its checklist is done and its tags
         // TODO: multiline
         // TODO: descriptions too?
         // TODO: what if user selects it
+        let mut title = match task.checklist {
+            Some(progress) => format!("{} ({}/{})", task.title, progress.done, progress.total),
+            None => task.title.clone(),
+        };
+        for tag in &task.tags {
+            title = format!("{} #{}", title, tag);
+        }
         text::padded_text_styled(
-            if task.complete {
-                (&task.title as &str).dark_grey()
-            } else {
-                (&task.title as &str).reset()
+            match tag_color {
+                _ if task.status.is_closed() || task.is_blocked() => (&title as &str).dark_grey(),
+                Some(color) => (&title as &str).with(color),
+                None => (&title as &str).reset(),
             },
-            tasks_pane_width - COL_TIME_WIDTH - 6,
+            title_width,
             " ".reset(),
         )?;
         queue!(stdout, style::Print("│"))?;
         queue!(stdout, terminal::Clear(terminal::ClearType::UntilNewLine))?;
         cursory += 1;
+        if is_task_expanded && let Some(expanded) = expanded {
+            // Line the items up with the task's checkbox
+            cursory = checklist::render_items(
+                expanded,
+                x,
+                cursory,
+                COL_TIME_WIDTH + 2,
+                tasks_pane_width,
+            )?;
+        }
     }
     queue!(stdout, cursor::MoveTo(x, cursory))?;
     text::pad_characters(tasks_pane_width, 0, " ")?;
@@ -600,6 +843,15 @@ pub fn render(currstate: &state::CalendarState, api_handler: &mut ApiHandler) ->
     if currstate.editing_task.is_some() {
         return edit_task_form::render(currstate);
     }
+    if currstate.searching.is_some() {
+        return search::render(currstate);
+    }
+    if currstate.filtering_tags.is_some() {
+        return tag_filter::render(currstate);
+    }
+    if currstate.viewing_projects.is_some() {
+        return projects::render(currstate);
+    }
 
     let mut stdout = io::stdout();
 
@@ -612,6 +864,7 @@ pub fn render(currstate: &state::CalendarState, api_handler: &mut ApiHandler) ->
         selected_date.month as i32,
         CacheType::PreferCache,
     );
+    let tags = api_handler.fetch_tags(CacheType::PreferCache);
 
     // Responsive layout
     let viewport_width = get_viewport_width()?;
@@ -622,11 +875,39 @@ pub fn render(currstate: &state::CalendarState, api_handler: &mut ApiHandler) ->
     // Previously included '| (^S) settings'
     let top_right_str = "(^M) menu/log out | (^C) quit";
     queue!(stdout, cursor::MoveTo(0, 0))?;
-    text::padded_text(
-        "[username]'s Calendar ([private])",
-        viewport_width - top_right_str.chars().count() as u16,
-        " ",
-    )?;
+    let top_left_width = viewport_width - top_right_str.chars().count() as u16;
+    match api_handler.last_error() {
+        // Tasks that failed to load are shown as empty, so make sure that's not mistaken for real data
+        Some(err) => {
+            let error_str = format!("(!) Could not load tasks: {}", utils::display_error(err));
+            text::padded_text_styled((&error_str as &str).red(), top_left_width, " ".reset())?;
+        }
+        None => match api_handler.fetch_timer() {
+            // Only redrawn on input, so the start time is shown along with how long it's been
+            Some(timer) => {
+                let started = chrono::Local
+                    .timestamp_opt(timer.started_at, 0)
+                    .single()
+                    .map(|started| started.format("%H:%M").to_string())
+                    .unwrap_or_default();
+                let elapsed = chrono::Local::now().timestamp() - timer.started_at;
+                let timer_str = format!(
+                    "⏱ {} since {} ({}) | (S) stop",
+                    timer.title,
+                    started,
+                    utils::fmt_elapsed(elapsed)
+                );
+                text::padded_text_styled(
+                    (&timer_str as &str).yellow(),
+                    top_left_width,
+                    " ".reset(),
+                )?;
+            }
+            None => {
+                text::padded_text("[username]'s Calendar ([private])", top_left_width, " ")?;
+            }
+        },
+    }
     queue!(stdout, style::Print(top_right_str))?;
     queue!(stdout, terminal::Clear(terminal::ClearType::UntilNewLine))?;
     text::println(1, "")?;
@@ -669,7 +950,15 @@ pub fn render(currstate: &state::CalendarState, api_handler: &mut ApiHandler) ->
             state::CalendarPane::Tasks => "─".blue(),
         })
     )?;
-    let tasks_title_str = " Tasks ";
+    // Say which tags the tasks are filtered by, so that hidden tasks aren't mistaken for missing ones
+    let tasks_title = api_handler
+        .tag_filter()
+        .iter()
+        .fold(" Tasks".to_string(), |title, tag| {
+            format!("{} #{}", title, tag)
+        })
+        + " ";
+    let tasks_title_str: &str = &tasks_title;
     text::padded_text_styled(
         match currstate.pane {
             state::CalendarPane::Month => tasks_title_str.dark_grey(),
@@ -720,6 +1009,7 @@ pub fn render(currstate: &state::CalendarState, api_handler: &mut ApiHandler) ->
                 is_selected,
                 tasks,
                 &currstate.pane,
+                &tags,
             )?;
 
             cursorx += DATE_SQUARE_WIDTH;
@@ -742,7 +1032,9 @@ pub fn render(currstate: &state::CalendarState, api_handler: &mut ApiHandler) ->
     const DAYS_DISPLAYED: u64 = 7;
     cursory = CALENDAR_MARGIN_TOP + 1;
     let cursorx = CALENDAR_WIDTH;
-    let key_help = if currstate.task_clipboard.is_some() {
+    let key_help = if currstate.expanded.is_some() {
+        "(x) tick (esc) close"
+    } else if currstate.task_clipboard.is_some() {
         "(p) paste"
     } else {
         ""
@@ -771,6 +1063,8 @@ pub fn render(currstate: &state::CalendarState, api_handler: &mut ApiHandler) ->
             tasks,
             &currstate.pane,
             key_help,
+            currstate.expanded.as_ref(),
+            &tags,
         )?;
         // Divider between selected date and upcoming dates
         if date_offset == 0 {
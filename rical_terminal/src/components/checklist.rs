@@ -0,0 +1,152 @@
+use crossterm::{
+    cursor,
+    event::{KeyCode, KeyModifiers},
+    queue,
+    style::{self, Stylize},
+    terminal,
+};
+use std::io;
+
+use crate::api::ApiHandler;
+use crate::state;
+use crate::utils::{self, KeyInfo, key_pressed};
+
+use crate::components::{calendar, text};
+
+// A task expanded in the tasks pane to show its checklist, whose items can be ticked off
+
+/// Expand a task, or leave the state as it is if its checklist can't be fetched
+pub fn expand(
+    currstate: &state::CalendarState,
+    task_id: i64,
+    api_handler: &mut ApiHandler,
+) -> state::CalendarState {
+    match api_handler.fetch_checklist(task_id) {
+        Ok(items) => state::CalendarState {
+            expanded: Some(state::ExpandedTask {
+                task_id,
+                items,
+                selected: None,
+            }),
+            ..currstate.clone()
+        },
+        Err(_) => currstate.clone(),
+    }
+}
+
+pub fn handle_input(
+    currstate: &state::CalendarState,
+    key: &KeyInfo,
+    api_handler: &mut ApiHandler,
+) -> state::ScreenState {
+    let expanded = currstate
+        .expanded
+        .as_ref()
+        .expect("checklist should never be used if no task is expanded");
+    let item_count = expanded.items.len();
+
+    let new_expanded = if key_pressed(key, KeyModifiers::NONE, KeyCode::Esc)
+        || key_pressed(key, KeyModifiers::NONE, KeyCode::Enter)
+    {
+        None
+    } else if key_pressed(key, KeyModifiers::NONE, KeyCode::Char('j')) {
+        Some(state::ExpandedTask {
+            selected: match expanded.selected {
+                _ if item_count == 0 => None,
+                Some(index) => Some((index + 1).min(item_count - 1)),
+                None => Some(0),
+            },
+            ..expanded.clone()
+        })
+    } else if key_pressed(key, KeyModifiers::NONE, KeyCode::Char('k')) {
+        Some(state::ExpandedTask {
+            // Going up from the first item selects the task itself again
+            selected: expanded.selected.and_then(|index| index.checked_sub(1)),
+            ..expanded.clone()
+        })
+    } else if key_pressed(key, KeyModifiers::NONE, KeyCode::Char('x')) {
+        let selected_date = utils::RicalDate::new(currstate.year, currstate.month, currstate.day);
+        let task = calendar::get_selected_task(api_handler, &selected_date, Some(expanded.task_id));
+        let selected_item = expanded
+            .selected
+            .and_then(|index| expanded.items.get(index));
+        match (task, selected_item) {
+            (Some(task), Some(item)) => {
+                let items = api_handler
+                    .toggle_checklist_item(&task, item)
+                    .and_then(|_| api_handler.fetch_checklist(task.task_id));
+                Some(match items {
+                    Ok(items) => state::ExpandedTask {
+                        items,
+                        ..expanded.clone()
+                    },
+                    Err(_) => expanded.clone(),
+                })
+            }
+            // With no item selected, move the task itself along its workflow
+            (Some(task), None) => {
+                let _ = api_handler.set_status(&task, task.status.cycle());
+                Some(expanded.clone())
+            }
+            _ => Some(expanded.clone()),
+        }
+    } else {
+        Some(expanded.clone())
+    };
+
+    state::ScreenState::Calendar(state::CalendarState {
+        expanded: new_expanded,
+        ..currstate.clone()
+    })
+}
+
+/// Render the expanded task's items beneath it in the tasks pane, returning the next free row
+pub fn render_items(
+    expanded: &state::ExpandedTask,
+    x: u16,
+    y: u16,
+    indent: u16,
+    tasks_pane_width: u16,
+) -> io::Result<u16> {
+    let mut stdout = io::stdout();
+
+    let mut cursory = y;
+    let text_width = tasks_pane_width.saturating_sub(indent + 4);
+    if expanded.items.is_empty() {
+        queue!(stdout, cursor::MoveTo(x, cursory))?;
+        text::pad_characters(indent, 0, " ")?;
+        text::padded_text_styled("(no checklist)".dark_grey(), text_width + 4, " ".reset())?;
+        queue!(stdout, style::Print("│"))?;
+        queue!(stdout, terminal::Clear(terminal::ClearType::UntilNewLine))?;
+        return Ok(cursory + 1);
+    }
+    for (index, item) in expanded.items.iter().enumerate() {
+        queue!(stdout, cursor::MoveTo(x, cursory))?;
+        text::pad_characters(indent, 0, " ")?;
+        let is_selected = expanded.selected == Some(index);
+        let checkbox = if item.done { "[x]" } else { "[ ]" };
+        queue!(
+            stdout,
+            style::PrintStyledContent(match (is_selected, item.done) {
+                (true, true) => checkbox.black().on_dark_green(),
+                (true, false) => checkbox.black().on_white(),
+                (false, true) => checkbox.green(),
+                (false, false) => checkbox.reset(),
+            }),
+            style::Print(" ")
+        )?;
+        text::padded_text_styled(
+            if item.done {
+                (&item.text as &str).dark_grey()
+            } else {
+                (&item.text as &str).reset()
+            },
+            text_width,
+            " ".reset(),
+        )?;
+        queue!(stdout, style::Print("│"))?;
+        queue!(stdout, terminal::Clear(terminal::ClearType::UntilNewLine))?;
+        cursory += 1;
+    }
+    Ok(cursory)
+}
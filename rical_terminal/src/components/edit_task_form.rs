@@ -3,7 +3,6 @@ use std::io;
 use crate::api::ApiHandler;
 use crate::state;
 use crate::styles;
-use crate::types;
 use crate::utils::{KeyInfo, time_shorthand_to_mins};
 
 use crate::components::form;
@@ -31,7 +30,7 @@ pub fn handle_input(
             "end_shorthand",
             "title",
             "description",
-            "complete",
+            "status",
         ],
         Some([
             |input| match input.parse::<i32>() {
@@ -56,12 +55,11 @@ pub fn handle_input(
             },
             |_| Ok(()),
             |_| Ok(()),
-            |input| {
-                if input == "Yes" || input == "No" {
-                    Ok(())
-                } else {
-                    Err("No".to_string())
-                }
+            // Any way of writing a status becomes its label
+            |input| match rical_types::TaskStatus::from_name(input) {
+                Some(status) if input == status.label() => Ok(()),
+                Some(status) => Err(status.label().to_string()),
+                None => Err(rical_types::TaskStatus::Todo.label().to_string()),
             },
         ]),
     );
@@ -69,7 +67,7 @@ pub fn handle_input(
         form::FormResult::InProgress => state::ScreenState::Calendar(state::CalendarState {
             editing_task: Some(state::EditTaskState {
                 form: res.0,
-                task_id: formstate.task_id,
+                ..formstate.clone()
             }),
             ..currstate.clone()
         }),
@@ -85,17 +83,30 @@ pub fn handle_input(
             let year = result["year"].parse::<i32>().unwrap();
             let month = result["month"].parse::<i32>().unwrap();
             let day = result["day"].parse::<i32>().unwrap();
-            let complete = result["complete"] == "Yes";
-            let new_task = types::TaskDataWithId {
+            let status = rical_types::TaskStatus::from_name(&result["status"]).unwrap_or_default();
+            let (title, tags) = rical_types::split_title_tags(&result["title"]);
+            let new_task = rical_types::TaskDataWithId {
                 year,
                 month,
                 day,
                 start_min,
                 end_min,
-                title: result["title"].clone(),
+                title,
                 description: Some(result["description"].clone()),
-                complete,
+                complete: status == rical_types::TaskStatus::Done,
+                status,
+                priority: formstate.priority,
+                position: formstate.position,
+                auto_complete: formstate.auto_complete,
+                project_id: formstate.project_id,
+                estimate_mins: formstate.estimate_mins,
+                tags,
                 task_id: formstate.task_id,
+                status_changed_at: None,
+                completed_at: None,
+                checklist: None,
+                blocked_by: Vec::new(),
+                later_dependencies: Vec::new(),
             };
             match api_handler.update_task(&new_task) {
                 Ok(date_changed) => state::ScreenState::Calendar(state::CalendarState {
@@ -110,11 +121,11 @@ pub fn handle_input(
                 }),
                 Err(_) => state::ScreenState::Calendar(state::CalendarState {
                     editing_task: Some(state::EditTaskState {
-                        task_id: formstate.task_id,
                         form: state::FormState::from_result_message(vec![
                             "This task could not be edited. Make sure you entered valid times"
                                 .to_string(),
                         ]),
+                        ..formstate.clone()
                     }),
                     ..currstate.clone()
                 }),
@@ -202,10 +213,10 @@ pub fn render(currstate: &state::CalendarState) -> io::Result<()> {
                 ..form::FormFieldParameters::default()
             },
             form::FormFieldParameters {
-                name: "Complete".to_string(),
+                name: "Status".to_string(),
                 styles: styles::Styles {
                     margin_top: 15,
-                    width: Some(7),
+                    width: Some(14),
                     ..styles::Styles::new()
                 },
                 ..form::FormFieldParameters::default()
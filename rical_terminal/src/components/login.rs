@@ -35,7 +35,7 @@ pub fn handle_input(
                 Err(err) => state::ScreenState::Menu(state::MenuState::Login(
                     state::FormState::from_result_message(vec![
                         "Login failed:".to_string(),
-                        format!("  - {}", display_error(err)),
+                        format!("  - {}", display_error(&err)),
                         "Make sure your username and password are correct.".to_string(),
                         "If you don't have an account, sign up first!".to_string(),
                     ]),
@@ -40,9 +40,10 @@ fn render_about() -> io::Result<()> {
     text::println(1, "")?;
     let version = env!("CARGO_PKG_VERSION");
     text::println(2, &format!("Rical Frontend v{}", version))?;
-    text::println(3, "")?;
+    text::println(3, &format!("Rical API v{}", rical_types::API_VERSION))?;
+    text::println(4, "")?;
     text::println(
-        4,
+        5,
         "By Cadecraft and any other Rical contributors (MIT license)",
     )?;
     text::clear_to_end()?;
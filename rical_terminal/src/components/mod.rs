@@ -1,11 +1,15 @@
 pub mod root;
 
 mod calendar;
+mod checklist;
 mod edit_task_form;
 mod form;
 mod inputtext;
 mod login;
 mod menu;
 mod new_task_form;
+mod projects;
+mod search;
 mod signup;
+mod tag_filter;
 mod text;
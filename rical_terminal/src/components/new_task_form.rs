@@ -3,7 +3,6 @@ use std::io;
 use crate::api::ApiHandler;
 use crate::state;
 use crate::styles;
-use crate::types;
 use crate::utils::{self, KeyInfo, display_error, time_shorthand_to_mins};
 
 use crate::components::form;
@@ -50,16 +49,25 @@ pub fn handle_input(
         form::FormResult::Submit(result) => {
             let start_min = time_shorthand_to_mins(&result["start_shorthand"]);
             let end_min = time_shorthand_to_mins(&result["end_shorthand"]);
+            let date = utils::RicalDate::new(currstate.year, currstate.month, currstate.day);
             // TODO: show loading screen
-            let new_task = types::TaskData {
+            let (title, tags) = rical_types::split_title_tags(&result["title"]);
+            let new_task = rical_types::TaskData {
                 year: currstate.year,
                 month: currstate.month as i32,
                 day: currstate.day as i32,
                 start_min,
                 end_min,
-                title: result["title"].clone(),
+                title,
                 description: Some(result["description"].clone()),
-                complete: false,
+                complete: None,
+                status: None,
+                priority: None,
+                position: api_handler.next_position(&date),
+                auto_complete: false,
+                project_id: None,
+                estimate_mins: None,
+                tags: Some(tags),
             };
             match api_handler.post_new_task(&new_task) {
                 Ok(_) => state::ScreenState::Calendar(state::CalendarState {
@@ -69,7 +77,7 @@ pub fn handle_input(
                 Err(err) => state::ScreenState::Calendar(state::CalendarState {
                     making_new_task: Some(state::FormState::from_result_message(vec![
                         "Could not create task:".to_string(),
-                        format!("  - {}", display_error(err)),
+                        format!("  - {}", display_error(&err)),
                         "Check that you entered valid times".to_string(),
                     ])),
                     ..currstate.clone()
@@ -0,0 +1,419 @@
+use crossterm::{
+    cursor,
+    event::{KeyCode, KeyModifiers},
+    queue,
+    style::{self, Stylize},
+    terminal,
+};
+use std::io;
+
+use crate::api::ApiHandler;
+use crate::state;
+use crate::styles;
+use crate::utils::{self, KeyInfo, display_error, key_pressed};
+
+use crate::components::{calendar, inputtext, search, text};
+
+// The overlay that lists the account's projects with their progress, and opens one as a timeline of its tasks
+
+/// How many characters the progress bars are wide
+const BAR_WIDTH: usize = 20;
+/// How many characters of a project's name are shown in the list
+const NAME_WIDTH: usize = 24;
+
+/// A bar like `██████░░░░` that's filled as much as the project is done
+fn progress_bar(progress: &rical_types::ProjectProgress) -> String {
+    let filled = ((progress.fraction() * BAR_WIDTH as f64).round() as usize).min(BAR_WIDTH);
+    "█".repeat(filled) + &"░".repeat(BAR_WIDTH - filled)
+}
+
+/// A `YYYY-MM-DD` due date the way the rest of the calendar shows dates
+fn fmt_due(due: &str) -> String {
+    utils::RicalDate::parse_iso(due).map_or_else(|| due.to_string(), |date| date.format())
+}
+
+/// Open the overlay with the account's projects
+pub fn open(
+    currstate: &state::CalendarState,
+    api_handler: &mut ApiHandler,
+) -> state::CalendarState {
+    let projects = state::ProjectsState {
+        projects: Vec::new(),
+        selected: 0,
+        new_name: None,
+        timeline: None,
+        error: None,
+    };
+    state::CalendarState {
+        viewing_projects: Some(refreshed(&projects, api_handler)),
+        ..currstate.clone()
+    }
+}
+
+/// The overlay with the projects fetched again, e.g. after one was created
+fn refreshed(
+    projects: &state::ProjectsState,
+    api_handler: &mut ApiHandler,
+) -> state::ProjectsState {
+    match api_handler.fetch_projects() {
+        Ok(fetched) => state::ProjectsState {
+            selected: projects.selected.min(fetched.len().saturating_sub(1)),
+            projects: fetched,
+            error: None,
+            ..projects.clone()
+        },
+        Err(err) => state::ProjectsState {
+            error: Some(display_error(&err)),
+            ..projects.clone()
+        },
+    }
+}
+
+pub fn handle_input(
+    currstate: &state::CalendarState,
+    key: &KeyInfo,
+    api_handler: &mut ApiHandler,
+) -> state::ScreenState {
+    let projects = currstate
+        .viewing_projects
+        .as_ref()
+        .expect("projects should never be used if not viewing projects");
+
+    let new_projects = if let Some(new_name) = &projects.new_name {
+        handle_new_name_input(projects, new_name, key, api_handler)
+    } else if let Some(timeline) = &projects.timeline {
+        let task_count = timeline.tasks.len();
+        if key_pressed(key, KeyModifiers::NONE, KeyCode::Esc) {
+            Some(state::ProjectsState {
+                timeline: None,
+                ..projects.clone()
+            })
+        } else if key_pressed(key, KeyModifiers::NONE, KeyCode::Enter) {
+            match timeline.tasks.get(timeline.selected) {
+                Some(task) => return search::jump_to_task(currstate, task),
+                None => Some(projects.clone()),
+            }
+        } else {
+            let selected = if key_pressed(key, KeyModifiers::NONE, KeyCode::Char('j')) {
+                (timeline.selected + 1).min(task_count.saturating_sub(1))
+            } else if key_pressed(key, KeyModifiers::NONE, KeyCode::Char('k')) {
+                timeline.selected.saturating_sub(1)
+            } else {
+                timeline.selected
+            };
+            Some(state::ProjectsState {
+                timeline: Some(state::ProjectTimeline {
+                    selected,
+                    ..timeline.clone()
+                }),
+                ..projects.clone()
+            })
+        }
+    } else {
+        handle_list_input(currstate, projects, key, api_handler)
+    };
+
+    state::ScreenState::Calendar(state::CalendarState {
+        viewing_projects: new_projects,
+        ..currstate.clone()
+    })
+}
+
+/// Typing a new project's name: `Enter` creates it and `Esc` gives up
+fn handle_new_name_input(
+    projects: &state::ProjectsState,
+    new_name: &state::TextInputState,
+    key: &KeyInfo,
+    api_handler: &mut ApiHandler,
+) -> Option<state::ProjectsState> {
+    if key_pressed(key, KeyModifiers::NONE, KeyCode::Esc) {
+        return Some(state::ProjectsState {
+            new_name: None,
+            ..projects.clone()
+        });
+    }
+    if !key_pressed(key, KeyModifiers::NONE, KeyCode::Enter) {
+        return Some(state::ProjectsState {
+            new_name: Some(inputtext::handle_input(new_name, key)),
+            ..projects.clone()
+        });
+    }
+    let name = new_name.contents.trim();
+    if name.is_empty() {
+        return Some(projects.clone());
+    }
+    match api_handler.create_project(name) {
+        Ok(()) => Some(refreshed(
+            &state::ProjectsState {
+                new_name: None,
+                ..projects.clone()
+            },
+            api_handler,
+        )),
+        Err(err) => Some(state::ProjectsState {
+            error: Some(display_error(&err)),
+            ..projects.clone()
+        }),
+    }
+}
+
+fn handle_list_input(
+    currstate: &state::CalendarState,
+    projects: &state::ProjectsState,
+    key: &KeyInfo,
+    api_handler: &mut ApiHandler,
+) -> Option<state::ProjectsState> {
+    let project = projects.projects.get(projects.selected);
+    if key_pressed(key, KeyModifiers::NONE, KeyCode::Esc) {
+        None
+    } else if key_pressed(key, KeyModifiers::NONE, KeyCode::Char('j')) {
+        Some(state::ProjectsState {
+            selected: (projects.selected + 1).min(projects.projects.len().saturating_sub(1)),
+            ..projects.clone()
+        })
+    } else if key_pressed(key, KeyModifiers::NONE, KeyCode::Char('k')) {
+        Some(state::ProjectsState {
+            selected: projects.selected.saturating_sub(1),
+            ..projects.clone()
+        })
+    } else if key_pressed(key, KeyModifiers::NONE, KeyCode::Char('o')) {
+        Some(state::ProjectsState {
+            new_name: Some(state::TextInputState::new()),
+            ..projects.clone()
+        })
+    } else if key_pressed(key, KeyModifiers::NONE, KeyCode::Enter)
+        && let Some(project) = project
+    {
+        Some(match api_handler.fetch_project_tasks(project.project_id) {
+            Ok(tasks) => state::ProjectsState {
+                timeline: Some(state::ProjectTimeline {
+                    project: project.clone(),
+                    tasks,
+                    selected: 0,
+                }),
+                error: None,
+                ..projects.clone()
+            },
+            Err(err) => state::ProjectsState {
+                error: Some(display_error(&err)),
+                ..projects.clone()
+            },
+        })
+    } else if key_pressed(key, KeyModifiers::NONE, KeyCode::Char('a'))
+        && let Some(project) = project
+    {
+        // Put the task that's selected in the calendar into the project
+        let selected_date = utils::RicalDate::new(currstate.year, currstate.month, currstate.day);
+        let Some(task) =
+            calendar::get_selected_task(api_handler, &selected_date, currstate.task_id)
+        else {
+            return Some(state::ProjectsState {
+                error: Some("Select a task in the tasks pane first".to_string()),
+                ..projects.clone()
+            });
+        };
+        let moved = rical_types::TaskDataWithId {
+            project_id: Some(project.project_id),
+            ..task
+        };
+        match api_handler.update_task(&moved) {
+            Ok(_) => Some(refreshed(projects, api_handler)),
+            Err(err) => Some(state::ProjectsState {
+                error: Some(display_error(&err)),
+                ..projects.clone()
+            }),
+        }
+    } else {
+        Some(projects.clone())
+    }
+}
+
+/// The first line of the projects (or a project's tasks), below the title
+const LIST_Y: u16 = 4;
+
+pub fn render(currstate: &state::CalendarState) -> io::Result<()> {
+    let projects = currstate
+        .viewing_projects
+        .as_ref()
+        .expect("projects should never be used if not viewing projects");
+
+    text::println(0, "(esc) back")?;
+    text::println(1, "")?;
+    let (mut cursory, hint) = match &projects.timeline {
+        Some(timeline) => (
+            render_timeline(timeline)?,
+            "(enter) Go to task | (j/k) Navigate",
+        ),
+        None => (
+            render_list(projects)?,
+            "(enter) Open | (o) New project | (a) Add the selected task | (j/k) Navigate",
+        ),
+    };
+
+    if let Some(err) = &projects.error {
+        queue!(
+            io::stdout(),
+            cursor::MoveTo(0, cursory),
+            style::PrintStyledContent(format!("(!) {}", err).red())
+        )?;
+        text::clear_rest_of_line()?;
+        cursory += 1;
+    }
+    text::println(cursory, "")?;
+    match &projects.new_name {
+        Some(new_name) => {
+            let (width, _) = terminal::size()?;
+            inputtext::render(
+                "New project",
+                new_name,
+                &styles::Styles {
+                    margin_top: cursory + 1,
+                    width: Some(50.min(width - 1)),
+                    active: true,
+                    ..styles::Styles::new()
+                },
+                &inputtext::InputMode::Normal,
+            )?;
+            text::println(cursory + 2, "(enter) Create | (esc) Cancel")?;
+        }
+        None => text::println(cursory + 1, hint)?,
+    }
+    text::clear_to_end()?;
+    Ok(())
+}
+
+/// Render every project with its progress, and return the line after them
+fn render_list(projects: &state::ProjectsState) -> io::Result<u16> {
+    let mut stdout = io::stdout();
+    text::println(2, "Projects")?;
+    text::println(3, "")?;
+
+    let mut cursory = LIST_Y;
+    if projects.projects.is_empty() {
+        text::println(cursory, "No projects yet: press (o) to start one")?;
+        cursory += 1;
+    }
+    for (index, project) in projects.projects.iter().enumerate() {
+        let name: String = project.name.chars().take(NAME_WIDTH).collect();
+        let name = format!(" {:<width$} ", name, width = NAME_WIDTH);
+        queue!(
+            stdout,
+            cursor::MoveTo(0, cursory),
+            style::PrintStyledContent(if index == projects.selected {
+                name.black().on_white()
+            } else {
+                name.reset()
+            })
+        )?;
+        render_progress(&project.progress)?;
+        if let Some(due) = &project.due {
+            queue!(stdout, style::Print(format!("  due {}", fmt_due(due))))?;
+        }
+        text::clear_rest_of_line()?;
+        cursory += 1;
+    }
+    Ok(cursory)
+}
+
+/// Print a progress bar, how many tasks are done, and how many are overdue if any are
+fn render_progress(progress: &rical_types::ProjectProgress) -> io::Result<()> {
+    let mut stdout = io::stdout();
+    queue!(
+        stdout,
+        style::Print(" "),
+        style::PrintStyledContent(progress_bar(progress).green()),
+        style::Print(format!(" {}/{}", progress.done, progress.total))
+    )?;
+    if progress.overdue > 0 {
+        queue!(
+            stdout,
+            style::PrintStyledContent(format!("  {} overdue", progress.overdue).red())
+        )?;
+    }
+    Ok(())
+}
+
+/// Render a project's tasks by date, and return the line after them
+fn render_timeline(timeline: &state::ProjectTimeline) -> io::Result<u16> {
+    let mut stdout = io::stdout();
+    let (width, height) = terminal::size()?;
+    let project = &timeline.project;
+
+    let title = match &project.due {
+        Some(due) => format!("{} (due {})", project.name, fmt_due(due)),
+        None => project.name.clone(),
+    };
+    text::println(2, &title)?;
+    queue!(stdout, cursor::MoveTo(0, 3))?;
+    render_progress(&project.progress)?;
+    text::clear_rest_of_line()?;
+
+    let mut cursory = LIST_Y;
+    if let Some(description) = &project.description {
+        let description: String = description
+            .chars()
+            .map(|c| if c.is_control() { ' ' } else { c })
+            .take(width.saturating_sub(2) as usize)
+            .collect();
+        queue!(
+            stdout,
+            cursor::MoveTo(0, cursory),
+            style::PrintStyledContent(format!(" {}", description).dark_grey())
+        )?;
+        text::clear_rest_of_line()?;
+        cursory += 1;
+    }
+    text::println(cursory, "")?;
+    cursory += 1;
+
+    if timeline.tasks.is_empty() {
+        text::println(cursory, "No tasks in this project yet")?;
+        return Ok(cursory + 1);
+    }
+    // Leave room for the hint at the bottom
+    let shown = height.saturating_sub(cursory + 3).max(1) as usize;
+    let first = (timeline.selected + 1).saturating_sub(shown);
+    for (index, task) in timeline.tasks.iter().enumerate().skip(first).take(shown) {
+        // Not `RicalDate::new`, which panics on dates like the 31st of June that tasks can have
+        let date = utils::RicalDate {
+            year: task.year,
+            month: task.month as u32,
+            day: task.day as u32,
+        }
+        .format();
+        let (checkbox, status_color) = calendar::status_checkbox(task.status);
+        let prefix = format!(" {} ", date);
+        let prefix_width = prefix.chars().count() as u16;
+        queue!(
+            stdout,
+            cursor::MoveTo(0, cursory),
+            style::PrintStyledContent(if index == timeline.selected {
+                prefix.black().on_white()
+            } else {
+                prefix.reset()
+            }),
+            style::Print(" "),
+            style::PrintStyledContent(match status_color {
+                Some(color) => checkbox.with(color),
+                None => checkbox.reset(),
+            }),
+            style::Print(" ")
+        )?;
+        let title: String = task
+            .title
+            .chars()
+            .take(width.saturating_sub(prefix_width + 6) as usize)
+            .collect();
+        queue!(
+            stdout,
+            style::PrintStyledContent(if task.status.is_closed() {
+                title.dark_grey()
+            } else {
+                title.reset()
+            })
+        )?;
+        text::clear_rest_of_line()?;
+        cursory += 1;
+    }
+    Ok(cursory)
+}
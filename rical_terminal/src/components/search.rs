@@ -0,0 +1,243 @@
+use crossterm::{
+    cursor,
+    event::{KeyCode, KeyModifiers},
+    queue,
+    style::{self, Stylize},
+    terminal,
+};
+use std::io;
+
+use crate::api::ApiHandler;
+use crate::state;
+use crate::styles;
+use crate::utils::{self, KeyInfo, display_error, key_pressed};
+
+use crate::components::{calendar, inputtext, text};
+
+// The search overlay, which finds tasks on any date and jumps to them
+
+pub fn handle_input(
+    currstate: &state::CalendarState,
+    key: &KeyInfo,
+    api_handler: &mut ApiHandler,
+) -> state::ScreenState {
+    let search = currstate
+        .searching
+        .as_ref()
+        .expect("search should never be used if not searching");
+    let hit_count = search.hits.as_ref().map_or(0, Vec::len);
+
+    let new_search = if key_pressed(key, KeyModifiers::NONE, KeyCode::Esc) {
+        None
+    } else if key_pressed(key, KeyModifiers::NONE, KeyCode::Down) {
+        Some(state::SearchState {
+            selected: match search.selected {
+                _ if hit_count == 0 => None,
+                Some(index) => Some((index + 1).min(hit_count - 1)),
+                None => Some(0),
+            },
+            ..search.clone()
+        })
+    } else if key_pressed(key, KeyModifiers::NONE, KeyCode::Up) {
+        Some(state::SearchState {
+            // Going up from the first hit goes back to typing
+            selected: search.selected.and_then(|index| index.checked_sub(1)),
+            ..search.clone()
+        })
+    } else if key_pressed(key, KeyModifiers::NONE, KeyCode::Enter) {
+        let selected_hit = search
+            .selected
+            .and_then(|index| search.hits.as_ref()?.get(index));
+        match selected_hit {
+            Some(hit) => return jump_to_task(currstate, &hit.task),
+            None if search.input.contents.trim().is_empty() => Some(search.clone()),
+            None => Some(match api_handler.search_tasks(&search.input.contents) {
+                Ok(hits) => state::SearchState {
+                    hits: Some(hits),
+                    selected: None,
+                    error: None,
+                    ..search.clone()
+                },
+                Err(err) => state::SearchState {
+                    hits: None,
+                    selected: None,
+                    error: Some(display_error(&err)),
+                    ..search.clone()
+                },
+            }),
+        }
+    } else {
+        Some(state::SearchState {
+            input: inputtext::handle_input(&search.input, key),
+            selected: None,
+            ..search.clone()
+        })
+    };
+
+    state::ScreenState::Calendar(state::CalendarState {
+        searching: new_search,
+        ..currstate.clone()
+    })
+}
+
+/// Close the search (or the projects) and select the task in the tasks pane
+pub fn jump_to_task(
+    currstate: &state::CalendarState,
+    task: &rical_types::TaskDataWithId,
+) -> state::ScreenState {
+    // Tasks can be on dates that don't exist (like the 31st of June), which the calendar can't show
+    if chrono::NaiveDate::from_ymd_opt(task.year, task.month as u32, task.day as u32).is_none() {
+        return state::ScreenState::Calendar(currstate.clone());
+    }
+    state::ScreenState::Calendar(state::CalendarState {
+        year: task.year,
+        month: task.month as u32,
+        day: task.day as u32,
+        task_id: Some(task.task_id),
+        pane: state::CalendarPane::Tasks,
+        searching: None,
+        viewing_projects: None,
+        ..currstate.clone()
+    })
+}
+
+/// The first line of the results, below the search box
+const RESULTS_Y: u16 = 6;
+
+pub fn render(currstate: &state::CalendarState) -> io::Result<()> {
+    let search = currstate
+        .searching
+        .as_ref()
+        .expect("search should never be used if not searching");
+    let mut stdout = io::stdout();
+    let (width, height) = terminal::size()?;
+
+    text::println(0, "(esc) back")?;
+    text::println(1, "")?;
+    text::println(2, "Search Tasks")?;
+    text::println(3, "")?;
+    inputtext::render(
+        "Search",
+        &search.input,
+        &styles::Styles {
+            margin_top: 4,
+            width: Some(50.min(width - 1)),
+            active: search.selected.is_none(),
+            ..styles::Styles::new()
+        },
+        &inputtext::InputMode::Normal,
+    )?;
+    text::println(5, "")?;
+
+    let hint = match (&search.hits, search.selected) {
+        (_, Some(_)) => "(enter) Go to task | (up/down) Choose",
+        (Some(hits), None) if !hits.is_empty() => "(enter) Search | (down) Choose a result",
+        _ => "(enter) Search",
+    };
+    let mut cursory = RESULTS_Y;
+    match (&search.error, &search.hits) {
+        (Some(err), _) => {
+            queue!(
+                stdout,
+                cursor::MoveTo(0, cursory),
+                style::PrintStyledContent(format!("(!) Could not search: {}", err).red())
+            )?;
+            text::clear_rest_of_line()?;
+            cursory += 1;
+        }
+        (None, Some(hits)) if hits.is_empty() => {
+            text::println(cursory, "No tasks found")?;
+            cursory += 1;
+        }
+        (None, Some(hits)) => {
+            // Two lines per hit, leaving room for the hint at the bottom
+            let shown = ((height.saturating_sub(RESULTS_Y + 2)) / 2).max(1) as usize;
+            let first = search
+                .selected
+                .map_or(0, |index| (index + 1).saturating_sub(shown));
+            for (index, hit) in hits.iter().enumerate().skip(first).take(shown) {
+                render_hit(hit, cursory, width, search.selected == Some(index))?;
+                cursory += 2;
+            }
+        }
+        (None, None) => (),
+    }
+    text::println(cursory, "")?;
+    text::println(cursory + 1, hint)?;
+    text::clear_to_end()?;
+    Ok(())
+}
+
+/// Render a hit on two lines: its date, checkbox and title, then its description
+fn render_hit(
+    hit: &rical_types::SearchHit,
+    y: u16,
+    width: u16,
+    is_selected: bool,
+) -> io::Result<()> {
+    let mut stdout = io::stdout();
+    let task = &hit.task;
+
+    // Not `RicalDate::new`, which panics on dates like the 31st of June that tasks can have
+    let date = utils::RicalDate {
+        year: task.year,
+        month: task.month as u32,
+        day: task.day as u32,
+    }
+    .format();
+    let prefix = format!(" {} {} ", date, calendar::status_checkbox(task.status).0);
+    let prefix_width = prefix.chars().count() as u16;
+    queue!(
+        stdout,
+        cursor::MoveTo(0, y),
+        style::PrintStyledContent(if is_selected {
+            prefix.black().on_white()
+        } else {
+            prefix.reset()
+        }),
+        style::Print(" ")
+    )?;
+    render_parts(&hit.title, width.saturating_sub(prefix_width + 2), false)?;
+    text::clear_rest_of_line()?;
+
+    queue!(
+        stdout,
+        cursor::MoveTo(0, y + 1),
+        style::Print(" ".repeat(prefix_width as usize + 1))
+    )?;
+    if let Some(description) = &hit.description {
+        render_parts(description, width.saturating_sub(prefix_width + 2), true)?;
+    }
+    text::clear_rest_of_line()?;
+    Ok(())
+}
+
+/// Print a snippet with its matches highlighted, cut off at `max_width` characters
+fn render_parts(parts: &[rical_types::SnippetPart], max_width: u16, dim: bool) -> io::Result<()> {
+    let mut stdout = io::stdout();
+    let mut remaining = max_width as usize;
+    for part in parts {
+        // Snippets can span lines, which would break the layout
+        let part_text: String = part
+            .text
+            .chars()
+            .map(|c| if c.is_control() { ' ' } else { c })
+            .take(remaining)
+            .collect();
+        remaining -= part_text.chars().count();
+        queue!(
+            stdout,
+            style::PrintStyledContent(if part.matched {
+                part_text.black().on_yellow()
+            } else if dim {
+                part_text.dark_grey()
+            } else {
+                part_text.reset()
+            })
+        )?;
+        if remaining == 0 {
+            break;
+        }
+    }
+    Ok(())
+}
@@ -41,7 +41,7 @@ pub fn handle_input(
                 Err(err) => {
                     state::ScreenState::Menu(state::MenuState::Signup(state::FormState::from_result_message(vec![
                         "Signing up failed:".to_string(),
-                        format!("  - {}", display_error(err)),
+                        format!("  - {}", display_error(&err)),
                         "Make sure you entered a unique username, and that you're connected to the server".to_string(),
                         "as specified in 'rical_frontend/.env'".to_string(),
                     ])))
@@ -0,0 +1,175 @@
+use crossterm::{
+    cursor,
+    event::{KeyCode, KeyModifiers},
+    queue,
+    style::{self, Stylize},
+};
+use std::io;
+
+use crate::api::{ApiHandler, CacheType};
+use crate::state;
+use crate::utils::{KeyInfo, key_pressed};
+
+use crate::components::text;
+
+// The overlay that chooses which tags' tasks the calendar shows, and the tags' colors
+
+/// The terminal color of a tag's color
+pub fn color(color: rical_types::TagColor) -> style::Color {
+    match color {
+        rical_types::TagColor::Red => style::Color::Red,
+        rical_types::TagColor::Yellow => style::Color::Yellow,
+        rical_types::TagColor::Green => style::Color::Green,
+        rical_types::TagColor::Cyan => style::Color::Cyan,
+        rical_types::TagColor::Blue => style::Color::Blue,
+        rical_types::TagColor::Magenta => style::Color::Magenta,
+        rical_types::TagColor::Grey => style::Color::Grey,
+    }
+}
+
+/// The color to draw a task in: its first tag's, if it has a tag
+pub fn task_color(
+    task: &rical_types::TaskDataWithId,
+    tags: &[rical_types::Tag],
+) -> Option<style::Color> {
+    let first = task.tags.first()?;
+    tags.iter()
+        .find(|tag| &tag.name == first)
+        .map(|tag| color(tag.color))
+}
+
+/// Open the overlay with the account's current tags, and the filter that's in use chosen
+pub fn open(
+    currstate: &state::CalendarState,
+    api_handler: &mut ApiHandler,
+) -> state::CalendarState {
+    state::CalendarState {
+        filtering_tags: Some(state::TagFilterState {
+            tags: api_handler.fetch_tags(CacheType::RefreshOne),
+            chosen: api_handler.tag_filter().to_vec(),
+            selected: 0,
+        }),
+        ..currstate.clone()
+    }
+}
+
+pub fn handle_input(
+    currstate: &state::CalendarState,
+    key: &KeyInfo,
+    api_handler: &mut ApiHandler,
+) -> state::ScreenState {
+    let filter = currstate
+        .filtering_tags
+        .as_ref()
+        .expect("tag_filter should never be used if not filtering by tags");
+    let tag_count = filter.tags.len();
+
+    if key_pressed(key, KeyModifiers::NONE, KeyCode::Esc) {
+        return state::ScreenState::Calendar(state::CalendarState {
+            filtering_tags: None,
+            ..currstate.clone()
+        });
+    }
+    if key_pressed(key, KeyModifiers::NONE, KeyCode::Enter) {
+        api_handler.set_tag_filter(filter.chosen.clone());
+        return state::ScreenState::Calendar(state::CalendarState {
+            // The selected task may be hidden now
+            task_id: None,
+            filtering_tags: None,
+            ..currstate.clone()
+        });
+    }
+
+    let new_filter = if key_pressed(key, KeyModifiers::NONE, KeyCode::Char('j')) {
+        state::TagFilterState {
+            selected: (filter.selected + 1).min(tag_count.saturating_sub(1)),
+            ..filter.clone()
+        }
+    } else if key_pressed(key, KeyModifiers::NONE, KeyCode::Char('k')) {
+        state::TagFilterState {
+            selected: filter.selected.saturating_sub(1),
+            ..filter.clone()
+        }
+    } else if key_pressed(key, KeyModifiers::NONE, KeyCode::Char('x')) {
+        let mut chosen = filter.chosen.clone();
+        if let Some(tag) = filter.tags.get(filter.selected) {
+            match chosen.iter().position(|name| name == &tag.name) {
+                Some(index) => {
+                    chosen.remove(index);
+                }
+                None => chosen.push(tag.name.clone()),
+            }
+        }
+        state::TagFilterState {
+            chosen,
+            ..filter.clone()
+        }
+    } else if key_pressed(key, KeyModifiers::NONE, KeyCode::Char('c')) {
+        state::TagFilterState {
+            chosen: Vec::new(),
+            ..filter.clone()
+        }
+    } else {
+        filter.clone()
+    };
+
+    state::ScreenState::Calendar(state::CalendarState {
+        filtering_tags: Some(new_filter),
+        ..currstate.clone()
+    })
+}
+
+/// The first line of the tags, below the title
+const TAGS_Y: u16 = 4;
+
+pub fn render(currstate: &state::CalendarState) -> io::Result<()> {
+    let filter = currstate
+        .filtering_tags
+        .as_ref()
+        .expect("tag_filter should never be used if not filtering by tags");
+    let mut stdout = io::stdout();
+
+    text::println(0, "(esc) back")?;
+    text::println(1, "")?;
+    text::println(
+        2,
+        if filter.chosen.is_empty() {
+            "Filter by Tags (showing every task)"
+        } else {
+            "Filter by Tags (showing the tasks with any chosen tag)"
+        },
+    )?;
+    text::println(3, "")?;
+
+    let mut cursory = TAGS_Y;
+    if filter.tags.is_empty() {
+        text::println(
+            cursory,
+            "No tags yet: add some with #tag in a new task's title",
+        )?;
+        cursory += 1;
+    }
+    for (index, tag) in filter.tags.iter().enumerate() {
+        let is_chosen = filter.chosen.contains(&tag.name);
+        let checkbox = if is_chosen { " [x] " } else { " [ ] " };
+        queue!(
+            stdout,
+            cursor::MoveTo(0, cursory),
+            style::PrintStyledContent(if index == filter.selected {
+                checkbox.black().on_white()
+            } else {
+                checkbox.reset()
+            }),
+            style::PrintStyledContent(format!("#{}", tag.name).with(color(tag.color)))
+        )?;
+        text::clear_rest_of_line()?;
+        cursory += 1;
+    }
+    text::println(cursory, "")?;
+    text::println(
+        cursory + 1,
+        "(x) Choose | (c) Show every task | (enter) Apply | (j/k) Navigate",
+    )?;
+    text::clear_to_end()?;
+    Ok(())
+}
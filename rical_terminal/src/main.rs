@@ -7,7 +7,6 @@ mod api;
 mod components;
 mod state;
 mod styles;
-mod types;
 mod utils;
 
 fn main() -> io::Result<()> {
@@ -1,5 +1,3 @@
-use crate::types;
-
 /// Stores the entire hierarchy of state in the app
 /// `screen_state` deals with the state of the UI
 /// Other fields can be added to represent important global state
@@ -8,6 +6,8 @@ pub struct RicalState {
     pub screen_state: ScreenState,
 }
 
+// Only one screen state exists at a time, so the size difference between variants doesn't matter
+#[allow(clippy::large_enum_variant)]
 #[derive(Clone)]
 pub enum ScreenState {
     Calendar(CalendarState),
@@ -91,7 +91,11 @@ pub struct CalendarState {
     pub pane: CalendarPane,
     pub making_new_task: Option<FormState<4>>,
     pub editing_task: Option<EditTaskState>,
-    pub task_clipboard: Option<types::TaskData>,
+    pub task_clipboard: Option<rical_types::TaskData>,
+    pub searching: Option<SearchState>,
+    pub expanded: Option<ExpandedTask>,
+    pub filtering_tags: Option<TagFilterState>,
+    pub viewing_projects: Option<ProjectsState>,
 }
 
 impl CalendarState {
@@ -105,6 +109,10 @@ impl CalendarState {
             making_new_task: None,
             editing_task: None,
             task_clipboard: None,
+            searching: None,
+            expanded: None,
+            filtering_tags: None,
+            viewing_projects: None,
         }
     }
 }
@@ -112,9 +120,82 @@ impl CalendarState {
 #[derive(Clone)]
 pub struct EditTaskState {
     pub task_id: i64,
+    /// Not in the form, but kept so that editing doesn't reset them
+    pub priority: rical_types::Priority,
+    pub position: i32,
+    pub auto_complete: bool,
+    pub project_id: Option<i64>,
+    pub estimate_mins: Option<i32>,
     pub form: FormState<8>,
 }
 
+/// The selected task in the tasks pane, expanded to show its checklist
+#[derive(Clone)]
+pub struct ExpandedTask {
+    pub task_id: i64,
+    pub items: Vec<rical_types::ChecklistItem>,
+    /// The index of the selected item, or None while the task itself is selected
+    pub selected: Option<usize>,
+}
+
+/// The overlay that chooses which tags' tasks the calendar shows
+#[derive(Clone)]
+pub struct TagFilterState {
+    /// Every tag of the account, by name
+    pub tags: Vec<rical_types::Tag>,
+    /// The names of the tags whose tasks are shown, or empty to show every task
+    pub chosen: Vec<String>,
+    /// The index of the selected tag
+    pub selected: usize,
+}
+
+/// The overlay that lists the account's projects with their progress
+#[derive(Clone)]
+pub struct ProjectsState {
+    /// The soonest due first
+    pub projects: Vec<rical_types::Project>,
+    /// The index of the selected project
+    pub selected: usize,
+    /// The name of the new project being typed, if one is
+    pub new_name: Option<TextInputState>,
+    /// The selected project's tasks, once it's opened
+    pub timeline: Option<ProjectTimeline>,
+    /// Why the last change or fetch failed, if it did
+    pub error: Option<String>,
+}
+
+/// A project's tasks by date, in the projects overlay
+#[derive(Clone)]
+pub struct ProjectTimeline {
+    pub project: rical_types::Project,
+    pub tasks: Vec<rical_types::TaskDataWithId>,
+    /// The index of the selected task
+    pub selected: usize,
+}
+
+/// The search overlay over the calendar
+#[derive(Clone)]
+pub struct SearchState {
+    pub input: TextInputState,
+    /// The hits of the last search, or None before the first one
+    pub hits: Option<Vec<rical_types::SearchHit>>,
+    /// The index of the selected hit, or None while typing the search
+    pub selected: Option<usize>,
+    /// Why the last search failed, if it did
+    pub error: Option<String>,
+}
+
+impl SearchState {
+    pub fn new() -> SearchState {
+        SearchState {
+            input: TextInputState::new(),
+            hits: None,
+            selected: None,
+            error: None,
+        }
+    }
+}
+
 #[derive(Clone)]
 pub enum CalendarPane {
     Month,
@@ -2,6 +2,11 @@ use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 
 use chrono::{Datelike, NaiveDate};
 use regex::Regex;
+use rical_client::ClientError;
+
+use rical_types::date::get_days_in_month;
+pub use rical_types::RicalDate;
+pub use rical_types::date::{fmt_mins, fmt_timerange, fmt_twodigit};
 
 
 pub struct KeyInfo {
@@ -20,113 +25,6 @@ pub fn key_pressed(key: &KeyInfo, modifiers: KeyModifiers, code: KeyCode) -> boo
     key.code == code && key.modifiers == modifiers
 }
 
-/// Represent a date internally to Rical
-#[derive(Clone, PartialEq, Eq, PartialOrd, Ord)]
-pub struct RicalDate {
-    pub year: i32,
-    pub month: u32,
-    pub day: u32,
-}
-
-impl RicalDate {
-    pub fn new(year: i32, month: u32, day: u32) -> RicalDate {
-        // Validate with chrono
-        if chrono::NaiveDate::from_ymd_opt(year, month, day).is_none() {
-            panic!(
-                "This date is invalid (be sure to use 1-based months and days): {}/{}/{}",
-                year, month, day
-            );
-        }
-
-        RicalDate { year, month, day }
-    }
-
-    pub fn from_naive_date(naive: chrono::NaiveDate) -> RicalDate {
-        RicalDate {
-            year: naive.year(),
-            month: naive.month0() + 1,
-            day: naive.day0() + 1,
-        }
-    }
-
-    pub fn to_naive_date(&self) -> chrono::NaiveDate {
-        // self should be valid because of checks in the constructors
-        chrono::NaiveDate::from_ymd_opt(self.year, self.month, self.day)
-            .expect("The RicalDate is not valid")
-    }
-
-    /// Return today's date (in local time)
-    pub fn today() -> RicalDate {
-        let curr_date = chrono::offset::Local::now().date_naive();
-        RicalDate::new(
-            curr_date.year(),
-            curr_date.month0() + 1,
-            curr_date.day0() + 1,
-        )
-    }
-
-    /// Add a certain number of days and return the new date
-    /// Might cross over into a different month or year
-    pub fn add_days(&self, days: u64) -> RicalDate {
-        RicalDate::from_naive_date(
-            self.to_naive_date()
-                .checked_add_days(chrono::Days::new(days))
-                .expect("Could not add days"),
-        )
-    }
-
-    /// Subtract a certain number of days and return the new date
-    /// Might cross over into a different month or year
-    pub fn sub_days(&self, days: u64) -> RicalDate {
-        RicalDate::from_naive_date(
-            self.to_naive_date()
-                .checked_sub_days(chrono::Days::new(days))
-                .expect("Could not subtract days"),
-        )
-    }
-
-    /// Format the date as a YYYY/MM/DD string
-    pub fn format(&self) -> String {
-        format!(
-            "{}/{}/{}",
-            self.year,
-            fmt_twodigit(self.month),
-            fmt_twodigit(self.day)
-        )
-    }
-
-    /// Get the name of this date's weekday
-    pub fn weekday_name(&self) -> String {
-        const WEEKDAY_NAMES: [&str; 7] = [
-            "Sunday",
-            "Monday",
-            "Tuesday",
-            "Wednesday",
-            "Thursday",
-            "Friday",
-            "Saturday",
-        ];
-        let weekday = self.to_naive_date().weekday().number_from_sunday() - 1;
-        WEEKDAY_NAMES[weekday as usize].to_string()
-    }
-}
-
-fn next_month(year: i32, month: u32) -> (i32, u32) {
-    match month {
-        12 => (year + 1, 1),
-        _ => (year, month + 1),
-    }
-}
-
-fn get_days_in_month(year: i32, month: u32) -> u32 {
-    NaiveDate::from_ymd_opt(next_month(year, month).0, next_month(year, month).1, 1)
-        .expect("Could not obtain previous month (calculating days in month)")
-        .signed_duration_since(
-            NaiveDate::from_ymd_opt(year, month, 1).expect("Could not obtain days in month"),
-        )
-        .num_days() as u32
-}
-
 type CalendarFrame = Vec<Vec<i32>>;
 
 /// Get the 2d array of days for a calendar month (res[row][weekday] gets you the day number of the month)
@@ -172,27 +70,6 @@ pub fn calendar_grid_navigation(current_date: &RicalDate, direction: GridDirecti
     }
 }
 
-/// Format a two-digit number with a leading zero
-pub fn fmt_twodigit<T: ToString>(number: T) -> String {
-    format!("{:0>2}", number.to_string())
-}
-
-/// Turn minutes into a 24-hour HR:MN format, or an empty string if None
-pub fn fmt_mins(mins_opt: Option<i32>) -> String {
-    match mins_opt {
-        Some(mins) => format!("{}:{}", fmt_twodigit(mins / 60), fmt_twodigit(mins % 60)),
-        None => String::new(),
-    }
-}
-
-/// Format a time range of minutes
-pub fn fmt_timerange(start_min: Option<i32>, end_min: Option<i32>) -> String {
-    if start_min.is_none() && end_min.is_none() {
-        return String::new();
-    }
-    format!("{}-{}", fmt_mins(start_min), fmt_mins(end_min))
-}
-
 enum PeriodType {
     AM,
     PM,
@@ -286,22 +163,20 @@ pub fn get_month_name(month: u32) -> String {
     MONTH_NAMES[month as usize - 1].to_string()
 }
 
-pub fn display_error(err: reqwest::Error) -> String {
-    if err.is_timeout() {
-        "Request timed out".to_string()
-    } else if err.is_connect() {
-        "Could not connect to the server".to_string()
-    } else if err.is_request() {
-        "Invalid request".to_string()
-    } else if err.is_body() {
-        "Invalid request body".to_string()
-    } else if err.is_status() {
-        format!("{}", err.status().unwrap())
+/// Format a number of seconds as hours and minutes, such as 1h05m, or just minutes under an hour
+pub fn fmt_elapsed(secs: i64) -> String {
+    let mins = secs.max(0) / 60;
+    if mins < 60 {
+        format!("{}m", mins)
     } else {
-        "Unknown error".to_string()
+        format!("{}h{}m", mins / 60, fmt_twodigit(mins % 60))
     }
 }
 
+pub fn display_error(err: &ClientError) -> String {
+    err.to_string()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -334,11 +209,12 @@ mod tests {
     }
 
     #[test]
-    fn test_fmt_mins() {
-        assert_eq!(fmt_mins(Some(3 * 60)), "03:00");
-        assert_eq!(fmt_mins(Some(22 * 60 + 12)), "22:12");
-        assert_eq!(fmt_mins(Some(0 * 60 + 2)), "00:02");
-        assert_eq!(fmt_mins(Some(23 * 60 + 59)), "23:59");
-        assert_eq!(fmt_mins(None), "");
+    fn test_fmt_elapsed() {
+        assert_eq!(fmt_elapsed(0), "0m");
+        assert_eq!(fmt_elapsed(59), "0m");
+        assert_eq!(fmt_elapsed(12 * 60 + 30), "12m");
+        assert_eq!(fmt_elapsed(3600 + 5 * 60), "1h05m");
+        assert_eq!(fmt_elapsed(26 * 3600), "26h00m");
+        assert_eq!(fmt_elapsed(-5), "0m");
     }
 }
@@ -0,0 +1,115 @@
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "openapi")]
+use utoipa::ToSchema;
+
+// Accounts and authentication
+
+/// The body of `/account/signup` and `/account/login`
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub struct UserCredentials {
+    pub username: String,
+    pub password: String,
+}
+
+/// A login token, returned by `/account/login`
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub struct AuthToken {
+    pub token: String,
+}
+
+/// What a personal API token is allowed to do
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum TokenScope {
+    Read,
+    ReadWrite,
+}
+
+impl TokenScope {
+    /// The same representation as in JSON
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TokenScope::Read => "read",
+            TokenScope::ReadWrite => "read_write",
+        }
+    }
+
+    pub fn from_db_str(s: &str) -> Option<TokenScope> {
+        match s {
+            "read" => Some(TokenScope::Read),
+            "read_write" => Some(TokenScope::ReadWrite),
+            _ => None,
+        }
+    }
+
+    /// Whether a token with this scope may perform an action requiring `required`
+    pub fn allows(&self, required: TokenScope) -> bool {
+        match required {
+            TokenScope::Read => true,
+            TokenScope::ReadWrite => *self == TokenScope::ReadWrite,
+        }
+    }
+}
+
+/// The body of `POST /account/tokens`
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub struct NewApiToken {
+    pub name: String,
+    pub scope: TokenScope,
+    /// If none, the token never expires
+    pub expires_in_days: Option<i64>,
+}
+
+/// A newly created personal API token, returned by `POST /account/tokens`
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub struct CreatedApiToken {
+    pub token_id: i64,
+    /// The plaintext token; it is only ever returned here, so the user must save it now
+    pub token: String,
+    pub name: String,
+    pub scope: TokenScope,
+    /// Unix seconds
+    pub created_at: i64,
+    /// Unix seconds
+    pub expires_at: Option<i64>,
+}
+
+/// A personal API token as listed by `GET /account/tokens` (without the token itself)
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub struct ApiTokenInfo {
+    pub token_id: i64,
+    pub name: String,
+    pub scope: TokenScope,
+    /// Unix seconds
+    pub created_at: i64,
+    /// Unix seconds
+    pub expires_at: Option<i64>,
+    /// Unix seconds
+    pub last_used_at: Option<i64>,
+}
+
+/// Per-account preferences, read and replaced with `/account/settings`
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
+pub struct AccountSettings {
+    /// Move the incomplete tasks of past days to today whenever the day changes
+    pub auto_rollover: bool,
+    /// Minutes ahead of UTC that the account's days start, from -840 to 840 (UTC-14 to UTC+14)
+    pub utc_offset_min: i32,
+}
+
+impl AccountSettings {
+    pub const MAX_UTC_OFFSET_MIN: i32 = 14 * 60;
+
+    pub fn is_valid(&self) -> bool {
+        (-Self::MAX_UTC_OFFSET_MIN..=Self::MAX_UTC_OFFSET_MIN).contains(&self.utc_offset_min)
+    }
+}
@@ -0,0 +1,106 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{RicalDate, TaskData};
+
+#[cfg(feature = "openapi")]
+use utoipa::ToSchema;
+
+// Changing many tasks in one request, as `/tasks/batch` and `/tasks/reschedule` do
+
+/// One change to make in a `TaskBatch`
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum TaskOperation {
+    Create {
+        task: TaskData,
+    },
+    /// Replace a task
+    Update {
+        task_id: i64,
+        task: TaskData,
+    },
+    /// Like `DELETE /task/{id}`, deleting a task that doesn't exist succeeds
+    Delete {
+        task_id: i64,
+    },
+    /// Mark a task as done or not done
+    Complete {
+        task_id: i64,
+        complete: bool,
+    },
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub struct TaskBatch {
+    /// Run in order, so later operations see the changes of earlier ones
+    pub operations: Vec<TaskOperation>,
+    /// Undo every operation if any of them fails, instead of keeping the ones that succeeded
+    #[serde(default)]
+    pub atomic: bool,
+}
+
+/// What happened to one operation of a batch
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum OperationResult {
+    /// The operation succeeded, on the task with this ID (the new one for a create)
+    Ok { task_id: i64 },
+    /// The account has no task with the operation's ID
+    NotFound,
+    /// The operation's task is invalid
+    Invalid,
+    /// Not run, because an earlier operation of an atomic batch failed
+    Skipped,
+}
+
+impl OperationResult {
+    pub fn is_ok(&self) -> bool {
+        matches!(self, OperationResult::Ok { .. })
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub struct BatchResults {
+    /// Whether the successful operations were kept; false only when an atomic batch failed
+    pub committed: bool,
+    /// One for each operation, in the same order
+    pub results: Vec<OperationResult>,
+}
+
+/// The body of `/tasks/reschedule`, which moves every incomplete task between two dates to another date
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub struct RescheduleTasks {
+    /// The first date (YYYY-MM-DD) to move tasks from; if none, every date up to `to`
+    pub from: Option<String>,
+    /// The last date (YYYY-MM-DD) to move tasks from
+    pub to: String,
+    /// The date (YYYY-MM-DD) to move the tasks to
+    pub target: String,
+    /// Keep the tasks' start and end times instead of making them untimed
+    #[serde(default)]
+    pub keep_times: bool,
+}
+
+impl RescheduleTasks {
+    /// Move every incomplete task before `today` to `today`, keeping their times
+    pub fn overdue_to(today: &RicalDate) -> RescheduleTasks {
+        RescheduleTasks {
+            from: None,
+            to: today.sub_days(1).format_iso(),
+            target: today.format_iso(),
+            keep_times: true,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub struct RescheduledTasks {
+    /// How many tasks were moved
+    pub moved: u64,
+}
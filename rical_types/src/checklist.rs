@@ -0,0 +1,62 @@
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "openapi")]
+use utoipa::ToSchema;
+
+// The checklists inside tasks, for tasks that are small procedures of their own
+
+/// One step of a task's checklist
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
+pub struct ChecklistItem {
+    pub item_id: i64,
+    pub text: String,
+    pub done: bool,
+    /// Where the item goes in the checklist, lowest first
+    pub position: i32,
+}
+
+/// The body of the requests that create or replace a checklist item
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub struct ChecklistItemData {
+    pub text: String,
+    #[serde(default)]
+    pub done: bool,
+    /// Where the item goes in the checklist; if none, after the other items when it's created,
+    /// or where it already was when it's replaced
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub position: Option<i32>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub struct ChecklistItemId {
+    pub item_id: i64,
+}
+
+/// A task's checklist, as returned by `/task/{id}/checklist`
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub struct Checklist {
+    /// In order
+    pub items: Vec<ChecklistItem>,
+}
+
+impl Checklist {
+    pub fn progress(&self) -> ChecklistProgress {
+        ChecklistProgress {
+            done: self.items.iter().filter(|item| item.done).count() as u32,
+            total: self.items.len() as u32,
+        }
+    }
+}
+
+/// How much of a task's checklist is done
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub struct ChecklistProgress {
+    pub done: u32,
+    pub total: u32,
+}
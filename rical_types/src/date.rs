@@ -0,0 +1,250 @@
+use chrono::{Datelike, NaiveDate};
+
+// Dates and times as Rical stores them: 1-based dates, and times as minutes after midnight
+
+/// Represent a date internally to Rical
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct RicalDate {
+    pub year: i32,
+    pub month: u32,
+    pub day: u32,
+}
+
+impl RicalDate {
+    pub fn new(year: i32, month: u32, day: u32) -> RicalDate {
+        // Validate with chrono
+        if chrono::NaiveDate::from_ymd_opt(year, month, day).is_none() {
+            panic!(
+                "This date is invalid (be sure to use 1-based months and days): {}/{}/{}",
+                year, month, day
+            );
+        }
+
+        RicalDate { year, month, day }
+    }
+
+    pub fn from_naive_date(naive: chrono::NaiveDate) -> RicalDate {
+        RicalDate {
+            year: naive.year(),
+            month: naive.month0() + 1,
+            day: naive.day0() + 1,
+        }
+    }
+
+    pub fn to_naive_date(&self) -> chrono::NaiveDate {
+        // self should be valid because of checks in the constructors
+        chrono::NaiveDate::from_ymd_opt(self.year, self.month, self.day)
+            .expect("The RicalDate is not valid")
+    }
+
+    /// Return today's date (in local time)
+    pub fn today() -> RicalDate {
+        let curr_date = chrono::offset::Local::now().date_naive();
+        RicalDate::new(
+            curr_date.year(),
+            curr_date.month0() + 1,
+            curr_date.day0() + 1,
+        )
+    }
+
+    /// The date at a moment in Unix seconds, `utc_offset_min` minutes ahead of UTC
+    pub fn at_unix(secs: i64, utc_offset_min: i32) -> RicalDate {
+        let local = chrono::DateTime::from_timestamp(secs + i64::from(utc_offset_min) * 60, 0)
+            .expect("The time is out of range");
+        RicalDate::from_naive_date(local.date_naive())
+    }
+
+    /// The moment the date starts, in Unix seconds, `utc_offset_min` minutes ahead of UTC
+    /// (the opposite of `at_unix`)
+    pub fn start_unix(&self, utc_offset_min: i32) -> i64 {
+        let midnight = self
+            .to_naive_date()
+            .and_hms_opt(0, 0, 0)
+            .expect("Midnight is a valid time");
+        midnight.and_utc().timestamp() - i64::from(utc_offset_min) * 60
+    }
+
+    /// The Monday of the date's week
+    pub fn week_start(&self) -> RicalDate {
+        self.sub_days(u64::from(
+            self.to_naive_date().weekday().num_days_from_monday(),
+        ))
+    }
+
+    /// Add a certain number of days and return the new date
+    /// Might cross over into a different month or year
+    pub fn add_days(&self, days: u64) -> RicalDate {
+        RicalDate::from_naive_date(
+            self.to_naive_date()
+                .checked_add_days(chrono::Days::new(days))
+                .expect("Could not add days"),
+        )
+    }
+
+    /// Subtract a certain number of days and return the new date
+    /// Might cross over into a different month or year
+    pub fn sub_days(&self, days: u64) -> RicalDate {
+        RicalDate::from_naive_date(
+            self.to_naive_date()
+                .checked_sub_days(chrono::Days::new(days))
+                .expect("Could not subtract days"),
+        )
+    }
+
+    /// Format the date as a YYYY/MM/DD string
+    pub fn format(&self) -> String {
+        format!(
+            "{}/{}/{}",
+            self.year,
+            fmt_twodigit(self.month),
+            fmt_twodigit(self.day)
+        )
+    }
+
+    /// Parse a YYYY-MM-DD date, or None if it isn't one (or doesn't exist)
+    /// Only years 1 to 9999 are accepted, so that a date's `date_number` and its neighbors fit
+    pub fn parse_iso(text: &str) -> Option<RicalDate> {
+        // chrono alone would also take signed and longer years, like +250000-01-01
+        let well_formed = text.len() == 10
+            && text.bytes().enumerate().all(|(i, byte)| match i {
+                4 | 7 => byte == b'-',
+                _ => byte.is_ascii_digit(),
+            });
+        if !well_formed || text.starts_with("0000") {
+            return None;
+        }
+        NaiveDate::parse_from_str(text, "%Y-%m-%d")
+            .ok()
+            .map(RicalDate::from_naive_date)
+    }
+
+    /// Format the date as a YYYY-MM-DD string, like the API expects
+    pub fn format_iso(&self) -> String {
+        format!(
+            "{}-{}-{}",
+            self.year,
+            fmt_twodigit(self.month),
+            fmt_twodigit(self.day)
+        )
+    }
+
+    /// Get the name of this date's weekday
+    pub fn weekday_name(&self) -> String {
+        const WEEKDAY_NAMES: [&str; 7] = [
+            "Sunday",
+            "Monday",
+            "Tuesday",
+            "Wednesday",
+            "Thursday",
+            "Friday",
+            "Saturday",
+        ];
+        let weekday = self.to_naive_date().weekday().number_from_sunday() - 1;
+        WEEKDAY_NAMES[weekday as usize].to_string()
+    }
+}
+
+/// The year and 1-based month after this one
+pub fn next_month(year: i32, month: u32) -> (i32, u32) {
+    match month {
+        12 => (year + 1, 1),
+        _ => (year, month + 1),
+    }
+}
+
+pub fn get_days_in_month(year: i32, month: u32) -> u32 {
+    NaiveDate::from_ymd_opt(next_month(year, month).0, next_month(year, month).1, 1)
+        .expect("Could not obtain previous month (calculating days in month)")
+        .signed_duration_since(
+            NaiveDate::from_ymd_opt(year, month, 1).expect("Could not obtain days in month"),
+        )
+        .num_days() as u32
+}
+
+/// Format a two-digit number with a leading zero
+pub fn fmt_twodigit<T: ToString>(number: T) -> String {
+    format!("{:0>2}", number.to_string())
+}
+
+/// Turn minutes into a 24-hour HR:MN format, or an empty string if None
+pub fn fmt_mins(mins_opt: Option<i32>) -> String {
+    match mins_opt {
+        Some(mins) => format!("{}:{}", fmt_twodigit(mins / 60), fmt_twodigit(mins % 60)),
+        None => String::new(),
+    }
+}
+
+/// Format a time range of minutes
+pub fn fmt_timerange(start_min: Option<i32>, end_min: Option<i32>) -> String {
+    if start_min.is_none() && end_min.is_none() {
+        return String::new();
+    }
+    format!("{}-{}", fmt_mins(start_min), fmt_mins(end_min))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fmt_mins() {
+        assert_eq!(fmt_mins(Some(3 * 60)), "03:00");
+        assert_eq!(fmt_mins(Some(22 * 60 + 12)), "22:12");
+        assert_eq!(fmt_mins(Some(2)), "00:02");
+        assert_eq!(fmt_mins(Some(23 * 60 + 59)), "23:59");
+        assert_eq!(fmt_mins(None), "");
+    }
+
+    #[test]
+    fn test_iso_dates() {
+        let date = RicalDate::new(2025, 8, 4);
+        assert_eq!(date.format_iso(), "2025-08-04");
+        assert_eq!(RicalDate::parse_iso("2025-08-04"), Some(date));
+        assert_eq!(RicalDate::parse_iso("2025-02-30"), None);
+        assert_eq!(RicalDate::parse_iso("2025/08/04"), None);
+        assert_eq!(RicalDate::parse_iso("+250000-01-01"), None);
+        assert_eq!(RicalDate::parse_iso("-0001-01-01"), None);
+        assert_eq!(RicalDate::parse_iso("0000-01-01"), None);
+        assert_eq!(RicalDate::parse_iso("2025-8-04"), None);
+        assert_eq!(
+            RicalDate::parse_iso("9999-12-31"),
+            Some(RicalDate::new(9999, 12, 31))
+        );
+    }
+
+    #[test]
+    fn test_at_unix() {
+        // 2025-08-04 23:30 UTC
+        let secs = 1754350200;
+        assert_eq!(RicalDate::at_unix(secs, 0), RicalDate::new(2025, 8, 4));
+        assert_eq!(RicalDate::at_unix(secs, 60), RicalDate::new(2025, 8, 5));
+        assert_eq!(
+            RicalDate::at_unix(secs, -24 * 60),
+            RicalDate::new(2025, 8, 3)
+        );
+    }
+
+    #[test]
+    fn test_start_unix() {
+        let date = RicalDate::new(2025, 8, 4);
+        // 2025-08-04 00:00 UTC
+        assert_eq!(date.start_unix(0), 1754265600);
+        assert_eq!(date.start_unix(60), 1754265600 - 3600);
+        assert_eq!(RicalDate::at_unix(date.start_unix(-300), -300), date);
+        // A Monday
+        assert_eq!(date.week_start(), date);
+        assert_eq!(RicalDate::new(2025, 8, 10).week_start(), date);
+        assert_eq!(
+            RicalDate::new(2025, 8, 2).week_start(),
+            RicalDate::new(2025, 7, 28)
+        );
+    }
+
+    #[test]
+    fn test_get_days_in_month() {
+        assert_eq!(get_days_in_month(2025, 1), 31);
+        assert_eq!(get_days_in_month(2025, 2), 28);
+        assert_eq!(get_days_in_month(2024, 2), 29);
+        assert_eq!(get_days_in_month(2025, 12), 31);
+    }
+}
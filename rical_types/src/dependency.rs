@@ -0,0 +1,24 @@
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "openapi")]
+use utoipa::ToSchema;
+
+// Links between tasks that can't start until others are done, for planning projects
+
+/// The body of the request that makes a task depend on another
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub struct DependencyData {
+    /// The ID of the task that has to be done first
+    pub depends_on: i64,
+}
+
+/// A task's links to other tasks, as returned by `/task/{id}/dependencies`
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub struct Dependencies {
+    /// The IDs of the tasks that have to be done before this one can start
+    pub depends_on: Vec<i64>,
+    /// The IDs of the tasks that can't start until this one is done
+    pub dependents: Vec<i64>,
+}
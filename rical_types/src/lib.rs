@@ -0,0 +1,32 @@
+//! The types shared by the Rical backend and its clients
+//!
+//! Everything that goes over the wire is defined here once, so that a change to the protocol
+//! breaks at compile time on both ends instead of at runtime
+
+pub mod account;
+pub mod batch;
+pub mod checklist;
+pub mod date;
+pub mod dependency;
+pub mod project;
+pub mod search;
+pub mod server;
+pub mod tag;
+pub mod task;
+pub mod tracking;
+
+pub use account::*;
+pub use batch::*;
+pub use checklist::*;
+pub use date::RicalDate;
+pub use dependency::*;
+pub use project::*;
+pub use search::*;
+pub use server::*;
+pub use tag::*;
+pub use task::*;
+pub use tracking::*;
+
+/// The version of the HTTP API described by these types
+/// Bump this whenever a change to the types or routes is not backwards compatible
+pub const API_VERSION: &str = "1.0";
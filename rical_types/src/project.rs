@@ -0,0 +1,75 @@
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "openapi")]
+use utoipa::ToSchema;
+
+use crate::TaskDataWithId;
+
+// Projects, which group tasks on any number of dates under a name (e.g. "Move house")
+
+/// The body of the requests that create or replace a project
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub struct ProjectData {
+    /// Can't be empty
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    /// When the project should be done by, as `YYYY-MM-DD`
+    #[serde(default)]
+    pub due: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub struct ProjectId {
+    pub project_id: i64,
+}
+
+/// How far along a project's tasks are; cancelled tasks don't count
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub struct ProjectProgress {
+    pub done: u32,
+    pub total: u32,
+    /// The tasks that aren't done and are dated before the account's today
+    pub overdue: u32,
+}
+
+impl ProjectProgress {
+    /// How much of the project is done, from 0 to 1 (0 for a project without tasks)
+    pub fn fraction(&self) -> f64 {
+        if self.total == 0 {
+            0.0
+        } else {
+            f64::from(self.done) / f64::from(self.total)
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub struct Project {
+    pub project_id: i64,
+    pub name: String,
+    pub description: Option<String>,
+    /// As `YYYY-MM-DD`
+    pub due: Option<String>,
+    pub progress: ProjectProgress,
+}
+
+/// An account's projects, as returned by `/projects`
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub struct ProjectList {
+    /// The soonest due first (the ones without a due date last), then by name
+    pub projects: Vec<Project>,
+}
+
+/// A project's tasks, as returned by `/projects/{id}/tasks`
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub struct ProjectTasks {
+    /// By date, then in calendar order
+    pub tasks: Vec<TaskDataWithId>,
+}
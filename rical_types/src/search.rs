@@ -0,0 +1,67 @@
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "openapi")]
+use utoipa::ToSchema;
+
+use crate::TaskDataWithId;
+
+// Finding tasks by what they say, whatever their date
+
+/// The query string of `/search`
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "openapi", derive(utoipa::IntoParams))]
+#[cfg_attr(feature = "openapi", into_params(parameter_in = Query))]
+pub struct SearchQuery {
+    /// The words to find (all of them), or "quoted phrases"
+    pub q: String,
+    /// Only tasks on or after this date, as YYYY-MM-DD
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from: Option<String>,
+    /// Only tasks on or before this date, as YYYY-MM-DD
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub to: Option<String>,
+    /// Only completed (true) or incomplete (false) tasks
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub completed: Option<bool>,
+    /// The most tasks to return (default 20, at most 100)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u32>,
+}
+
+impl SearchQuery {
+    pub fn new(q: &str) -> SearchQuery {
+        SearchQuery {
+            q: q.to_string(),
+            ..SearchQuery::default()
+        }
+    }
+}
+
+/// The body of `/search`
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub struct SearchResults {
+    /// Best match first
+    pub hits: Vec<SearchHit>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub struct SearchHit {
+    pub task: TaskDataWithId,
+    /// How well the task matches (higher is better), only comparable within one search
+    pub rank: f64,
+    /// The whole title, split up around what matched
+    pub title: Vec<SnippetPart>,
+    /// An excerpt of the description around what matched, if it has one
+    pub description: Option<Vec<SnippetPart>>,
+}
+
+/// A piece of a highlighted snippet
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub struct SnippetPart {
+    pub text: String,
+    /// Whether this is what matched the search, to be highlighted
+    pub matched: bool,
+}
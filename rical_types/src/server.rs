@@ -0,0 +1,16 @@
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "openapi")]
+use utoipa::ToSchema;
+
+// The server itself, for operators and clients checking compatibility
+
+/// The body of `/version`
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub struct VersionInfo {
+    /// The version of the backend build, e.g. `0.1.0`
+    pub version: String,
+    /// The version of the HTTP API it speaks (see `API_VERSION`)
+    pub api_version: String,
+}
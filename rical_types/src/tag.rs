@@ -0,0 +1,158 @@
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "openapi")]
+use utoipa::ToSchema;
+
+// Tags, which put tasks into categories (e.g. "work" or "errand") across days
+
+/// The longest a tag's name can be, in characters
+pub const MAX_TAG_NAME_LEN: usize = 32;
+
+/// The colors that tags can be shown in, which every terminal has
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+#[cfg_attr(feature = "sqlx", derive(sqlx::Type))]
+#[serde(rename_all = "snake_case")]
+#[repr(i32)]
+pub enum TagColor {
+    Red = 0,
+    Yellow = 1,
+    Green = 2,
+    Cyan = 3,
+    #[default]
+    Blue = 4,
+    Magenta = 5,
+    Grey = 6,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
+pub struct Tag {
+    pub tag_id: i64,
+    pub name: String,
+    pub color: TagColor,
+}
+
+/// The body of the requests that create or replace a tag
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub struct TagData {
+    /// Letters, digits, `-` and `_`, starting with a letter, see `is_valid_tag_name`
+    pub name: String,
+    #[serde(default)]
+    pub color: TagColor,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub struct TagId {
+    pub tag_id: i64,
+}
+
+/// An account's tags, as returned by `/tags`
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub struct TagList {
+    /// By name
+    pub tags: Vec<Tag>,
+}
+
+/// Whether a tag name is 1 to `MAX_TAG_NAME_LEN` letters, digits, `-` or `_` starting with a letter,
+/// so that it can be written as `#name` in a title (without catching e.g. `#42`)
+/// and listed with commas in a filter
+pub fn is_valid_tag_name(name: &str) -> bool {
+    let len = name.chars().count();
+    (1..=MAX_TAG_NAME_LEN).contains(&len)
+        && name.starts_with(char::is_alphabetic)
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Take the `#tag`s out of a title, returning the rest of the title and the tags' names in order
+/// Only the tags and the space before them are removed, the rest of the title stays as written;
+/// words that only look like tags (e.g. `#42` or `#1!`) are left in it
+pub fn split_title_tags(title: &str) -> (String, Vec<String>) {
+    let mut rest = String::new();
+    let mut tags: Vec<String> = Vec::new();
+    // Where the space before the current word starts
+    let mut space_start = 0;
+    let mut word_start = None;
+    for (i, c) in title.char_indices().chain([(title.len(), ' ')]) {
+        match (c.is_whitespace(), word_start) {
+            (false, None) => word_start = Some(i),
+            (true, Some(start)) => {
+                let word = &title[start..i];
+                match word.strip_prefix('#') {
+                    Some(name) if is_valid_tag_name(name) => {
+                        if !tags.iter().any(|tag| tag == name) {
+                            tags.push(name.to_string());
+                        }
+                    }
+                    // A title starting with a tag doesn't keep the space after it
+                    _ if rest.is_empty() && !tags.is_empty() => rest.push_str(word),
+                    _ => rest.push_str(&title[space_start..i]),
+                }
+                space_start = i;
+                word_start = None;
+            }
+            _ => {}
+        }
+    }
+    (rest, tags)
+}
+
+/// The names in a `tags` query parameter, which lists them with commas
+pub fn parse_tag_filter(tags: &str) -> Vec<String> {
+    tags.split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_title_tags() {
+        assert_eq!(
+            split_title_tags("Buy milk #errand  #home #errand"),
+            (
+                "Buy milk".to_string(),
+                vec!["errand".to_string(), "home".to_string()]
+            )
+        );
+        assert_eq!(
+            split_title_tags("Issue #1! for #work"),
+            ("Issue #1! for".to_string(), vec!["work".to_string()])
+        );
+        assert_eq!(split_title_tags("# #"), ("# #".to_string(), vec![]));
+        assert_eq!(
+            split_title_tags("Fix bug #42"),
+            ("Fix bug #42".to_string(), vec![])
+        );
+        // Only the tags are taken out, the rest keeps its spacing
+        assert_eq!(
+            split_title_tags("#work Call  Ann #phone at\t9"),
+            (
+                "Call  Ann at\t9".to_string(),
+                vec!["work".to_string(), "phone".to_string()]
+            )
+        );
+    }
+
+    #[test]
+    fn test_tag_names() {
+        assert!(is_valid_tag_name("deep-work_2"));
+        assert!(!is_valid_tag_name(""));
+        assert!(!is_valid_tag_name("a,b"));
+        assert!(!is_valid_tag_name("two words"));
+        assert!(!is_valid_tag_name("42"));
+        assert!(!is_valid_tag_name("-work"));
+        assert!(!is_valid_tag_name(&"x".repeat(MAX_TAG_NAME_LEN + 1)));
+        assert_eq!(parse_tag_filter("work, ,home,"), ["work", "home"]);
+    }
+}
@@ -0,0 +1,623 @@
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::{ChecklistProgress, RicalDate};
+
+#[cfg(feature = "openapi")]
+use utoipa::ToSchema;
+
+// Tasks and the calendar views of them
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
+pub struct TaskData {
+    /// The task's date, which must exist, in the years 1 to 9999
+    pub year: i32,
+    pub month: i32,
+    pub day: i32,
+    /// Minutes after midnight that the task starts at, if it has a time
+    pub start_min: Option<i32>,
+    /// Minutes after midnight that the task ends at; requires `start_min`
+    pub end_min: Option<i32>,
+    pub title: String,
+    pub description: Option<String>,
+    /// Whether the task is done, i.e. whether `status` is `done`
+    /// Sending it marks the task done, or reopens it if it's done (for clients that predate statuses)
+    #[serde(default)]
+    pub complete: Option<bool>,
+    /// Leaving it out keeps the task's status (or makes a new task to do)
+    #[serde(default)]
+    pub status: Option<TaskStatus>,
+    /// Leaving it out keeps the task's priority (or gives a new task none)
+    #[serde(default)]
+    pub priority: Option<Priority>,
+    /// Where the task goes among its day's untimed tasks, lowest first
+    #[serde(default)]
+    pub position: i32,
+    /// Whether the task is marked done (or not done) whenever its checklist changes,
+    /// depending on whether every item of the checklist is done
+    #[serde(default)]
+    pub auto_complete: bool,
+    /// The ID of the project the task is part of, or null if it's in none.
+    /// Leaving it out keeps the task's project (or puts a new task in none)
+    #[serde(
+        default,
+        deserialize_with = "present",
+        skip_serializing_if = "Option::is_none"
+    )]
+    #[cfg_attr(feature = "openapi", schema(value_type = Option<i64>))]
+    pub project_id: Option<Option<i64>>,
+    /// How many minutes the task is expected to take, which can't be negative
+    #[serde(default)]
+    pub estimate_mins: Option<i32>,
+    /// The names of the task's tags; the account's tags that don't exist yet are created
+    /// Leaving it out keeps the task's tags (or gives a new task none)
+    #[serde(default)]
+    #[cfg_attr(feature = "sqlx", sqlx(skip))]
+    pub tags: Option<Vec<String>>,
+}
+
+impl TaskData {
+    /// The status the task is saved with, given the status it has (None for a new task),
+    /// with `complete` reconciled for clients that only send that
+    pub fn resolved_status(&self, stored: Option<TaskStatus>) -> TaskStatus {
+        let status = self.status.or(stored).unwrap_or_default();
+        match self.complete {
+            Some(complete) => status.marked(complete),
+            None => status,
+        }
+    }
+
+    /// Whether the task is on a day that exists, in the years 1 to 9999 that `RicalDate::parse_iso`
+    /// accepts, which the schema can't tell (it only checks that the day is at most 31)
+    pub fn has_valid_date(&self) -> bool {
+        let (Ok(month), Ok(day)) = (u32::try_from(self.month), u32::try_from(self.day)) else {
+            return false;
+        };
+        (1..=9999).contains(&self.year)
+            && chrono::NaiveDate::from_ymd_opt(self.year, month, day).is_some()
+    }
+}
+
+/// Deserializes a field that was sent, even as null, to Some, so that it can be told apart from
+/// one that was left out
+fn present<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    T::deserialize(deserializer).map(Some)
+}
+
+/// Where a task is in its workflow
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+#[cfg_attr(feature = "sqlx", derive(sqlx::Type))]
+#[serde(rename_all = "snake_case")]
+#[repr(i32)]
+pub enum TaskStatus {
+    #[default]
+    Todo = 0,
+    InProgress = 1,
+    Blocked = 2,
+    Deferred = 3,
+    Done = 4,
+    Cancelled = 5,
+}
+
+impl TaskStatus {
+    pub const ALL: [TaskStatus; 6] = [
+        TaskStatus::Todo,
+        TaskStatus::InProgress,
+        TaskStatus::Blocked,
+        TaskStatus::Deferred,
+        TaskStatus::Done,
+        TaskStatus::Cancelled,
+    ];
+
+    /// Whether nothing is left to do for the task, because it's done or cancelled
+    pub fn is_closed(self) -> bool {
+        matches!(self, TaskStatus::Done | TaskStatus::Cancelled)
+    }
+
+    /// The status after marking a task with this status done (or not done)
+    /// Marking a task not done only reopens it if it's done, so e.g. a cancelled task stays cancelled
+    pub fn marked(self, complete: bool) -> TaskStatus {
+        match (complete, self) {
+            (true, _) => TaskStatus::Done,
+            (false, TaskStatus::Done) => TaskStatus::Todo,
+            (false, status) => status,
+        }
+    }
+
+    /// The next status along the usual workflow: to do, then in progress, then done,
+    /// then to do again (the statuses off that path go back to to do)
+    pub fn cycle(self) -> TaskStatus {
+        match self {
+            TaskStatus::Todo => TaskStatus::InProgress,
+            TaskStatus::InProgress => TaskStatus::Done,
+            _ => TaskStatus::Todo,
+        }
+    }
+
+    /// The next of the statuses off the usual workflow: blocked, then deferred, then cancelled,
+    /// then to do again
+    pub fn cycle_held(self) -> TaskStatus {
+        match self {
+            TaskStatus::Blocked => TaskStatus::Deferred,
+            TaskStatus::Deferred => TaskStatus::Cancelled,
+            TaskStatus::Cancelled => TaskStatus::Todo,
+            _ => TaskStatus::Blocked,
+        }
+    }
+
+    /// The status's name in the API, e.g. `in_progress`
+    pub fn name(self) -> &'static str {
+        match self {
+            TaskStatus::Todo => "todo",
+            TaskStatus::InProgress => "in_progress",
+            TaskStatus::Blocked => "blocked",
+            TaskStatus::Deferred => "deferred",
+            TaskStatus::Done => "done",
+            TaskStatus::Cancelled => "cancelled",
+        }
+    }
+
+    /// The status's name for people, e.g. `In progress`
+    pub fn label(self) -> &'static str {
+        match self {
+            TaskStatus::Todo => "To do",
+            TaskStatus::InProgress => "In progress",
+            TaskStatus::Blocked => "Blocked",
+            TaskStatus::Deferred => "Deferred",
+            TaskStatus::Done => "Done",
+            TaskStatus::Cancelled => "Cancelled",
+        }
+    }
+
+    /// The status with a name or label, ignoring case and whether words are joined with
+    /// spaces, `-` or `_` (e.g. `in progress` or `IN_PROGRESS`)
+    pub fn from_name(name: &str) -> Option<TaskStatus> {
+        let normalized = name.trim().to_lowercase().replace([' ', '-'], "_");
+        match normalized.as_str() {
+            "to_do" => Some(TaskStatus::Todo),
+            "canceled" => Some(TaskStatus::Cancelled),
+            _ => TaskStatus::ALL
+                .into_iter()
+                .find(|status| status.name() == normalized),
+        }
+    }
+}
+
+/// The statuses in a `status` query parameter, which lists them with commas,
+/// or None if one of them isn't a status
+pub fn parse_status_filter(statuses: &str) -> Option<Vec<TaskStatus>> {
+    statuses
+        .split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(TaskStatus::from_name)
+        .collect()
+}
+
+/// How important a task is
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+#[cfg_attr(feature = "sqlx", derive(sqlx::Type))]
+#[serde(rename_all = "snake_case")]
+#[repr(i32)]
+pub enum Priority {
+    #[default]
+    None = 0,
+    Low = 1,
+    Medium = 2,
+    High = 3,
+    Urgent = 4,
+}
+
+impl Priority {
+    /// The next priority up, going back to none after urgent
+    pub fn cycle(self) -> Priority {
+        match self {
+            Priority::None => Priority::Low,
+            Priority::Low => Priority::Medium,
+            Priority::Medium => Priority::High,
+            Priority::High => Priority::Urgent,
+            Priority::Urgent => Priority::None,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub struct TaskId {
+    pub task_id: i64,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
+pub struct TaskDataWithId {
+    pub year: i32,
+    pub month: i32,
+    pub day: i32,
+    /// Minutes after midnight that the task starts at, if it has a time
+    pub start_min: Option<i32>,
+    /// Minutes after midnight that the task ends at; requires `start_min`
+    pub end_min: Option<i32>,
+    pub title: String,
+    pub description: Option<String>,
+    /// Whether the task is done, i.e. whether `status` is `done`
+    /// Sending true with the status `todo` (or none, from clients that predate statuses) marks it done
+    #[serde(default)]
+    pub complete: bool,
+    #[serde(default)]
+    pub status: TaskStatus,
+    #[serde(default)]
+    pub priority: Priority,
+    /// Where the task goes among its day's untimed tasks, lowest first
+    #[serde(default)]
+    pub position: i32,
+    /// Whether the task is marked done (or not done) whenever its checklist changes,
+    /// depending on whether every item of the checklist is done
+    #[serde(default)]
+    pub auto_complete: bool,
+    /// The ID of the project the task is part of, if it's in one
+    #[serde(default)]
+    pub project_id: Option<i64>,
+    /// How many minutes the task is expected to take, which can't be negative
+    #[serde(default)]
+    pub estimate_mins: Option<i32>,
+    /// The names of the task's tags, alphabetically
+    #[serde(default)]
+    #[cfg_attr(feature = "sqlx", sqlx(skip))]
+    pub tags: Vec<String>,
+    pub task_id: i64,
+    /// When the status was last set, in unix seconds (null for tasks made before statuses
+    /// existed, until their status changes)
+    #[serde(default)]
+    pub status_changed_at: Option<i64>,
+    /// When the task was marked done, in unix seconds (null unless it's done)
+    #[serde(default)]
+    pub completed_at: Option<i64>,
+    /// How much of the task's checklist is done, if it has one (only in `/calendar`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature = "sqlx", sqlx(skip))]
+    pub checklist: Option<ChecklistProgress>,
+    /// The IDs of the tasks this one depends on that aren't done (or cancelled) yet;
+    /// it's blocked until there are none
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[cfg_attr(feature = "sqlx", sqlx(skip))]
+    pub blocked_by: Vec<i64>,
+    /// The IDs of the tasks in `blocked_by` that are scheduled on a later date than this one,
+    /// which it probably can't be done by
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[cfg_attr(feature = "sqlx", sqlx(skip))]
+    pub later_dependencies: Vec<i64>,
+}
+
+impl TaskDataWithId {
+    pub fn is_blocked(&self) -> bool {
+        !self.blocked_by.is_empty()
+    }
+
+    /// How long the task is scheduled for (see `estimate_mins` for how long it's expected to take)
+    pub fn duration_mins(&self) -> Option<i32> {
+        match (self.start_min, self.end_min) {
+            (Some(start_min), Some(end_min)) => Some(end_min - start_min),
+            _ => None,
+        }
+    }
+
+    pub fn without_id(&self) -> TaskData {
+        TaskData {
+            year: self.year,
+            month: self.month,
+            day: self.day,
+            start_min: self.start_min,
+            end_min: self.end_min,
+            title: self.title.clone(),
+            description: self.description.clone(),
+            complete: Some(self.complete),
+            status: Some(self.status),
+            priority: Some(self.priority),
+            position: self.position,
+            auto_complete: self.auto_complete,
+            project_id: Some(self.project_id),
+            estimate_mins: self.estimate_mins,
+            tags: Some(self.tags.clone()),
+        }
+    }
+
+    /// The task with another status, and `complete` to match
+    pub fn with_status(self, status: TaskStatus) -> TaskDataWithId {
+        TaskDataWithId {
+            status,
+            complete: status == TaskStatus::Done,
+            ..self
+        }
+    }
+}
+
+/// One of the changes of a task's status
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
+pub struct StatusChange {
+    /// The status the task changed to
+    pub status: TaskStatus,
+    /// In unix seconds
+    pub changed_at: i64,
+}
+
+/// Every status a task has had, as returned by `/task/{id}/history`
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub struct StatusHistory {
+    /// Oldest first, starting with the status the task was created with (tasks made before
+    /// statuses existed only have the changes since)
+    pub changes: Vec<StatusChange>,
+}
+
+/// The number of days in `CalendarTasks::days`, whatever the month
+pub const MAX_DAYS_PER_MONTH: usize = 31;
+
+/// How each day's tasks are ordered
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum DayOrder {
+    /// By start time (untimed tasks last, in their `position`), then longest first, then by title
+    #[default]
+    Time,
+    /// Highest priority first, then like `time`
+    Priority,
+}
+
+/// The query string of `/calendar/{year}/{month}`
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "openapi", derive(utoipa::IntoParams))]
+#[cfg_attr(feature = "openapi", into_params(parameter_in = Query))]
+pub struct CalendarQuery {
+    #[serde(default)]
+    #[cfg_attr(feature = "openapi", param(inline))]
+    pub order: DayOrder,
+    /// Only tasks with any of these tags, listed with commas (e.g. `work,errand`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tags: Option<String>,
+    /// Only tasks with any of these statuses, listed with commas (e.g. `todo,in_progress`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<String>,
+}
+
+/// One month of tasks, as returned by `/calendar/{year}/{month}`
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub struct CalendarTasks {
+    /// Always 31 days long; `days[0]` holds the tasks on the 1st of the month
+    pub days: Vec<Vec<TaskDataWithId>>,
+}
+
+/// How `/tasks` orders what it returns
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum TaskSort {
+    /// By date, and each day like `/calendar` orders it
+    #[default]
+    Date,
+    /// The reverse of `date`, latest first
+    DateDesc,
+    /// By title, then by date
+    Title,
+    /// By date, and each day like `/calendar` orders it with `order=priority`
+    Priority,
+}
+
+/// How `/tasks` lays out what it returns
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum TaskLayout {
+    /// One flat list, as a `TaskList`
+    #[default]
+    List,
+    /// Grouped by date, as `TasksByDate` (needs a date sort)
+    ByDate,
+}
+
+/// The query string of `/tasks`
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "openapi", derive(utoipa::IntoParams))]
+#[cfg_attr(feature = "openapi", into_params(parameter_in = Query))]
+pub struct TaskQuery {
+    /// The first date, as YYYY-MM-DD
+    pub from: String,
+    /// The last date (included), as YYYY-MM-DD
+    pub to: String,
+    /// Only completed (true) or incomplete (false) tasks
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub completed: Option<bool>,
+    /// Only tasks with (true) or without (false) a start time
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timed: Option<bool>,
+    /// Only tasks with this in their title or description, ignoring case
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub q: Option<String>,
+    /// Only tasks with any of these tags, listed with commas (e.g. `work,errand`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tags: Option<String>,
+    /// Only tasks with any of these statuses, listed with commas (e.g. `todo,in_progress`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<String>,
+    #[serde(default)]
+    #[cfg_attr(feature = "openapi", param(inline))]
+    pub sort: TaskSort,
+    #[serde(default)]
+    #[cfg_attr(feature = "openapi", param(inline))]
+    pub layout: TaskLayout,
+    /// The most tasks to return at once (default 100, at most 500)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u32>,
+    /// The `next_cursor` of the previous page, to continue from there
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<String>,
+}
+
+impl TaskQuery {
+    /// Every task from one date to another (both included)
+    pub fn between(from: &RicalDate, to: &RicalDate) -> TaskQuery {
+        TaskQuery {
+            from: from.format_iso(),
+            to: to.format_iso(),
+            ..TaskQuery::default()
+        }
+    }
+}
+
+/// A page of `/tasks` as one list
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub struct TaskList {
+    pub tasks: Vec<TaskDataWithId>,
+    /// Pass this as `cursor` to get the next page, or null if this is the last one
+    pub next_cursor: Option<String>,
+}
+
+/// A page of `/tasks` grouped by date
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub struct TasksByDate {
+    /// Only the dates that have tasks, in the order of the sort
+    pub days: Vec<DateTasks>,
+    /// Pass this as `cursor` to get the next page, or null if this is the last one
+    /// (a date's tasks may continue on the next page)
+    pub next_cursor: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub struct DateTasks {
+    /// As YYYY-MM-DD
+    pub date: String,
+    pub tasks: Vec<TaskDataWithId>,
+}
+
+/// A page of `/tasks`, in the requested layout
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+#[serde(untagged)]
+pub enum TaskPage {
+    List(TaskList),
+    ByDate(TasksByDate),
+}
+
+impl TaskPage {
+    pub fn next_cursor(&self) -> Option<&str> {
+        match self {
+            TaskPage::List(list) => list.next_cursor.as_deref(),
+            TaskPage::ByDate(by_date) => by_date.next_cursor.as_deref(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status_names() {
+        for status in TaskStatus::ALL {
+            assert_eq!(TaskStatus::from_name(status.name()), Some(status));
+            assert_eq!(TaskStatus::from_name(status.label()), Some(status));
+        }
+        assert_eq!(
+            TaskStatus::from_name(" In-Progress "),
+            Some(TaskStatus::InProgress)
+        );
+        assert_eq!(TaskStatus::from_name("finished"), None);
+        assert_eq!(
+            parse_status_filter("todo, blocked,"),
+            Some(vec![TaskStatus::Todo, TaskStatus::Blocked])
+        );
+        assert_eq!(parse_status_filter("todo,finished"), None);
+    }
+
+    fn task(year: i32, month: i32, day: i32) -> TaskData {
+        TaskData {
+            year,
+            month,
+            day,
+            start_min: None,
+            end_min: None,
+            title: "Task".to_string(),
+            description: None,
+            complete: None,
+            status: None,
+            priority: None,
+            position: 0,
+            auto_complete: false,
+            project_id: None,
+            estimate_mins: None,
+            tags: None,
+        }
+    }
+
+    #[test]
+    fn test_valid_dates() {
+        assert!(task(2024, 2, 29).has_valid_date());
+        assert!(!task(2025, 2, 29).has_valid_date());
+        assert!(!task(2025, 4, 31).has_valid_date());
+        assert!(!task(2025, 13, 1).has_valid_date());
+        assert!(!task(2025, 8, 0).has_valid_date());
+        assert!(!task(2025, -1, 1).has_valid_date());
+        assert!(task(9999, 12, 31).has_valid_date());
+        assert!(!task(10000, 1, 1).has_valid_date());
+        assert!(!task(300000, 1, 1).has_valid_date());
+        assert!(!task(0, 1, 1).has_valid_date());
+    }
+
+    #[test]
+    fn test_resolved_status() {
+        let task = TaskData {
+            complete: Some(true),
+            ..task(2025, 8, 4)
+        };
+        // Clients that predate statuses only send `complete`
+        assert_eq!(task.resolved_status(None), TaskStatus::Done);
+        assert_eq!(
+            task.resolved_status(Some(TaskStatus::InProgress)),
+            TaskStatus::Done
+        );
+        let reopened = TaskData {
+            complete: Some(false),
+            ..task.clone()
+        };
+        assert_eq!(
+            reopened.resolved_status(Some(TaskStatus::Done)),
+            TaskStatus::Todo
+        );
+        // Not done keeps a task that isn't done as it is
+        assert_eq!(
+            reopened.resolved_status(Some(TaskStatus::Blocked)),
+            TaskStatus::Blocked
+        );
+        // `complete` still counts alongside a status that disagrees
+        let contradicting = TaskData {
+            status: Some(TaskStatus::Done),
+            ..reopened
+        };
+        assert_eq!(contradicting.resolved_status(None), TaskStatus::Todo);
+        // Without either, the task keeps its status
+        let neither = TaskData {
+            complete: None,
+            status: None,
+            ..task
+        };
+        assert_eq!(
+            neither.resolved_status(Some(TaskStatus::Deferred)),
+            TaskStatus::Deferred
+        );
+        assert_eq!(neither.resolved_status(None), TaskStatus::Todo);
+    }
+}
@@ -0,0 +1,116 @@
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "openapi")]
+use utoipa::ToSchema;
+
+// Tracking the time actually spent on tasks, in sessions that a timer starts and stops
+
+/// A stretch of time spent on a task, in unix seconds
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
+pub struct TimeSession {
+    pub session_id: i64,
+    pub task_id: i64,
+    pub started_at: i64,
+    /// None while the timer is still running
+    pub stopped_at: Option<i64>,
+}
+
+impl TimeSession {
+    /// How long the session lasted, or has lasted so far if it's still running
+    pub fn tracked_secs(&self, now: i64) -> i64 {
+        (self.stopped_at.unwrap_or(now) - self.started_at).max(0)
+    }
+}
+
+/// The body of the request that starts the timer on a task
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub struct StartTimer {
+    pub task_id: i64,
+}
+
+/// The session that the account's timer is running, with its task's title
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
+pub struct RunningTimer {
+    pub session_id: i64,
+    pub task_id: i64,
+    pub title: String,
+    /// In unix seconds
+    pub started_at: i64,
+}
+
+/// The account's timer, as returned by `/tracking/timer`
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub struct Timer {
+    /// None when the timer isn't running
+    pub running: Option<RunningTimer>,
+}
+
+/// A task's sessions, as returned by `/tracking/sessions`
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub struct SessionList {
+    /// Oldest first
+    pub sessions: Vec<TimeSession>,
+}
+
+/// The query string of `/tracking/sessions`
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::IntoParams))]
+#[cfg_attr(feature = "openapi", into_params(parameter_in = Query))]
+pub struct SessionQuery {
+    pub task_id: i64,
+}
+
+/// What `/tracking/report` adds tracked time up by
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum ReportGrouping {
+    #[default]
+    Day,
+    /// Weeks from Monday to Sunday
+    Week,
+    /// A task with several tags counts towards each of them
+    Tag,
+    Project,
+}
+
+/// The query string of `/tracking/report`
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::IntoParams))]
+#[cfg_attr(feature = "openapi", into_params(parameter_in = Query))]
+pub struct ReportQuery {
+    /// The first date, as YYYY-MM-DD
+    pub from: String,
+    /// The last date (included), as YYYY-MM-DD
+    pub to: String,
+    #[serde(default)]
+    #[cfg_attr(feature = "openapi", param(inline))]
+    pub group_by: ReportGrouping,
+}
+
+/// The time tracked in one day, week, tag or project
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub struct TimeReportEntry {
+    /// The day or the week's Monday (as YYYY-MM-DD), or the tag's or project's name;
+    /// None for the time spent on tasks without a tag or project
+    pub key: Option<String>,
+    pub tracked_secs: i64,
+}
+
+/// The time tracked in sessions started between two dates, as returned by `/tracking/report`
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub struct TimeReport {
+    /// By date for days and weeks, by name for tags and projects
+    pub entries: Vec<TimeReportEntry>,
+    /// Every session's time, counted once
+    pub total_secs: i64,
+}